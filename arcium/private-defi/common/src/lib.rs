@@ -0,0 +1,106 @@
+//! Helpers shared by the programs that queue Arcium computations, so each
+//! program builds callback accounts and checks computation fees the same way
+
+use anchor_lang::prelude::*;
+use arcium_anchor::prelude::*;
+
+/// Lamports a payer must hold to queue a computation, on top of anything the
+/// instruction locks, covering the Arcium fee with headroom for the accounts
+/// the callback may create
+pub const MIN_COMPUTATION_FEE_LAMPORTS: u64 = 5_000_000;
+
+/// Whether a payer holding `lamports` can queue a computation that also
+/// locks `locked` lamports
+/// Each program wraps this in its `ensure_computation_fee` to fail with its
+/// own error code before the queue CPI fails opaquely.
+pub fn covers_computation_fee(lamports: u64, locked: u64) -> bool {
+    MIN_COMPUTATION_FEE_LAMPORTS
+        .checked_add(locked)
+        .is_some_and(|required| lamports >= required)
+}
+
+/// Builds the extra accounts a computation callback receives, after the
+/// comp def, MXE, computation, cluster and instructions sysvar accounts that
+/// `callback_ix` always prepends
+/// Accounts are appended in the order of the callback's `Accounts` struct.
+#[derive(Default)]
+pub struct CallbackAccountsBuilder {
+    accounts: Vec<CallbackAccount>,
+}
+
+impl CallbackAccountsBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append an account the callback writes to
+    pub fn writable(mut self, pubkey: Pubkey) -> Self {
+        self.accounts.push(CallbackAccount {
+            pubkey,
+            is_writable: true,
+        });
+        self
+    }
+
+    /// Append an account the callback only reads
+    pub fn readonly(mut self, pubkey: Pubkey) -> Self {
+        self.accounts.push(CallbackAccount {
+            pubkey,
+            is_writable: false,
+        });
+        self
+    }
+
+    pub fn build(self) -> Vec<CallbackAccount> {
+        self.accounts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flags(accounts: &[CallbackAccount]) -> Vec<(Pubkey, bool)> {
+        accounts
+            .iter()
+            .map(|account| (account.pubkey, account.is_writable))
+            .collect()
+    }
+
+    #[test]
+    fn builder_matches_hand_built_accounts() {
+        let (pool, pending, user, token_program) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let built = CallbackAccountsBuilder::new()
+            .writable(pool)
+            .writable(pending)
+            .writable(user)
+            .readonly(token_program)
+            .build();
+        let by_hand = vec![
+            CallbackAccount { pubkey: pool, is_writable: true },
+            CallbackAccount { pubkey: pending, is_writable: true },
+            CallbackAccount { pubkey: user, is_writable: true },
+            CallbackAccount { pubkey: token_program, is_writable: false },
+        ];
+        assert_eq!(flags(&built), flags(&by_hand));
+    }
+
+    #[test]
+    fn empty_builder_adds_no_accounts() {
+        assert!(CallbackAccountsBuilder::new().build().is_empty());
+    }
+
+    #[test]
+    fn fee_check_counts_locked_lamports() {
+        assert!(covers_computation_fee(MIN_COMPUTATION_FEE_LAMPORTS, 0));
+        assert!(!covers_computation_fee(MIN_COMPUTATION_FEE_LAMPORTS - 1, 0));
+        assert!(covers_computation_fee(MIN_COMPUTATION_FEE_LAMPORTS + 10, 10));
+        assert!(!covers_computation_fee(MIN_COMPUTATION_FEE_LAMPORTS + 9, 10));
+        assert!(!covers_computation_fee(u64::MAX, u64::MAX));
+    }
+}
@@ -1,9 +1,100 @@
 use arcis_imports::*;
 
+// Deterministic order ids are for test suites only and must never ship
+#[cfg(all(feature = "test", not(debug_assertions)))]
+compile_error!("the `test` feature is not allowed in release builds");
+
+/// Whether the circuits derive order ids and priority draws as in
+/// `deterministic` instead of drawing them from the MPC RNG
+#[cfg(feature = "test")]
+const DETERMINISTIC_ORDER_IDS: bool = true;
+#[cfg(not(feature = "test"))]
+const DETERMINISTIC_ORDER_IDS: bool = false;
+
+/// Order ids and priority draws the circuits produce under the `test`
+/// feature, for suites asserting exact ids
+/// Mirrors `next_order_id` and `priority_draw` in the circuits.
+#[cfg(feature = "test")]
+pub mod deterministic {
+    /// Fixed base for order ids
+    pub const ORDER_ID_SEED: u64 = 0x5eed_0000_0000_0000;
+
+    /// Id of the order inserted into a book whose priority counter is at
+    /// `next_priority`
+    pub fn order_id(next_priority: u64) -> u64 {
+        ORDER_ID_SEED + next_priority + 1
+    }
+
+    /// Tie-break for an order entering a randomized priority window: the low
+    /// word of its owner
+    pub fn priority_draw(owner_lo: u128) -> u64 {
+        (owner_lo % (1u128 << 32)) as u64
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::{order_id, priority_draw, ORDER_ID_SEED};
+
+        const OWNERS: [u128; 4] = [
+            0x0123_4567_89ab_cdef_0011_2233_4455_6677,
+            0xfedc_ba98_7654_3210_8899_aabb_ccdd_eeff,
+            7,
+            u128::MAX,
+        ];
+
+        /// Ids and draws for `owners` placed in order into a fresh book
+        fn run(owners: &[u128]) -> Vec<(u64, u64)> {
+            owners
+                .iter()
+                .enumerate()
+                .map(|(placed, owner)| (order_id(placed as u64), priority_draw(*owner)))
+                .collect()
+        }
+
+        #[test]
+        fn replays_produce_identical_ids() {
+            assert_eq!(run(&OWNERS), run(&OWNERS));
+        }
+
+        #[test]
+        fn ids_follow_the_priority_counter() {
+            let ids: Vec<u64> = run(&OWNERS).into_iter().map(|(id, _)| id).collect();
+            assert_eq!(
+                ids,
+                vec![ORDER_ID_SEED + 1, ORDER_ID_SEED + 2, ORDER_ID_SEED + 3, ORDER_ID_SEED + 4]
+            );
+        }
+
+        #[test]
+        fn ids_keep_rising_across_matching_runs() {
+            // next_priority carries over between runs, so an order placed
+            // after a match run ranks behind every order still resting
+            let first_run = run(&OWNERS[..2]);
+            let resumed: Vec<u64> = (first_run.len()..OWNERS.len())
+                .map(|placed| order_id(placed as u64))
+                .collect();
+            assert!(first_run.iter().all(|(id, _)| resumed.iter().all(|later| later > id)));
+            assert_eq!(
+                first_run.iter().map(|(id, _)| *id).chain(resumed).collect::<Vec<_>>(),
+                run(&OWNERS).into_iter().map(|(id, _)| id).collect::<Vec<_>>()
+            );
+        }
+
+        #[test]
+        fn draws_fit_the_window_word() {
+            assert_eq!(priority_draw(OWNERS[0]), 0x4455_6677);
+            assert_eq!(priority_draw(7), 7);
+            assert_eq!(priority_draw(u128::MAX), u32::MAX as u64);
+        }
+    }
+}
+
 #[encrypted]
 mod circuits {
     use arcis_imports::*;
 
+    use super::DETERMINISTIC_ORDER_IDS;
+
     // ============ Private Swap Circuits ============
 
     /// Encrypted swap input containing the amount to swap
@@ -22,142 +113,2686 @@ mod circuits {
         min_output: u64,
         is_a_to_b: bool,
         fee_rate: u16, // Fee in basis points (100 = 1%)
-    ) -> (u64, u64, bool) {
+        min_fee: u64,  // Fee floor so small swaps can't round the fee to zero
+        min_swap_notional: u64, // Smallest input worth swapping, in token B; 0 disables
+    ) -> (u64, u64, bool, bool) {
         let input = input_ctxt.to_arcis();
         let amount_in = input.amount_in;
+        let (amount_out, success) = quote_swap(
+            amount_in, reserve_a, reserve_b, min_output, is_a_to_b, fee_rate, min_fee,
+        );
 
-        // Calculate fee
-        let fee = (amount_in * (fee_rate as u64)) / 10000;
-        let amount_in_after_fee = amount_in - fee;
-
-        // Calculate output using constant product formula
-        let (reserve_in, reserve_out) = if is_a_to_b {
-            (reserve_a, reserve_b)
+        // Token A inputs are valued at the spot price so both directions
+        // share one minimum
+        let notional = if is_a_to_b {
+            if reserve_a > 0 {
+                (amount_in as u128) * (reserve_b as u128) / (reserve_a as u128)
+            } else {
+                0
+            }
         } else {
-            (reserve_b, reserve_a)
+            amount_in as u128
         };
+        let below_minimum = min_swap_notional > 0 && notional < min_swap_notional as u128;
+        let success = success && !below_minimum;
 
-        // amount_out = (amount_in * reserve_out) / (reserve_in + amount_in)
-        let numerator = amount_in_after_fee * reserve_out;
-        let denominator = reserve_in + amount_in_after_fee;
-        let amount_out = numerator / denominator;
+        // Reveal the amounts and success status
+        // amount_in is revealed as submitted (fee included), and amount_out is
+        // the quote for that input even when `success` is false, so callers
+        // must check `success` before acting on either amount
+        (amount_in.reveal(), amount_out.reveal(), success.reveal(), below_minimum.reveal())
+    }
 
-        // Check slippage
-        let success = amount_out >= min_output;
+    /// Swap amounts kept encrypted until the pool's reveal delay has passed
+    pub struct SwapResult {
+        pub amount_in: u64,
+        pub amount_out: u64,
+    }
+
+    /// Same swap as `execute_swap`, but the amounts stay encrypted to the MXE
+    /// Only the success flag is revealed; the program stores the result and
+    /// settles it once `reveal_swap_result` runs after the reveal delay
+    #[instruction]
+    pub fn execute_swap_sealed(
+        input_ctxt: Enc<Shared, SwapInput>,
+        reserve_a: u64,
+        reserve_b: u64,
+        min_output: u64,
+        is_a_to_b: bool,
+        fee_rate: u16,
+        min_fee: u64,
+    ) -> (Enc<Mxe, SwapResult>, bool) {
+        let amount_in = input_ctxt.to_arcis().amount_in;
+        let (amount_out, success) = quote_swap(
+            amount_in, reserve_a, reserve_b, min_output, is_a_to_b, fee_rate, min_fee,
+        );
+
+        let result = SwapResult { amount_in, amount_out };
+        (Mxe::get().from_arcis(result), success.reveal())
+    }
+
+    /// Reveal a sealed swap result for settlement
+    #[instruction]
+    pub fn reveal_swap_result(result_ctxt: Enc<Mxe, SwapResult>) -> (u64, u64) {
+        let result = result_ctxt.to_arcis();
+        (result.amount_in.reveal(), result.amount_out.reveal())
+    }
+
+    /// Encrypted desired output for an exact-output route
+    pub struct ExactOutInput {
+        pub amount_out: u64,
+    }
+
+    /// Swap for an exact encrypted output, the dual of `execute_swap`
+    /// Back-solves the fee-inclusive input the pool needs to pay out
+    /// `amount_out` (see `required_input`), failing if it exceeds `max_input`
+    /// or the pool can't cover the output.
+    #[instruction]
+    pub fn execute_swap_exact_out(
+        output_ctxt: Enc<Shared, ExactOutInput>,
+        reserve_a: u64,
+        reserve_b: u64,
+        max_input: u64,
+        is_a_to_b: bool,
+        fee_rate: u16,
+        min_fee: u64,
+    ) -> (u64, u64, bool) {
+        let amount_out = output_ctxt.to_arcis().amount_out;
+        let (reserve_in, reserve_out) = if is_a_to_b { (reserve_a, reserve_b) } else { (reserve_b, reserve_a) };
+        let (amount_in, feasible) = required_input(amount_out, reserve_in, reserve_out, fee_rate, min_fee);
+
+        let success = amount_out > 0 && feasible && amount_in <= max_input;
 
-        // Reveal the amounts and success status
         (amount_in.reveal(), amount_out.reveal(), success.reveal())
     }
 
+    /// Route an exact-output swap through two pools
+    /// Back-solves the input required by the second hop, then the input the
+    /// first hop needs to produce it. The intermediate amount is revealed with
+    /// the result because both pools' public reserves move by it on settlement.
+    #[instruction]
+    pub fn route_swap_exact_out(
+        output_ctxt: Enc<Shared, ExactOutInput>,
+        first_reserve_in: u64,
+        first_reserve_out: u64,
+        first_fee_rate: u16,
+        first_min_fee: u64,
+        second_reserve_in: u64,
+        second_reserve_out: u64,
+        second_fee_rate: u16,
+        second_min_fee: u64,
+        max_input: u64,
+    ) -> (u64, u64, u64, bool) {
+        let input = output_ctxt.to_arcis();
+        let amount_out = input.amount_out;
+
+        let (intermediate, second_ok) = required_input(
+            amount_out,
+            second_reserve_in,
+            second_reserve_out,
+            second_fee_rate,
+            second_min_fee,
+        );
+        let (amount_in, first_ok) = required_input(
+            intermediate,
+            first_reserve_in,
+            first_reserve_out,
+            first_fee_rate,
+            first_min_fee,
+        );
+
+        let success = amount_out > 0 && second_ok && first_ok && amount_in <= max_input;
+
+        (amount_in.reveal(), intermediate.reveal(), amount_out.reveal(), success.reveal())
+    }
+
+    /// Swap through two pools in one computation (e.g. A -> B -> C)
+    /// The first hop's output is the second hop's input without leaving MPC,
+    /// and only the end-to-end output is held to `min_output`. The
+    /// intermediate amount is revealed with the result because both pools'
+    /// public reserves move by it on settlement.
+    #[instruction]
+    pub fn execute_multi_hop_swap(
+        input_ctxt: Enc<Shared, SwapInput>,
+        first_reserve_in: u64,
+        first_reserve_out: u64,
+        first_fee_rate: u16,
+        first_min_fee: u64,
+        second_reserve_in: u64,
+        second_reserve_out: u64,
+        second_fee_rate: u16,
+        second_min_fee: u64,
+        min_output: u64,
+    ) -> (u64, u64, u64, bool) {
+        let amount_in = input_ctxt.to_arcis().amount_in;
+
+        // Reserves are passed in hop order, so each hop quotes as "a to b"
+        let (intermediate, first_ok) = quote_swap(
+            amount_in, first_reserve_in, first_reserve_out, 0, true, first_fee_rate, first_min_fee,
+        );
+        let (amount_out, second_ok) = quote_swap(
+            intermediate, second_reserve_in, second_reserve_out, min_output, true, second_fee_rate,
+            second_min_fee,
+        );
+
+        let success = first_ok && second_ok && intermediate > 0;
+
+        (amount_in.reveal(), intermediate.reveal(), amount_out.reveal(), success.reveal())
+    }
+
     // ============ Private Pay Circuits ============
 
     /// Initialize a private balance account
+    /// Produces MXE encryptions of a zero balance and a zero minimum balance
+    /// for later circuits to build on
     #[instruction]
-    pub fn init_balance(
-        nonce: u128,
-        owner: [u8; 32],
-    ) -> bool {
-        // Initialize encrypted balance to 0
-        // In production, this would set up encrypted state
-        true.reveal()
+    pub fn init_balance() -> (Enc<Mxe, Balance>, Enc<Mxe, Amount>) {
+        (
+            Mxe::get().from_arcis(Balance { amount: 0 }),
+            Mxe::get().from_arcis(Amount { amount: 0 }),
+        )
     }
 
     /// Deposit funds into private balance
+    /// Adds the public deposit amount to the stored encrypted balance. Fails
+    /// without changing the balance on a zero amount or overflow.
     #[instruction]
     pub fn deposit(
         amount: u64,
-        owner: [u8; 32],
-        nonce: u128,
-    ) -> (u64, bool) {
-        // In production, this would:
-        // 1. Load current encrypted balance
-        // 2. Add deposit amount
-        // 3. Store new encrypted balance
-        // 4. Return new balance and success
-        
-        let new_balance = amount; // Simplified
-        let success = amount > 0;
-        
-        (new_balance.reveal(), success.reveal())
+        balance_ctxt: Enc<Mxe, Balance>,
+    ) -> (Enc<Mxe, Balance>, bool) {
+        let mut balance = balance_ctxt.to_arcis();
+
+        let success = amount > 0 && balance.amount <= u64::MAX - amount;
+        if success {
+            balance.amount += amount;
+        }
+
+        (balance_ctxt.owner.from_arcis(balance), success.reveal())
+    }
+
+    /// Withdraw a public amount from an encrypted SOL or SPL token balance
+    /// The sufficiency check runs entirely in MPC: only whether the balance
+    /// covered the amount, and still met the private minimum balance, is
+    /// revealed, along with the public amount for the payout. Fails without
+    /// changing the balance otherwise. The program pays out of the SOL vault
+    /// or the mint's token vault depending on the balance.
+    #[instruction]
+    pub fn withdraw(
+        amount: u64,
+        balance_ctxt: Enc<Mxe, Balance>,
+        min_balance_ctxt: Enc<Mxe, Amount>,
+    ) -> (Enc<Mxe, Balance>, u64, bool) {
+        let mut balance = balance_ctxt.to_arcis();
+        let min_balance = min_balance_ctxt.to_arcis().amount;
+
+        let (remaining, covered) = checked_sub_encrypted(balance.amount, amount);
+        let success = amount > 0 && covered && remaining >= min_balance;
+        if success {
+            balance.amount = remaining;
+        }
+
+        (
+            balance_ctxt.owner.from_arcis(balance),
+            amount.reveal(),
+            success.reveal(),
+        )
+    }
+
+    /// Pre-flight for `transfer`: whether the sender could cover `amount`
+    /// Applies the same checks as `transfer` on the sender side but writes
+    /// nothing back, so only the verdict leaves the MXE
+    #[instruction]
+    pub fn check_transfer_feasible(
+        amount_ctxt: Enc<Shared, Amount>,
+        sender_ctxt: Enc<Mxe, Balance>,
+        sender_min_ctxt: Enc<Mxe, Amount>,
+    ) -> bool {
+        let amount = amount_ctxt.to_arcis().amount;
+        let sender = sender_ctxt.to_arcis();
+        let sender_min = sender_min_ctxt.to_arcis().amount;
+
+        let (remaining, covered) = checked_sub_encrypted(sender.amount, amount);
+        let feasible = amount > 0 && covered && remaining >= sender_min;
+        feasible.reveal()
+    }
+
+    /// Encrypted private balance state
+    pub struct Balance {
+        pub amount: u64,
+    }
+
+    /// Re-encrypt a balance snapshot to the compliance auditor's key
+    /// Only the auditor can decrypt the result; nothing is revealed on-chain
+    #[instruction]
+    pub fn disclose_balance(
+        balance_ctxt: Enc<Mxe, Balance>,
+        auditor: Shared,
+    ) -> Enc<Shared, Balance> {
+        let balance = balance_ctxt.to_arcis();
+        auditor.from_arcis(balance)
+    }
+
+    /// Re-encrypt a balance to a key the owner supplied for this request
+    /// Lets a wallet display the balance; nothing is revealed on-chain
+    #[instruction]
+    pub fn reveal_balance(
+        balance_ctxt: Enc<Mxe, Balance>,
+        owner: Shared,
+    ) -> Enc<Shared, Balance> {
+        let balance = balance_ctxt.to_arcis();
+        owner.from_arcis(balance)
+    }
+
+    /// Encrypted amount supplied by a user
+    pub struct Amount {
+        pub amount: u64,
+    }
+
+    /// Store a private spending cap for a delegate
+    #[instruction]
+    pub fn approve_allowance(amount_ctxt: Enc<Shared, Amount>) -> Enc<Mxe, Amount> {
+        let allowance = amount_ctxt.to_arcis();
+        Mxe::get().from_arcis(allowance)
+    }
+
+    /// Move an encrypted amount from the owner's balance to the delegate's,
+    /// drawing down the remaining allowance
+    /// Fails without changing anything if the amount exceeds either the
+    /// remaining allowance or the owner's balance
+    #[instruction]
+    pub fn spend_allowance(
+        amount_ctxt: Enc<Shared, Amount>,
+        allowance_ctxt: Enc<Mxe, Amount>,
+        owner_ctxt: Enc<Mxe, Balance>,
+        delegate_ctxt: Enc<Mxe, Balance>,
+    ) -> (Enc<Mxe, Amount>, Enc<Mxe, Balance>, Enc<Mxe, Balance>, bool) {
+        let spend = amount_ctxt.to_arcis().amount;
+        let mut allowance = allowance_ctxt.to_arcis();
+        let mut owner = owner_ctxt.to_arcis();
+        let mut delegate = delegate_ctxt.to_arcis();
+
+        let success = spend > 0
+            && spend <= allowance.amount
+            && spend <= owner.amount
+            && delegate.amount <= u64::MAX - spend;
+        if success {
+            allowance.amount -= spend;
+            owner.amount -= spend;
+            delegate.amount += spend;
+        }
+
+        (
+            allowance_ctxt.owner.from_arcis(allowance),
+            owner_ctxt.owner.from_arcis(owner),
+            delegate_ctxt.owner.from_arcis(delegate),
+            success.reveal(),
+        )
+    }
+
+    /// Move an encrypted amount from the sender's balance to the recipient's
+    /// Fails without changing either balance if the sender is underfunded or
+    /// would be left below its private minimum balance
+    #[instruction]
+    pub fn transfer(
+        amount_ctxt: Enc<Shared, Amount>,
+        sender_ctxt: Enc<Mxe, Balance>,
+        sender_min_ctxt: Enc<Mxe, Amount>,
+        recipient_ctxt: Enc<Mxe, Balance>,
+    ) -> (Enc<Mxe, Balance>, Enc<Mxe, Balance>, bool) {
+        let amount = amount_ctxt.to_arcis().amount;
+        let sender_min = sender_min_ctxt.to_arcis().amount;
+        let mut sender = sender_ctxt.to_arcis();
+        let mut recipient = recipient_ctxt.to_arcis();
+
+        let (remaining, covered) = checked_sub_encrypted(sender.amount, amount);
+        let success = amount > 0
+            && covered
+            && remaining >= sender_min
+            && recipient.amount <= u64::MAX - amount;
+        if success {
+            sender.amount = remaining;
+            recipient.amount += amount;
+        }
+
+        (
+            sender_ctxt.owner.from_arcis(sender),
+            recipient_ctxt.owner.from_arcis(recipient),
+            success.reveal(),
+        )
+    }
+
+    /// Debit a public escrow from a balance and hand an encrypted amount to
+    /// the dark pool
+    /// `escrow_amount` is what the dark pool escrows for the order, so it is
+    /// what leaves the balance; the handed-off amount sizes the order and
+    /// must fit within it. The amount is re-encrypted to `receiver`, the dark
+    /// pool MXE's key, so only `add_funded_order` can read it. On failure the
+    /// balance is unchanged and the handoff carries zero.
+    #[instruction]
+    pub fn export_to_dark_pool(
+        amount_ctxt: Enc<Shared, Amount>,
+        balance_ctxt: Enc<Mxe, Balance>,
+        min_balance_ctxt: Enc<Mxe, Amount>,
+        receiver: Shared,
+        escrow_amount: u64,
+    ) -> (Enc<Mxe, Balance>, Enc<Shared, Amount>, bool) {
+        let amount = amount_ctxt.to_arcis().amount;
+        let min_balance = min_balance_ctxt.to_arcis().amount;
+        let mut balance = balance_ctxt.to_arcis();
+
+        let (remaining, covered) = checked_sub_encrypted(balance.amount, escrow_amount);
+        let success = amount > 0 && amount <= escrow_amount && covered && remaining >= min_balance;
+        if success {
+            balance.amount = remaining;
+        }
+        let handoff = Amount { amount: if success { amount } else { 0 } };
+
+        (
+            balance_ctxt.owner.from_arcis(balance),
+            receiver.from_arcis(handoff),
+            success.reveal(),
+        )
+    }
+
+    /// Number of balances credited by one `batch_deposit`
+    const BATCH_DEPOSITS: usize = 4;
+
+    /// Deposit public amounts into several private balances at once
+    /// A zero amount marks an unused slot and leaves that balance unchanged.
+    /// Fails without changing any balance if nothing is deposited or any
+    /// balance would overflow. Returns the updated balances, how many slots
+    /// were credited and the total credited.
+    #[instruction]
+    pub fn batch_deposit(
+        amount_0: u64,
+        amount_1: u64,
+        amount_2: u64,
+        amount_3: u64,
+        balance_0_ctxt: Enc<Mxe, Balance>,
+        balance_1_ctxt: Enc<Mxe, Balance>,
+        balance_2_ctxt: Enc<Mxe, Balance>,
+        balance_3_ctxt: Enc<Mxe, Balance>,
+    ) -> (
+        Enc<Mxe, Balance>,
+        Enc<Mxe, Balance>,
+        Enc<Mxe, Balance>,
+        Enc<Mxe, Balance>,
+        u8,
+        u64,
+        bool,
+    ) {
+        let amounts = [amount_0, amount_1, amount_2, amount_3];
+        let mut balances = [
+            balance_0_ctxt.to_arcis(),
+            balance_1_ctxt.to_arcis(),
+            balance_2_ctxt.to_arcis(),
+            balance_3_ctxt.to_arcis(),
+        ];
+
+        let mut count = 0u8;
+        let mut total = 0u128;
+        let mut fits = true;
+        for i in 0..BATCH_DEPOSITS {
+            if amounts[i] > 0 {
+                count += 1;
+            }
+            total += amounts[i] as u128;
+            fits = fits && balances[i].amount <= u64::MAX - amounts[i];
+        }
+
+        let success = count > 0 && fits && total <= u64::MAX as u128;
+        if success {
+            for i in 0..BATCH_DEPOSITS {
+                balances[i].amount += amounts[i];
+            }
+        }
+
+        (
+            balance_0_ctxt.owner.from_arcis(balances[0]),
+            balance_1_ctxt.owner.from_arcis(balances[1]),
+            balance_2_ctxt.owner.from_arcis(balances[2]),
+            balance_3_ctxt.owner.from_arcis(balances[3]),
+            count.reveal(),
+            (total as u64).reveal(),
+            success.reveal(),
+        )
+    }
+
+    /// Number of recipients paid by one `batch_transfer`
+    const BATCH_RECIPIENTS: usize = 3;
+
+    /// Encrypted amount owed to each recipient of a batch transfer
+    pub struct BatchAmounts {
+        pub amounts: [u64; BATCH_RECIPIENTS],
+    }
+
+    /// Pay several recipients from one sender balance (e.g. payroll)
+    /// The sender is debited the total. Fails without changing any balance
+    /// if the total is zero, the sender can't cover it above its private
+    /// minimum balance, or any recipient would overflow.
+    #[instruction]
+    pub fn batch_transfer(
+        amounts_ctxt: Enc<Shared, BatchAmounts>,
+        sender_ctxt: Enc<Mxe, Balance>,
+        sender_min_ctxt: Enc<Mxe, Amount>,
+        recipient_0_ctxt: Enc<Mxe, Balance>,
+        recipient_1_ctxt: Enc<Mxe, Balance>,
+        recipient_2_ctxt: Enc<Mxe, Balance>,
+    ) -> (
+        Enc<Mxe, Balance>,
+        Enc<Mxe, Balance>,
+        Enc<Mxe, Balance>,
+        Enc<Mxe, Balance>,
+        bool,
+    ) {
+        let amounts = amounts_ctxt.to_arcis().amounts;
+        let sender_min = sender_min_ctxt.to_arcis().amount;
+        let mut sender = sender_ctxt.to_arcis();
+        let mut recipients = [
+            recipient_0_ctxt.to_arcis(),
+            recipient_1_ctxt.to_arcis(),
+            recipient_2_ctxt.to_arcis(),
+        ];
+
+        // Sum in u128 so the total can't overflow before it is checked
+        let mut total = 0u128;
+        let mut fits = true;
+        for i in 0..BATCH_RECIPIENTS {
+            total += amounts[i] as u128;
+            fits = fits && recipients[i].amount <= u64::MAX - amounts[i];
+        }
+
+        let covered = total <= sender.amount as u128;
+        let remaining = if covered { sender.amount - total as u64 } else { 0 };
+        let success = total > 0 && covered && remaining >= sender_min && fits;
+        if success {
+            sender.amount = remaining;
+            for i in 0..BATCH_RECIPIENTS {
+                recipients[i].amount += amounts[i];
+            }
+        }
+
+        (
+            sender_ctxt.owner.from_arcis(sender),
+            recipient_0_ctxt.owner.from_arcis(recipients[0]),
+            recipient_1_ctxt.owner.from_arcis(recipients[1]),
+            recipient_2_ctxt.owner.from_arcis(recipients[2]),
+            success.reveal(),
+        )
+    }
+
+    /// Fold three source balances into a destination balance
+    /// Every source is zeroed so its account can be retired. Fails without
+    /// changing anything if the combined balance would overflow.
+    #[instruction]
+    pub fn merge_balances(
+        destination_ctxt: Enc<Mxe, Balance>,
+        first_ctxt: Enc<Mxe, Balance>,
+        second_ctxt: Enc<Mxe, Balance>,
+        third_ctxt: Enc<Mxe, Balance>,
+    ) -> (
+        Enc<Mxe, Balance>,
+        Enc<Mxe, Balance>,
+        Enc<Mxe, Balance>,
+        Enc<Mxe, Balance>,
+        bool,
+    ) {
+        let mut destination = destination_ctxt.to_arcis();
+        let mut first = first_ctxt.to_arcis();
+        let mut second = second_ctxt.to_arcis();
+        let mut third = third_ctxt.to_arcis();
+
+        // Sum in u128 so the overflow check can't itself overflow
+        let total = destination.amount as u128
+            + first.amount as u128
+            + second.amount as u128
+            + third.amount as u128;
+        let success = total <= u64::MAX as u128;
+        if success {
+            destination.amount = total as u64;
+            first.amount = 0;
+            second.amount = 0;
+            third.amount = 0;
+        }
+
+        (
+            destination_ctxt.owner.from_arcis(destination),
+            first_ctxt.owner.from_arcis(first),
+            second_ctxt.owner.from_arcis(second),
+            third_ctxt.owner.from_arcis(third),
+            success.reveal(),
+        )
+    }
+
+    /// Re-encrypt a balance and its private minimum under fresh MXE nonces
+    /// Values are unchanged and nothing is revealed
+    #[instruction]
+    pub fn rotate_balance_nonce(
+        balance_ctxt: Enc<Mxe, Balance>,
+        min_balance_ctxt: Enc<Mxe, Amount>,
+    ) -> (Enc<Mxe, Balance>, Enc<Mxe, Amount>) {
+        let balance = balance_ctxt.to_arcis();
+        let min_balance = min_balance_ctxt.to_arcis();
+        (
+            balance_ctxt.owner.from_arcis(balance),
+            min_balance_ctxt.owner.from_arcis(min_balance),
+        )
+    }
+
+    /// Store a private minimum balance that transfers out may not breach
+    #[instruction]
+    pub fn set_min_balance(amount_ctxt: Enc<Shared, Amount>) -> Enc<Mxe, Amount> {
+        let min_balance = amount_ctxt.to_arcis();
+        Mxe::get().from_arcis(min_balance)
+    }
+
+    /// Store the fixed amount of a recurring payment
+    #[instruction]
+    pub fn schedule_payment(amount_ctxt: Enc<Shared, Amount>) -> Enc<Mxe, Amount> {
+        let amount = amount_ctxt.to_arcis();
+        Mxe::get().from_arcis(amount)
+    }
+
+    /// Debit one installment of a recurring payment from the payer's balance
+    /// and credit it to the recipient's
+    /// Same checks as `transfer`: fails without changing anything on a zero
+    /// installment, or if the payer is underfunded or would be left below its
+    /// private minimum balance
+    #[instruction]
+    pub fn execute_scheduled_payment(
+        amount_ctxt: Enc<Mxe, Amount>,
+        payer_ctxt: Enc<Mxe, Balance>,
+        payer_min_ctxt: Enc<Mxe, Amount>,
+        recipient_ctxt: Enc<Mxe, Balance>,
+    ) -> (Enc<Mxe, Balance>, Enc<Mxe, Balance>, bool) {
+        let amount = amount_ctxt.to_arcis().amount;
+        let payer_min = payer_min_ctxt.to_arcis().amount;
+        let mut payer = payer_ctxt.to_arcis();
+        let mut recipient = recipient_ctxt.to_arcis();
+
+        let (remaining, covered) = checked_sub_encrypted(payer.amount, amount);
+        let success = amount > 0
+            && covered
+            && remaining >= payer_min
+            && recipient.amount <= u64::MAX - amount;
+        if success {
+            payer.amount = remaining;
+            recipient.amount += amount;
+        }
+
+        (
+            payer_ctxt.owner.from_arcis(payer),
+            recipient_ctxt.owner.from_arcis(recipient),
+            success.reveal(),
+        )
+    }
+
+    /// Encrypted vesting grant: the escrowed total and how much of it has
+    /// been released to the beneficiary
+    pub struct VestingState {
+        pub total: u64,
+        pub claimed: u64,
+    }
+
+    /// Move an encrypted amount from the grantor's balance into a vesting escrow
+    /// Fails without changing the balance if the grantor is underfunded or
+    /// would be left below its private minimum balance
+    #[instruction]
+    pub fn create_vesting(
+        amount_ctxt: Enc<Shared, Amount>,
+        grantor_ctxt: Enc<Mxe, Balance>,
+        grantor_min_ctxt: Enc<Mxe, Amount>,
+    ) -> (Enc<Mxe, Balance>, Enc<Mxe, VestingState>, bool) {
+        let amount = amount_ctxt.to_arcis().amount;
+        let grantor_min = grantor_min_ctxt.to_arcis().amount;
+        let mut grantor = grantor_ctxt.to_arcis();
+
+        let (remaining, covered) = checked_sub_encrypted(grantor.amount, amount);
+        let success = amount > 0 && covered && remaining >= grantor_min;
+        if success {
+            grantor.amount = remaining;
+        }
+
+        let vesting = VestingState {
+            total: if success { amount } else { 0 },
+            claimed: 0,
+        };
+
+        (
+            grantor_ctxt.owner.from_arcis(grantor),
+            Mxe::get().from_arcis(vesting),
+            success.reveal(),
+        )
+    }
+
+    /// Credit the vested but unclaimed part of a grant to the beneficiary
+    /// The grant vests linearly over `duration_slots` (see `vested_amount`).
+    /// Only whether the credit succeeded is revealed, never the amounts.
+    #[instruction]
+    pub fn claim_vested(
+        vesting_ctxt: Enc<Mxe, VestingState>,
+        beneficiary_ctxt: Enc<Mxe, Balance>,
+        elapsed_slots: u64,
+        duration_slots: u64,
+    ) -> (Enc<Mxe, VestingState>, Enc<Mxe, Balance>, bool) {
+        let mut vesting = vesting_ctxt.to_arcis();
+        let mut beneficiary = beneficiary_ctxt.to_arcis();
+
+        let vested = vested_amount(vesting.total, elapsed_slots, duration_slots);
+        let claimable = if vested > vesting.claimed { vested - vesting.claimed } else { 0 };
+        let success = beneficiary.amount <= u64::MAX - claimable;
+        if success {
+            beneficiary.amount += claimable;
+            vesting.claimed += claimable;
+        }
+
+        (
+            vesting_ctxt.owner.from_arcis(vesting),
+            beneficiary_ctxt.owner.from_arcis(beneficiary),
+            success.reveal(),
+        )
+    }
+
+    /// Number of balances a single deposit can be split across
+    const SPLIT_TARGETS: usize = 4;
+
+    /// Encrypted split of a deposit, in basis points per target balance
+    pub struct SplitRatios {
+        pub ratios: [u16; SPLIT_TARGETS],
+    }
+
+    /// Credit a public deposit to several balances by private ratios
+    /// Rounding dust goes to the first balance. Fails without changing
+    /// anything unless the ratios sum to exactly 10000 bps.
+    #[instruction]
+    pub fn split_deposit(
+        amount: u64,
+        ratios_ctxt: Enc<Shared, SplitRatios>,
+        balance_0_ctxt: Enc<Mxe, Balance>,
+        balance_1_ctxt: Enc<Mxe, Balance>,
+        balance_2_ctxt: Enc<Mxe, Balance>,
+        balance_3_ctxt: Enc<Mxe, Balance>,
+    ) -> (
+        Enc<Mxe, Balance>,
+        Enc<Mxe, Balance>,
+        Enc<Mxe, Balance>,
+        Enc<Mxe, Balance>,
+        bool,
+    ) {
+        let ratios = ratios_ctxt.to_arcis().ratios;
+        let mut balances = [
+            balance_0_ctxt.to_arcis(),
+            balance_1_ctxt.to_arcis(),
+            balance_2_ctxt.to_arcis(),
+            balance_3_ctxt.to_arcis(),
+        ];
+
+        let mut ratio_sum = 0u64;
+        let mut shares = [0u64; SPLIT_TARGETS];
+        let mut allocated = 0u64;
+        for i in 0..SPLIT_TARGETS {
+            ratio_sum += ratios[i] as u64;
+            shares[i] = ((amount as u128) * (ratios[i] as u128) / 10000) as u64;
+            allocated += shares[i];
+        }
+        shares[0] += amount - allocated;
+
+        let mut fits = true;
+        for i in 0..SPLIT_TARGETS {
+            fits = fits && balances[i].amount <= u64::MAX - shares[i];
+        }
+
+        let success = ratio_sum == 10000 && fits;
+        if success {
+            for i in 0..SPLIT_TARGETS {
+                balances[i].amount += shares[i];
+            }
+        }
+
+        (
+            balance_0_ctxt.owner.from_arcis(balances[0]),
+            balance_1_ctxt.owner.from_arcis(balances[1]),
+            balance_2_ctxt.owner.from_arcis(balances[2]),
+            balance_3_ctxt.owner.from_arcis(balances[3]),
+            success.reveal(),
+        )
+    }
+
+    /// Fixed-point scale of interest factors (1e12 = 1.0)
+    const INTEREST_SCALE: u128 = 1_000_000_000_000;
+
+    /// Multiply an encrypted balance by a public compound interest factor
+    /// The factor is computed on-chain from the public rate and elapsed slots.
+    /// The interest is paid out of the encrypted interest reserve and capped
+    /// at what it holds, so every credited lamport is already in the vault.
+    #[instruction]
+    pub fn accrue_interest(
+        balance_ctxt: Enc<Mxe, Balance>,
+        reserve_ctxt: Enc<Mxe, Balance>,
+        factor: u128,
+    ) -> (Enc<Mxe, Balance>, Enc<Mxe, Balance>) {
+        let mut balance = balance_ctxt.to_arcis();
+        let mut reserve = reserve_ctxt.to_arcis();
+
+        let accrued = (balance.amount as u128) * factor / INTEREST_SCALE;
+        let accrued = if accrued > (u64::MAX as u128) { u64::MAX } else { accrued as u64 };
+        let interest = if accrued > balance.amount { accrued - balance.amount } else { 0 };
+        let interest = if interest > reserve.amount { reserve.amount } else { interest };
+        balance.amount += interest;
+        reserve.amount -= interest;
+
+        (
+            balance_ctxt.owner.from_arcis(balance),
+            reserve_ctxt.owner.from_arcis(reserve),
+        )
+    }
+
+    /// Add a public amount to the encrypted interest reserve
+    /// `fresh` starts from zero, for a reserve that was never funded and so
+    /// holds no ciphertext yet. Fails without changing the reserve on a zero
+    /// amount or overflow.
+    #[instruction]
+    pub fn fund_interest_reserve(
+        amount: u64,
+        reserve_ctxt: Enc<Mxe, Balance>,
+        fresh: bool,
+    ) -> (Enc<Mxe, Balance>, bool) {
+        let mut reserve = reserve_ctxt.to_arcis();
+        if fresh {
+            reserve.amount = 0;
+        }
+
+        let success = amount > 0 && reserve.amount <= u64::MAX - amount;
+        if success {
+            reserve.amount += amount;
+        }
+
+        (reserve_ctxt.owner.from_arcis(reserve), success.reveal())
+    }
+
+    // ============ Mixing Pool Circuits ============
+
+    /// Number of deposit slots in a mixing pool
+    const MIXING_POOL_CAPACITY: usize = 16;
+
+    /// Encrypted note secret chosen by the depositor
+    /// Whoever knows the secret can later withdraw one denomination
+    pub struct MixingNote {
+        pub secret: u128,
+    }
+
+    /// Shared commitment set of a mixing pool, only ever held by the MXE
+    /// A slot is freed when its note is spent, so the pool takes new
+    /// deposits for as long as notes keep being withdrawn
+    pub struct MixingPoolState {
+        pub secrets: [u128; MIXING_POOL_CAPACITY],
+        pub used: [bool; MIXING_POOL_CAPACITY],
+    }
+
+    /// Create the empty encrypted commitment set for a new mixing pool
+    #[instruction]
+    pub fn init_mixing_pool() -> Enc<Mxe, MixingPoolState> {
+        let state = MixingPoolState {
+            secrets: [0u128; MIXING_POOL_CAPACITY],
+            used: [false; MIXING_POOL_CAPACITY],
+        };
+        Mxe::get().from_arcis(state)
+    }
+
+    /// Insert a depositor's note into the first free slot of the pool
+    /// The slot index is never revealed, only whether the insert succeeded
+    #[instruction]
+    pub fn pool_deposit(
+        note_ctxt: Enc<Shared, MixingNote>,
+        pool_ctxt: Enc<Mxe, MixingPoolState>,
+    ) -> (Enc<Mxe, MixingPoolState>, bool) {
+        let note = note_ctxt.to_arcis();
+        let mut pool = pool_ctxt.to_arcis();
+
+        let valid = note.secret != 0;
+        let mut inserted = false;
+        for i in 0..MIXING_POOL_CAPACITY {
+            let take = valid && !inserted && !pool.used[i];
+            if take {
+                pool.secrets[i] = note.secret;
+                pool.used[i] = true;
+                inserted = true;
+            }
+        }
+
+        (pool_ctxt.owner.from_arcis(pool), inserted.reveal())
+    }
+
+    /// Spend a note from the pool without revealing which deposit it came from
+    /// The note's slot is cleared, so the same secret can't be spent twice
+    #[instruction]
+    pub fn pool_withdraw(
+        note_ctxt: Enc<Shared, MixingNote>,
+        pool_ctxt: Enc<Mxe, MixingPoolState>,
+    ) -> (Enc<Mxe, MixingPoolState>, bool) {
+        let note = note_ctxt.to_arcis();
+        let mut pool = pool_ctxt.to_arcis();
+
+        let mut found = false;
+        for i in 0..MIXING_POOL_CAPACITY {
+            let hit = !found
+                && note.secret != 0
+                && pool.used[i]
+                && pool.secrets[i] == note.secret;
+            if hit {
+                pool.secrets[i] = 0;
+                pool.used[i] = false;
+                found = true;
+            }
+        }
+
+        (pool_ctxt.owner.from_arcis(pool), found.reveal())
     }
 
     // ============ Dark Pool Circuits ============
 
+    /// Maximum number of resting orders in the encrypted order book
+    const MAX_ORDERS: usize = 16;
+
+    /// Order type discriminants for `OrderInput::order_type`
+    const ORDER_TYPE_LIMIT: u8 = 0;
+    const ORDER_TYPE_MARKET: u8 = 1;
+
     /// Encrypted order input
+    /// For a market order `price` is the reference price that
+    /// `max_slippage_bps` is measured from
     pub struct OrderInput {
         pub price: u64,
         pub size: u64,
+        pub display_size: u64,     // Iceberg slice size, 0 for a fully visible order
+        pub order_type: u8,        // ORDER_TYPE_LIMIT or ORDER_TYPE_MARKET
+        pub max_slippage_bps: u16, // Market orders only, worst price vs the reference
+    }
+
+    /// A resting order in the encrypted order book
+    /// The owner pubkey is split into two u128 words to keep the state compact
+    pub struct Order {
+        pub active: bool,
+        pub is_buy: bool,
+        pub owner_lo: u128,
+        pub owner_hi: u128,
+        pub order_id: u64,
+        pub price: u64,
+        pub size: u64,         // Size currently available to match
+        pub hidden_size: u64,  // Iceberg reserve not yet displayed
+        pub display_size: u64, // Iceberg slice size, 0 for a fully visible order
+        pub priority: u64,     // Time priority, lower matches first
+        pub start_price: u64,  // Dutch auction start price, unused for limit orders
+        pub end_price: u64,    // Dutch auction floor price
+        pub start_slot: u64,   // Slot the order was added, and the dutch auction started
+        pub duration: u64,     // Dutch auction length in slots, 0 for a limit order
+        pub expiry_slot: u64,  // Last slot the order may match, 0 = never expires
+        pub rested_since: u64, // Slot up to which the maker was credited for this order
+    }
+
+    /// Resting time, in slots, after which an order's matching fee is halved
+    /// (roughly a minute); it drops to a quarter after ten times that
+    const FEE_TIER_SLOTS: u64 = 150;
+
+    /// Matches reported by one `match_orders` run; matching stops once the
+    /// report is full, so every match is settled from its two escrows
+    const MAX_REPORTED_MATCHES: usize = 4;
+
+    /// A side is heavy once its resting volume exceeds the other side's by
+    /// this many percent (150 = 1.5x)
+    const IMBALANCE_RATIO_PCT: u64 = 150;
+
+    /// Share of the resting-adjusted fee, in basis points, added for fills
+    /// on the heavy side and waived for fills on the light side
+    const IMBALANCE_FEE_ADJUST_BPS: u64 = 2000;
+
+    /// Fee tiers revealed by `match_orders`
+    const IMBALANCE_BALANCED: u8 = 0;
+    const IMBALANCE_BID_HEAVY: u8 = 1;
+    const IMBALANCE_ASK_HEAVY: u8 = 2;
+
+    /// A match between a bid and an ask, as revealed by every circuit that
+    /// trades against the book. Both sides are reported together so settlement can pay them
+    /// atomically. An entry with `bid_order_id` 0 is unused.
+    pub struct Fill {
+        pub bid_order_id: u64,
+        pub ask_order_id: u64,
+        pub filled_size: u64,
+        pub price: u64,         // Price the match executed at
+        pub bid_is_maker: bool, // Bid rested before the ask; false if the bid was the taker
+        pub bid_closed: bool,   // The bid left the book with this fill
+        pub ask_closed: bool,
+        pub bid_fee: u64, // Quote atoms the buyer owes on top of the notional
+        pub ask_fee: u64, // Quote atoms withheld from the seller's proceeds
+    }
+
+    /// Maximum number of makers tracked for liquidity incentives
+    const MAX_MAKERS: usize = 16;
+
+    /// Liquidity a maker has provided, as matchable size x slots rested
+    /// An entry with both owner words zero is free
+    pub struct MakerScore {
+        pub owner_lo: u128,
+        pub owner_hi: u128,
+        pub liquidity_slots: u128,
+    }
+
+    /// Encrypted order book state, only ever held by the MXE
+    pub struct OrderBookData {
+        pub orders: [Order; MAX_ORDERS],
+        pub next_priority: u64, // Never reset, so priorities stay ordered across runs
+        pub makers: [MakerScore; MAX_MAKERS],
+    }
+
+    /// Create an empty encrypted order book
+    #[instruction]
+    pub fn init_order_book_state() -> Enc<Mxe, OrderBookData> {
+        let book = OrderBookData {
+            orders: [empty_order(); MAX_ORDERS],
+            next_priority: 0,
+            makers: [empty_maker(); MAX_MAKERS],
+        };
+        Mxe::get().from_arcis(book)
     }
 
     /// Add an order to the dark pool
-    /// Returns order ID and success status
+    /// A limit order rests on the book until matched. A market order never
+    /// rests: it immediately fills against up to MAX_REPORTED_MATCHES of the
+    /// best opposite orders from other owners priced within its slippage
+    /// bound, each at the resting order's price, and whatever is left
+    /// unfilled is dropped. Each fill is reported for settlement. Resting orders
+    /// it fills partially keep their size remainder and time priority, like
+    /// any other partial fill. A market order that fills nothing is rejected.
+    /// A post-only order must be a limit order and is rejected, rather than
+    /// resting and matching later, if its price crosses the best live order
+    /// on the other side of the book. Any order whose `escrow_amount` can't
+    /// cover it in full is rejected like any other invalid order.
+    /// With a nonzero `random_priority_window_slots` a resting limit order's
+    /// time priority is randomized among orders entering in the same window
+    /// (see `entry_priority`); Dutch and TWAP orders keep arrival order.
+    /// A market order's fills pay fees as in `match_orders`: the resting
+    /// order at `maker_fee_rate`, the market order at `taker_fee_rate`.
+    /// Returns the updated book, order ID,
+    /// success status, whether an auction is ready, whether the order was
+    /// rejected only because the book is full, whether it rested, how many
+    /// resting orders a market order completely filled, whether it was
+    /// rejected as a duplicate of the owner's own resting order and a market
+    /// order's fills.
     #[instruction]
     pub fn add_order(
         input_ctxt: Enc<Shared, OrderInput>,
+        book_ctxt: Enc<Mxe, OrderBookData>,
         is_buy: bool,
-        owner: [u8; 32],
-    ) -> (u64, bool) {
+        post_only: bool,
+        owner_lo: u128,
+        owner_hi: u128,
+        tick_size: u64,
+        min_order_size: u64,
+        max_order_size: u64,
+        auction_volume_threshold: u64, // 0 disables the auction trigger
+        expiry_slot: u64,              // 0 for an order that never expires
+        escrow_amount: u64,            // Base for a sell, quote for a buy
+        random_priority_window_slots: u64, // 0 for strict time priority
+        maker_fee_rate: u16,
+        taker_fee_rate: u16,
+        current_slot: u64,
+    ) -> (Enc<Mxe, OrderBookData>, u64, bool, bool, bool, bool, u32, bool, [Fill; MAX_REPORTED_MATCHES]) {
         let input = input_ctxt.to_arcis();
-        
-        // Validate order
-        let valid = input.price > 0 && input.size > 0;
-        
-        if !valid {
-            return (0u64.reveal(), false.reveal());
-        }
+        let mut book = book_ctxt.to_arcis();
+        accrue_liquidity(&mut book, current_slot);
+        reprice_dutch_orders(&mut book, current_slot);
 
-        // Generate order ID (in production, use proper ID generation)
-        let order_id = ArcisRNG::u64();
-        
-        // Store order in encrypted state (simplified)
-        // In production, maintain encrypted order book state
-        
-        (order_id.reveal(), true.reveal())
-    }
+        let is_market = input.order_type == ORDER_TYPE_MARKET;
+        let is_limit = input.order_type == ORDER_TYPE_LIMIT;
 
-    /// Match orders in the dark pool
-    /// Finds overlapping buy/sell orders and executes trades
-    #[instruction]
-    pub fn match_orders() -> (u32, u64) {
-        // In production, this would:
-        // 1. Load encrypted order book state
-        // 2. Find matching buy/sell orders (buy_price >= sell_price)
-        // 3. Execute trades at mid-price
-        // 4. Update order book state
-        // 5. Return match count and total volume
-        
-        // Simplified version for demo
-        let matches_count = 0u32;
-        let total_volume = 0u64;
+        // Validate order against the book's tick and size params. A market
+        // order's reference price needn't sit on the tick grid, and it can't
+        // be an iceberg since it never rests.
+        let valid = input.price > 0
+            && (is_market || input.price % tick_size == 0)
+            && input.size >= min_order_size
+            && input.size <= max_order_size
+            && input.display_size <= input.size
+            && (is_limit || (is_market && input.display_size == 0 && input.max_slippage_bps <= 10000))
+            && (expiry_slot == 0 || expiry_slot >= current_slot);
+
+        // A post-only order may only add liquidity, so one that would cross
+        // the opposite side on entry fails like any other invalid order
+        let (_, best_opposite, _, opposite_found) = best_order(&book, !is_buy, current_slot);
+        let crosses = opposite_found
+            && if is_buy { input.price >= best_opposite } else { input.price <= best_opposite };
+        let valid = valid && !(post_only && (is_market || crosses));
+
+        // Worst price a market order accepts
+        let slippage_bps = if input.max_slippage_bps > 10000 { 10000 } else { input.max_slippage_bps };
+        let slippage = (input.price as u128) * (slippage_bps as u128) / 10000;
+        let worst_price = if is_buy {
+            ((input.price as u128) + slippage) as u64
+        } else {
+            ((input.price as u128) - slippage) as u64
+        };
+
+        // The escrow must cover the order at its worst: the full size in base
+        // for a sell, or the full size at the worst accepted price for a buy
+        let bound_price = if is_market { worst_price } else { input.price };
+        let escrow_needed =
+            if is_buy { (input.size as u128) * (bound_price as u128) } else { input.size as u128 };
+        let valid = valid && escrow_needed <= escrow_amount as u128;
+
+        // Drawn up front so a market order's fills can name it
+        let order_id = next_order_id(&book);
+
+        let mut remaining = if valid && is_market { input.size } else { 0 };
+        let mut filled = 0u64;
+        let mut closed_orders = 0u32;
+        let mut market_fills = [empty_fill(); MAX_REPORTED_MATCHES];
+        let tier = imbalance_tier(resting_volume(&book, true), resting_volume(&book, false));
+        for round in 0..MAX_REPORTED_MATCHES {
+            let (idx, price, size, _, _, found) =
+                best_order_excluding(&book, !is_buy, current_slot, true, owner_lo, owner_hi);
+            let within = if is_buy { price <= worst_price } else { price >= worst_price };
+            let fills = found && remaining > 0 && within;
+            let trade_size = calculate_trade_size(size, remaining);
+
+            let mut maker_id = 0u64;
+            let mut maker_closed = false;
+            let mut maker_fee = 0u64;
+            for i in 0..MAX_ORDERS {
+                if fills && (i as u64) == idx {
+                    maker_id = book.orders[i].order_id;
+                    let rested = rested_slots(&book.orders[i], current_slot);
+                    maker_fee = fill_fee(trade_size, price, maker_fee_rate, rested, tier, !is_buy);
+                    let (order, next_priority, closed) =
+                        fill_order(book.orders[i], trade_size, book.next_priority);
+                    book.orders[i] = order;
+                    book.next_priority = next_priority;
+                    maker_closed = closed;
+                    if closed {
+                        closed_orders += 1;
+                    }
+                }
+            }
+
+            if fills {
+                remaining -= trade_size;
+                filled += trade_size;
+                // The resting side made the liquidity the market order took
+                let taker_fee = fill_fee(trade_size, price, taker_fee_rate, 0, tier, is_buy);
+                market_fills[round] = Fill {
+                    bid_order_id: if is_buy { order_id } else { maker_id },
+                    ask_order_id: if is_buy { maker_id } else { order_id },
+                    filled_size: trade_size,
+                    price,
+                    bid_is_maker: !is_buy,
+                    bid_closed: !is_buy && maker_closed,
+                    ask_closed: is_buy && maker_closed,
+                    bid_fee: if is_buy { taker_fee } else { maker_fee },
+                    ask_fee: if is_buy { maker_fee } else { taker_fee },
+                };
+            }
+        }
+
+        // A limit order matching one of the trader's own live limit orders on
+        // side, price and remaining size is taken as an accidental resubmission
+        let mut duplicate = false;
+        for i in 0..MAX_ORDERS {
+            let order = book.orders[i];
+            let same = is_live(&order, current_slot)
+                && order.duration == 0
+                && order.owner_lo == owner_lo
+                && order.owner_hi == owner_hi
+                && order.is_buy == is_buy
+                && order.price == input.price
+                && order.size + order.hidden_size == input.size;
+            duplicate = duplicate || same;
+        }
+        let duplicate = valid && is_limit && duplicate;
+
+        // Icebergs only show one slice at a time
+        let visible = if input.display_size > 0 { input.display_size } else { input.size };
+        let (priority, next_priority) = entry_priority(
+            book.next_priority,
+            owner_lo,
+            current_slot,
+            random_priority_window_slots,
+        );
+
+        let mut inserted = false;
+        for i in 0..MAX_ORDERS {
+            let take = valid && is_limit && !duplicate && !inserted && !book.orders[i].active;
+            if take {
+                book.orders[i] = Order {
+                    active: true,
+                    is_buy,
+                    owner_lo,
+                    owner_hi,
+                    order_id,
+                    price: input.price,
+                    size: visible,
+                    hidden_size: input.size - visible,
+                    display_size: input.display_size,
+                    priority,
+                    start_price: 0,
+                    end_price: 0,
+                    start_slot: current_slot,
+                    duration: 0,
+                    expiry_slot,
+                    rested_since: current_slot,
+                };
+                inserted = true;
+            }
+        }
+        if inserted {
+            book.next_priority = next_priority;
+        }
+
+        let executed = is_market && filled > 0;
+        let success = inserted || executed;
+        let revealed_id = if success { order_id } else { 0 };
+        // A valid limit order that found no free slot means the book is full
+        let book_full = valid && is_limit && !duplicate && !inserted;
+
+        // Only whether the threshold was crossed is revealed, not the volume
+        let (_, crossable_volume) = book_clearing_price(&book, current_slot);
+        let auction_ready = auction_volume_threshold > 0
+            && crossable_volume >= auction_volume_threshold;
         
-        (matches_count.reveal(), total_volume.reveal())
+        (
+            book_ctxt.owner.from_arcis(book),
+            revealed_id.reveal(),
+            success.reveal(),
+            auction_ready.reveal(),
+            book_full.reveal(),
+            inserted.reveal(),
+            closed_orders.reveal(),
+            duplicate.reveal(),
+            market_fills.reveal(),
+        )
+    }
+
+    /// Encrypted price of an order funded from a private_pay balance
+    pub struct FundedOrderInput {
+        pub price: u64,
+    }
+
+    /// Add a limit order funded by a private_pay handoff
+    /// `funding_ctxt` is the amount `export_to_dark_pool` debited, encrypted
+    /// by the private_pay MXE to this one. It is the order's size for a sell,
+    /// and buys `amount / price` for a buy. The order always rests and is
+    /// never an iceberg; it's rejected, like an invalid `add_order`, if that
+    /// size falls outside the book's limits. Only the order ID, success and
+    /// whether the book was full are revealed.
+    #[instruction]
+    pub fn add_funded_order(
+        input_ctxt: Enc<Shared, FundedOrderInput>,
+        funding_ctxt: Enc<Shared, Amount>,
+        book_ctxt: Enc<Mxe, OrderBookData>,
+        is_buy: bool,
+        owner_lo: u128,
+        owner_hi: u128,
+        tick_size: u64,
+        min_order_size: u64,
+        max_order_size: u64,
+        expiry_slot: u64, // 0 for an order that never expires
+        random_priority_window_slots: u64,
+        current_slot: u64,
+    ) -> (Enc<Mxe, OrderBookData>, u64, bool, bool) {
+        let price = input_ctxt.to_arcis().price;
+        let funded = funding_ctxt.to_arcis().amount;
+        let mut book = book_ctxt.to_arcis();
+        accrue_liquidity(&mut book, current_slot);
+        reprice_dutch_orders(&mut book, current_slot);
+
+        let size = if is_buy {
+            if price > 0 { funded / price } else { 0 }
+        } else {
+            funded
+        };
+        let valid = price > 0
+            && price % tick_size == 0
+            && size >= min_order_size
+            && size <= max_order_size
+            && (expiry_slot == 0 || expiry_slot >= current_slot);
+
+        let order_id = next_order_id(&book);
+        let (priority, next_priority) = entry_priority(
+            book.next_priority,
+            owner_lo,
+            current_slot,
+            random_priority_window_slots,
+        );
+
+        let mut inserted = false;
+        for i in 0..MAX_ORDERS {
+            let take = valid && !inserted && !book.orders[i].active;
+            if take {
+                book.orders[i] = Order {
+                    active: true,
+                    is_buy,
+                    owner_lo,
+                    owner_hi,
+                    order_id,
+                    price,
+                    size,
+                    hidden_size: 0,
+                    display_size: 0,
+                    priority,
+                    start_price: 0,
+                    end_price: 0,
+                    start_slot: current_slot,
+                    duration: 0,
+                    expiry_slot,
+                    rested_since: current_slot,
+                };
+                inserted = true;
+            }
+        }
+        if inserted {
+            book.next_priority = next_priority;
+        }
+
+        let revealed_id = if inserted { order_id } else { 0 };
+        let book_full = valid && !inserted;
+
+        (
+            book_ctxt.owner.from_arcis(book),
+            revealed_id.reveal(),
+            inserted.reveal(),
+            book_full.reveal(),
+        )
+    }
+
+    /// Encrypted dutch auction sell order input
+    /// The price declines linearly from start_price to end_price over
+    /// duration slots and then rests at end_price
+    pub struct DutchOrderInput {
+        pub start_price: u64,
+        pub end_price: u64,
+        pub size: u64,
+        pub duration: u64,
+    }
+
+    /// Add a dutch auction ask to the encrypted order book
+    /// The whole price schedule stays encrypted; only the order ID and
+    /// success status are revealed. An order larger than the base
+    /// `escrow_amount` deposited for it is rejected.
+    #[instruction]
+    pub fn add_dutch_order(
+        input_ctxt: Enc<Shared, DutchOrderInput>,
+        book_ctxt: Enc<Mxe, OrderBookData>,
+        owner_lo: u128,
+        owner_hi: u128,
+        start_slot: u64,
+        tick_size: u64,
+        min_order_size: u64,
+        max_order_size: u64,
+        escrow_amount: u64,
+    ) -> (Enc<Mxe, OrderBookData>, u64, bool, bool) {
+        let input = input_ctxt.to_arcis();
+        let mut book = book_ctxt.to_arcis();
+
+        // Both ends of the schedule must sit on the tick grid, and the
+        // price may only decline
+        let valid = input.end_price > 0
+            && input.start_price >= input.end_price
+            && input.start_price % tick_size == 0
+            && input.end_price % tick_size == 0
+            && input.duration > 0
+            && input.size >= min_order_size
+            && input.size <= max_order_size
+            && input.size <= escrow_amount;
+
+        let order_id = next_order_id(&book);
+
+        let mut inserted = false;
+        for i in 0..MAX_ORDERS {
+            let take = valid && !inserted && !book.orders[i].active;
+            if take {
+                book.orders[i] = Order {
+                    active: true,
+                    is_buy: false,
+                    owner_lo,
+                    owner_hi,
+                    order_id,
+                    price: input.start_price,
+                    size: input.size,
+                    hidden_size: 0,
+                    display_size: 0,
+                    priority: book.next_priority,
+                    start_price: input.start_price,
+                    end_price: input.end_price,
+                    start_slot,
+                    duration: input.duration,
+                    expiry_slot: 0,
+                    rested_since: start_slot,
+                };
+                inserted = true;
+            }
+        }
+        if inserted {
+            book.next_priority += 1;
+        }
+
+        let revealed_id = if inserted { order_id } else { 0 };
+        let book_full = valid && !inserted;
+
+        (
+            book_ctxt.owner.from_arcis(book),
+            revealed_id.reveal(),
+            inserted.reveal(),
+            book_full.reveal(),
+        )
+    }
+
+    /// Encrypted parent order for TWAP execution
+    pub struct TwapInput {
+        pub price: u64, // Limit price of every slice
+        pub total_size: u64,
+        pub slice_size: u64,
+    }
+
+    /// Unreleased remainder of a TWAP parent order
+    pub struct TwapState {
+        pub price: u64,
+        pub remaining: u64,
+        pub slice_size: u64,
+    }
+
+    /// Encrypt a TWAP parent order to the MXE for release slice by slice
+    /// Slices rest as limit orders, so the price must sit on the tick grid
+    /// and a full slice must be within the book's size bounds. Each slice is
+    /// escrowed with `slice_escrow` (base for a sell, quote for a buy) out of
+    /// a reserve good for `max_slices` slices, so a full slice must fit in
+    /// `slice_escrow` and `max_slices` slices must cover the total. Returns
+    /// the state and whether the order was valid.
+    #[instruction]
+    pub fn schedule_twap_order(
+        input_ctxt: Enc<Shared, TwapInput>,
+        is_buy: bool,
+        tick_size: u64,
+        min_order_size: u64,
+        max_order_size: u64,
+        slice_escrow: u64,
+        max_slices: u64,
+    ) -> (Enc<Mxe, TwapState>, bool) {
+        let input = input_ctxt.to_arcis();
+
+        let slice_cost = if is_buy {
+            (input.slice_size as u128) * (input.price as u128)
+        } else {
+            input.slice_size as u128
+        };
+        let valid = input.price > 0
+            && input.price % tick_size == 0
+            && input.slice_size >= min_order_size
+            && input.slice_size <= max_order_size
+            && input.total_size >= input.slice_size
+            && slice_cost <= slice_escrow as u128
+            && (max_slices as u128) * (input.slice_size as u128) >= input.total_size as u128;
+
+        let state = TwapState {
+            price: input.price,
+            remaining: if valid { input.total_size } else { 0 },
+            slice_size: input.slice_size,
+        };
+        (Mxe::get().from_arcis(state), valid.reveal())
+    }
+
+    /// Rest the next slice of a TWAP order on the book as a limit order
+    /// The slice is the smaller of the slice size and what remains, so the
+    /// final slice may fall below the book's minimum order size. Returns the
+    /// updated TWAP state and book, the slice's order ID, whether it was
+    /// placed and whether the parent order is now exhausted.
+    #[instruction]
+    pub fn release_twap_slice(
+        twap_ctxt: Enc<Mxe, TwapState>,
+        book_ctxt: Enc<Mxe, OrderBookData>,
+        is_buy: bool,
+        owner_lo: u128,
+        owner_hi: u128,
+        current_slot: u64,
+    ) -> (Enc<Mxe, TwapState>, Enc<Mxe, OrderBookData>, u64, bool, bool) {
+        let mut twap = twap_ctxt.to_arcis();
+        let mut book = book_ctxt.to_arcis();
+        accrue_liquidity(&mut book, current_slot);
+
+        let slice = if twap.remaining < twap.slice_size { twap.remaining } else { twap.slice_size };
+        let order_id = next_order_id(&book);
+
+        let mut inserted = false;
+        for i in 0..MAX_ORDERS {
+            let take = slice > 0 && !inserted && !book.orders[i].active;
+            if take {
+                book.orders[i] = Order {
+                    active: true,
+                    is_buy,
+                    owner_lo,
+                    owner_hi,
+                    order_id,
+                    price: twap.price,
+                    size: slice,
+                    hidden_size: 0,
+                    display_size: 0,
+                    priority: book.next_priority,
+                    start_price: 0,
+                    end_price: 0,
+                    start_slot: current_slot,
+                    duration: 0,
+                    expiry_slot: 0,
+                    rested_since: current_slot,
+                };
+                inserted = true;
+            }
+        }
+        if inserted {
+            book.next_priority += 1;
+            twap.remaining -= slice;
+        }
+
+        let exhausted = twap.remaining == 0;
+        let revealed_id = if inserted { order_id } else { 0 };
+
+        (
+            twap_ctxt.owner.from_arcis(twap),
+            book_ctxt.owner.from_arcis(book),
+            revealed_id.reveal(),
+            inserted.reveal(),
+            exhausted.reveal(),
+        )
+    }
+
+    /// Number of price levels per side in a batch auction
+    const AUCTION_LEVELS: usize = 8;
+
+    /// Sealed batch of bid and ask levels for a uniform-price auction
+    /// Bids are sorted by descending price and asks by ascending price;
+    /// unused levels have size 0
+    pub struct AuctionBatch {
+        pub bid_prices: [u64; AUCTION_LEVELS],
+        pub bid_sizes: [u64; AUCTION_LEVELS],
+        pub ask_prices: [u64; AUCTION_LEVELS],
+        pub ask_sizes: [u64; AUCTION_LEVELS],
+    }
+
+    /// Compute the uniform clearing price of a sealed batch
+    /// Only the clearing price and matched volume are revealed
+    #[instruction]
+    pub fn compute_clearing_price(
+        batch_ctxt: Enc<Shared, AuctionBatch>,
+    ) -> (u64, u64) {
+        let batch = batch_ctxt.to_arcis();
+        let (price, volume) = find_clearing_price(
+            batch.bid_prices,
+            batch.bid_sizes,
+            batch.ask_prices,
+            batch.ask_sizes,
+        );
+        (price.reveal(), volume.reveal())
+    }
+
+    /// Match orders in the dark pool
+    /// Repeatedly crosses the best bid against the best ask in price-time
+    /// priority, executing each trade at the mid-price. An owner's bid never
+    /// trades against the same owner's ask: the best bid is crossed against
+    /// the best ask from another owner, falling back to the best ask against
+    /// the best bid from another owner. Self-crossing orders are left resting.
+    /// At most `max_matches`, and never more than MAX_REPORTED_MATCHES,
+    /// pairs trade per call, so each match is reported and can be settled.
+    /// Returns the updated book, the number of matches, the matched volume,
+    /// how many orders were completely filled, how many crossing pairs were
+    /// left resting because they share an owner, and the total fees charged.
+    /// Each side of a fill pays its rate on its notional: `maker_fee_rate` for
+    /// the side with the older time priority and `taker_fee_rate` for the
+    /// other, as each match reports in `bid_is_maker`. That rate is discounted
+    /// by how long that order has rested (see `resting_fee_bps`), then raised on the
+    /// heavy side of an imbalanced book and lowered on the light side (see
+    /// `imbalance_fee_bps`). Only the applied imbalance tier is revealed.
+    /// Every run searches the whole book, so a sweep split across runs by
+    /// `max_matches` still fills in price-time priority; the cap only bounds
+    /// the work done per run.
+    #[instruction]
+    pub fn match_orders(
+        book_ctxt: Enc<Mxe, OrderBookData>,
+        reference_price: u64,
+        price_band_bps: u16,
+        current_slot: u64,
+        maker_fee_rate: u16,
+        taker_fee_rate: u16,
+        max_matches: u32,
+    ) -> (Enc<Mxe, OrderBookData>, u32, u64, u32, bool, bool, u32, u64, [Fill; MAX_REPORTED_MATCHES], u8) {
+        let mut book = book_ctxt.to_arcis();
+        accrue_liquidity(&mut book, current_slot);
+        reprice_dutch_orders(&mut book, current_slot);
+        let mut matches_count = 0u32;
+        let mut total_volume = 0u64;
+        let mut closed_orders = 0u32;
+        let mut tripped = false;
+        let mut fees_charged = 0u64;
+        let mut fills = [empty_fill(); MAX_REPORTED_MATCHES];
+        let mut reported = 0usize;
+
+        let bids_before = resting_volume(&book, true);
+        let asks_before = resting_volume(&book, false);
+        let tier = imbalance_tier(bids_before, asks_before);
+
+        // Each round fills one pair, and rounds stop once the fill report is
+        // full so every fill is reported for settlement; a further call
+        // picks up whatever still crosses
+        for _round in 0..MAX_REPORTED_MATCHES {
+            let (top_bid_idx, top_bid_price, top_bid_size, bid_lo, bid_hi, has_top_bid) =
+                best_order_excluding(&book, true, current_slot, false, 0, 0);
+            let (other_ask_idx, other_ask_price, other_ask_size, _, _, has_other_ask) =
+                best_order_excluding(&book, false, current_slot, true, bid_lo, bid_hi);
+            let (top_ask_idx, top_ask_price, top_ask_size, ask_lo, ask_hi, has_top_ask) =
+                best_order_excluding(&book, false, current_slot, false, 0, 0);
+            let (other_bid_idx, other_bid_price, other_bid_size, _, _, has_other_bid) =
+                best_order_excluding(&book, true, current_slot, true, ask_lo, ask_hi);
+
+            // Prefer the best bid; only if it can't cross another owner's ask
+            // try the best ask against another owner's bid
+            let bid_first = has_top_bid && has_other_ask && top_bid_price >= other_ask_price;
+            let ask_first = has_other_bid && has_top_ask && other_bid_price >= top_ask_price;
+            let bid_idx = if bid_first { top_bid_idx } else { other_bid_idx };
+            let bid_price = if bid_first { top_bid_price } else { other_bid_price };
+            let bid_size = if bid_first { top_bid_size } else { other_bid_size };
+            let ask_idx = if bid_first { other_ask_idx } else { top_ask_idx };
+            let ask_price = if bid_first { other_ask_price } else { top_ask_price };
+            let ask_size = if bid_first { other_ask_size } else { top_ask_size };
+
+            let crosses = bid_first || ask_first;
+            let trade_size = calculate_trade_size(bid_size, ask_size);
+
+            // Halt matching for the rest of the call once a cross would
+            // trade outside the configured band around the reference price
+            let mid_price = calculate_mid_price(bid_price, ask_price);
+            let out_of_band = outside_price_band(mid_price, reference_price, price_band_bps);
+            tripped = tripped || (crosses && out_of_band);
+            let crosses = crosses && !tripped && matches_count < max_matches;
+
+            // The side with the older time priority was resting when the
+            // other arrived, so it made the liquidity the other took
+            let mut bid_priority = 0u64;
+            let mut ask_priority = 0u64;
+            for i in 0..MAX_ORDERS {
+                if (i as u64) == bid_idx {
+                    bid_priority = book.orders[i].priority;
+                }
+                if (i as u64) == ask_idx {
+                    ask_priority = book.orders[i].priority;
+                }
+            }
+            let bid_is_maker = bid_priority < ask_priority;
+
+            let mut bid_id = 0u64;
+            let mut ask_id = 0u64;
+            let mut bid_closed = false;
+            let mut ask_closed = false;
+            let mut bid_fee = 0u64;
+            let mut ask_fee = 0u64;
+            for i in 0..MAX_ORDERS {
+                let is_bid = crosses && (i as u64) == bid_idx;
+                let is_ask = crosses && (i as u64) == ask_idx;
+                if is_bid {
+                    bid_id = book.orders[i].order_id;
+                }
+                if is_ask {
+                    ask_id = book.orders[i].order_id;
+                }
+                if is_bid || is_ask {
+                    let is_maker = if is_bid { bid_is_maker } else { !bid_is_maker };
+                    let fee_rate = if is_maker { maker_fee_rate } else { taker_fee_rate };
+                    let rested = rested_slots(&book.orders[i], current_slot);
+                    let fee = fill_fee(trade_size, mid_price, fee_rate, rested, tier, is_bid);
+                    fees_charged += fee;
+                    if is_bid {
+                        bid_fee = fee;
+                    }
+                    if is_ask {
+                        ask_fee = fee;
+                    }
+
+                    let (order, next_priority, closed) =
+                        fill_order(book.orders[i], trade_size, book.next_priority);
+                    book.orders[i] = order;
+                    book.next_priority = next_priority;
+                    if closed {
+                        closed_orders += 1;
+                    }
+                    if is_bid {
+                        bid_closed = closed;
+                    }
+                    if is_ask {
+                        ask_closed = closed;
+                    }
+                }
+            }
+
+            for k in 0..MAX_REPORTED_MATCHES {
+                if crosses && k == reported {
+                    fills[k] = Fill {
+                        bid_order_id: bid_id,
+                        ask_order_id: ask_id,
+                        filled_size: trade_size,
+                        price: mid_price,
+                        bid_is_maker,
+                        bid_closed,
+                        ask_closed,
+                        bid_fee,
+                        ask_fee,
+                    };
+                }
+            }
+
+            if crosses {
+                matches_count += 1;
+                total_volume += trade_size;
+                reported += 1;
+            }
+        }
+
+        // Base leaving the bid side must equal base leaving the ask side and
+        // the reported volume, i.e. every fill has exactly one counterparty
+        let bids_filled = bids_before - resting_volume(&book, true);
+        let asks_filled = asks_before - resting_volume(&book, false);
+        let conserved = bids_filled == total_volume && asks_filled == total_volume;
+
+        // Crossing pairs still resting because both sides share an owner
+        let mut self_trade_prevented = 0u32;
+        for i in 0..MAX_ORDERS {
+            for j in 0..MAX_ORDERS {
+                let bid = book.orders[i];
+                let ask = book.orders[j];
+                let self_cross = is_live(&bid, current_slot)
+                    && is_live(&ask, current_slot)
+                    && bid.is_buy
+                    && !ask.is_buy
+                    && bid.size > 0
+                    && ask.size > 0
+                    && bid.owner_lo == ask.owner_lo
+                    && bid.owner_hi == ask.owner_hi
+                    && bid.price >= ask.price;
+                if self_cross {
+                    self_trade_prevented += 1;
+                }
+            }
+        }
+
+        (
+            book_ctxt.owner.from_arcis(book),
+            matches_count.reveal(),
+            total_volume.reveal(),
+            closed_orders.reveal(),
+            tripped.reveal(),
+            conserved.reveal(),
+            self_trade_prevented.reveal(),
+            fees_charged.reveal(),
+            fills.reveal(),
+            tier.reveal(),
+        )
+    }
+
+    /// Clear the resting book in a uniform-price batch auction
+    /// All crossing orders trade at the single clearing price that maximizes
+    /// volume, filled in price-time priority. Returns the updated book, the
+    /// clearing price, matched volume, number of fills and closed orders, the
+    /// fills themselves and whether volume was left uncleared. At most
+    /// MAX_REPORTED_MATCHES pairs trade per run so each is reported for
+    /// settlement; a capped run leaves the auction to be run again, which
+    /// clears the rest at a price recomputed from the remaining book.
+    /// Fills pay fees as in `match_orders`, the side with the older time
+    /// priority at `maker_fee_rate` and the other at `taker_fee_rate`.
+    #[instruction]
+    pub fn run_batch_auction(
+        book_ctxt: Enc<Mxe, OrderBookData>,
+        maker_fee_rate: u16,
+        taker_fee_rate: u16,
+        current_slot: u64,
+    ) -> (Enc<Mxe, OrderBookData>, u64, u64, u32, u32, [Fill; MAX_REPORTED_MATCHES], bool) {
+        let mut book = book_ctxt.to_arcis();
+        accrue_liquidity(&mut book, current_slot);
+        reprice_dutch_orders(&mut book, current_slot);
+        let (clearing_price, clearing_volume) = book_clearing_price(&book, current_slot);
+        let tier = imbalance_tier(resting_volume(&book, true), resting_volume(&book, false));
+
+        let mut remaining = clearing_volume;
+        let mut matches_count = 0u32;
+        let mut closed_orders = 0u32;
+        let mut fills = [empty_fill(); MAX_REPORTED_MATCHES];
+
+        for round in 0..MAX_REPORTED_MATCHES {
+            let (bid_idx, bid_price, bid_size, has_bid) = best_order(&book, true, current_slot);
+            let (ask_idx, ask_price, ask_size, has_ask) = best_order(&book, false, current_slot);
+
+            let crosses = has_bid
+                && has_ask
+                && remaining > 0
+                && bid_price >= clearing_price
+                && ask_price <= clearing_price;
+            let trade_size = calculate_trade_size(
+                calculate_trade_size(bid_size, ask_size),
+                remaining,
+            );
+
+            // Both sides trade at the uniform price; as in `match_orders` the
+            // one that rested first made the liquidity
+            let mut bid_priority = 0u64;
+            let mut ask_priority = 0u64;
+            for i in 0..MAX_ORDERS {
+                if (i as u64) == bid_idx {
+                    bid_priority = book.orders[i].priority;
+                }
+                if (i as u64) == ask_idx {
+                    ask_priority = book.orders[i].priority;
+                }
+            }
+            let mut fill = empty_fill();
+            fill.bid_is_maker = bid_priority < ask_priority;
+            for i in 0..MAX_ORDERS {
+                let is_bid = crosses && (i as u64) == bid_idx;
+                let is_ask = crosses && (i as u64) == ask_idx;
+                if is_bid {
+                    fill.bid_order_id = book.orders[i].order_id;
+                }
+                if is_ask {
+                    fill.ask_order_id = book.orders[i].order_id;
+                }
+                if is_bid || is_ask {
+                    let is_maker = if is_bid { fill.bid_is_maker } else { !fill.bid_is_maker };
+                    let fee_rate = if is_maker { maker_fee_rate } else { taker_fee_rate };
+                    let rested = rested_slots(&book.orders[i], current_slot);
+                    let fee = fill_fee(trade_size, clearing_price, fee_rate, rested, tier, is_bid);
+                    if is_bid {
+                        fill.bid_fee = fee;
+                    }
+                    if is_ask {
+                        fill.ask_fee = fee;
+                    }
+
+                    let (order, next_priority, closed) =
+                        fill_order(book.orders[i], trade_size, book.next_priority);
+                    book.orders[i] = order;
+                    book.next_priority = next_priority;
+                    if closed {
+                        closed_orders += 1;
+                    }
+                    if is_bid {
+                        fill.bid_closed = closed;
+                    }
+                    if is_ask {
+                        fill.ask_closed = closed;
+                    }
+                }
+            }
+
+            if crosses {
+                remaining -= trade_size;
+                matches_count += 1;
+                fill.filled_size = trade_size;
+                fill.price = clearing_price;
+                fills[round] = fill;
+            }
+        }
+
+        let capped = remaining > 0;
+
+        (
+            book_ctxt.owner.from_arcis(book),
+            clearing_price.reveal(),
+            (clearing_volume - remaining).reveal(),
+            matches_count.reveal(),
+            closed_orders.reveal(),
+            fills.reveal(),
+            capped.reveal(),
+        )
     }
 
     /// Cancel an order from the dark pool
+    /// Clears the slot holding `order_id` if it is still resting and belongs
+    /// to the caller. Returns the updated book, order ID and success status.
     #[instruction]
     pub fn cancel_order(
+        book_ctxt: Enc<Mxe, OrderBookData>,
         order_id: u64,
-        owner: [u8; 32],
-    ) -> (u64, bool) {
-        // In production, this would:
-        // 1. Find the order in encrypted state
-        // 2. Verify ownership
-        // 3. Remove from order book
-        // 4. Return order ID and success
-        
-        // Simplified version
-        let success = order_id > 0;
-        
-        (order_id.reveal(), success.reveal())
+        owner_lo: u128,
+        owner_hi: u128,
+        current_slot: u64,
+    ) -> (Enc<Mxe, OrderBookData>, u64, bool) {
+        let mut book = book_ctxt.to_arcis();
+        accrue_liquidity(&mut book, current_slot);
+
+        let mut removed = false;
+        for i in 0..MAX_ORDERS {
+            let order = book.orders[i];
+            let hit = order_id > 0
+                && order.active
+                && order.order_id == order_id
+                && order.owner_lo == owner_lo
+                && order.owner_hi == owner_hi;
+            if hit {
+                book.orders[i] = empty_order();
+                removed = true;
+            }
+        }
+
+        (
+            book_ctxt.owner.from_arcis(book),
+            order_id.reveal(),
+            removed.reveal(),
+        )
+    }
+
+    /// Cancel every order the caller has on the book
+    /// Ownership is checked inside MPC against each order's encrypted owner.
+    /// Returns the updated book, the number of orders removed and their IDs
+    /// (0 in unused entries), so their escrows can be released. The IDs are
+    /// already public through the orders' escrows.
+    #[instruction]
+    pub fn cancel_all_orders(
+        book_ctxt: Enc<Mxe, OrderBookData>,
+        owner_lo: u128,
+        owner_hi: u128,
+        current_slot: u64,
+    ) -> (Enc<Mxe, OrderBookData>, u32, [u64; MAX_ORDERS]) {
+        let mut book = book_ctxt.to_arcis();
+        accrue_liquidity(&mut book, current_slot);
+
+        let mut cancelled = 0u32;
+        let mut cancelled_ids = [0u64; MAX_ORDERS];
+        for i in 0..MAX_ORDERS {
+            let order = book.orders[i];
+            let hit = order.active && order.owner_lo == owner_lo && order.owner_hi == owner_hi;
+            if hit {
+                cancelled_ids[i] = order.order_id;
+                book.orders[i] = empty_order();
+                cancelled += 1;
+            }
+        }
+
+        (
+            book_ctxt.owner.from_arcis(book),
+            cancelled.reveal(),
+            cancelled_ids.reveal(),
+        )
+    }
+
+    /// Free every slot whose order is past its expiry slot
+    /// Returns the updated book and the number of orders removed
+    #[instruction]
+    pub fn prune_expired_orders(
+        book_ctxt: Enc<Mxe, OrderBookData>,
+        current_slot: u64,
+    ) -> (Enc<Mxe, OrderBookData>, u32) {
+        let mut book = book_ctxt.to_arcis();
+        accrue_liquidity(&mut book, current_slot);
+
+        let mut pruned = 0u32;
+        for i in 0..MAX_ORDERS {
+            let order = book.orders[i];
+            if order.active && !is_live(&order, current_slot) {
+                book.orders[i] = empty_order();
+                pruned += 1;
+            }
+        }
+
+        (book_ctxt.owner.from_arcis(book), pruned.reveal())
+    }
+
+    /// Resting volume one owner has on each side of the book
+    pub struct Depth {
+        pub bid_volume: u64,
+        pub ask_volume: u64,
+    }
+
+    /// Sum the caller's own resting volume per side, including iceberg
+    /// reserves, and encrypt it to the caller's key
+    /// Orders from other owners never contribute to the result
+    #[instruction]
+    pub fn reveal_my_depth(
+        book_ctxt: Enc<Mxe, OrderBookData>,
+        owner_lo: u128,
+        owner_hi: u128,
+        receiver: Shared,
+    ) -> Enc<Shared, Depth> {
+        let book = book_ctxt.to_arcis();
+        let mut depth = Depth {
+            bid_volume: 0,
+            ask_volume: 0,
+        };
+
+        for i in 0..MAX_ORDERS {
+            let order = book.orders[i];
+            let mine = order.active && order.owner_lo == owner_lo && order.owner_hi == owner_hi;
+            let volume = if mine { order.size + order.hidden_size } else { 0 };
+            if order.is_buy {
+                depth.bid_volume += volume;
+            } else {
+                depth.ask_volume += volume;
+            }
+        }
+
+        receiver.from_arcis(depth)
+    }
+
+    /// Price bands reported by `snapshot_depth`
+    const DEPTH_BANDS: usize = 8;
+
+    /// Live orders from owners other than the caller a band needs before
+    /// its total is reported, so a band never reveals a handful of orders
+    const DEPTH_MIN_ORDERS: u64 = 5;
+
+    /// Total displayed size of the live orders in each of DEPTH_BANDS price
+    /// bands, for public depth analytics
+    /// Band i covers prices from `band_floor + i * band_width` up to the next
+    /// band's floor. Both come from the order book's config, so a caller
+    /// can't draw a band around a single order; orders priced outside the
+    /// bands are left out. Bids and asks are summed together, iceberg
+    /// reserves are left out, and a band holding fewer than DEPTH_MIN_ORDERS
+    /// live orders from owners other than the caller reports zero, so the
+    /// caller can't fill a band up to the floor with orders of its own. Only
+    /// the band totals are revealed.
+    #[instruction]
+    pub fn snapshot_depth(
+        book_ctxt: Enc<Mxe, OrderBookData>,
+        band_floor: u64,
+        band_width: u64,
+        owner_lo: u128,
+        owner_hi: u128,
+        current_slot: u64,
+    ) -> [u64; DEPTH_BANDS] {
+        let mut book = book_ctxt.to_arcis();
+        reprice_dutch_orders(&mut book, current_slot);
+        let width = if band_width > 0 { band_width } else { 1 };
+
+        let mut bands = [0u64; DEPTH_BANDS];
+        let mut counts = [0u64; DEPTH_BANDS];
+        for i in 0..MAX_ORDERS {
+            let order = book.orders[i];
+            let live = is_live(&order, current_slot) && order.price >= band_floor;
+            let band = if live { (order.price - band_floor) / width } else { 0 };
+            let callers = order.owner_lo == owner_lo && order.owner_hi == owner_hi;
+            for b in 0..DEPTH_BANDS {
+                if live && band == b as u64 {
+                    bands[b] += order.size;
+                    if !callers {
+                        counts[b] += 1;
+                    }
+                }
+            }
+        }
+        for b in 0..DEPTH_BANDS {
+            if counts[b] < DEPTH_MIN_ORDERS {
+                bands[b] = 0;
+            }
+        }
+
+        bands.reveal()
+    }
+
+    /// `indicative_price` result when the book lacks a live bid or ask
+    const NO_INDICATIVE_PRICE: u64 = 0;
+
+    /// Mid of the best live bid and ask, for front-end price tickers
+    /// Reveals only that one price, no sizes or owners, and moves nothing;
+    /// a one-sided or empty book reads NO_INDICATIVE_PRICE.
+    #[instruction]
+    pub fn indicative_price(book_ctxt: Enc<Mxe, OrderBookData>, current_slot: u64) -> u64 {
+        let mut book = book_ctxt.to_arcis();
+        reprice_dutch_orders(&mut book, current_slot);
+
+        let (_, bid_price, _, has_bid) = best_order(&book, true, current_slot);
+        let (_, ask_price, _, has_ask) = best_order(&book, false, current_slot);
+        let price = if has_bid && has_ask {
+            calculate_mid_price(bid_price, ask_price)
+        } else {
+            NO_INDICATIVE_PRICE
+        };
+
+        price.reveal()
+    }
+
+    /// A maker's accrued liquidity score
+    pub struct LiquidityScore {
+        pub liquidity_slots: u128,
+    }
+
+    /// Total the caller's liquidity score, including time its live orders
+    /// have rested since the last accrual, and encrypt it to the caller's key
+    /// Only the caller can decrypt it, e.g. to submit to an off-chain rewards
+    /// distributor; no order sizes are revealed
+    #[instruction]
+    pub fn reveal_maker_score(
+        book_ctxt: Enc<Mxe, OrderBookData>,
+        owner_lo: u128,
+        owner_hi: u128,
+        current_slot: u64,
+        receiver: Shared,
+    ) -> Enc<Shared, LiquidityScore> {
+        let mut book = book_ctxt.to_arcis();
+        accrue_liquidity(&mut book, current_slot);
+
+        let mut score = LiquidityScore { liquidity_slots: 0 };
+        for j in 0..MAX_MAKERS {
+            let maker = book.makers[j];
+            if maker.owner_lo == owner_lo && maker.owner_hi == owner_hi {
+                score.liquidity_slots += maker.liquidity_slots;
+            }
+        }
+
+        receiver.from_arcis(score)
+    }
+
+    /// Encrypted collateral size a liquidator wants to sell
+    pub struct LiquidationInput {
+        pub size: u64,
+    }
+
+    /// Sell liquidated collateral into resting bids priced at or above `min_price`
+    /// Bids fill best-first, each at its own price, against up to
+    /// MAX_REPORTED_MATCHES bids; whatever can't be filled is left unsold.
+    /// Collateral beyond the base the liquidator escrowed (`escrow_amount`)
+    /// is rejected outright. The liquidation trades as a one-off ask under a
+    /// fresh order ID so its fills settle from the liquidator's escrow, and
+    /// pays fees as in `match_orders`: the resting bid at `maker_fee_rate`
+    /// and the liquidation, which takes its liquidity, at `taker_fee_rate`.
+    /// Reveals the liquidation's order ID, the liquidated amount, the number
+    /// of closed bids and the fills.
+    #[instruction]
+    pub fn liquidate_order(
+        input_ctxt: Enc<Shared, LiquidationInput>,
+        book_ctxt: Enc<Mxe, OrderBookData>,
+        min_price: u64,
+        escrow_amount: u64,
+        maker_fee_rate: u16,
+        taker_fee_rate: u16,
+        current_slot: u64,
+    ) -> (Enc<Mxe, OrderBookData>, u64, u64, u32, [Fill; MAX_REPORTED_MATCHES]) {
+        let input_size = input_ctxt.to_arcis().size;
+        let size = if input_size <= escrow_amount { input_size } else { 0 };
+        let mut book = book_ctxt.to_arcis();
+        accrue_liquidity(&mut book, current_slot);
+        let order_id = next_order_id(&book);
+        let mut remaining = size;
+        let mut closed_orders = 0u32;
+        let mut fills = [empty_fill(); MAX_REPORTED_MATCHES];
+        let tier = imbalance_tier(resting_volume(&book, true), resting_volume(&book, false));
+
+        for round in 0..MAX_REPORTED_MATCHES {
+            let (bid_idx, bid_price, bid_size, has_bid) = best_order(&book, true, current_slot);
+
+            let fills_bid = has_bid && remaining > 0 && bid_price >= min_price;
+            let trade_size = calculate_trade_size(bid_size, remaining);
+
+            let mut bid_id = 0u64;
+            let mut bid_closed = false;
+            let mut bid_fee = 0u64;
+            for i in 0..MAX_ORDERS {
+                if fills_bid && (i as u64) == bid_idx {
+                    bid_id = book.orders[i].order_id;
+                    let rested = rested_slots(&book.orders[i], current_slot);
+                    bid_fee = fill_fee(trade_size, bid_price, maker_fee_rate, rested, tier, true);
+                    let (order, next_priority, closed) =
+                        fill_order(book.orders[i], trade_size, book.next_priority);
+                    book.orders[i] = order;
+                    book.next_priority = next_priority;
+                    bid_closed = closed;
+                    if closed {
+                        closed_orders += 1;
+                    }
+                }
+            }
+
+            if fills_bid {
+                remaining -= trade_size;
+                fills[round] = Fill {
+                    bid_order_id: bid_id,
+                    ask_order_id: order_id,
+                    filled_size: trade_size,
+                    price: bid_price,
+                    bid_is_maker: true,
+                    bid_closed,
+                    ask_closed: false,
+                    bid_fee,
+                    ask_fee: fill_fee(trade_size, bid_price, taker_fee_rate, 0, tier, false),
+                };
+            }
+        }
+
+        (
+            book_ctxt.owner.from_arcis(book),
+            order_id.reveal(),
+            (size - remaining).reveal(),
+            closed_orders.reveal(),
+            fills.reveal(),
+        )
     }
 
     // ============ Helper Functions ============
 
+    /// Base for order ids under the `test` feature, mirrors
+    /// `deterministic::ORDER_ID_SEED`
+    const TEST_ORDER_ID_SEED: u64 = 0x5eed_0000_0000_0000;
+
+    /// Fresh id for an order about to be inserted
+    /// Random in production so ids can't be predicted or linked. Under the
+    /// `test` feature ids follow the book's priority counter, so replaying
+    /// the same orders against a new book yields the same ids.
+    fn next_order_id(book: &OrderBookData) -> u64 {
+        let random = ArcisRNG::u64();
+        if DETERMINISTIC_ORDER_IDS {
+            TEST_ORDER_ID_SEED + book.next_priority + 1
+        } else {
+            random
+        }
+    }
+
+    /// Random tie-break for an order entering a randomized priority window
+    /// Under the `test` feature the draw is the low word of the owner, so
+    /// which of several same-window orders matches first is reproducible
+    fn priority_draw(owner_lo: u128) -> u64 {
+        let random = ArcisRNG::u64() >> 32;
+        if DETERMINISTIC_ORDER_IDS {
+            (owner_lo % (1u128 << 32)) as u64
+        } else {
+            random
+        }
+    }
+
+    /// Time priority for a limit order entering the book at `current_slot`
+    /// With `window_slots` 0 orders rank by arrival. Otherwise slots are
+    /// grouped into windows of `window_slots`: orders entering in the same
+    /// window rank among themselves by a draw from the MPC RNG, and behind
+    /// every order from an earlier window. The priority counter moves past
+    /// the window, so strict-priority entries never jump ahead of it.
+    /// Returns (priority, next priority counter).
+    fn entry_priority(
+        next_priority: u64,
+        owner_lo: u128,
+        current_slot: u64,
+        window_slots: u64,
+    ) -> (u64, u64) {
+        let randomized = window_slots > 0;
+        let divisor = if randomized { window_slots } else { 1 };
+        let window_start = (current_slot / divisor) << 32;
+        let window_end = window_start + (1u64 << 32);
+        if randomized {
+            let next = if next_priority > window_end { next_priority } else { window_end };
+            (window_start + priority_draw(owner_lo), next)
+        } else {
+            (next_priority, next_priority + 1)
+        }
+    }
+
+    /// Subtract `amount` from `value` when it covers it, without revealing either
+    /// Returns (value - amount, true), or (value, false) on underflow
+    fn checked_sub_encrypted(value: u64, amount: u64) -> (u64, bool) {
+        let covered = amount <= value;
+        let result = if covered { value - amount } else { value };
+        (result, covered)
+    }
+
+    /// Input needed for a constant product swap to yield exactly `amount_out`
+    /// Rounds up at both the curve and fee gross-up steps so the pool never
+    /// gives out more than it receives. Returns false if the pool can't cover it.
+    fn required_input(
+        amount_out: u64,
+        reserve_in: u64,
+        reserve_out: u64,
+        fee_rate: u16,
+        min_fee: u64,
+    ) -> (u64, bool) {
+        let feasible = amount_out < reserve_out && reserve_in > 0;
+        let remaining_out = if feasible { reserve_out - amount_out } else { 1 };
+
+        // amount_in_after_fee = ceil(reserve_in * amount_out / (reserve_out - amount_out))
+        let numerator = (reserve_in as u128) * (amount_out as u128);
+        let after_fee = (numerator + (remaining_out as u128) - 1) / (remaining_out as u128);
+
+        // amount_in = ceil(after_fee * 10000 / (10000 - fee_rate)),
+        // or enough to cover the fee floor if that is larger
+        let fee_denominator = 10000u128 - (fee_rate as u128);
+        let grossed_up = (after_fee * 10000 + fee_denominator - 1) / fee_denominator;
+        let floored = after_fee + (min_fee as u128);
+        let amount_in = if grossed_up - after_fee < (min_fee as u128) { floored } else { grossed_up };
+
+        let fits = amount_in <= (u64::MAX as u128);
+        let result = if feasible && fits { amount_in as u64 } else { 0 };
+
+        (result, feasible && fits)
+    }
+
+    /// Find the price that maximizes crossed volume
+    /// Every submitted price is a candidate. At each candidate, demand is the
+    /// bid size priced at or above it and supply the ask size priced at or
+    /// below it. Ties on volume are broken by the smallest demand/supply
+    /// imbalance. Returns (0, 0) if the book doesn't cross.
+    fn find_clearing_price(
+        bid_prices: [u64; AUCTION_LEVELS],
+        bid_sizes: [u64; AUCTION_LEVELS],
+        ask_prices: [u64; AUCTION_LEVELS],
+        ask_sizes: [u64; AUCTION_LEVELS],
+    ) -> (u64, u64) {
+        let mut best_price = 0u64;
+        let mut best_volume = 0u64;
+        let mut best_imbalance = u64::MAX;
+
+        for c in 0..(2 * AUCTION_LEVELS) {
+            let (candidate, candidate_size) = if c < AUCTION_LEVELS {
+                (bid_prices[c], bid_sizes[c])
+            } else {
+                (ask_prices[c - AUCTION_LEVELS], ask_sizes[c - AUCTION_LEVELS])
+            };
+
+            let mut demand = 0u64;
+            let mut supply = 0u64;
+            for i in 0..AUCTION_LEVELS {
+                if bid_sizes[i] > 0 && bid_prices[i] >= candidate {
+                    demand += bid_sizes[i];
+                }
+                if ask_sizes[i] > 0 && ask_prices[i] <= candidate {
+                    supply += ask_sizes[i];
+                }
+            }
+
+            let volume = calculate_trade_size(demand, supply);
+            let imbalance = if demand > supply { demand - supply } else { supply - demand };
+
+            let better = candidate_size > 0
+                && volume > 0
+                && (volume > best_volume
+                    || (volume == best_volume && imbalance < best_imbalance));
+            if better {
+                best_price = candidate;
+                best_volume = volume;
+                best_imbalance = imbalance;
+            }
+        }
+
+        (best_price, best_volume)
+    }
+
+    /// Clearing price and volume of the resting order book
+    /// Same volume-maximizing search as `find_clearing_price`, with every
+    /// resting order's displayed size as a level
+    fn book_clearing_price(book: &OrderBookData, current_slot: u64) -> (u64, u64) {
+        let mut best_price = 0u64;
+        let mut best_volume = 0u64;
+        let mut best_imbalance = u64::MAX;
+
+        for c in 0..MAX_ORDERS {
+            let candidate = book.orders[c].price;
+
+            let mut demand = 0u64;
+            let mut supply = 0u64;
+            for i in 0..MAX_ORDERS {
+                let order = book.orders[i];
+                let live = is_live(&order, current_slot);
+                if live && order.is_buy && order.price >= candidate {
+                    demand += order.size;
+                }
+                if live && !order.is_buy && order.price <= candidate {
+                    supply += order.size;
+                }
+            }
+
+            let volume = calculate_trade_size(demand, supply);
+            let imbalance = if demand > supply { demand - supply } else { supply - demand };
+
+            let better = is_live(&book.orders[c], current_slot)
+                && volume > 0
+                && (volume > best_volume
+                    || (volume == best_volume && imbalance < best_imbalance));
+            if better {
+                best_price = candidate;
+                best_volume = volume;
+                best_imbalance = imbalance;
+            }
+        }
+
+        (best_price, best_volume)
+    }
+
+    /// An inactive order slot
+    fn empty_order() -> Order {
+        Order {
+            active: false,
+            is_buy: false,
+            owner_lo: 0,
+            owner_hi: 0,
+            order_id: 0,
+            price: 0,
+            size: 0,
+            hidden_size: 0,
+            display_size: 0,
+            priority: 0,
+            start_price: 0,
+            end_price: 0,
+            start_slot: 0,
+            duration: 0,
+            expiry_slot: 0,
+            rested_since: 0,
+        }
+    }
+
+    /// A free maker score entry
+    fn empty_maker() -> MakerScore {
+        MakerScore {
+            owner_lo: 0,
+            owner_hi: 0,
+            liquidity_slots: 0,
+        }
+    }
+
+    /// An unused `Fill` report entry
+    fn empty_fill() -> Fill {
+        Fill {
+            bid_order_id: 0,
+            ask_order_id: 0,
+            filled_size: 0,
+            price: 0,
+            bid_is_maker: false,
+            bid_closed: false,
+            ask_closed: false,
+            bid_fee: 0,
+            ask_fee: 0,
+        }
+    }
+
+    /// Credit each resting order's maker with its matchable size times the
+    /// slots it rested since it was last credited, then restart its clock
+    /// Time past an order's expiry isn't credited. Makers take the first free
+    /// entry the first time they accrue; once every entry is taken, new
+    /// makers stop accruing.
+    fn accrue_liquidity(book: &mut OrderBookData, current_slot: u64) {
+        for i in 0..MAX_ORDERS {
+            let order = book.orders[i];
+            let expired = order.expiry_slot > 0 && order.expiry_slot < current_slot;
+            let until = if expired { order.expiry_slot } else { current_slot };
+            let rested = if until > order.rested_since { until - order.rested_since } else { 0 };
+            let earned = (order.size as u128) * (rested as u128);
+
+            let mut credited = false;
+            for j in 0..MAX_MAKERS {
+                let maker = book.makers[j];
+                let mine = maker.owner_lo == order.owner_lo && maker.owner_hi == order.owner_hi;
+                if order.active && !credited && mine {
+                    book.makers[j].liquidity_slots = maker.liquidity_slots + earned;
+                    credited = true;
+                }
+            }
+            for j in 0..MAX_MAKERS {
+                let free = book.makers[j].owner_lo == 0 && book.makers[j].owner_hi == 0;
+                if order.active && !credited && free {
+                    book.makers[j] = MakerScore {
+                        owner_lo: order.owner_lo,
+                        owner_hi: order.owner_hi,
+                        liquidity_slots: earned,
+                    };
+                    credited = true;
+                }
+            }
+
+            if order.active {
+                book.orders[i].rested_since = current_slot;
+            }
+        }
+    }
+
+    /// Move every dutch auction ask to its current price
+    /// price = start - (start - end) * elapsed / duration, with elapsed
+    /// capped at duration so the order rests at its floor once it expires
+    fn reprice_dutch_orders(book: &mut OrderBookData, current_slot: u64) {
+        for i in 0..MAX_ORDERS {
+            let order = book.orders[i];
+            let is_dutch = order.active && order.duration > 0;
+            let elapsed = if current_slot > order.start_slot {
+                current_slot - order.start_slot
+            } else {
+                0
+            };
+            let elapsed = if elapsed > order.duration { order.duration } else { elapsed };
+            let duration = if order.duration > 0 { order.duration } else { 1 };
+            let decline = ((order.start_price - order.end_price) as u128 * elapsed as u128
+                / duration as u128) as u64;
+            if is_dutch {
+                book.orders[i].price = order.start_price - decline;
+            }
+        }
+    }
+
+    /// Whether an order is resting and not past its expiry slot
+    fn is_live(order: &Order, current_slot: u64) -> bool {
+        order.active && (order.expiry_slot == 0 || order.expiry_slot >= current_slot)
+    }
+
+    /// Find the best live order on one side of the book
+    /// Bids rank by highest price, asks by lowest, then by time priority.
+    /// Returns (slot index, price, size, found).
+    fn best_order(book: &OrderBookData, is_buy: bool, current_slot: u64) -> (u64, u64, u64, bool) {
+        let (idx, price, size, _, _, found) =
+            best_order_excluding(book, is_buy, current_slot, false, 0, 0);
+        (idx, price, size, found)
+    }
+
+    /// `best_order`, optionally passing over one owner's orders, that also
+    /// returns the owner of the order found
+    /// Returns (slot index, price, size, owner_lo, owner_hi, found).
+    fn best_order_excluding(
+        book: &OrderBookData,
+        is_buy: bool,
+        current_slot: u64,
+        exclude: bool,
+        excluded_lo: u128,
+        excluded_hi: u128,
+    ) -> (u64, u64, u64, u128, u128, bool) {
+        let mut best_idx = 0u64;
+        let mut best_price = 0u64;
+        let mut best_size = 0u64;
+        let mut best_priority = 0u64;
+        let mut best_lo = 0u128;
+        let mut best_hi = 0u128;
+        let mut found = false;
+
+        for i in 0..MAX_ORDERS {
+            let order = book.orders[i];
+            let excluded = exclude && order.owner_lo == excluded_lo && order.owner_hi == excluded_hi;
+            let eligible = is_live(&order, current_slot)
+                && order.is_buy == is_buy
+                && order.size > 0
+                && !excluded;
+            let better_price = if is_buy {
+                order.price > best_price
+            } else {
+                order.price < best_price
+            };
+            let earlier = order.price == best_price && order.priority < best_priority;
+            if eligible && (!found || better_price || earlier) {
+                best_idx = i as u64;
+                best_price = order.price;
+                best_size = order.size;
+                best_priority = order.priority;
+                best_lo = order.owner_lo;
+                best_hi = order.owner_hi;
+                found = true;
+            }
+        }
+
+        (best_idx, best_price, best_size, best_lo, best_hi, found)
+    }
+
+    /// Fill `trade_size` of an order's displayed size
+    /// When an iceberg's displayed slice is exhausted it is replenished from
+    /// the hidden reserve and sent to the back of the queue at its price, so
+    /// the new slice can't jump ahead of orders that arrived after the
+    /// iceberg was placed. A partial fill keeps the order's priority, and since
+    /// the priority is stored in the book state it carries over unchanged into
+    /// later matching runs and auctions. Returns (order, next priority, fully filled).
+    fn fill_order(mut order: Order, trade_size: u64, next_priority: u64) -> (Order, u64, bool) {
+        let mut next = next_priority;
+        order.size -= trade_size;
+
+        let replenish = order.size == 0 && order.hidden_size > 0;
+        if replenish {
+            let slice = calculate_trade_size(order.display_size, order.hidden_size);
+            order.size = slice;
+            order.hidden_size -= slice;
+            order.priority = next;
+            next += 1;
+        }
+
+        let closed = order.size == 0;
+        if closed {
+            order.active = false;
+        }
+
+        (order, next, closed)
+    }
+
+    /// Fee in basis points for an order that has rested `rested_slots`
+    /// Full `fee_rate` below FEE_TIER_SLOTS, half up to 10x that, then a quarter
+    fn resting_fee_bps(fee_rate: u16, rested_slots: u64) -> u16 {
+        if rested_slots >= FEE_TIER_SLOTS * 10 {
+            fee_rate / 4
+        } else if rested_slots >= FEE_TIER_SLOTS {
+            fee_rate / 2
+        } else {
+            fee_rate
+        }
+    }
+
+    /// Fee one side of a fill pays on `trade_size` at `price`
+    /// `fee_rate` is the side's maker or taker rate, discounted by how long
+    /// its order has rested and adjusted for the book's imbalance `tier`.
+    fn fill_fee(
+        trade_size: u64,
+        price: u64,
+        fee_rate: u16,
+        rested_slots: u64,
+        tier: u8,
+        is_bid: bool,
+    ) -> u64 {
+        let fee_bps = imbalance_fee_bps(resting_fee_bps(fee_rate, rested_slots), tier, is_bid);
+        ((trade_size as u128) * (price as u128) * (fee_bps as u128) / 10000) as u64
+    }
+
+    /// Slots a resting order has been on the book
+    fn rested_slots(order: &Order, current_slot: u64) -> u64 {
+        if current_slot > order.start_slot {
+            current_slot - order.start_slot
+        } else {
+            0
+        }
+    }
+
+    /// Which side of the book, if any, outweighs the other by IMBALANCE_RATIO_PCT
+    fn imbalance_tier(bid_volume: u64, ask_volume: u64) -> u8 {
+        let bids = bid_volume as u128;
+        let asks = ask_volume as u128;
+        let ratio = IMBALANCE_RATIO_PCT as u128;
+        if bids * 100 > asks * ratio {
+            IMBALANCE_BID_HEAVY
+        } else if asks * 100 > bids * ratio {
+            IMBALANCE_ASK_HEAVY
+        } else {
+            IMBALANCE_BALANCED
+        }
+    }
+
+    /// Adjust a side's fee for the book's imbalance `tier`
+    /// The heavy side pays IMBALANCE_FEE_ADJUST_BPS of `fee_bps` more, the
+    /// light side that much less; a balanced book leaves it unchanged
+    fn imbalance_fee_bps(fee_bps: u16, tier: u8, is_bid: bool) -> u16 {
+        let adjust = ((fee_bps as u64) * IMBALANCE_FEE_ADJUST_BPS / 10000) as u16;
+        let heavy = (tier == IMBALANCE_BID_HEAVY && is_bid) || (tier == IMBALANCE_ASK_HEAVY && !is_bid);
+        let light = (tier == IMBALANCE_BID_HEAVY && !is_bid) || (tier == IMBALANCE_ASK_HEAVY && is_bid);
+        if heavy {
+            fee_bps + adjust
+        } else if light {
+            fee_bps - adjust
+        } else {
+            fee_bps
+        }
+    }
+
+    /// Part of a linearly vesting `total` released after `elapsed` of
+    /// `duration` slots, all of it once the duration has passed
+    fn vested_amount(total: u64, elapsed: u64, duration: u64) -> u64 {
+        if elapsed >= duration {
+            total
+        } else {
+            ((total as u128) * (elapsed as u128) / (duration as u128)) as u64
+        }
+    }
+
+    /// Swap fee in basis points of `amount_in`, floored at `min_fee`
+    fn swap_fee(amount_in: u64, fee_rate: u16, min_fee: u64) -> u64 {
+        let fee = ((amount_in as u128) * (fee_rate as u128) / 10000) as u64;
+        if fee < min_fee { min_fee } else { fee }
+    }
+
+    /// Constant-product output for `amount_in` and whether the swap succeeds
+    /// The swap fails if the input doesn't cover the fee or the output is
+    /// below `min_output`
+    fn quote_swap(
+        amount_in: u64,
+        reserve_a: u64,
+        reserve_b: u64,
+        min_output: u64,
+        is_a_to_b: bool,
+        fee_rate: u16,
+        min_fee: u64,
+    ) -> (u64, bool) {
+        // Calculate fee, never below the pool's minimum
+        let fee = swap_fee(amount_in, fee_rate, min_fee);
+        let covers_fee = amount_in > fee;
+        let amount_in_after_fee = if covers_fee { amount_in - fee } else { 0 };
+
+        // Calculate output using constant product formula
+        let (reserve_in, reserve_out) = if is_a_to_b {
+            (reserve_a, reserve_b)
+        } else {
+            (reserve_b, reserve_a)
+        };
+
+        // amount_out = (amount_in * reserve_out) / (reserve_in + amount_in),
+        // in u128 like required_input so large reserves can't overflow
+        let numerator = (amount_in_after_fee as u128) * (reserve_out as u128);
+        let denominator = (reserve_in as u128) + (amount_in_after_fee as u128);
+        let amount_out = (numerator / denominator) as u64;
+
+        // Check slippage
+        (amount_out, covers_fee && amount_out >= min_output)
+    }
+
     /// Calculate mid-price between two orders
     fn calculate_mid_price(buy_price: u64, sell_price: u64) -> u64 {
         (buy_price + sell_price) / 2
     }
 
+    /// Total resting size on one side of the book, including iceberg reserves
+    fn resting_volume(book: &OrderBookData, is_buy: bool) -> u64 {
+        let mut volume = 0u64;
+        for i in 0..MAX_ORDERS {
+            let order = book.orders[i];
+            if order.active && order.is_buy == is_buy {
+                volume += order.size + order.hidden_size;
+            }
+        }
+        volume
+    }
+
+    /// Whether `price` deviates from `reference` by more than `band_bps`
+    /// A zero reference or band disables the check
+    fn outside_price_band(price: u64, reference: u64, band_bps: u16) -> bool {
+        let deviation = if price > reference {
+            price - reference
+        } else {
+            reference - price
+        };
+        let enabled = reference > 0 && band_bps > 0;
+        enabled && (deviation as u128) * 10000 > (reference as u128) * (band_bps as u128)
+    }
+
     /// Calculate trade size (minimum of buy and sell sizes)
     fn calculate_trade_size(buy_size: u64, sell_size: u64) -> u64 {
         if buy_size < sell_size {
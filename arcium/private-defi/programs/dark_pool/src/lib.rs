@@ -1,10 +1,63 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
 use arcium_anchor::prelude::*;
+use private_defi_common::{covers_computation_fee, CallbackAccountsBuilder};
+use private_pay::program::PrivatePay;
 
 const COMP_DEF_OFFSET_ADD_ORDER: u32 = comp_def_offset("add_order");
 const COMP_DEF_OFFSET_MATCH_ORDERS: u32 = comp_def_offset("match_orders");
 const COMP_DEF_OFFSET_CANCEL_ORDER: u32 = comp_def_offset("cancel_order");
+const COMP_DEF_OFFSET_CLEARING_PRICE: u32 = comp_def_offset("compute_clearing_price");
+const COMP_DEF_OFFSET_INIT_BOOK_STATE: u32 = comp_def_offset("init_order_book_state");
+const COMP_DEF_OFFSET_BATCH_AUCTION: u32 = comp_def_offset("run_batch_auction");
+const COMP_DEF_OFFSET_REVEAL_MY_DEPTH: u32 = comp_def_offset("reveal_my_depth");
+const COMP_DEF_OFFSET_LIQUIDATE_ORDER: u32 = comp_def_offset("liquidate_order");
+const COMP_DEF_OFFSET_ADD_DUTCH_ORDER: u32 = comp_def_offset("add_dutch_order");
+const COMP_DEF_OFFSET_PRUNE_EXPIRED_ORDERS: u32 = comp_def_offset("prune_expired_orders");
+const COMP_DEF_OFFSET_REVEAL_MAKER_SCORE: u32 = comp_def_offset("reveal_maker_score");
+const COMP_DEF_OFFSET_CANCEL_ALL_ORDERS: u32 = comp_def_offset("cancel_all_orders");
+const COMP_DEF_OFFSET_SCHEDULE_TWAP: u32 = comp_def_offset("schedule_twap_order");
+const COMP_DEF_OFFSET_RELEASE_TWAP_SLICE: u32 = comp_def_offset("release_twap_slice");
+const COMP_DEF_OFFSET_SNAPSHOT_DEPTH: u32 = comp_def_offset("snapshot_depth");
+const COMP_DEF_OFFSET_INDICATIVE_PRICE: u32 = comp_def_offset("indicative_price");
+const COMP_DEF_OFFSET_ADD_FUNDED_ORDER: u32 = comp_def_offset("add_funded_order");
+
+/// Comp def offsets in the order `init_all_comp_defs` expects their account groups
+pub const ALL_COMP_DEF_OFFSETS: [u32; 17] = [
+    COMP_DEF_OFFSET_ADD_ORDER,
+    COMP_DEF_OFFSET_MATCH_ORDERS,
+    COMP_DEF_OFFSET_CANCEL_ORDER,
+    COMP_DEF_OFFSET_CLEARING_PRICE,
+    COMP_DEF_OFFSET_INIT_BOOK_STATE,
+    COMP_DEF_OFFSET_BATCH_AUCTION,
+    COMP_DEF_OFFSET_REVEAL_MY_DEPTH,
+    COMP_DEF_OFFSET_LIQUIDATE_ORDER,
+    COMP_DEF_OFFSET_ADD_DUTCH_ORDER,
+    COMP_DEF_OFFSET_PRUNE_EXPIRED_ORDERS,
+    COMP_DEF_OFFSET_REVEAL_MAKER_SCORE,
+    COMP_DEF_OFFSET_CANCEL_ALL_ORDERS,
+    COMP_DEF_OFFSET_SCHEDULE_TWAP,
+    COMP_DEF_OFFSET_RELEASE_TWAP_SLICE,
+    COMP_DEF_OFFSET_SNAPSHOT_DEPTH,
+    COMP_DEF_OFFSET_INDICATIVE_PRICE,
+    COMP_DEF_OFFSET_ADD_FUNDED_ORDER,
+];
+
+/// Deserialize one comp def init context from the front of `$accounts` and
+/// initialize it unless the comp def account already exists
+macro_rules! init_comp_def_group {
+    ($ctx:ident, $bumps:ident, $offset:expr, $program_id:expr, $accounts:ident, $initialized:ident) => {{
+        let mut bumps = $bumps::default();
+        let mut reallocs = std::collections::BTreeSet::new();
+        let mut group = $ctx::try_accounts($program_id, &mut $accounts, &[], &mut bumps, &mut reallocs)?;
+        if group.comp_def_account.data_is_empty() {
+            init_comp_def(&mut group, None, None)?;
+            group.circuit_version.stamp(bumps.circuit_version);
+            group.exit($program_id)?;
+            $initialized.push($offset);
+        }
+    }};
+}
 
 declare_id!("ExmtDaTNpjZbgx2qABKG4AkxV5NTKbg5P7WY1iThqJAG");
 
@@ -15,71 +68,900 @@ pub mod dark_pool {
     /// Initialize computation definitions for dark pool operations
     pub fn init_add_order_comp_def(ctx: Context<InitAddOrderCompDef>) -> Result<()> {
         init_comp_def(ctx.accounts, None, None)?;
+        ctx.accounts.circuit_version.stamp(ctx.bumps.circuit_version);
         Ok(())
     }
 
     pub fn init_match_orders_comp_def(ctx: Context<InitMatchOrdersCompDef>) -> Result<()> {
         init_comp_def(ctx.accounts, None, None)?;
+        ctx.accounts.circuit_version.stamp(ctx.bumps.circuit_version);
         Ok(())
     }
 
     pub fn init_cancel_order_comp_def(ctx: Context<InitCancelOrderCompDef>) -> Result<()> {
         init_comp_def(ctx.accounts, None, None)?;
+        ctx.accounts.circuit_version.stamp(ctx.bumps.circuit_version);
+        Ok(())
+    }
+
+    pub fn init_clearing_price_comp_def(ctx: Context<InitClearingPriceCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        ctx.accounts.circuit_version.stamp(ctx.bumps.circuit_version);
+        Ok(())
+    }
+
+    pub fn init_order_book_state_comp_def(ctx: Context<InitOrderBookStateCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        ctx.accounts.circuit_version.stamp(ctx.bumps.circuit_version);
+        Ok(())
+    }
+
+    pub fn init_batch_auction_comp_def(ctx: Context<InitBatchAuctionCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        ctx.accounts.circuit_version.stamp(ctx.bumps.circuit_version);
+        Ok(())
+    }
+
+    pub fn init_reveal_my_depth_comp_def(ctx: Context<InitRevealMyDepthCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        ctx.accounts.circuit_version.stamp(ctx.bumps.circuit_version);
+        Ok(())
+    }
+
+    pub fn init_liquidate_order_comp_def(ctx: Context<InitLiquidateOrderCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        ctx.accounts.circuit_version.stamp(ctx.bumps.circuit_version);
+        Ok(())
+    }
+
+    pub fn init_add_dutch_order_comp_def(ctx: Context<InitAddDutchOrderCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        ctx.accounts.circuit_version.stamp(ctx.bumps.circuit_version);
+        Ok(())
+    }
+
+    pub fn init_prune_expired_orders_comp_def(ctx: Context<InitPruneExpiredOrdersCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        ctx.accounts.circuit_version.stamp(ctx.bumps.circuit_version);
+        Ok(())
+    }
+
+    pub fn init_reveal_maker_score_comp_def(ctx: Context<InitRevealMakerScoreCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        ctx.accounts.circuit_version.stamp(ctx.bumps.circuit_version);
+        Ok(())
+    }
+
+    pub fn init_cancel_all_orders_comp_def(ctx: Context<InitCancelAllOrdersCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        ctx.accounts.circuit_version.stamp(ctx.bumps.circuit_version);
+        Ok(())
+    }
+
+    pub fn init_schedule_twap_order_comp_def(ctx: Context<InitScheduleTwapOrderCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        ctx.accounts.circuit_version.stamp(ctx.bumps.circuit_version);
+        Ok(())
+    }
+
+    pub fn init_release_twap_slice_comp_def(ctx: Context<InitReleaseTwapSliceCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        ctx.accounts.circuit_version.stamp(ctx.bumps.circuit_version);
+        Ok(())
+    }
+
+    pub fn init_snapshot_depth_comp_def(ctx: Context<InitSnapshotDepthCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        ctx.accounts.circuit_version.stamp(ctx.bumps.circuit_version);
+        Ok(())
+    }
+
+    pub fn init_indicative_price_comp_def(ctx: Context<InitIndicativePriceCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        ctx.accounts.circuit_version.stamp(ctx.bumps.circuit_version);
+        Ok(())
+    }
+
+    pub fn init_add_funded_order_comp_def(ctx: Context<InitAddFundedOrderCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        ctx.accounts.circuit_version.stamp(ctx.bumps.circuit_version);
+        Ok(())
+    }
+
+    /// Initialize every dark pool computation definition in one call
+    /// Remaining accounts hold one group per comp def, in the order of
+    /// `ALL_COMP_DEF_OFFSETS`, each laid out like that comp def's own init
+    /// context. Comp defs that already exist are skipped, so the instruction
+    /// is safe to re-run after a partially failed deployment.
+    pub fn init_all_comp_defs<'info>(
+        ctx: Context<'_, '_, 'info, 'info, InitAllCompDefs<'info>>,
+    ) -> Result<()> {
+        let program_id = ctx.program_id;
+        let mut accounts: &'info [AccountInfo<'info>] = ctx.remaining_accounts;
+        let mut initialized = Vec::new();
+
+        init_comp_def_group!(
+            InitAddOrderCompDef,
+            InitAddOrderCompDefBumps,
+            COMP_DEF_OFFSET_ADD_ORDER,
+            program_id,
+            accounts,
+            initialized
+        );
+        init_comp_def_group!(
+            InitMatchOrdersCompDef,
+            InitMatchOrdersCompDefBumps,
+            COMP_DEF_OFFSET_MATCH_ORDERS,
+            program_id,
+            accounts,
+            initialized
+        );
+        init_comp_def_group!(
+            InitCancelOrderCompDef,
+            InitCancelOrderCompDefBumps,
+            COMP_DEF_OFFSET_CANCEL_ORDER,
+            program_id,
+            accounts,
+            initialized
+        );
+        init_comp_def_group!(
+            InitClearingPriceCompDef,
+            InitClearingPriceCompDefBumps,
+            COMP_DEF_OFFSET_CLEARING_PRICE,
+            program_id,
+            accounts,
+            initialized
+        );
+        init_comp_def_group!(
+            InitOrderBookStateCompDef,
+            InitOrderBookStateCompDefBumps,
+            COMP_DEF_OFFSET_INIT_BOOK_STATE,
+            program_id,
+            accounts,
+            initialized
+        );
+        init_comp_def_group!(
+            InitBatchAuctionCompDef,
+            InitBatchAuctionCompDefBumps,
+            COMP_DEF_OFFSET_BATCH_AUCTION,
+            program_id,
+            accounts,
+            initialized
+        );
+        init_comp_def_group!(
+            InitRevealMyDepthCompDef,
+            InitRevealMyDepthCompDefBumps,
+            COMP_DEF_OFFSET_REVEAL_MY_DEPTH,
+            program_id,
+            accounts,
+            initialized
+        );
+        init_comp_def_group!(
+            InitLiquidateOrderCompDef,
+            InitLiquidateOrderCompDefBumps,
+            COMP_DEF_OFFSET_LIQUIDATE_ORDER,
+            program_id,
+            accounts,
+            initialized
+        );
+        init_comp_def_group!(
+            InitAddDutchOrderCompDef,
+            InitAddDutchOrderCompDefBumps,
+            COMP_DEF_OFFSET_ADD_DUTCH_ORDER,
+            program_id,
+            accounts,
+            initialized
+        );
+        init_comp_def_group!(
+            InitPruneExpiredOrdersCompDef,
+            InitPruneExpiredOrdersCompDefBumps,
+            COMP_DEF_OFFSET_PRUNE_EXPIRED_ORDERS,
+            program_id,
+            accounts,
+            initialized
+        );
+        init_comp_def_group!(
+            InitRevealMakerScoreCompDef,
+            InitRevealMakerScoreCompDefBumps,
+            COMP_DEF_OFFSET_REVEAL_MAKER_SCORE,
+            program_id,
+            accounts,
+            initialized
+        );
+        init_comp_def_group!(
+            InitCancelAllOrdersCompDef,
+            InitCancelAllOrdersCompDefBumps,
+            COMP_DEF_OFFSET_CANCEL_ALL_ORDERS,
+            program_id,
+            accounts,
+            initialized
+        );
+        init_comp_def_group!(
+            InitScheduleTwapOrderCompDef,
+            InitScheduleTwapOrderCompDefBumps,
+            COMP_DEF_OFFSET_SCHEDULE_TWAP,
+            program_id,
+            accounts,
+            initialized
+        );
+        init_comp_def_group!(
+            InitReleaseTwapSliceCompDef,
+            InitReleaseTwapSliceCompDefBumps,
+            COMP_DEF_OFFSET_RELEASE_TWAP_SLICE,
+            program_id,
+            accounts,
+            initialized
+        );
+        init_comp_def_group!(
+            InitSnapshotDepthCompDef,
+            InitSnapshotDepthCompDefBumps,
+            COMP_DEF_OFFSET_SNAPSHOT_DEPTH,
+            program_id,
+            accounts,
+            initialized
+        );
+        init_comp_def_group!(
+            InitIndicativePriceCompDef,
+            InitIndicativePriceCompDefBumps,
+            COMP_DEF_OFFSET_INDICATIVE_PRICE,
+            program_id,
+            accounts,
+            initialized
+        );
+        init_comp_def_group!(
+            InitAddFundedOrderCompDef,
+            InitAddFundedOrderCompDefBumps,
+            COMP_DEF_OFFSET_ADD_FUNDED_ORDER,
+            program_id,
+            accounts,
+            initialized
+        );
+
+        let clock = Clock::get()?;
+        emit!(CompDefsInitialized {
+            payer: ctx.accounts.payer.key(),
+            initialized,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+
         Ok(())
     }
 
     /// Initialize a dark pool order book for a trading pair
+    /// `fee_rate` starts as both the maker and the taker rate
     pub fn init_order_book(
         ctx: Context<InitOrderBook>,
         fee_rate: u16,
+        tick_size: u64,
+        min_order_size: u64,
+        max_order_size: u64,
     ) -> Result<()> {
+        validate_order_params(tick_size, min_order_size, max_order_size)?;
+        validate_fee_rate(fee_rate)?;
+
         let order_book = &mut ctx.accounts.order_book;
         order_book.authority = ctx.accounts.authority.key();
         order_book.base_mint = ctx.accounts.base_mint.key();
         order_book.quote_mint = ctx.accounts.quote_mint.key();
-        order_book.fee_rate = fee_rate;
+        order_book.taker_fee_rate = fee_rate;
         order_book.bump = ctx.bumps.order_book;
         order_book.total_orders = 0;
         order_book.total_matches = 0;
         order_book.active_orders = 0;
+        order_book.tick_size = tick_size;
+        order_book.min_order_size = min_order_size;
+        order_book.max_order_size = max_order_size;
+        order_book.epoch_ids = [0; EPOCH_VOLUME_SLOTS];
+        order_book.epoch_volumes = [0; EPOCH_VOLUME_SLOTS];
+        order_book.forfeited_bonds = 0;
+        order_book.auction_volume_threshold = 0;
+        order_book.auction_pending = false;
+        order_book.reference_price = 0;
+        order_book.price_band_bps = 0;
+        order_book.circuit_breaker_tripped = false;
+        order_book.base_decimals = ctx.accounts.base_mint.decimals;
+        order_book.quote_decimals = ctx.accounts.quote_mint.decimals;
+        order_book.price_display_decimals = DEFAULT_PRICE_DISPLAY_DECIMALS;
+        order_book.liquidator = Pubkey::default();
+        order_book.liquidation_band_bps = 0;
+        order_book.paused_ops = 0;
+        order_book.paused = false;
+        order_book.random_priority_window_slots = 0;
+        order_book.fees_accrued = 0;
+        order_book.match_cap = 0;
+        order_book.match_cursor = 0;
+        order_book.maker_fee_rate = fee_rate;
+        order_book.oracle = Pubkey::default();
+        order_book.depth_band_floor = 0;
+        order_book.depth_band_width = 0;
         Ok(())
     }
 
-    /// Add a hidden order to the dark pool
-    /// Order details (price, size) are encrypted
-    pub fn add_order(
-        ctx: Context<AddOrder>,
-        computation_offset: u64,
-        encrypted_price: [u8; 64],  // Encrypted limit price
-        encrypted_size: [u8; 64],   // Encrypted order size
-        is_buy: bool,               // Order side (buy/sell)
-        pub_key: [u8; 32],
-        nonce: u128,
+    /// Update the order book's taker fee rate, in basis points up to
+    /// `MAX_FEE_BPS`
+    /// Kept from before the maker/taker split; `set_fee_tiers` sets both
+    pub fn update_fee_rate(ctx: Context<SetOrderParams>, fee_rate: u16) -> Result<()> {
+        validate_fee_rate(fee_rate)?;
+        let order_book = &mut ctx.accounts.order_book;
+        order_book.taker_fee_rate = fee_rate;
+        let clock = Clock::get()?;
+        emit!(FeeRateUpdated {
+            order_book: order_book.key(),
+            fee_rate,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Set separate fee rates for the maker and taker side of each match
+    /// The maker is whichever side of a matched pair rested first. Both
+    /// rates are in basis points up to `MAX_FEE_BPS`.
+    pub fn set_fee_tiers(
+        ctx: Context<SetOrderParams>,
+        maker_fee_rate: u16,
+        taker_fee_rate: u16,
+    ) -> Result<()> {
+        validate_fee_rate(maker_fee_rate)?;
+        validate_fee_rate(taker_fee_rate)?;
+        let order_book = &mut ctx.accounts.order_book;
+        order_book.maker_fee_rate = maker_fee_rate;
+        order_book.taker_fee_rate = taker_fee_rate;
+        let clock = Clock::get()?;
+        emit!(FeeTiersUpdated {
+            order_book: order_book.key(),
+            maker_fee_rate,
+            taker_fee_rate,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Fix the price bands `snapshot_depth` reports
+    /// Band i covers `band_floor + i * band_width` up to the next band, so
+    /// the bands span `DEPTH_BANDS * band_width` from `band_floor`.
+    pub fn set_depth_bands(
+        ctx: Context<SetOrderParams>,
+        band_floor: u64,
+        band_width: u64,
+    ) -> Result<()> {
+        require!(
+            valid_depth_bands(band_floor, band_width),
+            ErrorCode::InvalidOrderParams
+        );
+        let order_book = &mut ctx.accounts.order_book;
+        order_book.depth_band_floor = band_floor;
+        order_book.depth_band_width = band_width;
+        let clock = Clock::get()?;
+        emit!(DepthBandsUpdated {
+            order_book: order_book.key(),
+            band_floor,
+            band_width,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Update the tick and size parameters of an order book
+    /// Only applies to orders added after the update
+    pub fn set_order_params(
+        ctx: Context<SetOrderParams>,
+        tick_size: u64,
+        min_order_size: u64,
+        max_order_size: u64,
+    ) -> Result<()> {
+        validate_order_params(tick_size, min_order_size, max_order_size)?;
+
+        let order_book = &mut ctx.accounts.order_book;
+        order_book.tick_size = tick_size;
+        order_book.min_order_size = min_order_size;
+        order_book.max_order_size = max_order_size;
+
+        let clock = Clock::get()?;
+        emit!(OrderParamsUpdated {
+            order_book: order_book.key(),
+            tick_size,
+            min_order_size,
+            max_order_size,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Halt or resume every computation on this order book
+    /// Accounts are left untouched, and setting the current value is a no-op
+    pub fn set_paused(ctx: Context<SetOrderParams>, paused: bool) -> Result<()> {
+        let order_book = &mut ctx.accounts.order_book;
+        order_book.paused = paused;
+        let clock = Clock::get()?;
+        emit!(PausedUpdated {
+            order_book: order_book.key(),
+            paused,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Pause or resume individual order book operations (see the PAUSE_* bits)
+    pub fn set_paused_ops(ctx: Context<SetOrderParams>, paused_ops: u8) -> Result<()> {
+        let order_book = &mut ctx.accounts.order_book;
+        order_book.paused_ops = paused_ops;
+        let clock = Clock::get()?;
+        emit!(PausedOpsUpdated {
+            order_book: order_book.key(),
+            paused_ops,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Close an empty order book and return its rent to the authority
+    /// The sign PDA is shared by every book, so it is left open
+    pub fn close_order_book(ctx: Context<CloseOrderBook>) -> Result<()> {
+        require!(ctx.accounts.order_book.active_orders == 0, ErrorCode::NonEmptyAccount);
+
+        let clock = Clock::get()?;
+        emit!(OrderBookClosed {
+            order_book: ctx.accounts.order_book.key(),
+            authority: ctx.accounts.authority.key(),
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Set the crossable volume that triggers a batch auction (0 disables it)
+    pub fn set_auction_threshold(
+        ctx: Context<SetOrderParams>,
+        auction_volume_threshold: u64,
+    ) -> Result<()> {
+        ctx.accounts.order_book.auction_volume_threshold = auction_volume_threshold;
+        Ok(())
+    }
+
+    /// Set how far (in bps) a match may stray from the reference price
+    /// before the circuit breaker halts matching (0 disables it)
+    pub fn set_price_band(
+        ctx: Context<SetOrderParams>,
+        price_band_bps: u16,
     ) -> Result<()> {
+        require!(price_band_bps <= 10000, ErrorCode::InvalidOrderParams);
+        ctx.accounts.order_book.price_band_bps = price_band_bps;
+        Ok(())
+    }
+
+    /// Create the quote-mint vault matching fees are settled into
+    pub fn init_fee_vault(_ctx: Context<InitFeeVault>) -> Result<()> {
+        Ok(())
+    }
+
+    /// Pay accrued matching fees out of the fee vault to the authority
+    /// Fees accrue in `settle_fill` as they are moved into the vault, so
+    /// everything accrued is backed by the vault balance.
+    pub fn collect_fees(ctx: Context<CollectFees>) -> Result<()> {
+        let amount = ctx.accounts.order_book.take_fees(ctx.accounts.fee_vault.amount)?;
+
         let order_book = &ctx.accounts.order_book;
-        
-        // Build encrypted arguments
-        let args = ArgBuilder::new()
-            .x25519_pubkey(pub_key)
-            .plaintext_u128(nonce)
-            .encrypted_bytes(encrypted_price)
-            .encrypted_bytes(encrypted_size)
-            .plaintext_bool(is_buy)
-            .plaintext_pubkey(ctx.accounts.payer.key())
-            .build();
+        let bump = [order_book.bump];
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"orderbook",
+            order_book.base_mint.as_ref(),
+            order_book.quote_mint.as_ref(),
+            &bump,
+        ]];
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.fee_vault.to_account_info(),
+            to: ctx.accounts.destination.to_account_info(),
+            authority: order_book.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        );
+        token::transfer(cpi_ctx, amount)?;
+
+        let order_book = &ctx.accounts.order_book;
+        let clock = Clock::get()?;
+        emit!(FeesCollected {
+            order_book: order_book.key(),
+            amount,
+            remaining: order_book.fees_accrued,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Create the settlement ledger and the base and quote escrow vaults
+    pub fn init_settlement(ctx: Context<InitSettlement>) -> Result<()> {
+        let ledger = &mut ctx.accounts.settlement_ledger;
+        ledger.order_book = ctx.accounts.order_book.key();
+        ledger.base_vault = ctx.accounts.base_vault.key();
+        ledger.quote_vault = ctx.accounts.quote_vault.key();
+        ledger.fills = [PendingFill::default(); SETTLEMENT_LEDGER_SLOTS];
+        ledger.bump = ctx.bumps.settlement_ledger;
+        ledger.released = [0; RELEASED_ESCROW_SLOTS];
+        Ok(())
+    }
+
+    /// Pay out a matched pair from the escrow vaults
+    /// Permissionless. The buyer is paid `filled_size` base out of the
+    /// seller's escrow and the seller `filled_size * price` quote out of the
+    /// buyer's, in one instruction: a match settles in full against both
+    /// escrows or not at all, so no leg is ever paid from another order's
+    /// deposit. The match's maker/taker fees move to the fee vault: the
+    /// seller's out of its proceeds, the buyer's out of its escrow. Either
+    /// side's order bond may be passed to mark it filled for `release_bond`.
+    pub fn settle_fill(ctx: Context<SettleFill>, ledger_slot: u8) -> Result<()> {
+        let fill = *ctx.accounts.settlement_ledger.fills
+            .get(ledger_slot as usize)
+            .ok_or(ErrorCode::InvalidSettlement)?;
+        let bid_escrow = &ctx.accounts.bid_escrow;
+        let ask_escrow = &ctx.accounts.ask_escrow;
+        require!(
+            fill.bid_order_id != 0
+                && bid_escrow.is_buy
+                && bid_escrow.order_id == fill.bid_order_id
+                && !ask_escrow.is_buy
+                && ask_escrow.order_id == fill.ask_order_id,
+            ErrorCode::InvalidSettlement
+        );
+        let ledger = &ctx.accounts.settlement_ledger;
+        require!(
+            ctx.accounts.base_vault.key() == ledger.base_vault
+                && ctx.accounts.quote_vault.key() == ledger.quote_vault,
+            ErrorCode::InvalidSettlement
+        );
+
+        let (notional, proceeds, fees) = charge_escrows(
+            &fill,
+            &mut ctx.accounts.bid_escrow,
+            &mut ctx.accounts.ask_escrow,
+        )?;
+        ctx.accounts.order_book.accrue_fees(fees)?;
+
+        ctx.accounts.settlement_ledger.fills[ledger_slot as usize] = PendingFill::default();
+
+        // A settled fill proves the order traded, which frees its bond
+        if let Some(bid_bond) = ctx.accounts.bid_bond.as_mut() {
+            bid_bond.filled = true;
+        }
+        if let Some(ask_bond) = ctx.accounts.ask_bond.as_mut() {
+            ask_bond.filled = true;
+        }
+
+        let order_book = &ctx.accounts.order_book;
+        let bump = [order_book.bump];
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"orderbook",
+            order_book.base_mint.as_ref(),
+            order_book.quote_mint.as_ref(),
+            &bump,
+        ]];
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.base_vault.to_account_info(),
+            to: ctx.accounts.buyer_token.to_account_info(),
+            authority: order_book.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        );
+        token::transfer(cpi_ctx, fill.filled_size)?;
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.quote_vault.to_account_info(),
+            to: ctx.accounts.seller_token.to_account_info(),
+            authority: order_book.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        );
+        token::transfer(cpi_ctx, proceeds)?;
+
+        if fees > 0 {
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.quote_vault.to_account_info(),
+                to: ctx.accounts.fee_vault.to_account_info(),
+                authority: order_book.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                cpi_accounts,
+                signer_seeds,
+            );
+            token::transfer(cpi_ctx, fees)?;
+        }
+
+        let clock = Clock::get()?;
+        emit!(FillSettled {
+            order_book: order_book.key(),
+            bid_order_id: fill.bid_order_id,
+            ask_order_id: fill.ask_order_id,
+            buyer: ctx.accounts.bid_escrow.owner,
+            seller: ctx.accounts.ask_escrow.owner,
+            filled_size: fill.filled_size,
+            notional,
+            fees,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Drop a ledger match without paying either side
+    /// Every order that can rest is escrowed, so this is only an escape hatch
+    /// for a match whose escrows can't cover it; both escrows keep their
+    /// deposits and become withdrawable once the order is off the book
+    pub fn discard_fill(ctx: Context<DiscardFill>, ledger_slot: u8) -> Result<()> {
+        let ledger = &mut ctx.accounts.settlement_ledger;
+        let fill = *ledger.fills
+            .get(ledger_slot as usize)
+            .ok_or(ErrorCode::InvalidSettlement)?;
+        require!(fill.bid_order_id != 0, ErrorCode::InvalidSettlement);
+        ledger.fills[ledger_slot as usize] = PendingFill::default();
+        Ok(())
+    }
+
+    /// Return whatever an order's escrow didn't trade away
+    /// Open once the order was cancelled, filled or never rested, once it has been
+    /// expired for ESCROW_REFUND_DELAY_SLOTS, or once its placing computation
+    /// has gone COMPUTATION_TIMEOUT_SLOTS without a callback, and only while
+    /// none of its fills wait in the ledger. Closes the escrow to its owner.
+    pub fn withdraw_escrow(ctx: Context<WithdrawEscrow>) -> Result<()> {
+        let order_escrow = &ctx.accounts.order_escrow;
+        let current_slot = Clock::get()?.slot;
+        let expired = order_escrow.expiry_slot != 0
+            && current_slot > order_escrow.expiry_slot.saturating_add(ESCROW_REFUND_DELAY_SLOTS);
+        // An order ID is only ever assigned by a successful callback
+        let never_placed = order_escrow.order_id == 0
+            && current_slot > order_escrow.placed_slot.saturating_add(COMPUTATION_TIMEOUT_SLOTS);
+        let released = ctx.accounts.settlement_ledger.take_release(order_escrow.order_id);
+        require!(
+            order_escrow.refundable || released || expired || never_placed,
+            ErrorCode::EscrowLocked
+        );
+        let ledger = &ctx.accounts.settlement_ledger;
+        require!(!ledger.has_fill(order_escrow.order_id), ErrorCode::EscrowLocked);
+        let vault = if order_escrow.is_buy { ledger.quote_vault } else { ledger.base_vault };
+        require!(ctx.accounts.escrow_vault.key() == vault, ErrorCode::InvalidSettlement);
+
+        let refund = order_escrow.deposited - order_escrow.spent;
+        if refund > 0 {
+            let order_book = &ctx.accounts.order_book;
+            let bump = [order_book.bump];
+            let signer_seeds: &[&[&[u8]]] = &[&[
+                b"orderbook",
+                order_book.base_mint.as_ref(),
+                order_book.quote_mint.as_ref(),
+                &bump,
+            ]];
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.escrow_vault.to_account_info(),
+                to: ctx.accounts.owner_token.to_account_info(),
+                authority: order_book.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                cpi_accounts,
+                signer_seeds,
+            );
+            token::transfer(cpi_ctx, refund)?;
+        }
+
+        let clock = Clock::get()?;
+        emit!(EscrowWithdrawn {
+            order_book: ctx.accounts.order_book.key(),
+            order_id: order_escrow.order_id,
+            owner: order_escrow.owner,
+            refund,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Mark an escrow refundable once its order has left the book
+    /// Permissionless. Fills that close an order and `cancel_all_orders` list
+    /// the order in the ledger's `released` entries; this clears the entry
+    /// so the list doesn't fill up while owners have yet to withdraw.
+    pub fn release_escrow(ctx: Context<ReleaseEscrow>) -> Result<()> {
+        let order_id = ctx.accounts.order_escrow.order_id;
+        require!(
+            ctx.accounts.settlement_ledger.take_release(order_id),
+            ErrorCode::EscrowLocked
+        );
+        ctx.accounts.order_escrow.refundable = true;
+        Ok(())
+    }
+
+    /// Check that the escrow vaults hold what the escrows say they should
+    /// Permissionless and read-only. Remaining accounts are every open
+    /// `OrderEscrow` and `TwapOrder` of the book. The base and quote vaults
+    /// must hold exactly what those escrows haven't paid out yet plus the
+    /// unreleased TWAP reserves, and every unsettled ledger match must be
+    /// covered by its two escrows. The result is emitted as
+    /// `SettlementReconciled`; a missing escrow shows up as a shortfall.
+    pub fn reconcile_settlement<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ReconcileSettlement<'info>>,
+    ) -> Result<()> {
+        let ledger = &ctx.accounts.settlement_ledger;
+        require!(
+            ctx.accounts.base_vault.key() == ledger.base_vault
+                && ctx.accounts.quote_vault.key() == ledger.quote_vault,
+            ErrorCode::InvalidSettlement
+        );
+
+        let order_book_key = ctx.accounts.order_book.key();
+        let mut seen = Vec::with_capacity(ctx.remaining_accounts.len());
+        let mut escrows = Vec::new();
+        let (mut base_reserve, mut quote_reserve) = (0u64, 0u64);
+        for info in ctx.remaining_accounts.iter() {
+            require!(!seen.contains(info.key), ErrorCode::InvalidSettlement);
+            seen.push(*info.key);
+
+            if let Ok(escrow) = Account::<OrderEscrow>::try_from(info) {
+                require!(escrow.order_book == order_book_key, ErrorCode::InvalidSettlement);
+                escrows.push(escrow.into_inner());
+            } else {
+                let twap_order = Account::<TwapOrder>::try_from(info)
+                    .map_err(|_| ErrorCode::InvalidSettlement)?;
+                require!(twap_order.order_book == order_book_key, ErrorCode::InvalidSettlement);
+                let reserve = if twap_order.is_buy { &mut quote_reserve } else { &mut base_reserve };
+                *reserve = reserve.checked_add(twap_order.reserve).ok_or(ErrorCode::MathOverflow)?;
+            }
+        }
+
+        let tally = ledger.reconcile(&escrows, base_reserve, quote_reserve)?;
+        let base_held = ctx.accounts.base_vault.amount;
+        let quote_held = ctx.accounts.quote_vault.amount;
+
+        let clock = Clock::get()?;
+        emit!(SettlementReconciled {
+            order_book: order_book_key,
+            base_expected: tally.base_expected,
+            base_held,
+            quote_expected: tally.quote_expected,
+            quote_held,
+            pending_fills: tally.pending_fills,
+            unbacked_fills: tally.unbacked_fills,
+            consistent: tally.is_consistent(base_held, quote_held),
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Randomize time priority among orders entering within the same window
+    /// Limit orders added within one window of `random_priority_window_slots`
+    /// rank at equal prices by a draw from the MPC RNG rather than arrival,
+    /// blunting latency races. 0 restores strict time priority.
+    pub fn set_random_priority_window(
+        ctx: Context<SetOrderParams>,
+        random_priority_window_slots: u64,
+    ) -> Result<()> {
+        validate_random_priority_window(random_priority_window_slots)?;
+        ctx.accounts.order_book.random_priority_window_slots = random_priority_window_slots;
+        Ok(())
+    }
+
+    /// Set the fixed-point precision of normalized prices in events
+    pub fn set_price_display_decimals(
+        ctx: Context<SetOrderParams>,
+        price_display_decimals: u8,
+    ) -> Result<()> {
+        require!(
+            price_display_decimals <= MAX_PRICE_DISPLAY_DECIMALS,
+            ErrorCode::InvalidOrderParams
+        );
+        ctx.accounts.order_book.price_display_decimals = price_display_decimals;
+        Ok(())
+    }
+
+    /// Create the price oracle liquidations on this book are priced from
+    /// `updater` is the only key allowed to publish prices
+    pub fn init_price_oracle(ctx: Context<InitPriceOracle>, updater: Pubkey) -> Result<()> {
+        let oracle = &mut ctx.accounts.oracle;
+        oracle.order_book = ctx.accounts.order_book.key();
+        oracle.updater = updater;
+        oracle.price = 0;
+        oracle.updated_slot = 0;
+        oracle.bump = ctx.bumps.oracle;
+        ctx.accounts.order_book.oracle = oracle.key();
+        Ok(())
+    }
+
+    /// Publish the current price, in raw quote atoms per base atom
+    pub fn update_oracle_price(ctx: Context<UpdateOraclePrice>, price: u64) -> Result<()> {
+        require!(price > 0, ErrorCode::InvalidOraclePrice);
+        let oracle = &mut ctx.accounts.oracle;
+        oracle.price = price;
+        oracle.updated_slot = Clock::get()?.slot;
+
+        let clock = Clock::get()?;
+        emit!(OraclePriceUpdated {
+            order_book: oracle.order_book,
+            price,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Authorize a liquidator and the band below the oracle price it may sell into
+    pub fn set_liquidator(
+        ctx: Context<SetOrderParams>,
+        liquidator: Pubkey,
+        liquidation_band_bps: u16,
+    ) -> Result<()> {
+        require!(liquidation_band_bps <= 10000, ErrorCode::InvalidOrderParams);
+        let order_book = &mut ctx.accounts.order_book;
+        order_book.liquidator = liquidator;
+        order_book.liquidation_band_bps = liquidation_band_bps;
+        Ok(())
+    }
+
+    /// Re-arm a tripped circuit breaker around a new reference price
+    pub fn reset_circuit_breaker(
+        ctx: Context<SetOrderParams>,
+        reference_price: u64,
+    ) -> Result<()> {
+        let order_book = &mut ctx.accounts.order_book;
+        order_book.reference_price = reference_price;
+        order_book.circuit_breaker_tripped = false;
+
+        let clock = Clock::get()?;
+        emit!(CircuitBreakerReset {
+            order_book: order_book.key(),
+            reference_price,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Create the encrypted order book state for an order book
+    /// Must run once before orders can be added
+    pub fn init_order_book_state(
+        ctx: Context<InitOrderBookState>,
+        computation_offset: u64,
+    ) -> Result<()> {
+        require!(!ctx.accounts.order_book.paused, ErrorCode::ProgramPaused);
+        assert_circuit_version(&ctx.accounts.circuit_version)?;
+        ensure_computation_fee(&ctx.accounts.payer, 0)?;
+
+        let book_state = &mut ctx.accounts.order_book_state;
+        book_state.order_book = ctx.accounts.order_book.key();
+        book_state.bump = ctx.bumps.order_book_state;
+        book_state.state_nonce = 0;
+        book_state.encrypted_state = [[0u8; 32]; ORDER_BOOK_STATE_WORDS];
+
+        let args = ArgBuilder::new().build();
 
         ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
 
-        // Queue MPC computation to add order
         queue_computation(
             ctx.accounts,
             computation_offset,
             args,
             None,
-            vec![AddOrderCallback::callback_ix(
+            vec![InitOrderBookStateCallback::callback_ix(
                 computation_offset,
                 &ctx.accounts.mxe_account,
-                &[],
+                &CallbackAccountsBuilder::new()
+                    .writable(ctx.accounts.order_book_state.key())
+                    .build(),
             )?],
             1,
             0,
@@ -88,53 +970,180 @@ pub mod dark_pool {
         Ok(())
     }
 
-    /// Callback after order is added
-    #[arcium_callback(encrypted_ix = "add_order")]
-    pub fn add_order_callback(
-        ctx: Context<AddOrderCallback>,
-        output: SignedComputationOutputs<AddOrderOutput>,
+    /// Callback storing the empty encrypted order book
+    #[arcium_callback(encrypted_ix = "init_order_book_state")]
+    pub fn init_order_book_state_callback(
+        ctx: Context<InitOrderBookStateCallback>,
+        output: SignedComputationOutputs<InitOrderBookStateOutput>,
     ) -> Result<()> {
-        let result = match output.verify_output(
+        let book_state = match output.verify_output(
             &ctx.accounts.cluster_account,
             &ctx.accounts.computation_account,
         ) {
-            Ok(AddOrderOutput { order_id, success }) => {
-                if !success {
-                    return Err(ErrorCode::OrderFailed.into());
-                }
-                order_id
-            }
+            Ok(InitOrderBookStateOutput { book_state }) => book_state,
             Err(_) => return Err(ErrorCode::AbortedComputation.into()),
         };
 
-        emit!(OrderAdded { order_id: result });
+        ctx.accounts.order_book_state.write(book_state);
         Ok(())
     }
 
-    /// Trigger order matching in the dark pool
-    /// MPC nodes will match orders without revealing individual order details
-    pub fn match_orders(
-        ctx: Context<MatchOrders>,
+    /// Add a hidden order to the dark pool
+    /// Order details (price, size, type) are encrypted. A market order fills
+    /// immediately against resting orders within its slippage bound and never
+    /// rests; any unfilled remainder is dropped, and one that fills nothing
+    /// fails. Its bond is releasable as soon as it executes. A `post_only`
+    /// limit order fails instead of resting if it would cross the book.
+    /// `fee_payer` may be a relayer sponsoring the transaction; the order,
+    /// its bond and the bond account's rent all belong to the signing trader.
+    /// The trader escrows `escrow_amount` of base (sell) or quote (buy) into
+    /// the book's vault; fills settle from it via `settle_fill` and the rest
+    /// is returned by `withdraw_escrow`.
+    pub fn add_order(
+        ctx: Context<AddOrder>,
         computation_offset: u64,
+        encrypted_price: [u8; 64],  // Encrypted limit price, or reference price for a market order
+        encrypted_size: [u8; 64],   // Encrypted order size
+        encrypted_display_size: [u8; 64], // Encrypted iceberg slice size (0 = fully visible)
+        encrypted_order_type: [u8; 64], // Encrypted ORDER_TYPE_LIMIT or ORDER_TYPE_MARKET
+        encrypted_max_slippage: [u8; 64], // Encrypted market order slippage bound, in bps
+        is_buy: bool,               // Order side (buy/sell)
+        post_only: bool,            // Reject rather than rest if the order would cross
+        expiry_slot: u64,           // Last slot the order may match, 0 = never expires
+        escrow_amount: u64,         // Base for a sell, quote for a buy; must cover the order
         pub_key: [u8; 32],
         nonce: u128,
     ) -> Result<()> {
+        require!(!ctx.accounts.order_book.paused, ErrorCode::ProgramPaused);
+        assert_circuit_version(&ctx.accounts.circuit_version)?;
+        let locked = fee_payer_locked(&ctx.accounts.fee_payer.key(), &ctx.accounts.payer.key());
+        ensure_computation_fee(&ctx.accounts.fee_payer, locked)?;
+        require!(
+            ctx.accounts.order_book.paused_ops & PAUSE_ADD_ORDERS == 0,
+            ErrorCode::OperationPaused
+        );
+        let current_slot = Clock::get()?.slot;
+        require!(
+            expiry_slot == 0 || expiry_slot >= current_slot,
+            ErrorCode::InvalidExpiry
+        );
+        // Ciphertexts are opaque, but an all-zero blob is never a real order;
+        // reject it here rather than paying for an MPC round-trip
+        ensure_nonempty_ciphertexts(&[encrypted_price, encrypted_size])?;
+
+        // Lock the anti-spam bond alongside the order
+        let transfer_ix = anchor_lang::solana_program::system_instruction::transfer(
+            &ctx.accounts.payer.key(),
+            &ctx.accounts.order_bond.key(),
+            ORDER_BOND_LAMPORTS,
+        );
+        anchor_lang::solana_program::program::invoke(
+            &transfer_ix,
+            &[
+                ctx.accounts.payer.to_account_info(),
+                ctx.accounts.order_bond.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+
+        let order_bond = &mut ctx.accounts.order_bond;
+        order_bond.order_book = ctx.accounts.order_book.key();
+        order_bond.owner = ctx.accounts.payer.key();
+        order_bond.order_id = 0; // Set once the order is accepted
+        order_bond.amount = ORDER_BOND_LAMPORTS;
+        order_bond.placed_slot = current_slot;
+        order_bond.filled = false;
+        order_bond.bump = ctx.bumps.order_bond;
+
+        // Escrow what the order could trade away: base for a sell, quote for
+        // a buy. The deposit is public, so it only bounds the hidden size;
+        // the circuit rejects an order it doesn't cover.
+        require!(escrow_amount > 0, ErrorCode::InvalidOrderParams);
+        let ledger = &ctx.accounts.settlement_ledger;
+        let vault = if is_buy { ledger.quote_vault } else { ledger.base_vault };
+        require!(ctx.accounts.escrow_vault.key() == vault, ErrorCode::InvalidSettlement);
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.trader_token.to_account_info(),
+            to: ctx.accounts.escrow_vault.to_account_info(),
+            authority: ctx.accounts.payer.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+        token::transfer(cpi_ctx, escrow_amount)?;
+
+        let order_escrow = &mut ctx.accounts.order_escrow;
+        order_escrow.order_book = ctx.accounts.order_book.key();
+        order_escrow.owner = ctx.accounts.payer.key();
+        order_escrow.order_id = 0; // Set once the order is accepted
+        order_escrow.is_buy = is_buy;
+        order_escrow.deposited = escrow_amount;
+        order_escrow.spent = 0;
+        order_escrow.received = 0;
+        order_escrow.expiry_slot = expiry_slot;
+        order_escrow.placed_slot = current_slot;
+        order_escrow.refundable = false;
+        order_escrow.bump = ctx.bumps.order_escrow;
+
+        // A market order reports its fills, and any maker it closes, for settlement
+        require!(
+            ledger.free_slots() >= MAX_REPORTED_MATCHES
+                && ledger.free_release_slots() >= MAX_REPORTED_MATCHES,
+            ErrorCode::SettlementBacklog
+        );
+
+        let order_book = &ctx.accounts.order_book;
+        let book_state = &ctx.accounts.order_book_state;
+        let (owner_lo, owner_hi) = owner_words(&ctx.accounts.payer.key());
+        
+        // Build encrypted arguments
+        // The book state is passed by reference so nodes read the latest ciphertext
         let args = ArgBuilder::new()
             .x25519_pubkey(pub_key)
             .plaintext_u128(nonce)
+            .encrypted_bytes(encrypted_price)
+            .encrypted_bytes(encrypted_size)
+            .encrypted_bytes(encrypted_display_size)
+            .encrypted_bytes(encrypted_order_type)
+            .encrypted_bytes(encrypted_max_slippage)
+            .plaintext_u128(book_state.state_nonce)
+            .account(
+                book_state.key(),
+                OrderBookState::STATE_OFFSET,
+                OrderBookState::STATE_LEN,
+            )
+            .plaintext_bool(is_buy)
+            .plaintext_bool(post_only)
+            .plaintext_u128(owner_lo)
+            .plaintext_u128(owner_hi)
+            .plaintext_u64(order_book.tick_size)
+            .plaintext_u64(order_book.min_order_size)
+            .plaintext_u64(order_book.max_order_size)
+            .plaintext_u64(order_book.auction_volume_threshold)
+            .plaintext_u64(expiry_slot)
+            .plaintext_u64(escrow_amount)
+            .plaintext_u64(order_book.random_priority_window_slots)
+            .plaintext_u16(order_book.maker_fee_rate)
+            .plaintext_u16(order_book.taker_fee_rate)
+            .plaintext_u64(current_slot)
             .build();
 
         ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
 
+        // Queue MPC computation to add order
         queue_computation(
             ctx.accounts,
             computation_offset,
             args,
             None,
-            vec![MatchOrdersCallback::callback_ix(
+            vec![AddOrderCallback::callback_ix(
                 computation_offset,
                 &ctx.accounts.mxe_account,
-                &[],
+                &CallbackAccountsBuilder::new()
+                    .writable(ctx.accounts.order_book.key())
+                    .writable(ctx.accounts.order_book_state.key())
+                    .writable(ctx.accounts.order_bond.key())
+                    .writable(ctx.accounts.order_escrow.key())
+                    .writable(ctx.accounts.settlement_ledger.key())
+                    .build(),
             )?],
             1,
             0,
@@ -143,40 +1152,358 @@ pub mod dark_pool {
         Ok(())
     }
 
-    /// Callback after order matching completes
-    #[arcium_callback(encrypted_ix = "match_orders")]
-    pub fn match_orders_callback(
-        ctx: Context<MatchOrdersCallback>,
-        output: SignedComputationOutputs<MatchOutput>,
+    /// Add a resting limit order funded from a private_pay balance
+    /// `funding` holds the amount `fund_dark_pool_order` debited, encrypted
+    /// to this program's MXE; it becomes the order's base size for a sell,
+    /// or its quote budget for a buy, depending on the funding's mint. The
+    /// amount and price stay encrypted throughout. The funding is consumed
+    /// only once the order rests, which moves its public escrow from
+    /// private_pay's vault into this book's vault; until then the order's
+    /// `OrderEscrow` holds nothing, so a rejected order can be retried or
+    /// its funding re-credited with `recredit_dark_pool_funding`. Once
+    /// placed, fills settle from the escrow like any other order's, and
+    /// `withdraw_escrow` returns whatever isn't traded, including a buy's
+    /// remainder below one unit at the limit price.
+    pub fn add_funded_order(
+        ctx: Context<AddFundedOrder>,
+        computation_offset: u64,
+        encrypted_price: [u8; 64], // Encrypted limit price
+        expiry_slot: u64,          // Last slot the order may match, 0 = never expires
+        pub_key: [u8; 32],
+        nonce: u128,
+    ) -> Result<()> {
+        require!(!ctx.accounts.order_book.paused, ErrorCode::ProgramPaused);
+        assert_circuit_version(&ctx.accounts.circuit_version)?;
+        ensure_computation_fee(&ctx.accounts.payer, 0)?;
+        require!(
+            ctx.accounts.order_book.paused_ops & PAUSE_ADD_ORDERS == 0,
+            ErrorCode::OperationPaused
+        );
+        let current_slot = Clock::get()?.slot;
+        require!(
+            expiry_slot == 0 || expiry_slot >= current_slot,
+            ErrorCode::InvalidExpiry
+        );
+        ensure_nonempty_ciphertexts(&[encrypted_price])?;
+
+        let order_book = &ctx.accounts.order_book;
+        let funding = &ctx.accounts.funding;
+        let is_buy = funding.mint == order_book.quote_mint;
+        require!(is_buy || funding.mint == order_book.base_mint, ErrorCode::InvalidFunding);
+        let ledger = &ctx.accounts.settlement_ledger;
+        let vault = if is_buy { ledger.quote_vault } else { ledger.base_vault };
+        require!(ctx.accounts.escrow_vault.key() == vault, ErrorCode::InvalidSettlement);
+
+        // Funded by the callback, once the funding's tokens arrive
+        let order_escrow = &mut ctx.accounts.order_escrow;
+        order_escrow.order_book = order_book.key();
+        order_escrow.owner = ctx.accounts.payer.key();
+        order_escrow.order_id = 0;
+        order_escrow.is_buy = is_buy;
+        order_escrow.deposited = 0;
+        order_escrow.spent = 0;
+        order_escrow.received = 0;
+        order_escrow.expiry_slot = expiry_slot;
+        order_escrow.placed_slot = current_slot;
+        order_escrow.refundable = false;
+        order_escrow.bump = ctx.bumps.order_escrow;
+
+        let book_state = &ctx.accounts.order_book_state;
+        let (owner_lo, owner_hi) = owner_words(&ctx.accounts.payer.key());
+
+        let args = ArgBuilder::new()
+            .x25519_pubkey(pub_key)
+            .plaintext_u128(nonce)
+            .encrypted_bytes(encrypted_price)
+            .x25519_pubkey(funding.mxe_pubkey)
+            .plaintext_u128(funding.nonce)
+            .encrypted_bytes(funding.encrypted_amount)
+            .plaintext_u128(book_state.state_nonce)
+            .account(
+                book_state.key(),
+                OrderBookState::STATE_OFFSET,
+                OrderBookState::STATE_LEN,
+            )
+            .plaintext_bool(is_buy)
+            .plaintext_u128(owner_lo)
+            .plaintext_u128(owner_hi)
+            .plaintext_u64(order_book.tick_size)
+            .plaintext_u64(order_book.min_order_size)
+            .plaintext_u64(order_book.max_order_size)
+            .plaintext_u64(expiry_slot)
+            .plaintext_u64(order_book.random_priority_window_slots)
+            .plaintext_u64(current_slot)
+            .build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![AddFundedOrderCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &CallbackAccountsBuilder::new()
+                    .writable(ctx.accounts.order_book.key())
+                    .writable(ctx.accounts.order_book_state.key())
+                    .writable(ctx.accounts.funding.key())
+                    .writable(ctx.accounts.payer.key())
+                    .readonly(ctx.accounts.funding_authority.key())
+                    .readonly(ctx.accounts.pay_config.key())
+                    .readonly(ctx.accounts.private_pay_program.key())
+                    .writable(ctx.accounts.order_escrow.key())
+                    .writable(ctx.accounts.pay_token_vault.key())
+                    .writable(ctx.accounts.escrow_vault.key())
+                    .readonly(ctx.accounts.token_program.key())
+                    .build(),
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Callback after a funded order is added
+    /// Consumes the funding through private_pay, moving its escrow into the
+    /// book's vault, so a second order queued against the same funding fails
+    /// here instead of resting unbacked
+    #[arcium_callback(encrypted_ix = "add_funded_order")]
+    pub fn add_funded_order_callback(
+        ctx: Context<AddFundedOrderCallback>,
+        output: SignedComputationOutputs<AddFundedOrderOutput>,
+    ) -> Result<()> {
+        let order_id = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(AddFundedOrderOutput { book_state, order_id, success, book_full }) => {
+                check_order_inserted(false, book_full, success)?;
+                ctx.accounts.order_book_state.write(book_state);
+                order_id
+            }
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let escrow_amount = ctx.accounts.funding.escrow_amount;
+        let bump = [ctx.bumps.funding_authority];
+        let signer_seeds: &[&[&[u8]]] = &[&[b"funding_authority", &bump]];
+        let cpi_accounts = private_pay::cpi::accounts::ConsumeDarkPoolFunding {
+            dark_pool_authority: ctx.accounts.funding_authority.to_account_info(),
+            config: ctx.accounts.pay_config.to_account_info(),
+            funding: ctx.accounts.funding.to_account_info(),
+            owner: ctx.accounts.owner.to_account_info(),
+            token_vault: ctx.accounts.pay_token_vault.to_account_info(),
+            destination: ctx.accounts.escrow_vault.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.private_pay_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        );
+        private_pay::cpi::consume_dark_pool_funding(cpi_ctx)?;
+
+        let order_escrow = &mut ctx.accounts.order_escrow;
+        order_escrow.order_id = order_id;
+        order_escrow.deposited = escrow_amount;
+
+        let order_book = &mut ctx.accounts.order_book;
+        order_book.record_resting_order()?;
+
+        let clock = Clock::get()?;
+        emit!(FundedOrderAdded {
+            order_book: order_book.key(),
+            order_id,
+            funding: ctx.accounts.funding.key(),
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Callback after order is added
+    #[arcium_callback(encrypted_ix = "add_order")]
+    pub fn add_order_callback(
+        ctx: Context<AddOrderCallback>,
+        output: SignedComputationOutputs<AddOrderOutput>,
     ) -> Result<()> {
         let result = match output.verify_output(
             &ctx.accounts.cluster_account,
             &ctx.accounts.computation_account,
         ) {
-            Ok(MatchOutput { matches_count, total_volume }) => (matches_count, total_volume),
+            Ok(AddOrderOutput {
+                book_state,
+                order_id,
+                success,
+                auction_ready,
+                book_full,
+                rested,
+                closed_orders,
+                duplicate,
+                market_fills,
+            }) => {
+                check_order_inserted(duplicate, book_full, success)?;
+                ctx.accounts.order_book_state.write(book_state);
+                (order_id, auction_ready, rested, closed_orders, market_fills)
+            }
             Err(_) => return Err(ErrorCode::AbortedComputation.into()),
         };
 
-        emit!(OrdersMatched {
-            matches_count: result.0,
-            total_volume: result.1,
+        let (order_id, auction_ready, rested, closed_orders, market_fills) = result;
+
+        let order_book = &mut ctx.accounts.order_book;
+        order_book.total_orders = order_book.total_orders.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+        order_book.record_removed_orders(closed_orders);
+        if rested {
+            order_book.active_orders = order_book.active_orders.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+        }
+
+        // Callbacks can't queue computations themselves, so flag the book
+        // and let any crank run the auction
+        let clock = Clock::get()?;
+        if auction_ready && !order_book.auction_pending {
+            order_book.auction_pending = true;
+            emit!(BatchAuctionTriggered {
+                order_book: order_book.key(),
+                slot: clock.slot,
+                unix_timestamp: clock.unix_timestamp,
+            });
+        }
+
+        // A market order executed without resting, so its bond is free
+        ctx.accounts.order_bond.order_id = order_id;
+        ctx.accounts.order_bond.filled = !rested;
+
+        // A market order's fills settle from its escrow like any other; it
+        // never rests, so the rest is refundable once they have
+        ctx.accounts.order_escrow.order_id = order_id;
+        ctx.accounts.order_escrow.refundable = !rested;
+        let ledger = &mut ctx.accounts.settlement_ledger;
+        for fill in market_fills.iter().filter(|fill| fill.bid_order_id != 0) {
+            let pending = PendingFill {
+                bid_order_id: fill.bid_order_id,
+                ask_order_id: fill.ask_order_id,
+                filled_size: fill.filled_size,
+                price: fill.price,
+                bid_fee: fill.bid_fee,
+                ask_fee: fill.ask_fee,
+            };
+            ledger.record(pending, fill.bid_closed, fill.ask_closed)?;
+            emit_order_filled(&pending, fill.bid_is_maker, &clock);
+        }
+
+        emit!(OrderAdded {
+            order_id,
+            rested,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
         });
         Ok(())
     }
 
-    /// Cancel an existing order
-    pub fn cancel_order(
-        ctx: Context<CancelOrder>,
+    /// Add a dutch auction sell order to the dark pool
+    /// The ask starts at an encrypted start price and declines linearly to an
+    /// encrypted floor over an encrypted number of slots; matching reprices it
+    /// from the current slot so the schedule itself is never revealed. The
+    /// seller escrows `escrow_amount` base like any `add_order` sell.
+    pub fn add_dutch_order(
+        ctx: Context<AddDutchOrder>,
         computation_offset: u64,
-        order_id: u64,
+        encrypted_start_price: [u8; 64],
+        encrypted_end_price: [u8; 64],
+        encrypted_size: [u8; 64],
+        encrypted_duration: [u8; 64],
+        escrow_amount: u64, // Base escrowed against the order; must cover its size
         pub_key: [u8; 32],
         nonce: u128,
     ) -> Result<()> {
+        require!(!ctx.accounts.order_book.paused, ErrorCode::ProgramPaused);
+        assert_circuit_version(&ctx.accounts.circuit_version)?;
+        ensure_computation_fee(&ctx.accounts.payer, ORDER_BOND_LAMPORTS)?;
+        require!(
+            ctx.accounts.order_book.paused_ops & PAUSE_ADD_ORDERS == 0,
+            ErrorCode::OperationPaused
+        );
+
+        // Dutch orders lock the same anti-spam bond as limit orders
+        let transfer_ix = anchor_lang::solana_program::system_instruction::transfer(
+            &ctx.accounts.payer.key(),
+            &ctx.accounts.order_bond.key(),
+            ORDER_BOND_LAMPORTS,
+        );
+        anchor_lang::solana_program::program::invoke(
+            &transfer_ix,
+            &[
+                ctx.accounts.payer.to_account_info(),
+                ctx.accounts.order_bond.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+
+        let start_slot = Clock::get()?.slot;
+
+        let order_bond = &mut ctx.accounts.order_bond;
+        order_bond.order_book = ctx.accounts.order_book.key();
+        order_bond.owner = ctx.accounts.payer.key();
+        order_bond.order_id = 0; // Set once the order is accepted
+        order_bond.amount = ORDER_BOND_LAMPORTS;
+        order_bond.placed_slot = start_slot;
+        order_bond.filled = false;
+        order_bond.bump = ctx.bumps.order_bond;
+
+        require!(escrow_amount > 0, ErrorCode::InvalidOrderParams);
+        require!(
+            ctx.accounts.escrow_vault.key() == ctx.accounts.settlement_ledger.base_vault,
+            ErrorCode::InvalidSettlement
+        );
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.trader_token.to_account_info(),
+            to: ctx.accounts.escrow_vault.to_account_info(),
+            authority: ctx.accounts.payer.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+        token::transfer(cpi_ctx, escrow_amount)?;
+
+        let order_escrow = &mut ctx.accounts.order_escrow;
+        order_escrow.order_book = ctx.accounts.order_book.key();
+        order_escrow.owner = ctx.accounts.payer.key();
+        order_escrow.order_id = 0; // Set once the order is accepted
+        order_escrow.is_buy = false;
+        order_escrow.deposited = escrow_amount;
+        order_escrow.spent = 0;
+        order_escrow.received = 0;
+        order_escrow.expiry_slot = 0;
+        order_escrow.placed_slot = start_slot;
+        order_escrow.refundable = false;
+        order_escrow.bump = ctx.bumps.order_escrow;
+
+        let order_book = &ctx.accounts.order_book;
+        let book_state = &ctx.accounts.order_book_state;
+        let (owner_lo, owner_hi) = owner_words(&ctx.accounts.payer.key());
+
         let args = ArgBuilder::new()
             .x25519_pubkey(pub_key)
             .plaintext_u128(nonce)
-            .plaintext_u64(order_id)
-            .plaintext_pubkey(ctx.accounts.payer.key())
+            .encrypted_bytes(encrypted_start_price)
+            .encrypted_bytes(encrypted_end_price)
+            .encrypted_bytes(encrypted_size)
+            .encrypted_bytes(encrypted_duration)
+            .plaintext_u128(book_state.state_nonce)
+            .account(
+                book_state.key(),
+                OrderBookState::STATE_OFFSET,
+                OrderBookState::STATE_LEN,
+            )
+            .plaintext_u128(owner_lo)
+            .plaintext_u128(owner_hi)
+            .plaintext_u64(start_slot)
+            .plaintext_u64(order_book.tick_size)
+            .plaintext_u64(order_book.min_order_size)
+            .plaintext_u64(order_book.max_order_size)
+            .plaintext_u64(escrow_amount)
             .build();
 
         ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
@@ -186,10 +1513,15 @@ pub mod dark_pool {
             computation_offset,
             args,
             None,
-            vec![CancelOrderCallback::callback_ix(
+            vec![AddDutchOrderCallback::callback_ix(
                 computation_offset,
                 &ctx.accounts.mxe_account,
-                &[],
+                &CallbackAccountsBuilder::new()
+                    .writable(ctx.accounts.order_book.key())
+                    .writable(ctx.accounts.order_book_state.key())
+                    .writable(ctx.accounts.order_bond.key())
+                    .writable(ctx.accounts.order_escrow.key())
+                    .build(),
             )?],
             1,
             0,
@@ -198,82 +1530,4261 @@ pub mod dark_pool {
         Ok(())
     }
 
-    /// Callback after order cancellation
-    #[arcium_callback(encrypted_ix = "cancel_order")]
-    pub fn cancel_order_callback(
-        ctx: Context<CancelOrderCallback>,
-        output: SignedComputationOutputs<CancelOutput>,
+    /// Callback after a dutch order is added
+    #[arcium_callback(encrypted_ix = "add_dutch_order")]
+    pub fn add_dutch_order_callback(
+        ctx: Context<AddDutchOrderCallback>,
+        output: SignedComputationOutputs<AddDutchOrderOutput>,
     ) -> Result<()> {
-        let result = match output.verify_output(
+        let order_id = match output.verify_output(
             &ctx.accounts.cluster_account,
             &ctx.accounts.computation_account,
         ) {
-            Ok(CancelOutput { order_id, success }) => {
-                if !success {
-                    return Err(ErrorCode::CancelFailed.into());
-                }
+            Ok(AddDutchOrderOutput { book_state, order_id, success, book_full }) => {
+                check_order_inserted(false, book_full, success)?;
+                ctx.accounts.order_book_state.write(book_state);
                 order_id
             }
             Err(_) => return Err(ErrorCode::AbortedComputation.into()),
         };
 
-        emit!(OrderCancelled { order_id: result });
+        let order_book = &mut ctx.accounts.order_book;
+        order_book.record_resting_order()?;
+
+        ctx.accounts.order_bond.order_id = order_id;
+        ctx.accounts.order_escrow.order_id = order_id;
+
+        let clock = Clock::get()?;
+        emit!(DutchOrderAdded {
+            order_book: order_book.key(),
+            order_id,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Schedule a TWAP order: an encrypted parent order rested on the book as
+    /// limit-order slices, one every `slice_interval_slots`, by
+    /// `release_twap_slice`. Price, total and slice sizes all stay encrypted.
+    /// The owner escrows `escrow_amount` (base for a sell, quote for a buy)
+    /// as a reserve each slice draws `slice_escrow` from, and prefunds the
+    /// rent of every slice escrow the reserve can back so cranks aren't out
+    /// of pocket. Slices carry no order bond; stop a schedule with
+    /// `close_twap_order` and pull resting slices with `cancel_all_orders`
+    pub fn schedule_twap_order(
+        ctx: Context<ScheduleTwapOrder>,
+        computation_offset: u64,
+        twap_id: u64,
+        encrypted_price: [u8; 64],
+        encrypted_total_size: [u8; 64],
+        encrypted_slice_size: [u8; 64],
+        is_buy: bool,
+        slice_interval_slots: u64,
+        escrow_amount: u64, // Reserve backing every slice
+        slice_escrow: u64,  // Escrowed per slice; must cover a full slice
+        pub_key: [u8; 32],
+        nonce: u128,
+    ) -> Result<()> {
+        require!(!ctx.accounts.order_book.paused, ErrorCode::ProgramPaused);
+        assert_circuit_version(&ctx.accounts.circuit_version)?;
+        ensure_computation_fee(&ctx.accounts.payer, 0)?;
+        require!(
+            ctx.accounts.order_book.paused_ops & PAUSE_ADD_ORDERS == 0,
+            ErrorCode::OperationPaused
+        );
+        require!(slice_interval_slots > 0, ErrorCode::InvalidOrderParams);
+        require!(
+            slice_escrow > 0 && escrow_amount >= slice_escrow,
+            ErrorCode::InvalidOrderParams
+        );
+        let max_slices = escrow_amount / slice_escrow;
+
+        let ledger = &ctx.accounts.settlement_ledger;
+        let vault = if is_buy { ledger.quote_vault } else { ledger.base_vault };
+        require!(ctx.accounts.escrow_vault.key() == vault, ErrorCode::InvalidSettlement);
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.trader_token.to_account_info(),
+            to: ctx.accounts.escrow_vault.to_account_info(),
+            authority: ctx.accounts.payer.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+        token::transfer(cpi_ctx, escrow_amount)?;
+
+        // Returned with the TWAP account by `close_twap_order` if unused
+        let slice_rent = Rent::get()?
+            .minimum_balance(8 + OrderEscrow::SIZE)
+            .checked_mul(max_slices)
+            .ok_or(ErrorCode::MathOverflow)?;
+        let transfer_ix = anchor_lang::solana_program::system_instruction::transfer(
+            &ctx.accounts.payer.key(),
+            &ctx.accounts.twap_order.key(),
+            slice_rent,
+        );
+        anchor_lang::solana_program::program::invoke(
+            &transfer_ix,
+            &[
+                ctx.accounts.payer.to_account_info(),
+                ctx.accounts.twap_order.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+
+        let twap_order = &mut ctx.accounts.twap_order;
+        twap_order.order_book = ctx.accounts.order_book.key();
+        twap_order.owner = ctx.accounts.payer.key();
+        twap_order.twap_id = twap_id;
+        twap_order.is_buy = is_buy;
+        twap_order.slice_interval_slots = slice_interval_slots;
+        twap_order.next_slice_slot = Clock::get()?.slot;
+        twap_order.slices_released = 0;
+        twap_order.active = false; // Set once the parent order is accepted
+        twap_order.bump = ctx.bumps.twap_order;
+        twap_order.reserve = escrow_amount;
+        twap_order.slice_escrow = slice_escrow;
+
+        let order_book = &ctx.accounts.order_book;
+        let args = ArgBuilder::new()
+            .x25519_pubkey(pub_key)
+            .plaintext_u128(nonce)
+            .encrypted_bytes(encrypted_price)
+            .encrypted_bytes(encrypted_total_size)
+            .encrypted_bytes(encrypted_slice_size)
+            .plaintext_bool(is_buy)
+            .plaintext_u64(order_book.tick_size)
+            .plaintext_u64(order_book.min_order_size)
+            .plaintext_u64(order_book.max_order_size)
+            .plaintext_u64(slice_escrow)
+            .plaintext_u64(max_slices)
+            .build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![ScheduleTwapOrderCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &CallbackAccountsBuilder::new()
+                    .writable(ctx.accounts.twap_order.key())
+                    .build(),
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Callback after a TWAP parent order is encrypted
+    #[arcium_callback(encrypted_ix = "schedule_twap_order")]
+    pub fn schedule_twap_order_callback(
+        ctx: Context<ScheduleTwapOrderCallback>,
+        output: SignedComputationOutputs<ScheduleTwapOrderOutput>,
+    ) -> Result<()> {
+        let twap_state = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(ScheduleTwapOrderOutput { twap_state, valid }) => {
+                if !valid {
+                    return Err(ErrorCode::OrderFailed.into());
+                }
+                twap_state
+            }
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let twap_order = &mut ctx.accounts.twap_order;
+        twap_order.write(twap_state);
+        twap_order.active = true;
+
+        let clock = Clock::get()?;
+        emit!(TwapOrderScheduled {
+            order_book: twap_order.order_book,
+            twap_order: twap_order.key(),
+            owner: twap_order.owner,
+            is_buy: twap_order.is_buy,
+            slice_interval_slots: twap_order.slice_interval_slots,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Rest the next slice of a TWAP order on the book
+    /// Permissionless: any crank may release a slice once it is due. The next
+    /// slice is scheduled when this one is queued, so a slice whose
+    /// computation fails is simply skipped until the following interval.
+    /// Each slice is escrowed with `slice_escrow` out of the TWAP's reserve;
+    /// the crank pays the escrow's rent and is refunded from the TWAP account.
+    pub fn release_twap_slice(
+        ctx: Context<ReleaseTwapSlice>,
+        computation_offset: u64,
+    ) -> Result<()> {
+        require!(!ctx.accounts.order_book.paused, ErrorCode::ProgramPaused);
+        assert_circuit_version(&ctx.accounts.circuit_version)?;
+        ensure_computation_fee(&ctx.accounts.payer, 0)?;
+        require!(
+            ctx.accounts.order_book.paused_ops & PAUSE_ADD_ORDERS == 0,
+            ErrorCode::OperationPaused
+        );
+
+        let current_slot = Clock::get()?.slot;
+        let twap_order = &mut ctx.accounts.twap_order;
+        twap_order.draw_slice(current_slot)?;
+
+        let order_escrow = &mut ctx.accounts.order_escrow;
+        order_escrow.order_book = ctx.accounts.order_book.key();
+        order_escrow.owner = twap_order.owner;
+        order_escrow.order_id = 0; // Set once the slice rests
+        order_escrow.is_buy = twap_order.is_buy;
+        order_escrow.deposited = twap_order.slice_escrow;
+        order_escrow.spent = 0;
+        order_escrow.received = 0;
+        order_escrow.expiry_slot = 0;
+        order_escrow.placed_slot = current_slot;
+        order_escrow.refundable = false;
+        order_escrow.bump = ctx.bumps.order_escrow;
+
+        let rent = Rent::get()?.minimum_balance(8 + OrderEscrow::SIZE);
+        **ctx.accounts.twap_order.to_account_info().try_borrow_mut_lamports()? -= rent;
+        **ctx.accounts.payer.to_account_info().try_borrow_mut_lamports()? += rent;
+
+        let twap_order = &ctx.accounts.twap_order;
+        let book_state = &ctx.accounts.order_book_state;
+        let (owner_lo, owner_hi) = owner_words(&twap_order.owner);
+
+        let args = ArgBuilder::new()
+            .plaintext_u128(twap_order.state_nonce)
+            .account(
+                twap_order.key(),
+                TwapOrder::STATE_OFFSET,
+                TwapOrder::STATE_LEN,
+            )
+            .plaintext_u128(book_state.state_nonce)
+            .account(
+                book_state.key(),
+                OrderBookState::STATE_OFFSET,
+                OrderBookState::STATE_LEN,
+            )
+            .plaintext_bool(twap_order.is_buy)
+            .plaintext_u128(owner_lo)
+            .plaintext_u128(owner_hi)
+            .plaintext_u64(current_slot)
+            .build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![ReleaseTwapSliceCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &CallbackAccountsBuilder::new()
+                    .writable(ctx.accounts.order_book.key())
+                    .writable(ctx.accounts.order_book_state.key())
+                    .writable(ctx.accounts.twap_order.key())
+                    .writable(ctx.accounts.order_escrow.key())
+                    .build(),
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Callback after a TWAP slice is released
+    #[arcium_callback(encrypted_ix = "release_twap_slice")]
+    pub fn release_twap_slice_callback(
+        ctx: Context<ReleaseTwapSliceCallback>,
+        output: SignedComputationOutputs<ReleaseTwapSliceOutput>,
+    ) -> Result<()> {
+        let (order_id, exhausted) = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(ReleaseTwapSliceOutput {
+                twap_state,
+                book_state,
+                order_id,
+                inserted,
+                exhausted,
+            }) => {
+                if !inserted {
+                    return Err(ErrorCode::OrderBookFull.into());
+                }
+                ctx.accounts.twap_order.write(twap_state);
+                ctx.accounts.order_book_state.write(book_state);
+                (order_id, exhausted)
+            }
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let order_book = &mut ctx.accounts.order_book;
+        order_book.record_resting_order()?;
+
+        ctx.accounts.order_escrow.order_id = order_id;
+
+        let twap_order = &mut ctx.accounts.twap_order;
+        twap_order.slices_released = twap_order.slices_released
+            .checked_add(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+        if exhausted {
+            twap_order.active = false;
+        }
+
+        let clock = Clock::get()?;
+        emit!(TwapSliceReleased {
+            twap_order: twap_order.key(),
+            order_id,
+            slices_released: twap_order.slices_released,
+            exhausted,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Stop a TWAP order and reclaim its rent and unreleased reserve
+    /// Slices already on the book keep resting with their own escrows;
+    /// cancel them with `cancel_all_orders`
+    pub fn close_twap_order(ctx: Context<CloseTwapOrder>) -> Result<()> {
+        let twap_order = &ctx.accounts.twap_order;
+        let ledger = &ctx.accounts.settlement_ledger;
+        let vault = if twap_order.is_buy { ledger.quote_vault } else { ledger.base_vault };
+        require!(ctx.accounts.escrow_vault.key() == vault, ErrorCode::InvalidSettlement);
+
+        let refund = twap_order.reserve;
+        if refund > 0 {
+            let order_book = &ctx.accounts.order_book;
+            let bump = [order_book.bump];
+            let signer_seeds: &[&[&[u8]]] = &[&[
+                b"orderbook",
+                order_book.base_mint.as_ref(),
+                order_book.quote_mint.as_ref(),
+                &bump,
+            ]];
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.escrow_vault.to_account_info(),
+                to: ctx.accounts.owner_token.to_account_info(),
+                authority: order_book.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                cpi_accounts,
+                signer_seeds,
+            );
+            token::transfer(cpi_ctx, refund)?;
+        }
+
+        let clock = Clock::get()?;
+        emit!(TwapOrderClosed {
+            twap_order: twap_order.key(),
+            owner: twap_order.owner,
+            slices_released: twap_order.slices_released,
+            refund,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Trigger order matching in the dark pool
+    /// MPC nodes will match orders without revealing individual order details.
+    /// `cu_budget` is the compute the crank can spare for the callback, which
+    /// settles and reports every matched pair; it caps the pairs matched at
+    /// `cu_budget / MATCH_PAIR_CU`. A run that uses its whole cap may have
+    /// left crosses behind and bumps `match_cursor`, so the crank calls again
+    /// until it returns to 0. Each run matches against the whole book.
+    pub fn match_orders(
+        ctx: Context<MatchOrders>,
+        computation_offset: u64,
+        cu_budget: u32,
+    ) -> Result<()> {
+        require!(!ctx.accounts.order_book.paused, ErrorCode::ProgramPaused);
+        assert_circuit_version(&ctx.accounts.circuit_version)?;
+        ensure_computation_fee(&ctx.accounts.payer, 0)?;
+        require!(
+            ctx.accounts.order_book.paused_ops & PAUSE_MATCHING == 0,
+            ErrorCode::OperationPaused
+        );
+
+        require!(
+            !ctx.accounts.order_book.circuit_breaker_tripped,
+            ErrorCode::CircuitBreakerTripped
+        );
+        let max_matches = max_matches_for_budget(cu_budget);
+        require!(max_matches > 0, ErrorCode::InvalidOrderParams);
+        ctx.accounts.order_book.match_cap = max_matches;
+        let order_book = &ctx.accounts.order_book;
+        // Every match this run reports needs a ledger slot to settle from,
+        // and every order it closes an entry to release its escrow
+        let ledger = &ctx.accounts.settlement_ledger;
+        require!(
+            ledger.free_slots() >= MAX_REPORTED_MATCHES
+                && ledger.free_release_slots() >= 2 * MAX_REPORTED_MATCHES,
+            ErrorCode::SettlementBacklog
+        );
+
+        let book_state = &ctx.accounts.order_book_state;
+
+        let args = ArgBuilder::new()
+            .plaintext_u128(book_state.state_nonce)
+            .account(
+                book_state.key(),
+                OrderBookState::STATE_OFFSET,
+                OrderBookState::STATE_LEN,
+            )
+            .plaintext_u64(order_book.reference_price)
+            .plaintext_u16(order_book.price_band_bps)
+            .plaintext_u64(Clock::get()?.slot)
+            .plaintext_u16(order_book.maker_fee_rate)
+            .plaintext_u16(order_book.taker_fee_rate)
+            .plaintext_u32(max_matches)
+            .build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![MatchOrdersCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &CallbackAccountsBuilder::new()
+                    .writable(ctx.accounts.order_book.key())
+                    .writable(ctx.accounts.order_book_state.key())
+                    .writable(ctx.accounts.settlement_ledger.key())
+                    .build(),
+            )?],
+            1,
+            0,
+        )?;
+
         Ok(())
     }
+
+    /// Callback after order matching completes
+    #[arcium_callback(encrypted_ix = "match_orders")]
+    pub fn match_orders_callback(
+        ctx: Context<MatchOrdersCallback>,
+        output: SignedComputationOutputs<MatchOutput>,
+    ) -> Result<()> {
+        let result = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(MatchOutput {
+                book_state,
+                matches_count,
+                total_volume,
+                closed_orders,
+                breaker_tripped,
+                conserved,
+                self_trade_prevented,
+                fees_charged,
+                fills,
+                imbalance_tier,
+            }) => {
+                ctx.accounts.order_book_state.write(book_state);
+                (
+                    matches_count,
+                    total_volume,
+                    closed_orders,
+                    breaker_tripped,
+                    conserved,
+                    self_trade_prevented,
+                    fees_charged,
+                    fills,
+                    imbalance_tier,
+                )
+            }
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let order_book = &mut ctx.accounts.order_book;
+        order_book.total_matches = order_book.total_matches
+            .checked_add(result.0 as u64)
+            .ok_or(ErrorCode::MathOverflow)?;
+        order_book.record_removed_orders(result.2);
+
+        // A run that used its whole cap may have left crosses behind
+        order_book.match_cursor =
+            next_match_cursor(order_book.match_cursor, result.0, order_book.match_cap);
+
+        let clock = Clock::get()?;
+        order_book.record_epoch_volume(clock.epoch, result.1)?;
+
+        if result.3 {
+            order_book.circuit_breaker_tripped = true;
+            emit!(CircuitBreakerTripped {
+                order_book: order_book.key(),
+                reference_price: order_book.reference_price,
+                price_band_bps: order_book.price_band_bps,
+                slot: clock.slot,
+                unix_timestamp: clock.unix_timestamp,
+            });
+        }
+
+        emit!(OrdersMatched {
+            matches_count: result.0,
+            total_volume: result.1,
+            self_trade_prevented: result.5,
+            fees_charged: result.6,
+            imbalance_tier: result.8,
+            match_cursor: order_book.match_cursor,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        let reported: Vec<PendingFill> = result
+            .7
+            .iter()
+            .filter(|fill| fill.bid_order_id != 0)
+            .map(|fill| PendingFill {
+                bid_order_id: fill.bid_order_id,
+                ask_order_id: fill.ask_order_id,
+                filled_size: fill.filled_size,
+                price: fill.price,
+                bid_fee: fill.bid_fee,
+                ask_fee: fill.ask_fee,
+            })
+            .collect();
+        emit!(MatchConservation {
+            order_book: order_book.key(),
+            total_volume: result.1,
+            conserved: result.4 && fills_account_for_volume(&reported, result.0, result.1),
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+
+        // match_orders stops once MAX_REPORTED_MATCHES pairs are reported,
+        // so every match of the run is queued for settlement here
+        let ledger = &mut ctx.accounts.settlement_ledger;
+        let flags = result.7.iter().filter(|fill| fill.bid_order_id != 0);
+        for (pending, fill) in reported.iter().zip(flags) {
+            ledger.record(*pending, fill.bid_closed, fill.ask_closed)?;
+            emit_order_filled(pending, fill.bid_is_maker, &clock);
+        }
+        Ok(())
+    }
+
+    /// Free the slots of orders past their expiry slot
+    /// Expiry is checked against the cluster clock when the computation is
+    /// queued. Anyone may crank this; bonds are reclaimed with `release_bond`.
+    pub fn prune_expired_orders(
+        ctx: Context<PruneExpiredOrders>,
+        computation_offset: u64,
+    ) -> Result<()> {
+        require!(!ctx.accounts.order_book.paused, ErrorCode::ProgramPaused);
+        assert_circuit_version(&ctx.accounts.circuit_version)?;
+        ensure_computation_fee(&ctx.accounts.payer, 0)?;
+
+        let book_state = &ctx.accounts.order_book_state;
+
+        let args = ArgBuilder::new()
+            .plaintext_u128(book_state.state_nonce)
+            .account(
+                book_state.key(),
+                OrderBookState::STATE_OFFSET,
+                OrderBookState::STATE_LEN,
+            )
+            .plaintext_u64(Clock::get()?.slot)
+            .build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![PruneExpiredOrdersCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &CallbackAccountsBuilder::new()
+                    .writable(ctx.accounts.order_book.key())
+                    .writable(ctx.accounts.order_book_state.key())
+                    .build(),
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Callback after expired orders are pruned
+    #[arcium_callback(encrypted_ix = "prune_expired_orders")]
+    pub fn prune_expired_orders_callback(
+        ctx: Context<PruneExpiredOrdersCallback>,
+        output: SignedComputationOutputs<PruneExpiredOrdersOutput>,
+    ) -> Result<()> {
+        let count = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(PruneExpiredOrdersOutput { book_state, pruned }) => {
+                ctx.accounts.order_book_state.write(book_state);
+                pruned
+            }
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let order_book = &mut ctx.accounts.order_book;
+        order_book.record_removed_orders(count);
+
+        let clock = Clock::get()?;
+        emit!(OrdersPruned {
+            order_book: order_book.key(),
+            count,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Cancel an existing order
+    pub fn cancel_order(
+        ctx: Context<CancelOrder>,
+        computation_offset: u64,
+        order_id: u64,
+    ) -> Result<()> {
+        require!(!ctx.accounts.order_book.paused, ErrorCode::ProgramPaused);
+        assert_circuit_version(&ctx.accounts.circuit_version)?;
+        ensure_computation_fee(&ctx.accounts.payer, 0)?;
+        require!(
+            ctx.accounts.order_book.paused_ops & PAUSE_CANCEL_ORDERS == 0,
+            ErrorCode::OperationPaused
+        );
+
+        let book_state = &ctx.accounts.order_book_state;
+        let (owner_lo, owner_hi) = owner_words(&ctx.accounts.payer.key());
+
+        // Only the owner's own resting slot with this ID is cleared
+        let args = ArgBuilder::new()
+            .plaintext_u128(book_state.state_nonce)
+            .account(
+                book_state.key(),
+                OrderBookState::STATE_OFFSET,
+                OrderBookState::STATE_LEN,
+            )
+            .plaintext_u64(order_id)
+            .plaintext_u128(owner_lo)
+            .plaintext_u128(owner_hi)
+            .plaintext_u64(Clock::get()?.slot)
+            .build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![CancelOrderCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &CallbackAccountsBuilder::new()
+                    .writable(ctx.accounts.order_book.key())
+                    .writable(ctx.accounts.order_book_state.key())
+                    .writable(ctx.accounts.order_bond.key())
+                    .writable(ctx.accounts.payer.key())
+                    .writable(ctx.accounts.order_escrow.key())
+                    .build(),
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Callback after order cancellation
+    #[arcium_callback(encrypted_ix = "cancel_order")]
+    pub fn cancel_order_callback(
+        ctx: Context<CancelOrderCallback>,
+        output: SignedComputationOutputs<CancelOutput>,
+    ) -> Result<()> {
+        let result = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(CancelOutput { book_state, order_id, success }) => {
+                if !success {
+                    return Err(ErrorCode::CancelFailed.into());
+                }
+                ctx.accounts.order_book_state.write(book_state);
+                order_id
+            }
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let order_book = &mut ctx.accounts.order_book;
+        order_book.record_removed_orders(1);
+
+        // Cancelling before the minimum resting period forfeits the bond to the protocol
+        let order_bond = &ctx.accounts.order_bond;
+        let rested = Clock::get()?.slot >= order_bond.placed_slot + MIN_RESTING_SLOTS;
+        let forfeited = !rested && !order_bond.filled;
+        if forfeited {
+            let amount = order_bond.amount;
+            **ctx.accounts.order_bond.to_account_info().try_borrow_mut_lamports()? -= amount;
+            **ctx.accounts.order_book.to_account_info().try_borrow_mut_lamports()? += amount;
+            let order_book = &mut ctx.accounts.order_book;
+            order_book.forfeited_bonds = order_book.forfeited_bonds
+                .checked_add(amount)
+                .ok_or(ErrorCode::MathOverflow)?;
+        }
+        ctx.accounts.order_bond.close(ctx.accounts.owner.to_account_info())?;
+
+        // Off the book, so no new fills can land against the escrow
+        ctx.accounts.order_escrow.refundable = true;
+
+        let clock = Clock::get()?;
+        emit!(OrderCancelled {
+            order_id: result,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        emit!(BondSettled {
+            order_id: result,
+            owner: ctx.accounts.owner.key(),
+            forfeited,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Cancel every order the caller has resting on the book in one computation
+    /// The cancelled orders' escrows are listed as released in the ledger.
+    /// Bonds aren't touched; each is reclaimed with `release_bond` once its
+    /// minimum resting period has passed
+    pub fn cancel_all_orders(
+        ctx: Context<CancelAllOrders>,
+        computation_offset: u64,
+    ) -> Result<()> {
+        require!(!ctx.accounts.order_book.paused, ErrorCode::ProgramPaused);
+        assert_circuit_version(&ctx.accounts.circuit_version)?;
+        ensure_computation_fee(&ctx.accounts.payer, 0)?;
+        require!(
+            ctx.accounts.order_book.paused_ops & PAUSE_CANCEL_ORDERS == 0,
+            ErrorCode::OperationPaused
+        );
+        require!(
+            ctx.accounts.settlement_ledger.free_release_slots() >= MAX_ORDERS,
+            ErrorCode::SettlementBacklog
+        );
+
+        let book_state = &ctx.accounts.order_book_state;
+        let (owner_lo, owner_hi) = owner_words(&ctx.accounts.payer.key());
+
+        let args = ArgBuilder::new()
+            .plaintext_u128(book_state.state_nonce)
+            .account(
+                book_state.key(),
+                OrderBookState::STATE_OFFSET,
+                OrderBookState::STATE_LEN,
+            )
+            .plaintext_u128(owner_lo)
+            .plaintext_u128(owner_hi)
+            .plaintext_u64(Clock::get()?.slot)
+            .build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![CancelAllOrdersCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &CallbackAccountsBuilder::new()
+                    .writable(ctx.accounts.order_book.key())
+                    .writable(ctx.accounts.order_book_state.key())
+                    .readonly(ctx.accounts.payer.key())
+                    .writable(ctx.accounts.settlement_ledger.key())
+                    .build(),
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Callback after an owner's orders are cancelled
+    #[arcium_callback(encrypted_ix = "cancel_all_orders")]
+    pub fn cancel_all_orders_callback(
+        ctx: Context<CancelAllOrdersCallback>,
+        output: SignedComputationOutputs<CancelAllOrdersOutput>,
+    ) -> Result<()> {
+        let count = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(CancelAllOrdersOutput { book_state, cancelled, cancelled_ids }) => {
+                ctx.accounts.order_book_state.write(book_state);
+                let ledger = &mut ctx.accounts.settlement_ledger;
+                for order_id in cancelled_ids.iter().filter(|id| **id != 0) {
+                    ledger.release(*order_id)?;
+                }
+                cancelled
+            }
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let order_book = &mut ctx.accounts.order_book;
+        order_book.record_removed_orders(count);
+
+        let clock = Clock::get()?;
+        emit!(OrdersCancelled {
+            order_book: order_book.key(),
+            owner: ctx.accounts.owner.key(),
+            count,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Read the matched volume recorded for an epoch
+    /// Only the last `EPOCH_VOLUME_SLOTS` epochs are retained
+    pub fn get_epoch_volume(ctx: Context<GetEpochVolume>, epoch: u64) -> Result<u64> {
+        ctx.accounts.order_book.epoch_volume(epoch)
+    }
+
+    /// Compute the uniform clearing price for a sealed batch auction
+    /// Bid and ask levels are encrypted; only price and volume are revealed
+    pub fn compute_clearing_price(
+        ctx: Context<ComputeClearingPrice>,
+        computation_offset: u64,
+        encrypted_batch: Vec<[u8; 64]>, // Encrypted bid/ask levels, in AuctionBatch field order
+        pub_key: [u8; 32],
+        nonce: u128,
+    ) -> Result<()> {
+        require!(!ctx.accounts.order_book.paused, ErrorCode::ProgramPaused);
+        assert_circuit_version(&ctx.accounts.circuit_version)?;
+        ensure_computation_fee(&ctx.accounts.payer, 0)?;
+
+        require!(
+            encrypted_batch.len() == AUCTION_BATCH_FIELDS,
+            ErrorCode::InvalidAuctionBatch
+        );
+
+        let mut builder = ArgBuilder::new()
+            .x25519_pubkey(pub_key)
+            .plaintext_u128(nonce);
+        for level in encrypted_batch {
+            builder = builder.encrypted_bytes(level);
+        }
+        let args = builder.build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![ComputeClearingPriceCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &CallbackAccountsBuilder::new()
+                    .readonly(ctx.accounts.order_book.key())
+                    .build(),
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Callback after the clearing price is computed
+    #[arcium_callback(encrypted_ix = "compute_clearing_price")]
+    pub fn compute_clearing_price_callback(
+        ctx: Context<ComputeClearingPriceCallback>,
+        output: SignedComputationOutputs<ClearingPriceOutput>,
+    ) -> Result<()> {
+        let result = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(ClearingPriceOutput { clearing_price, volume }) => (clearing_price, volume),
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let clock = Clock::get()?;
+        emit!(ClearingPriceComputed {
+            clearing_price: result.0,
+            normalized_price: ctx.accounts.order_book.normalized_price(result.0)?,
+            volume: result.1,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Reclaim an order bond once the order has filled or rested long enough
+    /// `filled` is set when a market order executes without resting or when
+    /// `settle_fill` settles one of the order's fills.
+    pub fn release_bond(ctx: Context<ReleaseBond>) -> Result<()> {
+        let order_bond = &ctx.accounts.order_bond;
+        let clock = Clock::get()?;
+        let rested = clock.slot >= order_bond.placed_slot + MIN_RESTING_SLOTS;
+        require!(order_bond.filled || rested, ErrorCode::BondLocked);
+
+        emit!(BondSettled {
+            order_id: order_bond.order_id,
+            owner: order_bond.owner,
+            forfeited: false,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Run a triggered batch auction
+    /// Permissionless: anyone may crank it once the volume threshold flagged the book
+    pub fn run_batch_auction(
+        ctx: Context<RunBatchAuction>,
+        computation_offset: u64,
+    ) -> Result<()> {
+        require!(!ctx.accounts.order_book.paused, ErrorCode::ProgramPaused);
+        assert_circuit_version(&ctx.accounts.circuit_version)?;
+        ensure_computation_fee(&ctx.accounts.payer, 0)?;
+        require!(
+            ctx.accounts.order_book.paused_ops & PAUSE_MATCHING == 0,
+            ErrorCode::OperationPaused
+        );
+
+        require!(ctx.accounts.order_book.auction_pending, ErrorCode::AuctionNotTriggered);
+        let ledger = &ctx.accounts.settlement_ledger;
+        require!(
+            ledger.free_slots() >= MAX_REPORTED_MATCHES
+                && ledger.free_release_slots() >= 2 * MAX_REPORTED_MATCHES,
+            ErrorCode::SettlementBacklog
+        );
+
+        let book_state = &ctx.accounts.order_book_state;
+
+        let args = ArgBuilder::new()
+            .plaintext_u128(book_state.state_nonce)
+            .account(
+                book_state.key(),
+                OrderBookState::STATE_OFFSET,
+                OrderBookState::STATE_LEN,
+            )
+            .plaintext_u16(ctx.accounts.order_book.maker_fee_rate)
+            .plaintext_u16(ctx.accounts.order_book.taker_fee_rate)
+            .plaintext_u64(Clock::get()?.slot)
+            .build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![RunBatchAuctionCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &CallbackAccountsBuilder::new()
+                    .writable(ctx.accounts.order_book.key())
+                    .writable(ctx.accounts.order_book_state.key())
+                    .writable(ctx.accounts.settlement_ledger.key())
+                    .build(),
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Callback after a batch auction clears
+    #[arcium_callback(encrypted_ix = "run_batch_auction")]
+    pub fn run_batch_auction_callback(
+        ctx: Context<RunBatchAuctionCallback>,
+        output: SignedComputationOutputs<BatchAuctionOutput>,
+    ) -> Result<()> {
+        let result = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(BatchAuctionOutput {
+                book_state,
+                clearing_price,
+                volume,
+                matches_count,
+                closed_orders,
+                fills,
+                capped,
+            }) => {
+                ctx.accounts.order_book_state.write(book_state);
+                (clearing_price, volume, matches_count, closed_orders, fills, capped)
+            }
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let order_book = &mut ctx.accounts.order_book;
+        // A capped run leaves crossing volume for the crank to clear next
+        order_book.auction_pending = result.5;
+        if result.1 > 0 {
+            order_book.update_reference_price(result.0);
+        }
+        order_book.total_matches = order_book.total_matches
+            .checked_add(result.2 as u64)
+            .ok_or(ErrorCode::MathOverflow)?;
+        order_book.record_removed_orders(result.3);
+
+        let epoch = Clock::get()?.epoch;
+        order_book.record_epoch_volume(epoch, result.1)?;
+
+        let clock = Clock::get()?;
+        emit!(BatchAuctionCleared {
+            order_book: order_book.key(),
+            clearing_price: result.0,
+            normalized_price: order_book.normalized_price(result.0)?,
+            volume: result.1,
+            matches_count: result.2,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+
+        let ledger = &mut ctx.accounts.settlement_ledger;
+        for fill in result.4.iter().filter(|fill| fill.bid_order_id != 0) {
+            let pending = PendingFill {
+                bid_order_id: fill.bid_order_id,
+                ask_order_id: fill.ask_order_id,
+                filled_size: fill.filled_size,
+                price: fill.price,
+                bid_fee: fill.bid_fee,
+                ask_fee: fill.ask_fee,
+            };
+            ledger.record(pending, fill.bid_closed, fill.ask_closed)?;
+            emit_order_filled(&pending, fill.bid_is_maker, &clock);
+        }
+        Ok(())
+    }
+
+    /// Reveal the caller's own resting depth on each side, encrypted to `pub_key`
+    /// Lets market makers monitor their exposure without seeing anyone else's orders
+    pub fn reveal_my_depth(
+        ctx: Context<RevealMyDepth>,
+        computation_offset: u64,
+        pub_key: [u8; 32],
+        nonce: u128,
+    ) -> Result<()> {
+        require!(!ctx.accounts.order_book.paused, ErrorCode::ProgramPaused);
+        assert_circuit_version(&ctx.accounts.circuit_version)?;
+        ensure_computation_fee(&ctx.accounts.payer, 0)?;
+
+        let book_state = &ctx.accounts.order_book_state;
+        let (owner_lo, owner_hi) = owner_words(&ctx.accounts.payer.key());
+
+        let args = ArgBuilder::new()
+            .plaintext_u128(book_state.state_nonce)
+            .account(
+                book_state.key(),
+                OrderBookState::STATE_OFFSET,
+                OrderBookState::STATE_LEN,
+            )
+            .plaintext_u128(owner_lo)
+            .plaintext_u128(owner_hi)
+            .x25519_pubkey(pub_key)
+            .plaintext_u128(nonce)
+            .build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![RevealMyDepthCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &CallbackAccountsBuilder::new()
+                    .readonly(ctx.accounts.order_book.key())
+                    .build(),
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Callback emitting the caller-only depth ciphertexts
+    #[arcium_callback(encrypted_ix = "reveal_my_depth")]
+    pub fn reveal_my_depth_callback(
+        ctx: Context<RevealMyDepthCallback>,
+        output: SignedComputationOutputs<RevealMyDepthOutput>,
+    ) -> Result<()> {
+        let depth = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(RevealMyDepthOutput { depth }) => depth,
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let clock = Clock::get()?;
+        emit!(DepthRevealed {
+            order_book: ctx.accounts.order_book.key(),
+            encrypted_bid_volume: depth.ciphertexts[0],
+            encrypted_ask_volume: depth.ciphertexts[1],
+            nonce: depth.nonce,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Publish aggregate depth per price band
+    /// Permissionless once the authority has set the bands with
+    /// `set_depth_bands`. Only band totals are revealed, never an order's
+    /// price, size or owner, and the caller's own orders don't count toward
+    /// a band's minimum; see `snapshot_depth` in encrypted-ixs.
+    pub fn snapshot_depth(ctx: Context<SnapshotDepth>, computation_offset: u64) -> Result<()> {
+        require!(!ctx.accounts.order_book.paused, ErrorCode::ProgramPaused);
+        assert_circuit_version(&ctx.accounts.circuit_version)?;
+        ensure_computation_fee(&ctx.accounts.payer, 0)?;
+        let order_book = &ctx.accounts.order_book;
+        require!(order_book.depth_band_width > 0, ErrorCode::DepthBandsNotSet);
+
+        let (owner_lo, owner_hi) = owner_words(&ctx.accounts.payer.key());
+        let book_state = &ctx.accounts.order_book_state;
+
+        let args = ArgBuilder::new()
+            .plaintext_u128(book_state.state_nonce)
+            .account(
+                book_state.key(),
+                OrderBookState::STATE_OFFSET,
+                OrderBookState::STATE_LEN,
+            )
+            .plaintext_u64(order_book.depth_band_floor)
+            .plaintext_u64(order_book.depth_band_width)
+            .plaintext_u128(owner_lo)
+            .plaintext_u128(owner_hi)
+            .plaintext_u64(Clock::get()?.slot)
+            .build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![SnapshotDepthCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &CallbackAccountsBuilder::new()
+                    .readonly(ctx.accounts.order_book.key())
+                    .build(),
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Callback emitting the band totals
+    #[arcium_callback(encrypted_ix = "snapshot_depth")]
+    pub fn snapshot_depth_callback(
+        ctx: Context<SnapshotDepthCallback>,
+        output: SignedComputationOutputs<SnapshotDepthOutput>,
+    ) -> Result<()> {
+        let bands = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(SnapshotDepthOutput { bands }) => bands,
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let clock = Clock::get()?;
+        let order_book = &ctx.accounts.order_book;
+        emit!(DepthSnapshot {
+            order_book: order_book.key(),
+            band_floor: order_book.depth_band_floor,
+            band_width: order_book.depth_band_width,
+            bands,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Publish the mid of the best bid and ask without matching
+    /// Permissionless and cheaper than `match_orders`: the circuit walks each
+    /// side once and reveals only the mid. A one-sided or empty book reports
+    /// `NO_INDICATIVE_PRICE`.
+    pub fn indicative_price(
+        ctx: Context<IndicativePriceQuery>,
+        computation_offset: u64,
+    ) -> Result<()> {
+        require!(!ctx.accounts.order_book.paused, ErrorCode::ProgramPaused);
+        assert_circuit_version(&ctx.accounts.circuit_version)?;
+        ensure_computation_fee(&ctx.accounts.payer, 0)?;
+
+        let book_state = &ctx.accounts.order_book_state;
+
+        let args = ArgBuilder::new()
+            .plaintext_u128(book_state.state_nonce)
+            .account(
+                book_state.key(),
+                OrderBookState::STATE_OFFSET,
+                OrderBookState::STATE_LEN,
+            )
+            .plaintext_u64(Clock::get()?.slot)
+            .build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![IndicativePriceCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &CallbackAccountsBuilder::new()
+                    .readonly(ctx.accounts.order_book.key())
+                    .build(),
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Callback emitting the indicative price
+    #[arcium_callback(encrypted_ix = "indicative_price")]
+    pub fn indicative_price_callback(
+        ctx: Context<IndicativePriceCallback>,
+        output: SignedComputationOutputs<IndicativePriceOutput>,
+    ) -> Result<()> {
+        let price = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(IndicativePriceOutput { price }) => price,
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let clock = Clock::get()?;
+        emit!(IndicativePrice {
+            order_book: ctx.accounts.order_book.key(),
+            price,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Reveal the caller's liquidity score, encrypted to `pub_key`
+    /// The score is matchable size times slots rested, summed over the caller's
+    /// orders, for off-chain maker reward distribution
+    pub fn reveal_maker_score(
+        ctx: Context<RevealMakerScore>,
+        computation_offset: u64,
+        pub_key: [u8; 32],
+        nonce: u128,
+    ) -> Result<()> {
+        require!(!ctx.accounts.order_book.paused, ErrorCode::ProgramPaused);
+        assert_circuit_version(&ctx.accounts.circuit_version)?;
+        ensure_computation_fee(&ctx.accounts.payer, 0)?;
+
+        let book_state = &ctx.accounts.order_book_state;
+        let (owner_lo, owner_hi) = owner_words(&ctx.accounts.payer.key());
+
+        let args = ArgBuilder::new()
+            .plaintext_u128(book_state.state_nonce)
+            .account(
+                book_state.key(),
+                OrderBookState::STATE_OFFSET,
+                OrderBookState::STATE_LEN,
+            )
+            .plaintext_u128(owner_lo)
+            .plaintext_u128(owner_hi)
+            .plaintext_u64(Clock::get()?.slot)
+            .x25519_pubkey(pub_key)
+            .plaintext_u128(nonce)
+            .build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![RevealMakerScoreCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &CallbackAccountsBuilder::new()
+                    .readonly(ctx.accounts.order_book.key())
+                    .build(),
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Callback emitting the caller-only score ciphertext
+    #[arcium_callback(encrypted_ix = "reveal_maker_score")]
+    pub fn reveal_maker_score_callback(
+        ctx: Context<RevealMakerScoreCallback>,
+        output: SignedComputationOutputs<RevealMakerScoreOutput>,
+    ) -> Result<()> {
+        let score = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(RevealMakerScoreOutput { liquidity_score }) => liquidity_score,
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let clock = Clock::get()?;
+        emit!(MakerScoreRevealed {
+            order_book: ctx.accounts.order_book.key(),
+            encrypted_liquidity_slots: score.ciphertexts[0],
+            nonce: score.nonce,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Liquidate encrypted collateral against resting bids
+    /// Only the order book's liquidator may call this. Bids are filled down to
+    /// the book oracle's price less the liquidation band; only the liquidated amount is
+    /// revealed, never the bids' prices or owners. The liquidator escrows
+    /// `escrow_amount` base, which the fills settle from through the ledger
+    /// like any other match; the unsold rest is withdrawable straight away.
+    pub fn liquidate_order(
+        ctx: Context<LiquidateOrder>,
+        computation_offset: u64,
+        encrypted_size: [u8; 64], // Encrypted collateral size to sell
+        escrow_amount: u64,       // Base escrowed against the collateral; must cover it
+        pub_key: [u8; 32],
+        nonce: u128,
+    ) -> Result<()> {
+        require!(!ctx.accounts.order_book.paused, ErrorCode::ProgramPaused);
+        assert_circuit_version(&ctx.accounts.circuit_version)?;
+        ensure_computation_fee(&ctx.accounts.payer, 0)?;
+        require!(
+            ctx.accounts.order_book.paused_ops & PAUSE_MATCHING == 0,
+            ErrorCode::OperationPaused
+        );
+        let oracle = &ctx.accounts.oracle;
+        require!(
+            oracle.price > 0
+                && Clock::get()?.slot <= oracle.updated_slot.saturating_add(MAX_ORACLE_AGE_SLOTS),
+            ErrorCode::StaleOraclePrice
+        );
+        let oracle_price = oracle.price;
+        require!(escrow_amount > 0, ErrorCode::InvalidOrderParams);
+        let ledger = &ctx.accounts.settlement_ledger;
+        require!(
+            ledger.free_slots() >= MAX_REPORTED_MATCHES
+                && ledger.free_release_slots() >= MAX_REPORTED_MATCHES,
+            ErrorCode::SettlementBacklog
+        );
+        require!(ctx.accounts.escrow_vault.key() == ledger.base_vault, ErrorCode::InvalidSettlement);
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.trader_token.to_account_info(),
+            to: ctx.accounts.escrow_vault.to_account_info(),
+            authority: ctx.accounts.payer.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+        token::transfer(cpi_ctx, escrow_amount)?;
+
+        let current_slot = Clock::get()?.slot;
+        let order_escrow = &mut ctx.accounts.order_escrow;
+        order_escrow.order_book = ctx.accounts.order_book.key();
+        order_escrow.owner = ctx.accounts.payer.key();
+        order_escrow.order_id = 0; // Set to the liquidation's order ID by the callback
+        order_escrow.is_buy = false;
+        order_escrow.deposited = escrow_amount;
+        order_escrow.spent = 0;
+        order_escrow.received = 0;
+        order_escrow.expiry_slot = 0;
+        order_escrow.placed_slot = current_slot;
+        order_escrow.refundable = false;
+        order_escrow.bump = ctx.bumps.order_escrow;
+
+        let order_book = &ctx.accounts.order_book;
+        let min_price = (oracle_price as u128)
+            * (10000 - order_book.liquidation_band_bps as u128)
+            / 10000;
+
+        let book_state = &ctx.accounts.order_book_state;
+
+        let args = ArgBuilder::new()
+            .x25519_pubkey(pub_key)
+            .plaintext_u128(nonce)
+            .encrypted_bytes(encrypted_size)
+            .plaintext_u128(book_state.state_nonce)
+            .account(
+                book_state.key(),
+                OrderBookState::STATE_OFFSET,
+                OrderBookState::STATE_LEN,
+            )
+            .plaintext_u64(min_price as u64)
+            .plaintext_u64(escrow_amount)
+            .plaintext_u16(order_book.maker_fee_rate)
+            .plaintext_u16(order_book.taker_fee_rate)
+            .plaintext_u64(current_slot)
+            .build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![LiquidateOrderCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &CallbackAccountsBuilder::new()
+                    .writable(ctx.accounts.order_book.key())
+                    .writable(ctx.accounts.order_book_state.key())
+                    .writable(ctx.accounts.settlement_ledger.key())
+                    .writable(ctx.accounts.order_escrow.key())
+                    .build(),
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Callback after a liquidation fills against the book
+    #[arcium_callback(encrypted_ix = "liquidate_order")]
+    pub fn liquidate_order_callback(
+        ctx: Context<LiquidateOrderCallback>,
+        output: SignedComputationOutputs<LiquidateOrderOutput>,
+    ) -> Result<()> {
+        let result = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(LiquidateOrderOutput {
+                book_state,
+                order_id,
+                liquidated,
+                closed_orders,
+                fills,
+            }) => {
+                ctx.accounts.order_book_state.write(book_state);
+                (order_id, liquidated, closed_orders, fills)
+            }
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let order_book = &mut ctx.accounts.order_book;
+        order_book.record_removed_orders(result.2);
+
+        let epoch = Clock::get()?.epoch;
+        order_book.record_epoch_volume(epoch, result.1)?;
+
+        // The liquidation never rests, so whatever its fills don't take
+        // goes back once they settle
+        ctx.accounts.order_escrow.order_id = result.0;
+        ctx.accounts.order_escrow.refundable = true;
+
+        let clock = Clock::get()?;
+        let ledger = &mut ctx.accounts.settlement_ledger;
+        for fill in result.3.iter().filter(|fill| fill.bid_order_id != 0) {
+            let pending = PendingFill {
+                bid_order_id: fill.bid_order_id,
+                ask_order_id: fill.ask_order_id,
+                filled_size: fill.filled_size,
+                price: fill.price,
+                bid_fee: fill.bid_fee,
+                ask_fee: fill.ask_fee,
+            };
+            ledger.record(pending, fill.bid_closed, fill.ask_closed)?;
+            emit_order_filled(&pending, fill.bid_is_maker, &clock);
+        }
+
+        emit!(OrderLiquidated {
+            order_book: ctx.accounts.order_book.key(),
+            liquidated: result.1,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+}
+
+// ============ Account Structures ============
+
+#[account]
+pub struct OrderBook {
+    pub authority: Pubkey,
+    pub base_mint: Pubkey,
+    pub quote_mint: Pubkey,
+    pub taker_fee_rate: u16, // The single `fee_rate` before maker/taker tiers
+    pub bump: u8,
+    pub total_orders: u64,
+    pub total_matches: u64,
+    pub active_orders: u32,
+    pub tick_size: u64,
+    pub min_order_size: u64,
+    pub max_order_size: u64,
+    pub epoch_ids: [u64; EPOCH_VOLUME_SLOTS],
+    pub epoch_volumes: [u64; EPOCH_VOLUME_SLOTS],
+    pub forfeited_bonds: u64,
+    pub auction_volume_threshold: u64,
+    pub auction_pending: bool,
+    pub reference_price: u64,
+    pub price_band_bps: u16,
+    pub circuit_breaker_tripped: bool,
+    pub base_decimals: u8,
+    pub quote_decimals: u8,
+    pub price_display_decimals: u8,
+    pub liquidator: Pubkey, // Default pubkey while liquidations are disabled
+    pub liquidation_band_bps: u16,
+    pub paused_ops: u8, // Bitmask of PAUSE_* operations
+    pub paused: bool,   // Blocks every computation, set with `set_paused`
+    pub random_priority_window_slots: u64, // 0 for strict time priority among same-price orders
+    pub fees_accrued: u64, // Quote atoms of matching fees not yet collected
+    pub match_cap: u32,    // Pair cap of the last queued `match_orders`
+    pub match_cursor: u64, // Capped runs so far in an unfinished sweep, 0 when none
+    pub maker_fee_rate: u16, // Fee for the side of a match that rested first
+    pub oracle: Pubkey,      // Price oracle liquidations read, default until created
+    pub depth_band_floor: u64, // Lowest price `snapshot_depth` reports
+    pub depth_band_width: u64, // Price width of each depth band, 0 until set
+}
+
+/// Book price published by a designated updater for liquidations
+#[account]
+pub struct PriceOracle {
+    pub order_book: Pubkey,
+    pub updater: Pubkey,
+    pub price: u64, // Raw quote atoms per base atom
+    pub updated_slot: u64,
+    pub bump: u8,
+}
+
+impl PriceOracle {
+    pub const SIZE: usize = 32 + 32 + 8 + 8 + 1;
+}
+
+/// Oldest oracle price, in slots, a liquidation may be priced from
+pub const MAX_ORACLE_AGE_SLOTS: u64 = 150;
+
+/// `paused_ops` bit blocking new orders
+pub const PAUSE_ADD_ORDERS: u8 = 1 << 0;
+/// `paused_ops` bit blocking cancellations
+pub const PAUSE_CANCEL_ORDERS: u8 = 1 << 1;
+/// `paused_ops` bit blocking continuous matching, batch auctions and liquidations
+pub const PAUSE_MATCHING: u8 = 1 << 2;
+
+/// Default fixed-point precision of normalized prices in events
+pub const DEFAULT_PRICE_DISPLAY_DECIMALS: u8 = 6;
+
+/// Largest configurable normalized price precision
+pub const MAX_PRICE_DISPLAY_DECIMALS: u8 = 9;
+
+/// Longest randomized time priority window, about a minute
+pub const MAX_RANDOM_PRIORITY_WINDOW_SLOTS: u64 = 150;
+
+/// Number of epochs of matched volume kept in the order book ring buffer
+pub const EPOCH_VOLUME_SLOTS: usize = 8;
+
+impl OrderBook {
+    pub const SIZE: usize = 8 + 32 + 32 + 32 + 2 + 1 + 8 + 8 + 4 + 8 + 8 + 8
+        + 8 * EPOCH_VOLUME_SLOTS
+        + 8 * EPOCH_VOLUME_SLOTS
+        + 8 + 8 + 1
+        + 8 + 2 + 1
+        + 1 + 1 + 1
+        + 32 + 2
+        + 1 + 1
+        + 8 + 8
+        + 4 + 8
+        + 2
+        + 32
+        + 8 + 8;
+
+    /// Convert a raw price (quote atoms per base atom) into quote tokens per
+    /// base token, as a fixed-point value with `price_display_decimals` decimals
+    pub fn normalized_price(&self, raw_price: u64) -> Result<u64> {
+        let exponent = self.base_decimals as u32 + self.price_display_decimals as u32;
+        let numerator = 10u128.checked_pow(exponent).ok_or(ErrorCode::MathOverflow)?;
+        let denominator = 10u128
+            .checked_pow(self.quote_decimals as u32)
+            .ok_or(ErrorCode::MathOverflow)?;
+        let scaled = (raw_price as u128)
+            .checked_mul(numerator)
+            .ok_or(ErrorCode::MathOverflow)?
+            / denominator;
+        u64::try_from(scaled).map_err(|_| ErrorCode::MathOverflow.into())
+    }
+
+    /// Fold a revealed clearing price into the rolling reference price
+    /// used by the circuit breaker (EMA weighting the new price 1/4)
+    pub fn update_reference_price(&mut self, price: u64) {
+        self.reference_price = if self.reference_price == 0 {
+            price
+        } else {
+            ((self.reference_price as u128 * 3 + price as u128) / 4) as u64
+        };
+    }
+
+    /// Count an order that was accepted and now rests on the book
+    pub fn record_resting_order(&mut self) -> Result<()> {
+        self.total_orders = self.total_orders.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+        self.active_orders = self.active_orders.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Owe the authority `fees` quote atoms of settled matching fees
+    pub fn accrue_fees(&mut self, fees: u64) -> Result<()> {
+        self.fees_accrued = self.fees_accrued.checked_add(fees).ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Take the accrued fees the fee vault holding `vault_balance` can pay
+    /// out, leaving any shortfall accrued for a later collection
+    pub fn take_fees(&mut self, vault_balance: u64) -> Result<u64> {
+        let amount = self.fees_accrued.min(vault_balance);
+        require!(amount > 0, ErrorCode::NoFeesToCollect);
+        self.fees_accrued -= amount;
+        Ok(amount)
+    }
+
+    /// Count `count` orders that left the book by fill, cancel or expiry
+    pub fn record_removed_orders(&mut self, count: u32) {
+        self.active_orders = self.active_orders.saturating_sub(count);
+    }
+
+    /// Add matched volume to the ring buffer entry for `epoch`,
+    /// recycling the entry if it still holds an older epoch
+    pub fn record_epoch_volume(&mut self, epoch: u64, volume: u64) -> Result<()> {
+        let idx = (epoch % EPOCH_VOLUME_SLOTS as u64) as usize;
+        if self.epoch_ids[idx] != epoch {
+            self.epoch_ids[idx] = epoch;
+            self.epoch_volumes[idx] = 0;
+        }
+        self.epoch_volumes[idx] = self.epoch_volumes[idx]
+            .checked_add(volume)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Matched volume recorded for `epoch`
+    pub fn epoch_volume(&self, epoch: u64) -> Result<u64> {
+        let idx = (epoch % EPOCH_VOLUME_SLOTS as u64) as usize;
+        require!(self.epoch_ids[idx] == epoch, ErrorCode::EpochNotTracked);
+        Ok(self.epoch_volumes[idx])
+    }
+}
+
+/// Resting time, in slots, after which matching charges an order half the
+/// fee rate, and a quarter after ten times that (see `resting_fee_bps` in encrypted-ixs)
+pub const FEE_TIER_SLOTS: u64 = 150;
+
+/// Highest fee rate an order book may charge, in basis points
+pub const MAX_FEE_BPS: u16 = 1_000;
+
+/// Matched pairs `match_orders` reports per run (mirrors the circuit)
+pub const MAX_REPORTED_MATCHES: usize = 4;
+
+/// Estimated callback compute per matched pair: a ledger entry and two
+/// `OrderFilled` events
+pub const MATCH_PAIR_CU: u32 = 15_000;
+
+/// Pairs a `match_orders` run with `cu_budget` to spare may match, never
+/// more than the circuit reports
+pub fn max_matches_for_budget(cu_budget: u32) -> u32 {
+    (cu_budget / MATCH_PAIR_CU).min(MAX_REPORTED_MATCHES as u32)
+}
+
+/// `match_cursor` after a run that matched `matches_count` pairs under
+/// `match_cap`: one more capped run in the sweep, or 0 once a run finishes
+/// under its cap
+pub fn next_match_cursor(match_cursor: u64, matches_count: u32, match_cap: u32) -> u64 {
+    if matches_count >= match_cap {
+        match_cursor.saturating_add(1)
+    } else {
+        0
+    }
+}
+
+/// Price bands in a `snapshot_depth` result (mirrors the circuit)
+pub const DEPTH_BANDS: usize = 8;
+
+/// Whether `DEPTH_BANDS` bands of `band_width` from `band_floor` are
+/// non-empty and end below `u64::MAX`
+pub fn valid_depth_bands(band_floor: u64, band_width: u64) -> bool {
+    band_width > 0
+        && band_width
+            .checked_mul(DEPTH_BANDS as u64)
+            .and_then(|span| span.checked_add(band_floor))
+            .is_some()
+}
+
+/// `IndicativePrice` price when the book lacks a live bid or ask (mirrors
+/// the circuit)
+pub const NO_INDICATIVE_PRICE: u64 = 0;
+
+/// Whether the fills a match run reported are one per match and add up to
+/// the volume it revealed
+fn fills_account_for_volume(fills: &[PendingFill], matches_count: u32, total_volume: u64) -> bool {
+    let reported_volume = fills
+        .iter()
+        .try_fold(0u64, |volume, fill| volume.checked_add(fill.filled_size));
+    fills.len() == matches_count as usize && reported_volume == Some(total_volume)
+}
+
+/// Book a settled fill against both escrows and return the quote it moves:
+/// (notional, seller proceeds, fees)
+/// Each side's resting-time fee is capped by what that side can pay: the
+/// buyer's by its escrow past the notional, the seller's by the notional.
+fn charge_escrows(
+    fill: &PendingFill,
+    bid_escrow: &mut OrderEscrow,
+    ask_escrow: &mut OrderEscrow,
+) -> Result<(u64, u64, u64)> {
+    let notional = u64::try_from((fill.filled_size as u128) * (fill.price as u128))
+        .map_err(|_| ErrorCode::MathOverflow)?;
+
+    bid_escrow.spent = bid_escrow.spent.checked_add(notional).ok_or(ErrorCode::MathOverflow)?;
+    require!(bid_escrow.spent <= bid_escrow.deposited, ErrorCode::InvalidSettlement);
+    // The buyer's fee comes out of whatever its escrow holds past the
+    // notional; a bid escrowed exactly at the fill price pays what's left
+    let bid_fee = fill.bid_fee.min(bid_escrow.deposited - bid_escrow.spent);
+    bid_escrow.spent += bid_fee;
+    bid_escrow.received = bid_escrow.received
+        .checked_add(fill.filled_size)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    let ask_fee = fill.ask_fee.min(notional);
+    let proceeds = notional - ask_fee;
+    ask_escrow.spent = ask_escrow.spent
+        .checked_add(fill.filled_size)
+        .ok_or(ErrorCode::MathOverflow)?;
+    require!(ask_escrow.spent <= ask_escrow.deposited, ErrorCode::InvalidSettlement);
+    ask_escrow.received = ask_escrow.received
+        .checked_add(proceeds)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    let fees = bid_fee.checked_add(ask_fee).ok_or(ErrorCode::MathOverflow)?;
+    Ok((notional, proceeds, fees))
+}
+
+/// Emit an `OrderFilled` for each side of a reported match
+fn emit_order_filled(fill: &PendingFill, bid_is_maker: bool, clock: &Clock) {
+    for event in order_filled_events(fill, bid_is_maker, clock) {
+        emit!(event);
+    }
+}
+
+/// The bid's and then the ask's `OrderFilled` for a reported match
+fn order_filled_events(fill: &PendingFill, bid_is_maker: bool, clock: &Clock) -> [OrderFilled; 2] {
+    [(fill.bid_order_id, bid_is_maker), (fill.ask_order_id, !bid_is_maker)].map(
+        |(order_id, is_maker)| OrderFilled {
+            order_id,
+            filled_size: fill.filled_size,
+            price: fill.price,
+            is_maker,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        },
+    )
+}
+
+/// Lamports `add_order` locks on `fee_payer` besides the computation fee
+/// A relayer only covers fees; the bond always comes from the trader, so
+/// it lands on the fee payer only when the trader pays their own fees
+fn fee_payer_locked(fee_payer: &Pubkey, trader: &Pubkey) -> u64 {
+    if fee_payer == trader {
+        ORDER_BOND_LAMPORTS
+    } else {
+        0
+    }
+}
+
+/// Turn the flags an order-inserting circuit revealed into the error the
+/// callback fails with: a resubmitted limit order first, then a full book,
+/// then any other rejection
+fn check_order_inserted(duplicate: bool, book_full: bool, success: bool) -> Result<()> {
+    require!(!duplicate, ErrorCode::DuplicateOrder);
+    require!(!book_full, ErrorCode::OrderBookFull);
+    require!(success, ErrorCode::OrderFailed);
+    Ok(())
+}
+
+/// Validate order book tick and size parameters
+fn validate_order_params(tick_size: u64, min_order_size: u64, max_order_size: u64) -> Result<()> {
+    require!(
+        tick_size > 0 && min_order_size > 0 && min_order_size <= max_order_size,
+        ErrorCode::InvalidOrderParams
+    );
+    Ok(())
+}
+
+/// Reject an order whose encrypted fields include an all-zero blob
+fn ensure_nonempty_ciphertexts(ciphertexts: &[[u8; 64]]) -> Result<()> {
+    require!(
+        ciphertexts.iter().all(|c| *c != [0u8; 64]),
+        ErrorCode::InvalidOrder
+    );
+    Ok(())
+}
+
+/// Reject a randomized priority window longer than
+/// `MAX_RANDOM_PRIORITY_WINDOW_SLOTS`
+fn validate_random_priority_window(window_slots: u64) -> Result<()> {
+    require!(
+        window_slots <= MAX_RANDOM_PRIORITY_WINDOW_SLOTS,
+        ErrorCode::InvalidOrderParams
+    );
+    Ok(())
+}
+
+/// Reject a fee rate above `MAX_FEE_BPS`
+fn validate_fee_rate(fee_rate: u16) -> Result<()> {
+    require!(fee_rate <= MAX_FEE_BPS, ErrorCode::FeeTooHigh);
+    Ok(())
+}
+
+/// Number of encrypted values in an auction batch
+/// (prices and sizes for 8 bid levels and 8 ask levels)
+pub const AUCTION_BATCH_FIELDS: usize = 4 * 8;
+
+/// SOL bond locked with every order to deter place-and-cancel spam
+pub const ORDER_BOND_LAMPORTS: u64 = 10_000_000;
+
+/// Slots an order must rest before cancelling stops forfeiting its bond
+pub const MIN_RESTING_SLOTS: u64 = 150;
+
+#[account]
+pub struct OrderBond {
+    pub order_book: Pubkey,
+    pub owner: Pubkey,
+    pub order_id: u64,
+    pub amount: u64,
+    pub placed_slot: u64,
+    pub filled: bool,
+    pub bump: u8,
+}
+
+impl OrderBond {
+    pub const SIZE: usize = 32 + 32 + 8 + 8 + 8 + 1 + 1;
+}
+
+/// Tokens an order escrowed when it was placed and what its fills have settled
+/// `deposited` is public but only bounds the encrypted order: a trader may
+/// escrow more than the order needs, and the unused part is refunded by
+/// `withdraw_escrow` once the order is off the book.
+#[account]
+pub struct OrderEscrow {
+    pub order_book: Pubkey,
+    pub owner: Pubkey,
+    pub order_id: u64,
+    pub is_buy: bool,     // Quote escrowed for a buy, base for a sell
+    pub deposited: u64,
+    pub spent: u64,       // Escrowed tokens paid to counterparties
+    pub received: u64,    // Counterparty tokens paid out to the owner
+    pub expiry_slot: u64, // Mirrors the order's expiry, 0 = never expires
+    pub refundable: bool, // Set once the order can no longer fill
+    pub bump: u8,
+    pub placed_slot: u64, // Slot the placing computation was queued
+}
+
+impl OrderEscrow {
+    pub const SIZE: usize = 32 + 32 + 8 + 1 + 8 + 8 + 8 + 8 + 1 + 1 + 8;
+}
+
+/// A matched pair waiting to be paid out by `settle_fill`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct PendingFill {
+    pub bid_order_id: u64, // 0 marks a free slot
+    pub ask_order_id: u64,
+    pub filled_size: u64,
+    pub price: u64,
+    pub bid_fee: u64, // Quote atoms charged to the buyer on top of the notional
+    pub ask_fee: u64, // Quote atoms withheld from the seller's proceeds
+}
+
+/// Matches revealed by `match_orders` that haven't been settled yet, plus the
+/// vaults every escrow is held in
+#[account]
+pub struct SettlementLedger {
+    pub order_book: Pubkey,
+    pub base_vault: Pubkey,
+    pub quote_vault: Pubkey,
+    pub fills: [PendingFill; SETTLEMENT_LEDGER_SLOTS],
+    pub bump: u8,
+    pub released: [u64; RELEASED_ESCROW_SLOTS], // Orders taken off the book whose escrows await release, 0 = free
+}
+
+impl SettlementLedger {
+    pub const SIZE: usize = 32 + 32 + 32 + 48 * SETTLEMENT_LEDGER_SLOTS + 1 + 8 * RELEASED_ESCROW_SLOTS;
+
+    pub fn free_slots(&self) -> usize {
+        self.fills.iter().filter(|fill| fill.bid_order_id == 0).count()
+    }
+
+    /// Whether either side of a pending match is `order_id`
+    pub fn has_fill(&self, order_id: u64) -> bool {
+        order_id != 0
+            && self.fills.iter().any(|fill| {
+                fill.bid_order_id == order_id || fill.ask_order_id == order_id
+            })
+    }
+
+    pub fn push(&mut self, fill: PendingFill) -> Result<()> {
+        let slot = self.fills
+            .iter_mut()
+            .find(|slot| slot.bid_order_id == 0)
+            .ok_or(ErrorCode::SettlementBacklog)?;
+        *slot = fill;
+        Ok(())
+    }
+
+    pub fn free_release_slots(&self) -> usize {
+        self.released.iter().filter(|id| **id == 0).count()
+    }
+
+    /// List an order that left the book so its escrow can be released
+    pub fn release(&mut self, order_id: u64) -> Result<()> {
+        let slot = self.released
+            .iter_mut()
+            .find(|slot| **slot == 0)
+            .ok_or(ErrorCode::SettlementBacklog)?;
+        *slot = order_id;
+        Ok(())
+    }
+
+    /// Clear `order_id` from the released list, returning whether it was there
+    pub fn take_release(&mut self, order_id: u64) -> bool {
+        match self.released.iter_mut().find(|id| order_id != 0 && **id == order_id) {
+            Some(id) => {
+                *id = 0;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Tally what the vaults should hold for `escrows` and the TWAP reserves,
+    /// and how many pending matches those escrows can't cover
+    /// A match needs its notional left in the bid's escrow and its size in
+    /// the ask's, on top of what earlier pending matches already claim.
+    pub fn reconcile(
+        &self,
+        escrows: &[OrderEscrow],
+        base_reserve: u64,
+        quote_reserve: u64,
+    ) -> Result<EscrowTally> {
+        let mut tally = EscrowTally {
+            base_expected: base_reserve,
+            quote_expected: quote_reserve,
+            ..EscrowTally::default()
+        };
+        let mut available = Vec::with_capacity(escrows.len());
+        for escrow in escrows {
+            let outstanding = escrow.deposited
+                .checked_sub(escrow.spent)
+                .ok_or(ErrorCode::InvalidSettlement)?;
+            let expected = if escrow.is_buy { &mut tally.quote_expected } else { &mut tally.base_expected };
+            *expected = expected.checked_add(outstanding).ok_or(ErrorCode::MathOverflow)?;
+            available.push(outstanding);
+        }
+
+        for fill in self.fills.iter().filter(|fill| fill.bid_order_id != 0) {
+            tally.pending_fills += 1;
+            let notional = u64::try_from((fill.filled_size as u128) * (fill.price as u128))
+                .map_err(|_| ErrorCode::MathOverflow)?;
+            let bid = escrows.iter().position(|e| e.is_buy && e.order_id == fill.bid_order_id);
+            let ask = escrows.iter().position(|e| !e.is_buy && e.order_id == fill.ask_order_id);
+            match (bid, ask) {
+                (Some(bid), Some(ask))
+                    if available[bid] >= notional && available[ask] >= fill.filled_size =>
+                {
+                    available[bid] -= notional;
+                    available[ask] -= fill.filled_size;
+                }
+                _ => tally.unbacked_fills += 1,
+            }
+        }
+        Ok(tally)
+    }
+
+    /// Queue a reported match for settlement, releasing the escrow of any
+    /// side it took off the book
+    pub fn record(&mut self, fill: PendingFill, bid_closed: bool, ask_closed: bool) -> Result<()> {
+        self.push(fill)?;
+        if bid_closed {
+            self.release(fill.bid_order_id)?;
+        }
+        if ask_closed {
+            self.release(fill.ask_order_id)?;
+        }
+        Ok(())
+    }
+}
+
+/// What `reconcile_settlement` expects the escrow vaults to hold
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct EscrowTally {
+    pub base_expected: u64,
+    pub quote_expected: u64,
+    pub pending_fills: u8,
+    pub unbacked_fills: u8, // Pending matches their escrows can't cover
+}
+
+impl EscrowTally {
+    pub fn is_consistent(&self, base_held: u64, quote_held: u64) -> bool {
+        self.unbacked_fills == 0
+            && self.base_expected == base_held
+            && self.quote_expected == quote_held
+    }
+}
+
+/// Unsettled matches the ledger can hold, a few `match_orders` runs' worth
+pub const SETTLEMENT_LEDGER_SLOTS: usize = 32;
+
+/// Orders the ledger can list as off the book before `release_escrow` or
+/// `withdraw_escrow` clears them; a `cancel_all_orders` may list MAX_ORDERS
+pub const RELEASED_ESCROW_SLOTS: usize = 32;
+
+/// Slots past an order's expiry before its escrow can be withdrawn, leaving
+/// time for a match queued before the expiry to land its fills
+pub const ESCROW_REFUND_DELAY_SLOTS: u64 = 150;
+
+/// Slots after which a placing computation that never called back is taken
+/// as failed and its escrow released (mirrors private_pay)
+pub const COMPUTATION_TIMEOUT_SLOTS: u64 = 1_500;
+
+/// Order type discriminants for the encrypted order type in `add_order`
+pub const ORDER_TYPE_LIMIT: u8 = 0;
+pub const ORDER_TYPE_MARKET: u8 = 1;
+
+/// Maximum number of resting orders, mirrors `MAX_ORDERS` in encrypted-ixs
+/// Kept at 16 so `OrderBookState` stays below the 10 KiB limit for accounts
+/// created with `init`; 32 slots would already need ~15 KiB
+pub const MAX_ORDERS: usize = 16;
+
+/// Encrypted words per order slot (see `Order` in encrypted-ixs)
+pub const ORDER_WORDS: usize = 16;
+
+/// Makers tracked for liquidity incentives, mirrors `MAX_MAKERS` in encrypted-ixs
+pub const MAX_MAKERS: usize = 16;
+
+/// Encrypted words per maker score entry (see `MakerScore` in encrypted-ixs)
+pub const MAKER_WORDS: usize = 3;
+
+/// Encrypted words in the order book state: every slot, the priority counter
+/// and the maker score table
+pub const ORDER_BOOK_STATE_WORDS: usize = MAX_ORDERS * ORDER_WORDS + 1 + MAX_MAKERS * MAKER_WORDS;
+
+/// MXE-encrypted order book, written only by computation callbacks
+#[account]
+pub struct OrderBookState {
+    pub order_book: Pubkey,
+    pub bump: u8,
+    pub state_nonce: u128,
+    pub encrypted_state: [[u8; 32]; ORDER_BOOK_STATE_WORDS],
+}
+
+impl OrderBookState {
+    pub const SIZE: usize = 32 + 1 + 16 + 32 * ORDER_BOOK_STATE_WORDS;
+    /// Offset of `encrypted_state` in the account data, including the discriminator
+    pub const STATE_OFFSET: u32 = 8 + 32 + 1 + 16;
+    pub const STATE_LEN: u32 = 32 * ORDER_BOOK_STATE_WORDS as u32;
+
+    /// Store a freshly encrypted book returned by a computation
+    pub fn write(&mut self, book_state: MXEEncryptedStruct<ORDER_BOOK_STATE_WORDS>) {
+        self.encrypted_state = book_state.ciphertexts;
+        self.state_nonce = book_state.nonce;
+    }
+}
+
+/// Words in an encrypted `TwapState` (price, remaining, slice size)
+pub const TWAP_STATE_WORDS: usize = 3;
+
+/// A TWAP parent order rested on the book one slice at a time
+#[account]
+pub struct TwapOrder {
+    pub order_book: Pubkey,
+    pub owner: Pubkey,
+    pub twap_id: u64,
+    pub is_buy: bool,
+    pub slice_interval_slots: u64,
+    pub next_slice_slot: u64,
+    pub slices_released: u32,
+    pub active: bool, // Accepted by MPC and not yet exhausted
+    pub bump: u8,
+    pub state_nonce: u128,
+    pub encrypted_state: [[u8; 32]; TWAP_STATE_WORDS],
+    pub reserve: u64,      // Escrowed tokens not yet handed to a slice
+    pub slice_escrow: u64, // Escrow each released slice draws from the reserve
+}
+
+impl TwapOrder {
+    pub const SIZE: usize = 32 + 32 + 8 + 1 + 8 + 8 + 4 + 1 + 1 + 16 + 32 * TWAP_STATE_WORDS + 8 + 8;
+    /// Offset of `encrypted_state` in the account data, including the discriminator
+    pub const STATE_OFFSET: u32 = 8 + 32 + 32 + 8 + 1 + 8 + 8 + 4 + 1 + 1 + 16;
+    pub const STATE_LEN: u32 = 32 * TWAP_STATE_WORDS as u32;
+
+    /// Store a freshly encrypted TWAP state returned by a computation
+    pub fn write(&mut self, twap_state: MXEEncryptedStruct<TWAP_STATE_WORDS>) {
+        self.encrypted_state = twap_state.ciphertexts;
+        self.state_nonce = twap_state.nonce;
+    }
+
+    /// Schedule the slice after the one due at `current_slot` and take its
+    /// escrow out of the reserve
+    pub fn draw_slice(&mut self, current_slot: u64) -> Result<()> {
+        require!(self.active, ErrorCode::TwapNotActive);
+        require!(current_slot >= self.next_slice_slot, ErrorCode::TwapSliceNotDue);
+        require!(self.reserve >= self.slice_escrow, ErrorCode::TwapReserveExhausted);
+        self.next_slice_slot = current_slot
+            .checked_add(self.slice_interval_slots)
+            .ok_or(ErrorCode::MathOverflow)?;
+        self.reserve -= self.slice_escrow;
+        Ok(())
+    }
+}
+
+/// Split a pubkey into the two u128 words the circuits store owners as
+pub fn owner_words(owner: &Pubkey) -> (u128, u128) {
+    let bytes = owner.to_bytes();
+    let mut lo = [0u8; 16];
+    let mut hi = [0u8; 16];
+    lo.copy_from_slice(&bytes[..16]);
+    hi.copy_from_slice(&bytes[16..]);
+    (u128::from_le_bytes(lo), u128::from_le_bytes(hi))
+}
+
+/// Version of the circuits this program build was written against.
+/// Bump whenever a circuit in encrypted-ixs changes its inputs, outputs or
+/// semantics, so computations against comp defs initialized for an older
+/// build are rejected until the comp defs are re-initialized.
+pub const CIRCUIT_VERSION: u32 = 27;
+
+/// Circuit version a computation definition was initialized for
+#[account]
+pub struct CircuitVersion {
+    pub version: u32,
+    pub bump: u8,
+}
+
+impl CircuitVersion {
+    pub const SIZE: usize = 4 + 1;
+
+    pub fn stamp(&mut self, bump: u8) {
+        self.version = CIRCUIT_VERSION;
+        self.bump = bump;
+    }
+}
+
+/// Reject computations queued against a comp def from an older program build
+pub fn assert_circuit_version(circuit_version: &CircuitVersion) -> Result<()> {
+    require!(
+        circuit_version.version == CIRCUIT_VERSION,
+        ErrorCode::StaleComputationDefinition
+    );
+    Ok(())
+}
+
+/// Reject a payer that can't cover the computation fee plus `locked`
+/// lamports before the queue CPI fails opaquely
+fn ensure_computation_fee(payer: &Signer, locked: u64) -> Result<()> {
+    require!(
+        covers_computation_fee(payer.lamports(), locked),
+        ErrorCode::InsufficientComputationFee
+    );
+    Ok(())
+}
+
+// ============ Instruction Contexts ============
+
+#[derive(Accounts)]
+pub struct InitOrderBook<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + OrderBook::SIZE,
+        seeds = [b"orderbook", base_mint.key().as_ref(), quote_mint.key().as_ref()],
+        bump,
+    )]
+    pub order_book: Account<'info, OrderBook>,
+    
+    pub base_mint: Account<'info, Mint>,
+    pub quote_mint: Account<'info, Mint>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetOrderParams<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(mut, has_one = authority @ ErrorCode::Unauthorized)]
+    pub order_book: Account<'info, OrderBook>,
+}
+
+#[derive(Accounts)]
+pub struct InitPriceOracle<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(mut, has_one = authority @ ErrorCode::Unauthorized)]
+    pub order_book: Account<'info, OrderBook>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + PriceOracle::SIZE,
+        seeds = [b"oracle", order_book.key().as_ref()],
+        bump,
+    )]
+    pub oracle: Account<'info, PriceOracle>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateOraclePrice<'info> {
+    pub updater: Signer<'info>,
+    
+    #[account(mut, has_one = updater @ ErrorCode::Unauthorized)]
+    pub oracle: Account<'info, PriceOracle>,
+}
+
+#[derive(Accounts)]
+pub struct InitFeeVault<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(has_one = authority @ ErrorCode::Unauthorized, has_one = quote_mint)]
+    pub order_book: Account<'info, OrderBook>,
+    
+    pub quote_mint: Account<'info, Mint>,
+    
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"fee_vault", order_book.key().as_ref()],
+        bump,
+        token::mint = quote_mint,
+        token::authority = order_book,
+    )]
+    pub fee_vault: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CollectFees<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(mut, has_one = authority @ ErrorCode::Unauthorized)]
+    pub order_book: Account<'info, OrderBook>,
+    
+    #[account(
+        mut,
+        seeds = [b"fee_vault", order_book.key().as_ref()],
+        bump,
+    )]
+    pub fee_vault: Account<'info, TokenAccount>,
+    
+    #[account(mut, constraint = destination.mint == order_book.quote_mint @ ErrorCode::InvalidOrderParams)]
+    pub destination: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct InitSettlement<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(has_one = authority @ ErrorCode::Unauthorized, has_one = base_mint, has_one = quote_mint)]
+    pub order_book: Account<'info, OrderBook>,
+    
+    pub base_mint: Account<'info, Mint>,
+    pub quote_mint: Account<'info, Mint>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + SettlementLedger::SIZE,
+        seeds = [b"settlement", order_book.key().as_ref()],
+        bump,
+    )]
+    pub settlement_ledger: Box<Account<'info, SettlementLedger>>,
+    
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"base_vault", order_book.key().as_ref()],
+        bump,
+        token::mint = base_mint,
+        token::authority = order_book,
+    )]
+    pub base_vault: Box<Account<'info, TokenAccount>>,
+    
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"quote_vault", order_book.key().as_ref()],
+        bump,
+        token::mint = quote_mint,
+        token::authority = order_book,
+    )]
+    pub quote_vault: Box<Account<'info, TokenAccount>>,
+    
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SettleFill<'info> {
+    #[account(mut)]
+    pub order_book: Account<'info, OrderBook>,
+    
+    #[account(
+        mut,
+        seeds = [b"settlement", order_book.key().as_ref()],
+        bump = settlement_ledger.bump,
+    )]
+    pub settlement_ledger: Box<Account<'info, SettlementLedger>>,
+    
+    #[account(mut, constraint = bid_escrow.order_book == order_book.key() @ ErrorCode::Unauthorized)]
+    pub bid_escrow: Box<Account<'info, OrderEscrow>>,
+    
+    #[account(mut, constraint = ask_escrow.order_book == order_book.key() @ ErrorCode::Unauthorized)]
+    pub ask_escrow: Box<Account<'info, OrderEscrow>>,
+    
+    /// Checked against the ledger
+    #[account(mut)]
+    pub base_vault: Box<Account<'info, TokenAccount>>,
+    
+    /// Checked against the ledger
+    #[account(mut)]
+    pub quote_vault: Box<Account<'info, TokenAccount>>,
+    
+    /// Buyer's base account
+    #[account(mut, constraint = buyer_token.owner == bid_escrow.owner @ ErrorCode::Unauthorized)]
+    pub buyer_token: Box<Account<'info, TokenAccount>>,
+    
+    /// Seller's quote account
+    #[account(mut, constraint = seller_token.owner == ask_escrow.owner @ ErrorCode::Unauthorized)]
+    pub seller_token: Box<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [b"fee_vault", order_book.key().as_ref()],
+        bump,
+    )]
+    pub fee_vault: Box<Account<'info, TokenAccount>>,
+    
+    /// Buyer's order bond, released early once a fill settles
+    #[account(
+        mut,
+        constraint = bid_bond.order_book == order_book.key()
+            && bid_bond.order_id == bid_escrow.order_id @ ErrorCode::InvalidSettlement,
+    )]
+    pub bid_bond: Option<Box<Account<'info, OrderBond>>>,
+    
+    /// Seller's order bond, released early once a fill settles
+    #[account(
+        mut,
+        constraint = ask_bond.order_book == order_book.key()
+            && ask_bond.order_id == ask_escrow.order_id @ ErrorCode::InvalidSettlement,
+    )]
+    pub ask_bond: Option<Box<Account<'info, OrderBond>>>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct DiscardFill<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(has_one = authority @ ErrorCode::Unauthorized)]
+    pub order_book: Account<'info, OrderBook>,
+    
+    #[account(
+        mut,
+        seeds = [b"settlement", order_book.key().as_ref()],
+        bump = settlement_ledger.bump,
+    )]
+    pub settlement_ledger: Box<Account<'info, SettlementLedger>>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawEscrow<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    pub order_book: Account<'info, OrderBook>,
+    
+    #[account(
+        mut,
+        seeds = [b"settlement", order_book.key().as_ref()],
+        bump = settlement_ledger.bump,
+    )]
+    pub settlement_ledger: Box<Account<'info, SettlementLedger>>,
+    
+    #[account(
+        mut,
+        close = owner,
+        has_one = owner @ ErrorCode::Unauthorized,
+        constraint = order_escrow.order_book == order_book.key() @ ErrorCode::Unauthorized,
+    )]
+    pub order_escrow: Box<Account<'info, OrderEscrow>>,
+    
+    /// Quote vault for a buy, base vault for a sell, checked against the ledger
+    #[account(mut)]
+    pub escrow_vault: Box<Account<'info, TokenAccount>>,
+    
+    #[account(mut, constraint = owner_token.owner == owner.key() @ ErrorCode::Unauthorized)]
+    pub owner_token: Box<Account<'info, TokenAccount>>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseEscrow<'info> {
+    pub order_book: Account<'info, OrderBook>,
+    
+    #[account(
+        mut,
+        seeds = [b"settlement", order_book.key().as_ref()],
+        bump = settlement_ledger.bump,
+    )]
+    pub settlement_ledger: Box<Account<'info, SettlementLedger>>,
+    
+    #[account(mut, constraint = order_escrow.order_book == order_book.key() @ ErrorCode::Unauthorized)]
+    pub order_escrow: Box<Account<'info, OrderEscrow>>,
+}
+
+#[derive(Accounts)]
+pub struct ReconcileSettlement<'info> {
+    pub order_book: Account<'info, OrderBook>,
+    
+    #[account(
+        seeds = [b"settlement", order_book.key().as_ref()],
+        bump = settlement_ledger.bump,
+    )]
+    pub settlement_ledger: Box<Account<'info, SettlementLedger>>,
+    
+    /// Checked against the ledger
+    pub base_vault: Box<Account<'info, TokenAccount>>,
+    
+    /// Checked against the ledger
+    pub quote_vault: Box<Account<'info, TokenAccount>>,
+}
+
+#[derive(Accounts)]
+pub struct GetEpochVolume<'info> {
+    pub order_book: Account<'info, OrderBook>,
+}
+
+#[queue_computation_accounts("add_order", fee_payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct AddOrder<'info> {
+    /// Funds the computation and account rent; may be a relayer or the trader
+    #[account(mut)]
+    pub fee_payer: Signer<'info>,
+    
+    /// Trader the order and bond belong to
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(mut)]
+    pub order_book: Account<'info, OrderBook>,
+    
+    #[account(
+        init,
+        payer = fee_payer,
+        space = 8 + OrderBond::SIZE,
+        seeds = [
+            b"bond",
+            order_book.key().as_ref(),
+            payer.key().as_ref(),
+            computation_offset.to_le_bytes().as_ref(),
+        ],
+        bump,
+    )]
+    pub order_bond: Account<'info, OrderBond>,
+    
+    #[account(
+        init,
+        payer = fee_payer,
+        space = 8 + OrderEscrow::SIZE,
+        seeds = [
+            b"escrow",
+            order_book.key().as_ref(),
+            payer.key().as_ref(),
+            computation_offset.to_le_bytes().as_ref(),
+        ],
+        bump,
+    )]
+    pub order_escrow: Box<Account<'info, OrderEscrow>>,
+    
+    #[account(
+        seeds = [b"settlement", order_book.key().as_ref()],
+        bump = settlement_ledger.bump,
+    )]
+    pub settlement_ledger: Box<Account<'info, SettlementLedger>>,
+    
+    /// Trader's base account for a sell, quote account for a buy
+    #[account(mut, constraint = trader_token.owner == payer.key() @ ErrorCode::Unauthorized)]
+    pub trader_token: Box<Account<'info, TokenAccount>>,
+    
+    /// Vault matching `trader_token`'s side, checked against the ledger
+    #[account(mut)]
+    pub escrow_vault: Box<Account<'info, TokenAccount>>,
+    
+    pub token_program: Program<'info, Token>,
+    
+    #[account(
+        mut,
+        seeds = [b"orderbook_state", order_book.key().as_ref()],
+        bump = order_book_state.bump,
+    )]
+    pub order_book_state: Box<Account<'info, OrderBookState>>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = fee_payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_ADD_ORDER))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(seeds = [b"circuit_version", &COMP_DEF_OFFSET_ADD_ORDER.to_le_bytes()], bump = circuit_version.bump)]
+    pub circuit_version: Account<'info, CircuitVersion>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("add_order")]
+#[derive(Accounts)]
+pub struct AddOrderCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_ADD_ORDER))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(mut)]
+    pub order_book: Account<'info, OrderBook>,
+    #[account(mut)]
+    pub order_book_state: Box<Account<'info, OrderBookState>>,
+    #[account(mut)]
+    pub order_bond: Account<'info, OrderBond>,
+    #[account(mut)]
+    pub order_escrow: Box<Account<'info, OrderEscrow>>,
+    #[account(mut, has_one = order_book)]
+    pub settlement_ledger: Box<Account<'info, SettlementLedger>>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[queue_computation_accounts("add_dutch_order", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct AddDutchOrder<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(mut)]
+    pub order_book: Account<'info, OrderBook>,
+    
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + OrderBond::SIZE,
+        seeds = [
+            b"bond",
+            order_book.key().as_ref(),
+            payer.key().as_ref(),
+            computation_offset.to_le_bytes().as_ref(),
+        ],
+        bump,
+    )]
+    pub order_bond: Account<'info, OrderBond>,
+    
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + OrderEscrow::SIZE,
+        seeds = [
+            b"escrow",
+            order_book.key().as_ref(),
+            payer.key().as_ref(),
+            computation_offset.to_le_bytes().as_ref(),
+        ],
+        bump,
+    )]
+    pub order_escrow: Box<Account<'info, OrderEscrow>>,
+    
+    #[account(
+        seeds = [b"settlement", order_book.key().as_ref()],
+        bump = settlement_ledger.bump,
+    )]
+    pub settlement_ledger: Box<Account<'info, SettlementLedger>>,
+    
+    /// Seller's base account
+    #[account(mut, constraint = trader_token.owner == payer.key() @ ErrorCode::Unauthorized)]
+    pub trader_token: Box<Account<'info, TokenAccount>>,
+    
+    /// Base vault, checked against the ledger
+    #[account(mut)]
+    pub escrow_vault: Box<Account<'info, TokenAccount>>,
+    
+    pub token_program: Program<'info, Token>,
+    
+    #[account(
+        mut,
+        seeds = [b"orderbook_state", order_book.key().as_ref()],
+        bump = order_book_state.bump,
+    )]
+    pub order_book_state: Box<Account<'info, OrderBookState>>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_ADD_DUTCH_ORDER))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(seeds = [b"circuit_version", &COMP_DEF_OFFSET_ADD_DUTCH_ORDER.to_le_bytes()], bump = circuit_version.bump)]
+    pub circuit_version: Account<'info, CircuitVersion>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("add_dutch_order")]
+#[derive(Accounts)]
+pub struct AddDutchOrderCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_ADD_DUTCH_ORDER))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(mut)]
+    pub order_book: Account<'info, OrderBook>,
+    #[account(mut)]
+    pub order_book_state: Box<Account<'info, OrderBookState>>,
+    #[account(mut)]
+    pub order_bond: Account<'info, OrderBond>,
+    #[account(mut)]
+    pub order_escrow: Box<Account<'info, OrderEscrow>>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[queue_computation_accounts("match_orders", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct MatchOrders<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(mut)]
+    pub order_book: Account<'info, OrderBook>,
+    
+    #[account(
+        mut,
+        seeds = [b"orderbook_state", order_book.key().as_ref()],
+        bump = order_book_state.bump,
+    )]
+    pub order_book_state: Box<Account<'info, OrderBookState>>,
+    
+    #[account(
+        seeds = [b"settlement", order_book.key().as_ref()],
+        bump = settlement_ledger.bump,
+    )]
+    pub settlement_ledger: Box<Account<'info, SettlementLedger>>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_MATCH_ORDERS))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(seeds = [b"circuit_version", &COMP_DEF_OFFSET_MATCH_ORDERS.to_le_bytes()], bump = circuit_version.bump)]
+    pub circuit_version: Account<'info, CircuitVersion>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("match_orders")]
+#[derive(Accounts)]
+pub struct MatchOrdersCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_MATCH_ORDERS))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(mut)]
+    pub order_book: Account<'info, OrderBook>,
+    #[account(mut)]
+    pub order_book_state: Box<Account<'info, OrderBookState>>,
+    #[account(mut, has_one = order_book)]
+    pub settlement_ledger: Box<Account<'info, SettlementLedger>>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[queue_computation_accounts("prune_expired_orders", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct PruneExpiredOrders<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(mut)]
+    pub order_book: Account<'info, OrderBook>,
+    
+    #[account(
+        mut,
+        seeds = [b"orderbook_state", order_book.key().as_ref()],
+        bump = order_book_state.bump,
+    )]
+    pub order_book_state: Box<Account<'info, OrderBookState>>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_PRUNE_EXPIRED_ORDERS))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(seeds = [b"circuit_version", &COMP_DEF_OFFSET_PRUNE_EXPIRED_ORDERS.to_le_bytes()], bump = circuit_version.bump)]
+    pub circuit_version: Account<'info, CircuitVersion>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("prune_expired_orders")]
+#[derive(Accounts)]
+pub struct PruneExpiredOrdersCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_PRUNE_EXPIRED_ORDERS))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(mut)]
+    pub order_book: Account<'info, OrderBook>,
+    #[account(mut)]
+    pub order_book_state: Box<Account<'info, OrderBookState>>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[queue_computation_accounts("cancel_order", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, order_id: u64)]
+pub struct CancelOrder<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(mut)]
+    pub order_book: Account<'info, OrderBook>,
+    
+    #[account(
+        mut,
+        constraint = order_bond.order_book == order_book.key() @ ErrorCode::Unauthorized,
+        constraint = order_bond.owner == payer.key() @ ErrorCode::Unauthorized,
+        constraint = order_bond.order_id == order_id @ ErrorCode::Unauthorized,
+    )]
+    pub order_bond: Account<'info, OrderBond>,
+    
+    #[account(
+        mut,
+        constraint = order_escrow.order_book == order_book.key() @ ErrorCode::Unauthorized,
+        constraint = order_escrow.owner == payer.key() @ ErrorCode::Unauthorized,
+        constraint = order_escrow.order_id == order_id @ ErrorCode::Unauthorized,
+    )]
+    pub order_escrow: Box<Account<'info, OrderEscrow>>,
+    
+    #[account(
+        mut,
+        seeds = [b"orderbook_state", order_book.key().as_ref()],
+        bump = order_book_state.bump,
+    )]
+    pub order_book_state: Box<Account<'info, OrderBookState>>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_CANCEL_ORDER))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(seeds = [b"circuit_version", &COMP_DEF_OFFSET_CANCEL_ORDER.to_le_bytes()], bump = circuit_version.bump)]
+    pub circuit_version: Account<'info, CircuitVersion>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("cancel_order")]
+#[derive(Accounts)]
+pub struct CancelOrderCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_CANCEL_ORDER))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(mut)]
+    pub order_book: Account<'info, OrderBook>,
+    #[account(mut)]
+    pub order_book_state: Box<Account<'info, OrderBookState>>,
+    #[account(mut)]
+    pub order_bond: Account<'info, OrderBond>,
+    #[account(mut, address = order_bond.owner)]
+    /// CHECK: bond owner, receives the refund and bond rent
+    pub owner: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub order_escrow: Box<Account<'info, OrderEscrow>>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[init_computation_definition_accounts("add_order", payer)]
+#[derive(Accounts)]
+pub struct InitAddOrderCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + CircuitVersion::SIZE,
+        seeds = [b"circuit_version", &COMP_DEF_OFFSET_ADD_ORDER.to_le_bytes()],
+        bump,
+    )]
+    pub circuit_version: Account<'info, CircuitVersion>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("match_orders", payer)]
+#[derive(Accounts)]
+pub struct InitMatchOrdersCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + CircuitVersion::SIZE,
+        seeds = [b"circuit_version", &COMP_DEF_OFFSET_MATCH_ORDERS.to_le_bytes()],
+        bump,
+    )]
+    pub circuit_version: Account<'info, CircuitVersion>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("cancel_order", payer)]
+#[derive(Accounts)]
+pub struct InitCancelOrderCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + CircuitVersion::SIZE,
+        seeds = [b"circuit_version", &COMP_DEF_OFFSET_CANCEL_ORDER.to_le_bytes()],
+        bump,
+    )]
+    pub circuit_version: Account<'info, CircuitVersion>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("compute_clearing_price", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct ComputeClearingPrice<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub order_book: Account<'info, OrderBook>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_CLEARING_PRICE))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(seeds = [b"circuit_version", &COMP_DEF_OFFSET_CLEARING_PRICE.to_le_bytes()], bump = circuit_version.bump)]
+    pub circuit_version: Account<'info, CircuitVersion>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("compute_clearing_price")]
+#[derive(Accounts)]
+pub struct ComputeClearingPriceCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_CLEARING_PRICE))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    pub order_book: Account<'info, OrderBook>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[init_computation_definition_accounts("compute_clearing_price", payer)]
+#[derive(Accounts)]
+pub struct InitClearingPriceCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + CircuitVersion::SIZE,
+        seeds = [b"circuit_version", &COMP_DEF_OFFSET_CLEARING_PRICE.to_le_bytes()],
+        bump,
+    )]
+    pub circuit_version: Account<'info, CircuitVersion>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseBond<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    #[account(
+        mut,
+        close = owner,
+        has_one = owner @ ErrorCode::Unauthorized,
+    )]
+    pub order_bond: Account<'info, OrderBond>,
+}
+
+#[queue_computation_accounts("init_order_book_state", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct InitOrderBookState<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(has_one = authority @ ErrorCode::Unauthorized)]
+    pub order_book: Account<'info, OrderBook>,
+    
+    pub authority: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + OrderBookState::SIZE,
+        seeds = [b"orderbook_state", order_book.key().as_ref()],
+        bump,
+    )]
+    pub order_book_state: Box<Account<'info, OrderBookState>>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_INIT_BOOK_STATE))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(seeds = [b"circuit_version", &COMP_DEF_OFFSET_INIT_BOOK_STATE.to_le_bytes()], bump = circuit_version.bump)]
+    pub circuit_version: Account<'info, CircuitVersion>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("init_order_book_state")]
+#[derive(Accounts)]
+pub struct InitOrderBookStateCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_INIT_BOOK_STATE))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(mut)]
+    pub order_book_state: Box<Account<'info, OrderBookState>>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[init_computation_definition_accounts("init_order_book_state", payer)]
+#[derive(Accounts)]
+pub struct InitOrderBookStateCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + CircuitVersion::SIZE,
+        seeds = [b"circuit_version", &COMP_DEF_OFFSET_INIT_BOOK_STATE.to_le_bytes()],
+        bump,
+    )]
+    pub circuit_version: Account<'info, CircuitVersion>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("run_batch_auction", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct RunBatchAuction<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(mut)]
+    pub order_book: Account<'info, OrderBook>,
+    
+    #[account(
+        mut,
+        seeds = [b"orderbook_state", order_book.key().as_ref()],
+        bump = order_book_state.bump,
+    )]
+    pub order_book_state: Box<Account<'info, OrderBookState>>,
+    
+    #[account(
+        seeds = [b"settlement", order_book.key().as_ref()],
+        bump = settlement_ledger.bump,
+    )]
+    pub settlement_ledger: Box<Account<'info, SettlementLedger>>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_BATCH_AUCTION))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(seeds = [b"circuit_version", &COMP_DEF_OFFSET_BATCH_AUCTION.to_le_bytes()], bump = circuit_version.bump)]
+    pub circuit_version: Account<'info, CircuitVersion>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("run_batch_auction")]
+#[derive(Accounts)]
+pub struct RunBatchAuctionCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_BATCH_AUCTION))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(mut)]
+    pub order_book: Account<'info, OrderBook>,
+    #[account(mut)]
+    pub order_book_state: Box<Account<'info, OrderBookState>>,
+    #[account(mut, has_one = order_book)]
+    pub settlement_ledger: Box<Account<'info, SettlementLedger>>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[init_computation_definition_accounts("run_batch_auction", payer)]
+#[derive(Accounts)]
+pub struct InitBatchAuctionCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + CircuitVersion::SIZE,
+        seeds = [b"circuit_version", &COMP_DEF_OFFSET_BATCH_AUCTION.to_le_bytes()],
+        bump,
+    )]
+    pub circuit_version: Account<'info, CircuitVersion>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitAllCompDefs<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+}
+
+#[init_computation_definition_accounts("reveal_my_depth", payer)]
+#[derive(Accounts)]
+pub struct InitRevealMyDepthCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + CircuitVersion::SIZE,
+        seeds = [b"circuit_version", &COMP_DEF_OFFSET_REVEAL_MY_DEPTH.to_le_bytes()],
+        bump,
+    )]
+    pub circuit_version: Account<'info, CircuitVersion>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("reveal_my_depth", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct RevealMyDepth<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub order_book: Account<'info, OrderBook>,
+    
+    #[account(
+        seeds = [b"orderbook_state", order_book.key().as_ref()],
+        bump = order_book_state.bump,
+    )]
+    pub order_book_state: Box<Account<'info, OrderBookState>>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_REVEAL_MY_DEPTH))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(seeds = [b"circuit_version", &COMP_DEF_OFFSET_REVEAL_MY_DEPTH.to_le_bytes()], bump = circuit_version.bump)]
+    pub circuit_version: Account<'info, CircuitVersion>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("reveal_my_depth")]
+#[derive(Accounts)]
+pub struct RevealMyDepthCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_REVEAL_MY_DEPTH))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    pub order_book: Account<'info, OrderBook>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[init_computation_definition_accounts("liquidate_order", payer)]
+#[derive(Accounts)]
+pub struct InitLiquidateOrderCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + CircuitVersion::SIZE,
+        seeds = [b"circuit_version", &COMP_DEF_OFFSET_LIQUIDATE_ORDER.to_le_bytes()],
+        bump,
+    )]
+    pub circuit_version: Account<'info, CircuitVersion>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("liquidate_order", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct LiquidateOrder<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(
+        mut,
+        constraint = order_book.liquidator == payer.key() @ ErrorCode::Unauthorized,
+    )]
+    pub order_book: Account<'info, OrderBook>,
+    
+    #[account(address = order_book.oracle @ ErrorCode::Unauthorized)]
+    pub oracle: Account<'info, PriceOracle>,
+    
+    #[account(
+        mut,
+        seeds = [b"orderbook_state", order_book.key().as_ref()],
+        bump = order_book_state.bump,
+    )]
+    pub order_book_state: Box<Account<'info, OrderBookState>>,
+    
+    #[account(
+        seeds = [b"settlement", order_book.key().as_ref()],
+        bump = settlement_ledger.bump,
+    )]
+    pub settlement_ledger: Box<Account<'info, SettlementLedger>>,
+    
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + OrderEscrow::SIZE,
+        seeds = [
+            b"escrow",
+            order_book.key().as_ref(),
+            payer.key().as_ref(),
+            computation_offset.to_le_bytes().as_ref(),
+        ],
+        bump,
+    )]
+    pub order_escrow: Box<Account<'info, OrderEscrow>>,
+    
+    /// Liquidator's base account the collateral is escrowed from
+    #[account(mut, constraint = trader_token.owner == payer.key() @ ErrorCode::Unauthorized)]
+    pub trader_token: Box<Account<'info, TokenAccount>>,
+    
+    /// Base vault, checked against the ledger
+    #[account(mut)]
+    pub escrow_vault: Box<Account<'info, TokenAccount>>,
+    
+    pub token_program: Program<'info, Token>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_LIQUIDATE_ORDER))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(seeds = [b"circuit_version", &COMP_DEF_OFFSET_LIQUIDATE_ORDER.to_le_bytes()], bump = circuit_version.bump)]
+    pub circuit_version: Account<'info, CircuitVersion>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("liquidate_order")]
+#[derive(Accounts)]
+pub struct LiquidateOrderCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_LIQUIDATE_ORDER))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(mut)]
+    pub order_book: Account<'info, OrderBook>,
+    #[account(mut)]
+    pub order_book_state: Box<Account<'info, OrderBookState>>,
+    #[account(mut, has_one = order_book)]
+    pub settlement_ledger: Box<Account<'info, SettlementLedger>>,
+    #[account(mut)]
+    pub order_escrow: Box<Account<'info, OrderEscrow>>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[init_computation_definition_accounts("add_dutch_order", payer)]
+#[derive(Accounts)]
+pub struct InitAddDutchOrderCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + CircuitVersion::SIZE,
+        seeds = [b"circuit_version", &COMP_DEF_OFFSET_ADD_DUTCH_ORDER.to_le_bytes()],
+        bump,
+    )]
+    pub circuit_version: Account<'info, CircuitVersion>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("prune_expired_orders", payer)]
+#[derive(Accounts)]
+pub struct InitPruneExpiredOrdersCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + CircuitVersion::SIZE,
+        seeds = [b"circuit_version", &COMP_DEF_OFFSET_PRUNE_EXPIRED_ORDERS.to_le_bytes()],
+        bump,
+    )]
+    pub circuit_version: Account<'info, CircuitVersion>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("reveal_maker_score", payer)]
+#[derive(Accounts)]
+pub struct InitRevealMakerScoreCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + CircuitVersion::SIZE,
+        seeds = [b"circuit_version", &COMP_DEF_OFFSET_REVEAL_MAKER_SCORE.to_le_bytes()],
+        bump,
+    )]
+    pub circuit_version: Account<'info, CircuitVersion>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("reveal_maker_score", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct RevealMakerScore<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub order_book: Account<'info, OrderBook>,
+    
+    #[account(
+        seeds = [b"orderbook_state", order_book.key().as_ref()],
+        bump = order_book_state.bump,
+    )]
+    pub order_book_state: Box<Account<'info, OrderBookState>>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_REVEAL_MAKER_SCORE))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(seeds = [b"circuit_version", &COMP_DEF_OFFSET_REVEAL_MAKER_SCORE.to_le_bytes()], bump = circuit_version.bump)]
+    pub circuit_version: Account<'info, CircuitVersion>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("reveal_maker_score")]
+#[derive(Accounts)]
+pub struct RevealMakerScoreCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_REVEAL_MAKER_SCORE))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    pub order_book: Account<'info, OrderBook>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseOrderBook<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(mut, close = authority, has_one = authority @ ErrorCode::Unauthorized)]
+    pub order_book: Account<'info, OrderBook>,
+}
+
+#[init_computation_definition_accounts("cancel_all_orders", payer)]
+#[derive(Accounts)]
+pub struct InitCancelAllOrdersCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + CircuitVersion::SIZE,
+        seeds = [b"circuit_version", &COMP_DEF_OFFSET_CANCEL_ALL_ORDERS.to_le_bytes()],
+        bump,
+    )]
+    pub circuit_version: Account<'info, CircuitVersion>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("cancel_all_orders", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct CancelAllOrders<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(mut)]
+    pub order_book: Account<'info, OrderBook>,
+    
+    #[account(
+        mut,
+        seeds = [b"orderbook_state", order_book.key().as_ref()],
+        bump = order_book_state.bump,
+    )]
+    pub order_book_state: Box<Account<'info, OrderBookState>>,
+    
+    #[account(
+        seeds = [b"settlement", order_book.key().as_ref()],
+        bump = settlement_ledger.bump,
+    )]
+    pub settlement_ledger: Box<Account<'info, SettlementLedger>>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_CANCEL_ALL_ORDERS))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(seeds = [b"circuit_version", &COMP_DEF_OFFSET_CANCEL_ALL_ORDERS.to_le_bytes()], bump = circuit_version.bump)]
+    pub circuit_version: Account<'info, CircuitVersion>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("cancel_all_orders")]
+#[derive(Accounts)]
+pub struct CancelAllOrdersCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_CANCEL_ALL_ORDERS))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(mut)]
+    pub order_book: Account<'info, OrderBook>,
+    #[account(mut)]
+    pub order_book_state: Box<Account<'info, OrderBookState>>,
+    /// CHECK: owner whose orders were cancelled, only recorded in the event
+    pub owner: UncheckedAccount<'info>,
+    #[account(mut, has_one = order_book)]
+    pub settlement_ledger: Box<Account<'info, SettlementLedger>>,
+
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[init_computation_definition_accounts("schedule_twap_order", payer)]
+#[derive(Accounts)]
+pub struct InitScheduleTwapOrderCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + CircuitVersion::SIZE,
+        seeds = [b"circuit_version", &COMP_DEF_OFFSET_SCHEDULE_TWAP.to_le_bytes()],
+        bump,
+    )]
+    pub circuit_version: Account<'info, CircuitVersion>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("release_twap_slice", payer)]
+#[derive(Accounts)]
+pub struct InitReleaseTwapSliceCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + CircuitVersion::SIZE,
+        seeds = [b"circuit_version", &COMP_DEF_OFFSET_RELEASE_TWAP_SLICE.to_le_bytes()],
+        bump,
+    )]
+    pub circuit_version: Account<'info, CircuitVersion>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
 }
 
-// ============ Account Structures ============
-
-#[account]
-pub struct OrderBook {
-    pub authority: Pubkey,
-    pub base_mint: Pubkey,
-    pub quote_mint: Pubkey,
-    pub fee_rate: u16,
-    pub bump: u8,
-    pub total_orders: u64,
-    pub total_matches: u64,
-    pub active_orders: u32,
+#[queue_computation_accounts("schedule_twap_order", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, twap_id: u64)]
+pub struct ScheduleTwapOrder<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub order_book: Account<'info, OrderBook>,
+    
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + TwapOrder::SIZE,
+        seeds = [
+            b"twap",
+            order_book.key().as_ref(),
+            payer.key().as_ref(),
+            twap_id.to_le_bytes().as_ref(),
+        ],
+        bump,
+    )]
+    pub twap_order: Account<'info, TwapOrder>,
+    
+    #[account(
+        seeds = [b"settlement", order_book.key().as_ref()],
+        bump = settlement_ledger.bump,
+    )]
+    pub settlement_ledger: Box<Account<'info, SettlementLedger>>,
+    
+    /// Owner's base account for a sell, quote account for a buy
+    #[account(mut, constraint = trader_token.owner == payer.key() @ ErrorCode::Unauthorized)]
+    pub trader_token: Box<Account<'info, TokenAccount>>,
+    
+    /// Vault matching `trader_token`'s side, checked against the ledger
+    #[account(mut)]
+    pub escrow_vault: Box<Account<'info, TokenAccount>>,
+    
+    pub token_program: Program<'info, Token>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_SCHEDULE_TWAP))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(seeds = [b"circuit_version", &COMP_DEF_OFFSET_SCHEDULE_TWAP.to_le_bytes()], bump = circuit_version.bump)]
+    pub circuit_version: Account<'info, CircuitVersion>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
 }
 
-impl OrderBook {
-    pub const SIZE: usize = 8 + 32 + 32 + 32 + 2 + 1 + 8 + 8 + 4;
-}
+#[callback_accounts("schedule_twap_order")]
+#[derive(Accounts)]
+pub struct ScheduleTwapOrderCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_SCHEDULE_TWAP))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(mut)]
+    pub twap_order: Account<'info, TwapOrder>,
 
-// ============ Instruction Contexts ============
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+}
 
+#[queue_computation_accounts("release_twap_slice", payer)]
 #[derive(Accounts)]
-pub struct InitOrderBook<'info> {
+#[instruction(computation_offset: u64)]
+pub struct ReleaseTwapSlice<'info> {
     #[account(mut)]
-    pub authority: Signer<'info>,
+    pub payer: Signer<'info>,
+    
+    #[account(mut)]
+    pub order_book: Account<'info, OrderBook>,
+    
+    #[account(
+        mut,
+        seeds = [b"orderbook_state", order_book.key().as_ref()],
+        bump = order_book_state.bump,
+    )]
+    pub order_book_state: Box<Account<'info, OrderBookState>>,
+    
+    #[account(mut, has_one = order_book)]
+    pub twap_order: Account<'info, TwapOrder>,
     
     #[account(
         init,
-        payer = authority,
-        space = 8 + OrderBook::SIZE,
-        seeds = [b"orderbook", base_mint.key().as_ref(), quote_mint.key().as_ref()],
+        payer = payer,
+        space = 8 + OrderEscrow::SIZE,
+        seeds = [
+            b"escrow",
+            order_book.key().as_ref(),
+            twap_order.owner.as_ref(),
+            computation_offset.to_le_bytes().as_ref(),
+        ],
+        bump,
+    )]
+    pub order_escrow: Box<Account<'info, OrderEscrow>>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
         bump,
+        address = derive_sign_pda!(),
     )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_RELEASE_TWAP_SLICE))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(seeds = [b"circuit_version", &COMP_DEF_OFFSET_RELEASE_TWAP_SLICE.to_le_bytes()], bump = circuit_version.bump)]
+    pub circuit_version: Account<'info, CircuitVersion>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("release_twap_slice")]
+#[derive(Accounts)]
+pub struct ReleaseTwapSliceCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_RELEASE_TWAP_SLICE))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(mut)]
+    pub order_book: Account<'info, OrderBook>,
+    #[account(mut)]
+    pub order_book_state: Box<Account<'info, OrderBookState>>,
+    #[account(mut)]
+    pub twap_order: Account<'info, TwapOrder>,
+    #[account(mut)]
+    pub order_escrow: Box<Account<'info, OrderEscrow>>,
+
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseTwapOrder<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
     pub order_book: Account<'info, OrderBook>,
     
-    /// CHECK: Base token mint
-    pub base_mint: AccountInfo<'info>,
-    /// CHECK: Quote token mint
-    pub quote_mint: AccountInfo<'info>,
+    #[account(mut, close = owner, has_one = owner, has_one = order_book)]
+    pub twap_order: Account<'info, TwapOrder>,
+    
+    #[account(
+        seeds = [b"settlement", order_book.key().as_ref()],
+        bump = settlement_ledger.bump,
+    )]
+    pub settlement_ledger: Box<Account<'info, SettlementLedger>>,
+    
+    /// Quote vault for a buy, base vault for a sell, checked against the ledger
+    #[account(mut)]
+    pub escrow_vault: Box<Account<'info, TokenAccount>>,
+    
+    #[account(mut, constraint = owner_token.owner == owner.key() @ ErrorCode::Unauthorized)]
+    pub owner_token: Box<Account<'info, TokenAccount>>,
     
+    pub token_program: Program<'info, Token>,
+}
+
+#[init_computation_definition_accounts("snapshot_depth", payer)]
+#[derive(Accounts)]
+pub struct InitSnapshotDepthCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + CircuitVersion::SIZE,
+        seeds = [b"circuit_version", &COMP_DEF_OFFSET_SNAPSHOT_DEPTH.to_le_bytes()],
+        bump,
+    )]
+    pub circuit_version: Account<'info, CircuitVersion>,
+    pub arcium_program: Program<'info, Arcium>,
     pub system_program: Program<'info, System>,
 }
 
-#[queue_computation_accounts("add_order", payer)]
+#[queue_computation_accounts("snapshot_depth", payer)]
 #[derive(Accounts)]
 #[instruction(computation_offset: u64)]
-pub struct AddOrder<'info> {
+pub struct SnapshotDepth<'info> {
     #[account(mut)]
     pub payer: Signer<'info>,
     
-    #[account(mut)]
     pub order_book: Account<'info, OrderBook>,
     
+    #[account(
+        seeds = [b"orderbook_state", order_book.key().as_ref()],
+        bump = order_book_state.bump,
+    )]
+    pub order_book_state: Box<Account<'info, OrderBookState>>,
+    
     #[account(
         init_if_needed,
         space = 9,
@@ -299,9 +5810,12 @@ pub struct AddOrder<'info> {
     /// CHECK: computation_account
     pub computation_account: UncheckedAccount<'info>,
     
-    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_ADD_ORDER))]
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_SNAPSHOT_DEPTH))]
     pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
     
+    #[account(seeds = [b"circuit_version", &COMP_DEF_OFFSET_SNAPSHOT_DEPTH.to_le_bytes()], bump = circuit_version.bump)]
+    pub circuit_version: Account<'info, CircuitVersion>,
+    
     #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
     pub cluster_account: Account<'info, Cluster>,
     
@@ -315,11 +5829,11 @@ pub struct AddOrder<'info> {
     pub arcium_program: Program<'info, Arcium>,
 }
 
-#[callback_accounts("add_order")]
+#[callback_accounts("snapshot_depth")]
 #[derive(Accounts)]
-pub struct AddOrderCallback<'info> {
+pub struct SnapshotDepthCallback<'info> {
     pub arcium_program: Program<'info, Arcium>,
-    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_ADD_ORDER))]
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_SNAPSHOT_DEPTH))]
     pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
     #[account(address = derive_mxe_pda!())]
     pub mxe_account: Account<'info, MXEAccount>,
@@ -327,21 +5841,49 @@ pub struct AddOrderCallback<'info> {
     pub computation_account: UncheckedAccount<'info>,
     #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
     pub cluster_account: Account<'info, Cluster>,
+    pub order_book: Account<'info, OrderBook>,
     #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
     /// CHECK: instructions_sysvar
     pub instructions_sysvar: AccountInfo<'info>,
 }
 
-#[queue_computation_accounts("match_orders", payer)]
+#[init_computation_definition_accounts("indicative_price", payer)]
+#[derive(Accounts)]
+pub struct InitIndicativePriceCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + CircuitVersion::SIZE,
+        seeds = [b"circuit_version", &COMP_DEF_OFFSET_INDICATIVE_PRICE.to_le_bytes()],
+        bump,
+    )]
+    pub circuit_version: Account<'info, CircuitVersion>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("indicative_price", payer)]
 #[derive(Accounts)]
 #[instruction(computation_offset: u64)]
-pub struct MatchOrders<'info> {
+pub struct IndicativePriceQuery<'info> {
     #[account(mut)]
     pub payer: Signer<'info>,
     
-    #[account(mut)]
     pub order_book: Account<'info, OrderBook>,
     
+    #[account(
+        seeds = [b"orderbook_state", order_book.key().as_ref()],
+        bump = order_book_state.bump,
+    )]
+    pub order_book_state: Box<Account<'info, OrderBookState>>,
+    
     #[account(
         init_if_needed,
         space = 9,
@@ -367,9 +5909,12 @@ pub struct MatchOrders<'info> {
     /// CHECK: computation_account
     pub computation_account: UncheckedAccount<'info>,
     
-    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_MATCH_ORDERS))]
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_INDICATIVE_PRICE))]
     pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
     
+    #[account(seeds = [b"circuit_version", &COMP_DEF_OFFSET_INDICATIVE_PRICE.to_le_bytes()], bump = circuit_version.bump)]
+    pub circuit_version: Account<'info, CircuitVersion>,
+    
     #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
     pub cluster_account: Account<'info, Cluster>,
     
@@ -383,11 +5928,11 @@ pub struct MatchOrders<'info> {
     pub arcium_program: Program<'info, Arcium>,
 }
 
-#[callback_accounts("match_orders")]
+#[callback_accounts("indicative_price")]
 #[derive(Accounts)]
-pub struct MatchOrdersCallback<'info> {
+pub struct IndicativePriceCallback<'info> {
     pub arcium_program: Program<'info, Arcium>,
-    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_MATCH_ORDERS))]
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_INDICATIVE_PRICE))]
     pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
     #[account(address = derive_mxe_pda!())]
     pub mxe_account: Account<'info, MXEAccount>,
@@ -395,20 +5940,96 @@ pub struct MatchOrdersCallback<'info> {
     pub computation_account: UncheckedAccount<'info>,
     #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
     pub cluster_account: Account<'info, Cluster>,
+    pub order_book: Account<'info, OrderBook>,
     #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
     /// CHECK: instructions_sysvar
     pub instructions_sysvar: AccountInfo<'info>,
 }
 
-#[queue_computation_accounts("cancel_order", payer)]
+#[init_computation_definition_accounts("add_funded_order", payer)]
+#[derive(Accounts)]
+pub struct InitAddFundedOrderCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + CircuitVersion::SIZE,
+        seeds = [b"circuit_version", &COMP_DEF_OFFSET_ADD_FUNDED_ORDER.to_le_bytes()],
+        bump,
+    )]
+    pub circuit_version: Account<'info, CircuitVersion>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("add_funded_order", payer)]
 #[derive(Accounts)]
 #[instruction(computation_offset: u64)]
-pub struct CancelOrder<'info> {
+pub struct AddFundedOrder<'info> {
     #[account(mut)]
     pub payer: Signer<'info>,
     
     #[account(mut)]
-    pub order_book: Account<'info, OrderBook>,
+    pub order_book: Account<'info, OrderBook>,
+    
+    #[account(
+        mut,
+        seeds = [b"orderbook_state", order_book.key().as_ref()],
+        bump = order_book_state.bump,
+    )]
+    pub order_book_state: Box<Account<'info, OrderBookState>>,
+    
+    #[account(
+        mut,
+        constraint = funding.owner == payer.key() @ ErrorCode::Unauthorized,
+        constraint = funding.ready @ ErrorCode::InvalidFunding,
+    )]
+    pub funding: Box<Account<'info, private_pay::DarkPoolFunding>>,
+    
+    #[account(seeds = [b"funding_authority"], bump)]
+    /// CHECK: signs `consume_dark_pool_funding` in the callback
+    pub funding_authority: UncheckedAccount<'info>,
+    
+    #[account(seeds = [b"config"], bump = pay_config.bump, seeds::program = private_pay::ID)]
+    pub pay_config: Box<Account<'info, private_pay::PayConfig>>,
+    
+    pub private_pay_program: Program<'info, PrivatePay>,
+    
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + OrderEscrow::SIZE,
+        seeds = [
+            b"escrow",
+            order_book.key().as_ref(),
+            payer.key().as_ref(),
+            computation_offset.to_le_bytes().as_ref(),
+        ],
+        bump,
+    )]
+    pub order_escrow: Box<Account<'info, OrderEscrow>>,
+    
+    #[account(
+        seeds = [b"settlement", order_book.key().as_ref()],
+        bump = settlement_ledger.bump,
+    )]
+    pub settlement_ledger: Box<Account<'info, SettlementLedger>>,
+    
+    /// private_pay's vault for the funding's mint, paid out in the callback
+    #[account(seeds = [b"token_vault", funding.mint.as_ref()], bump, seeds::program = private_pay::ID)]
+    pub pay_token_vault: Box<Account<'info, TokenAccount>>,
+    
+    /// Vault matching the funding's side, checked against the ledger
+    #[account(mut)]
+    pub escrow_vault: Box<Account<'info, TokenAccount>>,
+    
+    pub token_program: Program<'info, Token>,
     
     #[account(
         init_if_needed,
@@ -435,9 +6056,12 @@ pub struct CancelOrder<'info> {
     /// CHECK: computation_account
     pub computation_account: UncheckedAccount<'info>,
     
-    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_CANCEL_ORDER))]
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_ADD_FUNDED_ORDER))]
     pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
     
+    #[account(seeds = [b"circuit_version", &COMP_DEF_OFFSET_ADD_FUNDED_ORDER.to_le_bytes()], bump = circuit_version.bump)]
+    pub circuit_version: Account<'info, CircuitVersion>,
+    
     #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
     pub cluster_account: Account<'info, Cluster>,
     
@@ -451,11 +6075,11 @@ pub struct CancelOrder<'info> {
     pub arcium_program: Program<'info, Arcium>,
 }
 
-#[callback_accounts("cancel_order")]
+#[callback_accounts("add_funded_order")]
 #[derive(Accounts)]
-pub struct CancelOrderCallback<'info> {
+pub struct AddFundedOrderCallback<'info> {
     pub arcium_program: Program<'info, Arcium>,
-    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_CANCEL_ORDER))]
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_ADD_FUNDED_ORDER))]
     pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
     #[account(address = derive_mxe_pda!())]
     pub mxe_account: Account<'info, MXEAccount>,
@@ -463,69 +6087,374 @@ pub struct CancelOrderCallback<'info> {
     pub computation_account: UncheckedAccount<'info>,
     #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
     pub cluster_account: Account<'info, Cluster>,
+    #[account(mut)]
+    pub order_book: Account<'info, OrderBook>,
+    #[account(mut)]
+    pub order_book_state: Box<Account<'info, OrderBookState>>,
+    #[account(mut)]
+    pub funding: Box<Account<'info, private_pay::DarkPoolFunding>>,
+    #[account(mut)]
+    /// CHECK: funding owner, checked by `consume_dark_pool_funding`
+    pub owner: UncheckedAccount<'info>,
+    #[account(seeds = [b"funding_authority"], bump)]
+    /// CHECK: signs `consume_dark_pool_funding`
+    pub funding_authority: UncheckedAccount<'info>,
+    pub pay_config: Box<Account<'info, private_pay::PayConfig>>,
+    pub private_pay_program: Program<'info, PrivatePay>,
+    #[account(mut)]
+    pub order_escrow: Box<Account<'info, OrderEscrow>>,
+    #[account(mut)]
+    /// CHECK: private_pay's token vault, checked by `consume_dark_pool_funding`
+    pub pay_token_vault: UncheckedAccount<'info>,
+    #[account(mut)]
+    /// CHECK: book vault checked against the ledger at queue time
+    pub escrow_vault: UncheckedAccount<'info>,
+    pub token_program: Program<'info, Token>,
     #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
     /// CHECK: instructions_sysvar
     pub instructions_sysvar: AccountInfo<'info>,
 }
 
-#[init_computation_definition_accounts("add_order", payer)]
-#[derive(Accounts)]
-pub struct InitAddOrderCompDef<'info> {
-    #[account(mut)]
-    pub payer: Signer<'info>,
-    #[account(mut, address = derive_mxe_pda!())]
-    pub mxe_account: Box<Account<'info, MXEAccount>>,
-    #[account(mut)]
-    /// CHECK: comp_def_account
-    pub comp_def_account: UncheckedAccount<'info>,
-    pub arcium_program: Program<'info, Arcium>,
-    pub system_program: Program<'info, System>,
+// ============ Events ============
+
+#[event]
+pub struct OrderAdded {
+    pub order_id: u64,
+    pub rested: bool, // False for a market order, which executes without resting
+    pub slot: u64,
+    pub unix_timestamp: i64,
 }
 
-#[init_computation_definition_accounts("match_orders", payer)]
-#[derive(Accounts)]
-pub struct InitMatchOrdersCompDef<'info> {
-    #[account(mut)]
-    pub payer: Signer<'info>,
-    #[account(mut, address = derive_mxe_pda!())]
-    pub mxe_account: Box<Account<'info, MXEAccount>>,
-    #[account(mut)]
-    /// CHECK: comp_def_account
-    pub comp_def_account: UncheckedAccount<'info>,
-    pub arcium_program: Program<'info, Arcium>,
-    pub system_program: Program<'info, System>,
+#[event]
+pub struct OrdersPruned {
+    pub order_book: Pubkey,
+    pub count: u32,
+    pub slot: u64,
+    pub unix_timestamp: i64,
 }
 
-#[init_computation_definition_accounts("cancel_order", payer)]
-#[derive(Accounts)]
-pub struct InitCancelOrderCompDef<'info> {
-    #[account(mut)]
-    pub payer: Signer<'info>,
-    #[account(mut, address = derive_mxe_pda!())]
-    pub mxe_account: Box<Account<'info, MXEAccount>>,
-    #[account(mut)]
-    /// CHECK: comp_def_account
-    pub comp_def_account: UncheckedAccount<'info>,
-    pub arcium_program: Program<'info, Arcium>,
-    pub system_program: Program<'info, System>,
+#[event]
+pub struct DutchOrderAdded {
+    pub order_book: Pubkey,
+    pub order_id: u64,
+    pub slot: u64,
+    pub unix_timestamp: i64,
 }
 
-// ============ Events ============
+#[event]
+pub struct OrderParamsUpdated {
+    pub order_book: Pubkey,
+    pub tick_size: u64,
+    pub min_order_size: u64,
+    pub max_order_size: u64,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
 
 #[event]
-pub struct OrderAdded {
+pub struct DepthBandsUpdated {
+    pub order_book: Pubkey,
+    pub band_floor: u64,
+    pub band_width: u64,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct FeeTiersUpdated {
+    pub order_book: Pubkey,
+    pub maker_fee_rate: u16,
+    pub taker_fee_rate: u16,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct FeeRateUpdated {
+    pub order_book: Pubkey,
+    pub fee_rate: u16,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct FillSettled {
+    pub order_book: Pubkey,
+    pub bid_order_id: u64,
+    pub ask_order_id: u64,
+    pub buyer: Pubkey,
+    pub seller: Pubkey,
+    pub filled_size: u64, // Base paid to the buyer
+    pub notional: u64,    // Quote the buyer paid for the base, before fees
+    pub fees: u64,        // Quote moved to the fee vault from both sides
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct SettlementReconciled {
+    pub order_book: Pubkey,
+    pub base_expected: u64, // Owed by open sell escrows and TWAP reserves
+    pub base_held: u64,
+    pub quote_expected: u64, // Owed by open buy escrows and TWAP reserves
+    pub quote_held: u64,
+    pub pending_fills: u8,
+    pub unbacked_fills: u8,
+    pub consistent: bool,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct EscrowWithdrawn {
+    pub order_book: Pubkey,
     pub order_id: u64,
+    pub owner: Pubkey,
+    pub refund: u64,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct OraclePriceUpdated {
+    pub order_book: Pubkey,
+    pub price: u64,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct FeesCollected {
+    pub order_book: Pubkey,
+    pub amount: u64,    // Quote atoms paid to the authority
+    pub remaining: u64, // Accrued fees left in the vault
+    pub slot: u64,
+    pub unix_timestamp: i64,
 }
 
 #[event]
 pub struct OrdersMatched {
     pub matches_count: u32,
     pub total_volume: u64,
+    pub self_trade_prevented: u32, // Crossing pairs left resting because they share an owner
+    pub fees_charged: u64,         // Quote atoms, after resting-time discounts
+    pub imbalance_tier: u8,        // 0 balanced, 1 bid-heavy, 2 ask-heavy; sets the fee skew
+    pub match_cursor: u64,         // Nonzero while the crank should match again
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+/// One side of a match; a match emits one for the bid and one for the ask
+#[event]
+pub struct OrderFilled {
+    pub order_id: u64,
+    pub filled_size: u64,
+    pub price: u64, // Price of the match: the mid, the resting price or the auction price
+    pub is_maker: bool, // Paid the maker rate; the counterparty paid the taker rate
+    pub slot: u64,
+    pub unix_timestamp: i64,
 }
 
 #[event]
 pub struct OrderCancelled {
     pub order_id: u64,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct ClearingPriceComputed {
+    pub clearing_price: u64,
+    pub normalized_price: u64, // See `OrderBook::normalized_price`
+    pub volume: u64,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct BondSettled {
+    pub order_id: u64,
+    pub owner: Pubkey,
+    pub forfeited: bool,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct BatchAuctionTriggered {
+    pub order_book: Pubkey,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct BatchAuctionCleared {
+    pub order_book: Pubkey,
+    pub clearing_price: u64,
+    pub normalized_price: u64, // See `OrderBook::normalized_price`
+    pub volume: u64,
+    pub matches_count: u32,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct CompDefsInitialized {
+    pub payer: Pubkey,
+    pub initialized: Vec<u32>, // Comp def offsets created by this call
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct CircuitBreakerTripped {
+    pub order_book: Pubkey,
+    pub reference_price: u64,
+    pub price_band_bps: u16,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct CircuitBreakerReset {
+    pub order_book: Pubkey,
+    pub reference_price: u64,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct DepthRevealed {
+    pub order_book: Pubkey,
+    pub encrypted_bid_volume: [u8; 32], // Decryptable only by the requesting owner
+    pub encrypted_ask_volume: [u8; 32],
+    pub nonce: u128,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+/// Aggregate displayed size per price band, low prices first
+/// Bands with too few orders to hide any one of them read zero
+#[event]
+pub struct DepthSnapshot {
+    pub order_book: Pubkey,
+    pub band_floor: u64, // Band i starts at band_floor + i * band_width
+    pub band_width: u64,
+    pub bands: [u64; DEPTH_BANDS],
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+/// Mid of the best live bid and ask, or `NO_INDICATIVE_PRICE` for a
+/// one-sided book
+#[event]
+pub struct IndicativePrice {
+    pub order_book: Pubkey,
+    pub price: u64,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct MakerScoreRevealed {
+    pub order_book: Pubkey,
+    pub encrypted_liquidity_slots: [u8; 32], // Decryptable only by the requesting maker
+    pub nonce: u128,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct OrderLiquidated {
+    pub order_book: Pubkey,
+    pub liquidated: u64,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+/// Attestation from the matching circuit that fills moved equal base
+/// volume out of the bid and ask sides, and that the reported fills add up
+/// to that volume
+#[event]
+pub struct MatchConservation {
+    pub order_book: Pubkey,
+    pub total_volume: u64,
+    pub conserved: bool,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct PausedOpsUpdated {
+    pub order_book: Pubkey,
+    pub paused_ops: u8,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct PausedUpdated {
+    pub order_book: Pubkey,
+    pub paused: bool,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct OrderBookClosed {
+    pub order_book: Pubkey,
+    pub authority: Pubkey,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct OrdersCancelled {
+    pub order_book: Pubkey,
+    pub owner: Pubkey,
+    pub count: u32,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct TwapOrderScheduled {
+    pub order_book: Pubkey,
+    pub twap_order: Pubkey,
+    pub owner: Pubkey,
+    pub is_buy: bool,
+    pub slice_interval_slots: u64,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct TwapSliceReleased {
+    pub twap_order: Pubkey,
+    pub order_id: u64,
+    pub slices_released: u32,
+    pub exhausted: bool,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct TwapOrderClosed {
+    pub twap_order: Pubkey,
+    pub owner: Pubkey,
+    pub slices_released: u32,
+    pub refund: u64, // Unreleased reserve returned to the owner
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct FundedOrderAdded {
+    pub order_book: Pubkey,
+    pub order_id: u64,
+    pub funding: Pubkey, // private_pay `DarkPoolFunding` the order was sized from
+    pub slot: u64,
+    pub unix_timestamp: i64,
 }
 
 // ============ Errors ============
@@ -542,4 +6471,475 @@ pub enum ErrorCode {
     CancelFailed,
     #[msg("Unauthorized")]
     Unauthorized,
+    #[msg("Invalid order params - tick must be non-zero and min size must not exceed max size")]
+    InvalidOrderParams,
+    #[msg("Epoch is not tracked in the volume history")]
+    EpochNotTracked,
+    #[msg("Arithmetic overflow")]
+    MathOverflow,
+    #[msg("Auction batch has the wrong number of levels")]
+    InvalidAuctionBatch,
+    #[msg("Order bond is still locked")]
+    BondLocked,
+    #[msg("No batch auction has been triggered")]
+    AuctionNotTriggered,
+    #[msg("Computation definition was initialized for an older circuit version")]
+    StaleComputationDefinition,
+    #[msg("Circuit breaker tripped; matching is halted until the authority resets it")]
+    CircuitBreakerTripped,
+    #[msg("Payer cannot cover the computation fee")]
+    InsufficientComputationFee,
+    #[msg("This operation is paused")]
+    OperationPaused,
+    #[msg("Order book has no free slots")]
+    OrderBookFull,
+    #[msg("Order expiry slot is in the past")]
+    InvalidExpiry,
+    #[msg("Order book is paused")]
+    ProgramPaused,
+    #[msg("Fee rate exceeds the maximum")]
+    FeeTooHigh,
+    #[msg("Order book still has active orders")]
+    NonEmptyAccount,
+    #[msg("An identical order from this owner is already resting")]
+    DuplicateOrder,
+    #[msg("Encrypted order price or size is empty")]
+    InvalidOrder,
+    #[msg("TWAP order is not active")]
+    TwapNotActive,
+    #[msg("Next TWAP slice is not due yet")]
+    TwapSliceNotDue,
+    #[msg("No settled fees to collect")]
+    NoFeesToCollect,
+    #[msg("Settlement ledger has no room for another match")]
+    SettlementBacklog,
+    #[msg("Fill, escrow or vault does not match the settlement ledger")]
+    InvalidSettlement,
+    #[msg("Escrow is still backing a live order or unsettled fill")]
+    EscrowLocked,
+    #[msg("Funding is not ready or its mint doesn't trade on this book")]
+    InvalidFunding,
+    #[msg("TWAP reserve can't escrow another slice")]
+    TwapReserveExhausted,
+    #[msg("Oracle price must be non-zero")]
+    InvalidOraclePrice,
+    #[msg("Oracle price is missing or too old")]
+    StaleOraclePrice,
+    #[msg("Depth bands have not been set for this order book")]
+    DepthBandsNotSet,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Order book with every field zeroed, as freshly allocated
+    fn empty_book() -> OrderBook {
+        OrderBook::deserialize(&mut &[0u8; OrderBook::SIZE][..]).unwrap()
+    }
+
+    #[test]
+    fn order_params_need_positive_tick_and_ordered_sizes() {
+        assert!(validate_order_params(1, 1, 1).is_ok());
+        assert!(validate_order_params(10, 5, 1_000).is_ok());
+        for (tick_size, min_order_size, max_order_size) in [(0, 1, 10), (1, 0, 10), (1, 11, 10)] {
+            assert_eq!(
+                validate_order_params(tick_size, min_order_size, max_order_size).unwrap_err(),
+                ErrorCode::InvalidOrderParams.into()
+            );
+        }
+    }
+
+    #[test]
+    fn fee_rates_are_capped_at_max_fee_bps() {
+        assert!(validate_fee_rate(0).is_ok());
+        assert!(validate_fee_rate(MAX_FEE_BPS).is_ok());
+        for fee_rate in [MAX_FEE_BPS + 1, u16::MAX] {
+            assert_eq!(validate_fee_rate(fee_rate).unwrap_err(), ErrorCode::FeeTooHigh.into());
+        }
+    }
+
+    #[test]
+    fn relayers_pay_fees_but_never_the_bond() {
+        let trader = Pubkey::new_unique();
+        let relayer = Pubkey::new_unique();
+        assert_eq!(fee_payer_locked(&trader, &trader), ORDER_BOND_LAMPORTS);
+        assert_eq!(fee_payer_locked(&relayer, &trader), 0);
+    }
+
+    #[test]
+    fn resubmitted_orders_are_reported_as_duplicates() {
+        assert!(check_order_inserted(false, false, true).is_ok());
+        // The circuit also clears `success` for a duplicate, which must not
+        // mask the more specific error
+        assert_eq!(
+            check_order_inserted(true, false, false).unwrap_err(),
+            ErrorCode::DuplicateOrder.into()
+        );
+        assert_eq!(
+            check_order_inserted(false, true, false).unwrap_err(),
+            ErrorCode::OrderBookFull.into()
+        );
+        assert_eq!(
+            check_order_inserted(false, false, false).unwrap_err(),
+            ErrorCode::OrderFailed.into()
+        );
+    }
+
+    #[test]
+    fn match_budget_caps_at_reported_matches() {
+        assert_eq!(max_matches_for_budget(0), 0);
+        assert_eq!(max_matches_for_budget(MATCH_PAIR_CU - 1), 0);
+        assert_eq!(max_matches_for_budget(2 * MATCH_PAIR_CU), 2);
+        assert_eq!(max_matches_for_budget(u32::MAX), MAX_REPORTED_MATCHES as u32);
+    }
+
+    #[test]
+    fn match_cursor_counts_capped_runs_until_one_finishes() {
+        let mut cursor = 0;
+        for _ in 0..3 {
+            cursor = next_match_cursor(cursor, 2, 2);
+        }
+        assert_eq!(cursor, 3);
+        assert_eq!(next_match_cursor(cursor, 1, 2), 0);
+        assert_eq!(next_match_cursor(0, 0, 4), 0);
+    }
+
+    #[test]
+    fn accepted_dutch_orders_rest_on_the_book() {
+        let mut book = empty_book();
+        book.record_resting_order().unwrap();
+        book.record_resting_order().unwrap();
+        assert_eq!((book.total_orders, book.active_orders), (2, 2));
+
+        book.active_orders = u32::MAX;
+        assert_eq!(book.record_resting_order().unwrap_err(), ErrorCode::MathOverflow.into());
+    }
+
+    #[test]
+    fn epoch_volume_accumulates_and_recycles_slots() {
+        let mut book = empty_book();
+        book.record_epoch_volume(3, 100).unwrap();
+        book.record_epoch_volume(3, 50).unwrap();
+        assert_eq!(book.epoch_volume(3).unwrap(), 150);
+
+        // The same ring slot a full cycle later starts from zero
+        let later = 3 + EPOCH_VOLUME_SLOTS as u64;
+        book.record_epoch_volume(later, 7).unwrap();
+        assert_eq!(book.epoch_volume(later).unwrap(), 7);
+        assert_eq!(book.epoch_volume(3).unwrap_err(), ErrorCode::EpochNotTracked.into());
+
+        assert_eq!(
+            book.record_epoch_volume(later, u64::MAX).unwrap_err(),
+            ErrorCode::MathOverflow.into()
+        );
+    }
+
+    #[test]
+    fn normalized_price_scales_by_decimals() {
+        let mut book = empty_book();
+        // 9-decimal base, 6-decimal quote: 1_000 quote atoms per base atom is
+        // 1_000_000 quote tokens per base token
+        book.base_decimals = 9;
+        book.quote_decimals = 6;
+        book.price_display_decimals = 2;
+        assert_eq!(book.normalized_price(1_000).unwrap(), 100_000_000);
+
+        book.base_decimals = 6;
+        book.quote_decimals = 6;
+        book.price_display_decimals = DEFAULT_PRICE_DISPLAY_DECIMALS;
+        assert_eq!(book.normalized_price(3).unwrap(), 3_000_000);
+
+        book.base_decimals = 18;
+        book.quote_decimals = 0;
+        book.price_display_decimals = MAX_PRICE_DISPLAY_DECIMALS;
+        assert_eq!(book.normalized_price(u64::MAX).unwrap_err(), ErrorCode::MathOverflow.into());
+    }
+
+    #[test]
+    fn depth_bands_need_a_width_and_must_fit_in_u64() {
+        assert!(valid_depth_bands(0, 1));
+        assert!(valid_depth_bands(1_000, 250));
+        assert!(!valid_depth_bands(1_000, 0));
+        assert!(valid_depth_bands(u64::MAX - 8 * DEPTH_BANDS as u64, 8));
+        assert!(!valid_depth_bands(u64::MAX - 8 * DEPTH_BANDS as u64 + 1, 8));
+        assert!(!valid_depth_bands(0, u64::MAX / 2));
+    }
+
+    fn escrow(order_id: u64, is_buy: bool, deposited: u64) -> OrderEscrow {
+        let mut escrow = OrderEscrow::deserialize(&mut &[0u8; OrderEscrow::SIZE][..]).unwrap();
+        escrow.order_id = order_id;
+        escrow.is_buy = is_buy;
+        escrow.deposited = deposited;
+        escrow
+    }
+
+    #[test]
+    fn reconciliation_follows_a_settled_run() {
+        let mut ledger =
+            SettlementLedger::deserialize(&mut &[0u8; SettlementLedger::SIZE][..]).unwrap();
+        let fill = PendingFill {
+            bid_order_id: 1,
+            ask_order_id: 2,
+            filled_size: 5,
+            price: 100,
+            ..PendingFill::default()
+        };
+        ledger.push(fill).unwrap();
+        let mut escrows = [escrow(1, true, 1_000), escrow(2, false, 10)];
+
+        // Matched but not paid: the vaults still hold both deposits, plus a
+        // TWAP reserve not yet sliced
+        let tally = ledger.reconcile(&escrows, 0, 300).unwrap();
+        assert_eq!(tally.pending_fills, 1);
+        assert!(tally.is_consistent(10, 1_300));
+
+        // settle_fill pays 5 base and 500 quote out of the vaults
+        ledger.fills[0] = PendingFill::default();
+        escrows[0].spent = 500;
+        escrows[1].spent = 5;
+        let tally = ledger.reconcile(&escrows, 0, 300).unwrap();
+        assert_eq!(tally.pending_fills, 0);
+        assert!(tally.is_consistent(5, 800));
+    }
+
+    #[test]
+    fn reconciliation_flags_mismatched_vaults_and_unbacked_fills() {
+        let mut ledger =
+            SettlementLedger::deserialize(&mut &[0u8; SettlementLedger::SIZE][..]).unwrap();
+        let escrows = [escrow(1, true, 1_000), escrow(2, false, 10)];
+
+        // A fill was paid out of the base vault without touching the escrow
+        let tally = ledger.reconcile(&escrows, 0, 0).unwrap();
+        assert!(!tally.is_consistent(5, 1_000));
+
+        // Two matches against the same ask claim more than it escrowed
+        let fill = PendingFill {
+            bid_order_id: 1,
+            ask_order_id: 2,
+            filled_size: 6,
+            price: 10,
+            ..PendingFill::default()
+        };
+        ledger.push(fill).unwrap();
+        ledger.push(fill).unwrap();
+        let tally = ledger.reconcile(&escrows, 0, 0).unwrap();
+        assert_eq!((tally.pending_fills, tally.unbacked_fills), (2, 1));
+        assert!(!tally.is_consistent(10, 1_000));
+
+        // A match whose escrow wasn't passed can't be backed either
+        let tally = ledger.reconcile(&escrows[..1], 0, 0).unwrap();
+        assert_eq!(tally.unbacked_fills, 2);
+    }
+
+    #[test]
+    fn settled_fees_are_capped_by_what_each_side_can_pay() {
+        let fill = PendingFill {
+            bid_order_id: 1,
+            ask_order_id: 2,
+            filled_size: 10,
+            price: 100,
+            bid_fee: 30,
+            ask_fee: 20,
+        };
+        let (mut bid, mut ask) = (escrow(1, true, 1_100), escrow(2, false, 10));
+        assert_eq!(charge_escrows(&fill, &mut bid, &mut ask).unwrap(), (1_000, 980, 50));
+        assert_eq!((bid.spent, bid.received), (1_030, 10));
+        assert_eq!((ask.spent, ask.received), (10, 980));
+
+        // A bid escrowed with only 12 quote past the notional pays 12
+        let (mut bid, mut ask) = (escrow(1, true, 1_012), escrow(2, false, 10));
+        assert_eq!(charge_escrows(&fill, &mut bid, &mut ask).unwrap(), (1_000, 980, 32));
+        assert_eq!(bid.spent, bid.deposited);
+
+        // A fill larger than either escrow can't settle
+        let (mut bid, mut ask) = (escrow(1, true, 999), escrow(2, false, 10));
+        assert_eq!(
+            charge_escrows(&fill, &mut bid, &mut ask).unwrap_err(),
+            ErrorCode::InvalidSettlement.into()
+        );
+        let (mut bid, mut ask) = (escrow(1, true, 1_100), escrow(2, false, 9));
+        assert_eq!(
+            charge_escrows(&fill, &mut bid, &mut ask).unwrap_err(),
+            ErrorCode::InvalidSettlement.into()
+        );
+    }
+
+    #[test]
+    fn each_side_of_a_match_gets_its_own_fill_event() {
+        let fill = PendingFill {
+            bid_order_id: 11,
+            ask_order_id: 12,
+            filled_size: 5,
+            price: 100,
+            ..PendingFill::default()
+        };
+        let clock = Clock { slot: 40, unix_timestamp: 1_700, ..Clock::default() };
+        let [bid, ask] = order_filled_events(&fill, false, &clock);
+        assert_eq!((bid.order_id, bid.is_maker), (11, false));
+        assert_eq!((ask.order_id, ask.is_maker), (12, true));
+        for side in [&bid, &ask] {
+            assert_eq!((side.filled_size, side.price), (5, 100));
+            assert_eq!((side.slot, side.unix_timestamp), (40, 1_700));
+        }
+    }
+
+    #[test]
+    fn reported_fills_must_add_up_to_the_matched_volume() {
+        let fill = |filled_size| PendingFill {
+            bid_order_id: 1,
+            ask_order_id: 2,
+            filled_size,
+            price: 100,
+            ..PendingFill::default()
+        };
+        assert!(fills_account_for_volume(&[], 0, 0));
+        assert!(fills_account_for_volume(&[fill(5), fill(7)], 2, 12));
+
+        // A dropped fill, a short fill and an extra fill all break the audit
+        assert!(!fills_account_for_volume(&[fill(5)], 2, 12));
+        assert!(!fills_account_for_volume(&[fill(5), fill(6)], 2, 12));
+        assert!(!fills_account_for_volume(&[fill(5), fill(7), fill(0)], 2, 12));
+        assert!(!fills_account_for_volume(&[fill(u64::MAX), fill(1)], 2, 0));
+    }
+
+    #[test]
+    fn owner_words_split_little_endian_halves() {
+        let mut bytes = [0u8; 32];
+        bytes[0] = 1;
+        bytes[16] = 2;
+        bytes[31] = 0x80;
+        let (lo, hi) = owner_words(&Pubkey::new_from_array(bytes));
+        assert_eq!(lo, 1);
+        assert_eq!(hi, 2 | (0x80u128 << 120));
+
+        let owner = Pubkey::new_unique();
+        let (lo, hi) = owner_words(&owner);
+        let mut rebuilt = [0u8; 32];
+        rebuilt[..16].copy_from_slice(&lo.to_le_bytes());
+        rebuilt[16..].copy_from_slice(&hi.to_le_bytes());
+        assert_eq!(Pubkey::new_from_array(rebuilt), owner);
+    }
+
+    #[test]
+    fn empty_order_ciphertexts_are_rejected() {
+        assert!(ensure_nonempty_ciphertexts(&[[1u8; 64], [2u8; 64]]).is_ok());
+        for fields in [[[0u8; 64], [2u8; 64]], [[1u8; 64], [0u8; 64]]] {
+            assert_eq!(
+                ensure_nonempty_ciphertexts(&fields).unwrap_err(),
+                ErrorCode::InvalidOrder.into()
+            );
+        }
+    }
+
+    #[test]
+    fn cancelling_all_orders_releases_their_book_slots() {
+        let mut book = empty_book();
+        for _ in 0..3 {
+            book.record_resting_order().unwrap();
+        }
+
+        book.record_removed_orders(2);
+        assert_eq!((book.total_orders, book.active_orders), (3, 1));
+        // A count above the tracked total can't wrap the counter
+        book.record_removed_orders(5);
+        assert_eq!(book.active_orders, 0);
+    }
+
+    #[test]
+    fn twap_slices_are_released_on_their_interval_until_the_reserve_runs_out() {
+        let mut twap = TwapOrder::deserialize(&mut &[0u8; TwapOrder::SIZE][..]).unwrap();
+        twap.slice_interval_slots = 10;
+        twap.next_slice_slot = 100;
+        twap.reserve = 250;
+        twap.slice_escrow = 100;
+        assert_eq!(twap.draw_slice(100).unwrap_err(), ErrorCode::TwapNotActive.into());
+
+        twap.active = true;
+        assert_eq!(twap.draw_slice(99).unwrap_err(), ErrorCode::TwapSliceNotDue.into());
+        twap.draw_slice(100).unwrap();
+        assert_eq!((twap.next_slice_slot, twap.reserve), (110, 150));
+        assert_eq!(twap.draw_slice(109).unwrap_err(), ErrorCode::TwapSliceNotDue.into());
+        // A late crank spaces the next slice from when it actually ran
+        twap.draw_slice(125).unwrap();
+        assert_eq!((twap.next_slice_slot, twap.reserve), (135, 50));
+        assert_eq!(twap.draw_slice(135).unwrap_err(), ErrorCode::TwapReserveExhausted.into());
+    }
+
+    #[test]
+    fn random_priority_windows_are_capped() {
+        // Zero keeps strict time priority
+        assert!(validate_random_priority_window(0).is_ok());
+        assert!(validate_random_priority_window(MAX_RANDOM_PRIORITY_WINDOW_SLOTS).is_ok());
+        assert_eq!(
+            validate_random_priority_window(MAX_RANDOM_PRIORITY_WINDOW_SLOTS + 1).unwrap_err(),
+            ErrorCode::InvalidOrderParams.into()
+        );
+    }
+
+    #[test]
+    fn collected_fees_are_bounded_by_the_vault_and_what_accrued() {
+        let mut book = empty_book();
+        assert_eq!(book.take_fees(100).unwrap_err(), ErrorCode::NoFeesToCollect.into());
+
+        book.accrue_fees(70).unwrap();
+        book.accrue_fees(30).unwrap();
+        assert_eq!(book.take_fees(60).unwrap(), 60);
+        assert_eq!(book.fees_accrued, 40);
+        assert_eq!(book.take_fees(1_000).unwrap(), 40);
+        assert_eq!(book.take_fees(1_000).unwrap_err(), ErrorCode::NoFeesToCollect.into());
+        book.accrue_fees(u64::MAX).unwrap();
+        assert_eq!(book.accrue_fees(1).unwrap_err(), ErrorCode::MathOverflow.into());
+    }
+
+    #[test]
+    fn ledger_queues_fills_until_full_and_tracks_their_orders() {
+        let mut ledger =
+            SettlementLedger::deserialize(&mut &[0u8; SettlementLedger::SIZE][..]).unwrap();
+        assert!(!ledger.has_fill(0));
+        for id in 1..=SETTLEMENT_LEDGER_SLOTS as u64 {
+            ledger
+                .push(PendingFill { bid_order_id: id, ask_order_id: id + 1_000, ..PendingFill::default() })
+                .unwrap();
+        }
+        assert_eq!(ledger.free_slots(), 0);
+        assert_eq!(
+            ledger.push(PendingFill { bid_order_id: 99, ..PendingFill::default() }).unwrap_err(),
+            ErrorCode::SettlementBacklog.into()
+        );
+        assert!(ledger.has_fill(3) && ledger.has_fill(1_003));
+        // Free slots read as zero ids, which never match an order
+        ledger.fills[2] = PendingFill::default();
+        assert!(!ledger.has_fill(3) && !ledger.has_fill(0));
+        assert_eq!(ledger.free_slots(), 1);
+
+        ledger.release(7).unwrap();
+        assert!(!ledger.take_release(0));
+        assert!(ledger.take_release(7));
+        assert!(!ledger.take_release(7));
+        assert_eq!(ledger.free_release_slots(), RELEASED_ESCROW_SLOTS);
+    }
+
+    #[test]
+    fn every_circuit_is_registered_for_init_all_comp_defs_once() {
+        for (i, offset) in ALL_COMP_DEF_OFFSETS.iter().enumerate() {
+            assert!(!ALL_COMP_DEF_OFFSETS[..i].contains(offset));
+        }
+        // The ticker's comp def is initialized along with the rest
+        assert!(ALL_COMP_DEF_OFFSETS.contains(&COMP_DEF_OFFSET_INDICATIVE_PRICE));
+    }
+
+    #[test]
+    fn taker_fee_rate_keeps_the_offset_of_the_old_single_rate() {
+        let mut book = empty_book();
+        book.taker_fee_rate = 25;
+        book.maker_fee_rate = 10;
+        let mut data = Vec::new();
+        book.serialize(&mut data).unwrap();
+        assert_eq!(8 + data.len(), OrderBook::SIZE);
+
+        // Books created before the split read their old `fee_rate`, right
+        // after the three keys, as the taker rate
+        assert_eq!(data[96..98], 25u16.to_le_bytes());
+    }
 }
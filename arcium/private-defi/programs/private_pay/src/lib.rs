@@ -1,8 +1,33 @@
 use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
 use arcium_anchor::prelude::*;
+use private_defi_common::{covers_computation_fee, CallbackAccountsBuilder};
 
 const COMP_DEF_OFFSET_INIT_BALANCE: u32 = comp_def_offset("init_balance");
 const COMP_DEF_OFFSET_DEPOSIT: u32 = comp_def_offset("deposit");
+const COMP_DEF_OFFSET_INIT_MIXING_POOL: u32 = comp_def_offset("init_mixing_pool");
+const COMP_DEF_OFFSET_POOL_DEPOSIT: u32 = comp_def_offset("pool_deposit");
+const COMP_DEF_OFFSET_POOL_WITHDRAW: u32 = comp_def_offset("pool_withdraw");
+const COMP_DEF_OFFSET_DISCLOSE_BALANCE: u32 = comp_def_offset("disclose_balance");
+const COMP_DEF_OFFSET_REVEAL_BALANCE: u32 = comp_def_offset("reveal_balance");
+const COMP_DEF_OFFSET_APPROVE_ALLOWANCE: u32 = comp_def_offset("approve_allowance");
+const COMP_DEF_OFFSET_SPEND_ALLOWANCE: u32 = comp_def_offset("spend_allowance");
+const COMP_DEF_OFFSET_ACCRUE_INTEREST: u32 = comp_def_offset("accrue_interest");
+const COMP_DEF_OFFSET_SPLIT_DEPOSIT: u32 = comp_def_offset("split_deposit");
+const COMP_DEF_OFFSET_SCHEDULE_PAYMENT: u32 = comp_def_offset("schedule_payment");
+const COMP_DEF_OFFSET_EXECUTE_SCHEDULED_PAYMENT: u32 = comp_def_offset("execute_scheduled_payment");
+const COMP_DEF_OFFSET_TRANSFER: u32 = comp_def_offset("transfer");
+const COMP_DEF_OFFSET_MERGE_BALANCES: u32 = comp_def_offset("merge_balances");
+const COMP_DEF_OFFSET_SET_MIN_BALANCE: u32 = comp_def_offset("set_min_balance");
+const COMP_DEF_OFFSET_WITHDRAW: u32 = comp_def_offset("withdraw");
+const COMP_DEF_OFFSET_CHECK_TRANSFER_FEASIBLE: u32 = comp_def_offset("check_transfer_feasible");
+const COMP_DEF_OFFSET_BATCH_TRANSFER: u32 = comp_def_offset("batch_transfer");
+const COMP_DEF_OFFSET_ROTATE_BALANCE_NONCE: u32 = comp_def_offset("rotate_balance_nonce");
+const COMP_DEF_OFFSET_CREATE_VESTING: u32 = comp_def_offset("create_vesting");
+const COMP_DEF_OFFSET_CLAIM_VESTED: u32 = comp_def_offset("claim_vested");
+const COMP_DEF_OFFSET_BATCH_DEPOSIT: u32 = comp_def_offset("batch_deposit");
+const COMP_DEF_OFFSET_EXPORT_TO_DARK_POOL: u32 = comp_def_offset("export_to_dark_pool");
+const COMP_DEF_OFFSET_FUND_INTEREST_RESERVE: u32 = comp_def_offset("fund_interest_reserve");
 
 declare_id!("7oNtYFkJ9sgDBLCEN8mYjLCYQUQ3ZvPRnTRAV9kb5QhP");
 
@@ -22,6 +47,138 @@ pub mod private_pay {
         Ok(())
     }
 
+    /// Initialize computation definitions for the mixing pool
+    pub fn init_mixing_pool_comp_def(ctx: Context<InitMixingPoolCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_pool_deposit_comp_def(ctx: Context<InitPoolDepositCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_pool_withdraw_comp_def(ctx: Context<InitPoolWithdrawCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    /// Initialize computation definition for auditor disclosures
+    pub fn init_disclose_balance_comp_def(ctx: Context<InitDiscloseBalanceCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    /// Initialize the computation definition for owner balance reveals
+    pub fn init_reveal_balance_comp_def(ctx: Context<InitRevealBalanceCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    /// Initialize computation definitions for delegated allowances
+    pub fn init_approve_allowance_comp_def(ctx: Context<InitApproveAllowanceCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_spend_allowance_comp_def(ctx: Context<InitSpendAllowanceCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    /// Initialize computation definition for interest accrual
+    pub fn init_accrue_interest_comp_def(ctx: Context<InitAccrueInterestCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    /// Initialize computation definition for interest reserve funding
+    pub fn init_fund_interest_reserve_comp_def(
+        ctx: Context<InitFundInterestReserveCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    /// Initialize computation definition for split deposits
+    pub fn init_split_deposit_comp_def(ctx: Context<InitSplitDepositCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    /// Initialize computation definitions for recurring payments
+    pub fn init_schedule_payment_comp_def(ctx: Context<InitSchedulePaymentCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_execute_scheduled_payment_comp_def(
+        ctx: Context<InitExecuteScheduledPaymentCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    /// Initialize computation definition for private transfers
+    pub fn init_transfer_comp_def(ctx: Context<InitTransferCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_merge_balances_comp_def(ctx: Context<InitMergeBalancesCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_set_min_balance_comp_def(ctx: Context<InitSetMinBalanceCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_withdraw_comp_def(ctx: Context<InitWithdrawCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_check_transfer_feasible_comp_def(
+        ctx: Context<InitCheckTransferFeasibleCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_batch_transfer_comp_def(ctx: Context<InitBatchTransferCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_rotate_balance_nonce_comp_def(
+        ctx: Context<InitRotateBalanceNonceCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_create_vesting_comp_def(ctx: Context<InitCreateVestingCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_claim_vested_comp_def(ctx: Context<InitClaimVestedCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_batch_deposit_comp_def(ctx: Context<InitBatchDepositCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_export_to_dark_pool_comp_def(ctx: Context<InitExportToDarkPoolCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
     /// Create a private balance account for a user
     /// The balance is encrypted and stored on-chain
     pub fn create_balance_account(
@@ -29,10 +186,10 @@ pub mod private_pay {
         computation_offset: u64,
         nonce: u128,
     ) -> Result<()> {
-        let args = ArgBuilder::new()
-            .plaintext_u128(nonce)
-            .plaintext_pubkey(ctx.accounts.payer.key())
-            .build();
+        ctx.accounts.config.ensure_open(0)?;
+        ensure_computation_fee(&ctx.accounts.payer, 0)?;
+
+        let args = ArgBuilder::new().build();
 
         ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
 
@@ -40,8 +197,13 @@ pub mod private_pay {
         let balance_account = &mut ctx.accounts.balance_account;
         balance_account.owner = ctx.accounts.payer.key();
         balance_account.bump = ctx.bumps.balance_account;
+        balance_account.label = 0;
         balance_account.nonce = nonce;
-        balance_account.balance_state = [0u8; 64]; // Will be set by MPC
+        balance_account.balance_state = [0u8; 64]; // Set to an encrypted zero by the callback
+        balance_account.last_accrual_slot = Clock::get()?.slot;
+        balance_account.min_balance_state = [0u8; 64];
+        balance_account.min_balance_nonce = 0;
+        balance_account.mint = Pubkey::default();
 
         queue_computation(
             ctx.accounts,
@@ -51,7 +213,9 @@ pub mod private_pay {
             vec![CreateBalanceCallback::callback_ix(
                 computation_offset,
                 &ctx.accounts.mxe_account,
-                &[],
+                &CallbackAccountsBuilder::new()
+                    .writable(ctx.accounts.balance_account.key())
+                    .build(),
             )?],
             1,
             0,
@@ -66,25 +230,100 @@ pub mod private_pay {
         ctx: Context<CreateBalanceCallback>,
         output: SignedComputationOutputs<InitBalanceOutput>,
     ) -> Result<()> {
-        let result = match output.verify_output(
+        match output.verify_output(
             &ctx.accounts.cluster_account,
             &ctx.accounts.computation_account,
         ) {
-            Ok(InitBalanceOutput { success }) => success,
+            Ok(InitBalanceOutput {
+                balance,
+                balance_nonce,
+                min_balance,
+                min_balance_nonce,
+            }) => {
+                let balance_account = &mut ctx.accounts.balance_account;
+                balance_account.write_balance(balance, balance_nonce);
+                balance_account.min_balance_state = min_balance;
+                balance_account.min_balance_nonce = min_balance_nonce;
+            }
             Err(_) => return Err(ErrorCode::AbortedComputation.into()),
         };
 
-        if !result {
-            return Err(ErrorCode::InitializationFailed.into());
-        }
-
+        let clock = Clock::get()?;
         emit!(BalanceCreated {
             owner: ctx.accounts.balance_account.owner,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
         });
 
         Ok(())
     }
 
+    /// Close a balance account whose creation computation never completed
+    /// Until `create_balance_callback` writes the encrypted zero the balance
+    /// state is all zeros. Once `COMPUTATION_TIMEOUT_SLOTS` have passed since
+    /// creation the owner can close it and recover the rent, then create it again.
+    pub fn cancel_balance_creation(ctx: Context<CancelBalanceCreation>) -> Result<()> {
+        let balance_account = &ctx.accounts.balance_account;
+        let clock = Clock::get()?;
+        balance_account.ensure_creation_stalled(clock.slot)?;
+
+        emit!(BalanceCreationCancelled {
+            owner: balance_account.owner,
+            label: balance_account.label,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Create an additional labeled balance for the payer
+    /// Label 0 is reserved for the primary balance from `create_balance_account`
+    /// The encrypted zero balance is written by `create_balance_callback`
+    pub fn create_labeled_balance_account(
+        ctx: Context<CreateLabeledBalanceAccount>,
+        computation_offset: u64,
+        label: u8,
+        nonce: u128,
+    ) -> Result<()> {
+        ctx.accounts.config.ensure_open(0)?;
+        require!(label != 0, ErrorCode::InvalidBalanceLabel);
+        ensure_computation_fee(&ctx.accounts.payer, 0)?;
+
+        let args = ArgBuilder::new().build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        let balance_account = &mut ctx.accounts.balance_account;
+        balance_account.owner = ctx.accounts.payer.key();
+        balance_account.bump = ctx.bumps.balance_account;
+        balance_account.label = label;
+        balance_account.nonce = nonce;
+        balance_account.balance_state = [0u8; 64]; // Set to an encrypted zero by the callback
+        balance_account.last_accrual_slot = Clock::get()?.slot;
+        balance_account.min_balance_state = [0u8; 64];
+        balance_account.min_balance_nonce = 0;
+        balance_account.mint = Pubkey::default();
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![CreateBalanceCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &CallbackAccountsBuilder::new()
+                    .writable(ctx.accounts.balance_account.key())
+                    .build(),
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
     /// Deposit funds into private balance
     /// Amount is encrypted and added to the user's balance
     pub fn deposit_funds(
@@ -92,10 +331,16 @@ pub mod private_pay {
         computation_offset: u64,
         amount: u64,
     ) -> Result<()> {
-        // Transfer SOL to the balance account
+        ctx.accounts.config.ensure_open(PAUSE_DEPOSITS)?;
+        ensure_computation_fee(&ctx.accounts.payer, 0)?;
+        require!(ctx.accounts.balance_account.mint == Pubkey::default(), ErrorCode::MintMismatch);
+        require!(amount > 0, ErrorCode::ZeroDeposit);
+
+        // Transfer SOL to the vault backing every SOL balance
+        let lamports_before = ctx.accounts.sol_vault.to_account_info().lamports();
         let transfer_ix = anchor_lang::solana_program::system_instruction::transfer(
             &ctx.accounts.payer.key(),
-            &ctx.accounts.balance_account.key(),
+            &ctx.accounts.sol_vault.key(),
             amount,
         );
         
@@ -103,15 +348,29 @@ pub mod private_pay {
             &transfer_ix,
             &[
                 ctx.accounts.payer.to_account_info(),
-                ctx.accounts.balance_account.to_account_info(),
+                ctx.accounts.sol_vault.to_account_info(),
                 ctx.accounts.system_program.to_account_info(),
             ],
         )?;
 
+        // MPC credits `amount` unconditionally, so make sure it actually arrived
+        let lamports_after = ctx.accounts.sol_vault.to_account_info().lamports();
+        ensure_deposit_received(lamports_before, lamports_after, amount)?;
+
+        // Record the deposit so it can be refunded if the computation never settles
+        let pending_deposit = &mut ctx.accounts.pending_deposit;
+        pending_deposit.owner = ctx.accounts.payer.key();
+        pending_deposit.balance_account = ctx.accounts.balance_account.key();
+        pending_deposit.computation_offset = computation_offset;
+        pending_deposit.amount = amount;
+        pending_deposit.queued_slot = Clock::get()?.slot;
+        pending_deposit.bump = ctx.bumps.pending_deposit;
+
+        let balance_account = &ctx.accounts.balance_account;
         let args = ArgBuilder::new()
             .plaintext_u64(amount)
-            .plaintext_pubkey(ctx.accounts.payer.key())
-            .plaintext_u128(ctx.accounts.balance_account.nonce)
+            .plaintext_u128(balance_account.nonce)
+            .encrypted_bytes(balance_account.balance_state)
             .build();
 
         ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
@@ -124,7 +383,11 @@ pub mod private_pay {
             vec![DepositCallback::callback_ix(
                 computation_offset,
                 &ctx.accounts.mxe_account,
-                &[],
+                &CallbackAccountsBuilder::new()
+                    .writable(ctx.accounts.balance_account.key())
+                    .writable(ctx.accounts.pending_deposit.key())
+                    .writable(ctx.accounts.payer.key())
+                    .build(),
             )?],
             1,
             0,
@@ -139,141 +402,6672 @@ pub mod private_pay {
         ctx: Context<DepositCallback>,
         output: SignedComputationOutputs<DepositOutput>,
     ) -> Result<()> {
-        let result = match output.verify_output(
+        match output.verify_output(
             &ctx.accounts.cluster_account,
             &ctx.accounts.computation_account,
         ) {
-            Ok(DepositOutput { new_balance, success }) => {
+            Ok(DepositOutput { new_balance, nonce, success }) => {
                 if !success {
                     return Err(ErrorCode::DepositFailed.into());
                 }
-                new_balance
+                ctx.accounts.balance_account.write_balance(new_balance, nonce);
             }
             Err(_) => return Err(ErrorCode::AbortedComputation.into()),
         };
 
+        let balance_account = &ctx.accounts.balance_account;
+        let clock = Clock::get()?;
         emit!(FundsDeposited {
-            owner: ctx.accounts.balance_account.owner,
-            new_balance: result,
+            owner: balance_account.owner,
+            encrypted_balance: balance_account.balance_state,
+            nonce: balance_account.nonce,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
         });
 
         Ok(())
     }
-}
 
-// ============ Account Structures ============
-
-#[account]
-pub struct PrivateBalanceAccount {
-    pub owner: Pubkey,
-    pub bump: u8,
-    pub balance_state: [u8; 64], // Encrypted balance
-    pub nonce: u128,
-}
+    /// Deposit SOL into up to BATCH_DEPOSITS private balances in one computation
+    /// `deposits` pairs each target balance, in the order of `balance_0..`,
+    /// with its amount. Slots past the batch repeat `balance_0` and are left
+    /// untouched. The batch size is bounded by the callback transaction,
+    /// which carries every target account and its new ciphertext and must fit
+    /// in the 1232-byte transaction limit alongside the signed output. As
+    /// with `deposit_funds`, a `PendingBatchDeposit` record lets the payer
+    /// reclaim the total through `reclaim_batch_deposit` if the computation
+    /// never settles.
+    pub fn batch_deposit(
+        ctx: Context<BatchDeposit>,
+        computation_offset: u64,
+        deposits: Vec<(Pubkey, u64)>,
+    ) -> Result<()> {
+        ctx.accounts.config.ensure_open(PAUSE_DEPOSITS)?;
+        ensure_computation_fee(&ctx.accounts.payer, 0)?;
 
-impl PrivateBalanceAccount {
-    pub const SIZE: usize = 8 + 32 + 1 + 64 + 16;
-}
+        let balances = [
+            &ctx.accounts.balance_0,
+            &ctx.accounts.balance_1,
+            &ctx.accounts.balance_2,
+            &ctx.accounts.balance_3,
+        ];
+        let (amounts, total) =
+            batch_deposit_amounts(&deposits, balances.map(|balance| (balance.key(), &**balance)))?;
+        let transfer_ix = anchor_lang::solana_program::system_instruction::transfer(
+            &ctx.accounts.payer.key(),
+            &ctx.accounts.sol_vault.key(),
+            total,
+        );
+        anchor_lang::solana_program::program::invoke(
+            &transfer_ix,
+            &[
+                ctx.accounts.payer.to_account_info(),
+                ctx.accounts.sol_vault.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
 
-// ============ Instruction Contexts ============
+        let pending_batch_deposit = &mut ctx.accounts.pending_batch_deposit;
+        pending_batch_deposit.payer = ctx.accounts.payer.key();
+        pending_batch_deposit.computation_offset = computation_offset;
+        pending_batch_deposit.total = total;
+        pending_batch_deposit.queued_slot = Clock::get()?.slot;
+        pending_batch_deposit.bump = ctx.bumps.pending_batch_deposit;
 
-#[queue_computation_accounts("init_balance", payer)]
-#[derive(Accounts)]
-#[instruction(computation_offset: u64)]
-pub struct CreateBalanceAccount<'info> {
-    #[account(mut)]
-    pub payer: Signer<'info>,
-    
-    #[account(
-        init,
-        payer = payer,
-        space = 8 + PrivateBalanceAccount::SIZE,
-        seeds = [b"balance", payer.key().as_ref()],
-        bump,
-    )]
-    pub balance_account: Account<'info, PrivateBalanceAccount>,
-    
-    #[account(
-        init_if_needed,
-        space = 9,
-        payer = payer,
-        seeds = [&SIGN_PDA_SEED],
-        bump,
-        address = derive_sign_pda!(),
-    )]
-    pub sign_pda_account: Account<'info, SignerAccount>,
-    
-    #[account(address = derive_mxe_pda!())]
-    pub mxe_account: Account<'info, MXEAccount>,
-    
-    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
-    /// CHECK: mempool_account
-    pub mempool_account: UncheckedAccount<'info>,
-    
-    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
-    /// CHECK: executing_pool
-    pub executing_pool: UncheckedAccount<'info>,
-    
-    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
-    /// CHECK: computation_account
-    pub computation_account: UncheckedAccount<'info>,
-    
-    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_INIT_BALANCE))]
-    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
-    
-    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
-    pub cluster_account: Account<'info, Cluster>,
-    
-    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
-    pub pool_account: Account<'info, FeePool>,
-    
-    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
-    pub clock_account: Account<'info, ClockAccount>,
-    
-    pub system_program: Program<'info, System>,
-    pub arcium_program: Program<'info, Arcium>,
-}
+        let mut args = ArgBuilder::new();
+        for amount in amounts {
+            args = args.plaintext_u64(amount);
+        }
+        for balance in balances {
+            args = args
+                .plaintext_u128(balance.nonce)
+                .encrypted_bytes(balance.balance_state);
+        }
+        let args = args.build();
 
-#[callback_accounts("init_balance")]
-#[derive(Accounts)]
-pub struct CreateBalanceCallback<'info> {
-    pub arcium_program: Program<'info, Arcium>,
-    
-    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_INIT_BALANCE))]
-    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
-    
-    #[account(address = derive_mxe_pda!())]
-    pub mxe_account: Account<'info, MXEAccount>,
-    
-    /// CHECK: computation_account
-    pub computation_account: UncheckedAccount<'info>,
-    
-    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
-    pub cluster_account: Account<'info, Cluster>,
-    
-    #[account(mut)]
-    pub balance_account: Account<'info, PrivateBalanceAccount>,
-    
-    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
-    /// CHECK: instructions_sysvar
-    pub instructions_sysvar: AccountInfo<'info>,
-}
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
 
-#[queue_computation_accounts("deposit", payer)]
-#[derive(Accounts)]
-#[instruction(computation_offset: u64)]
-pub struct DepositFunds<'info> {
-    #[account(mut)]
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![BatchDepositCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &CallbackAccountsBuilder::new()
+                    .writable(ctx.accounts.balance_0.key())
+                    .writable(ctx.accounts.balance_1.key())
+                    .writable(ctx.accounts.balance_2.key())
+                    .writable(ctx.accounts.balance_3.key())
+                    .writable(ctx.accounts.pending_batch_deposit.key())
+                    .writable(ctx.accounts.payer.key())
+                    .build(),
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Callback after a batch deposit
+    /// Padding slots repeat `balance_0` unchanged, so only the first write
+    /// to each account is kept
+    #[arcium_callback(encrypted_ix = "batch_deposit")]
+    pub fn batch_deposit_callback(
+        ctx: Context<BatchDepositCallback>,
+        output: SignedComputationOutputs<BatchDepositOutput>,
+    ) -> Result<()> {
+        let (count, total) = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(BatchDepositOutput {
+                balance_0,
+                nonce_0,
+                balance_1,
+                nonce_1,
+                balance_2,
+                nonce_2,
+                balance_3,
+                nonce_3,
+                count,
+                total,
+                success,
+            }) => {
+                if !success {
+                    return Err(ErrorCode::DepositFailed.into());
+                }
+                let first = ctx.accounts.balance_0.key();
+                ctx.accounts.balance_0.write_balance(balance_0, nonce_0);
+                if ctx.accounts.balance_1.key() != first {
+                    ctx.accounts.balance_1.write_balance(balance_1, nonce_1);
+                }
+                if ctx.accounts.balance_2.key() != first {
+                    ctx.accounts.balance_2.write_balance(balance_2, nonce_2);
+                }
+                if ctx.accounts.balance_3.key() != first {
+                    ctx.accounts.balance_3.write_balance(balance_3, nonce_3);
+                }
+                (count, total)
+            }
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let clock = Clock::get()?;
+        emit!(BatchDeposited {
+            count,
+            total,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Withdraw SOL from the private balance
+    /// The amount is public, as it leaves the balance account as lamports; the
+    /// balance itself is only compared against it inside MPC. The amount counts
+    /// against the spending limit when queued, even if MPC then rejects it.
+    pub fn withdraw_funds(
+        ctx: Context<WithdrawFunds>,
+        computation_offset: u64,
+        amount: u64,
+    ) -> Result<()> {
+        ctx.accounts.config.ensure_open(PAUSE_WITHDRAWALS)?;
+        ensure_computation_fee(&ctx.accounts.payer, 0)?;
+        require!(amount > 0, ErrorCode::WithdrawFailed);
+        require!(ctx.accounts.balance_account.mint == Pubkey::default(), ErrorCode::MintMismatch);
+        ctx.accounts.balance_account.record_spend(amount, Clock::get()?.slot)?;
+
+        let balance_account = &ctx.accounts.balance_account;
+        let args = ArgBuilder::new()
+            .plaintext_u64(amount)
+            .plaintext_u128(balance_account.nonce)
+            .encrypted_bytes(balance_account.balance_state)
+            .plaintext_u128(balance_account.min_balance_nonce)
+            .encrypted_bytes(balance_account.min_balance_state)
+            .build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![WithdrawCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &CallbackAccountsBuilder::new()
+                    .writable(ctx.accounts.balance_account.key())
+                    .writable(ctx.accounts.payer.key())
+                    .writable(ctx.accounts.sol_vault.key())
+                    // No token vault, token account or token program
+                    .readonly(crate::ID)
+                    .readonly(crate::ID)
+                    .readonly(crate::ID)
+                    .build(),
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Callback after a SOL or token withdrawal
+    /// Pays out of the SOL vault, or the mint's token vault for a token
+    /// balance, only when MPC confirmed the balance covered the amount
+    #[arcium_callback(encrypted_ix = "withdraw")]
+    pub fn withdraw_callback(
+        ctx: Context<WithdrawCallback>,
+        output: SignedComputationOutputs<WithdrawOutput>,
+    ) -> Result<()> {
+        let amount = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(WithdrawOutput { new_balance, nonce, amount, success }) => {
+                if !success {
+                    return Err(ErrorCode::InsufficientBalance.into());
+                }
+                ctx.accounts.balance_account.write_balance(new_balance, nonce);
+                amount
+            }
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let mint = ctx.accounts.balance_account.mint;
+        let clock = Clock::get()?;
+        if mint == Pubkey::default() {
+            let sol_vault = ctx.accounts.sol_vault.as_ref().ok_or(ErrorCode::MintMismatch)?;
+            pay_from_sol_vault(sol_vault, &ctx.accounts.owner, amount)?;
+
+            emit!(FundsWithdrawn {
+                owner: ctx.accounts.owner.key(),
+                amount,
+                slot: clock.slot,
+                unix_timestamp: clock.unix_timestamp,
+            });
+            return Ok(());
+        }
+
+        let (token_vault, user_token_account, token_program) = match (
+            ctx.accounts.token_vault.as_ref(),
+            ctx.accounts.user_token_account.as_ref(),
+            ctx.accounts.token_program.as_ref(),
+        ) {
+            (Some(vault), Some(account), Some(program)) => (vault, account, program),
+            _ => return Err(ErrorCode::MintMismatch.into()),
+        };
+        let (vault_key, vault_bump) = token_vault_address(&mint);
+        require!(token_vault.key() == vault_key, ErrorCode::MintMismatch);
+
+        let bump = [vault_bump];
+        let signer_seeds: &[&[&[u8]]] = &[&[b"token_vault", mint.as_ref(), &bump]];
+        let cpi_accounts = Transfer {
+            from: token_vault.to_account_info(),
+            to: user_token_account.to_account_info(),
+            authority: token_vault.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        );
+        token::transfer(cpi_ctx, amount)?;
+
+        emit!(TokensWithdrawn {
+            owner: ctx.accounts.balance_account.owner,
+            mint,
+            amount,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Refund a deposit whose computation aborted or timed out
+    /// An aborted computation fails its callback, leaving the deposited SOL in
+    /// the vault without crediting the encrypted balance. Once
+    /// `COMPUTATION_TIMEOUT_SLOTS` have passed the owner takes the lamports back
+    /// and the pending deposit is closed, so neither a second refund nor a late
+    /// callback can apply.
+    pub fn reclaim_failed_computation(
+        ctx: Context<ReclaimFailedComputation>,
+        computation_offset: u64,
+    ) -> Result<()> {
+        // Token deposits sit in the vault, not the balance account's lamports
+        require!(ctx.accounts.balance_account.mint == Pubkey::default(), ErrorCode::MintMismatch);
+
+        let pending_deposit = &ctx.accounts.pending_deposit;
+        let current_slot = Clock::get()?.slot;
+        ensure_timed_out(pending_deposit.queued_slot, current_slot)?;
+
+        let amount = pending_deposit.amount;
+        pay_from_sol_vault(&ctx.accounts.sol_vault, &ctx.accounts.owner, amount)?;
+
+        let clock = Clock::get()?;
+        emit!(ComputationReclaimed {
+            owner: ctx.accounts.owner.key(),
+            computation_offset,
+            refunded: amount,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Refund a token deposit whose computation aborted or timed out
+    /// The token counterpart of `reclaim_failed_computation`: the deposited
+    /// tokens sit in the mint's vault, so they are paid back from there
+    pub fn reclaim_failed_token_deposit(
+        ctx: Context<ReclaimFailedTokenDeposit>,
+        computation_offset: u64,
+    ) -> Result<()> {
+        let pending_deposit = &ctx.accounts.pending_deposit;
+        ensure_timed_out(pending_deposit.queued_slot, Clock::get()?.slot)?;
+
+        let amount = pending_deposit.amount;
+        let mint = ctx.accounts.balance_account.mint;
+        let bump = [ctx.bumps.token_vault];
+        let signer_seeds: &[&[&[u8]]] = &[&[b"token_vault", mint.as_ref(), &bump]];
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.token_vault.to_account_info(),
+            to: ctx.accounts.owner_token_account.to_account_info(),
+            authority: ctx.accounts.token_vault.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        );
+        token::transfer(cpi_ctx, amount)?;
+
+        let clock = Clock::get()?;
+        emit!(ComputationReclaimed {
+            owner: ctx.accounts.owner.key(),
+            computation_offset,
+            refunded: amount,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Refund a batch deposit whose computation aborted or timed out
+    /// Like `reclaim_failed_computation`, but returns the whole batch total
+    /// to the payer, since no target balance was credited
+    pub fn reclaim_batch_deposit(
+        ctx: Context<ReclaimBatchDeposit>,
+        computation_offset: u64,
+    ) -> Result<()> {
+        let pending_batch_deposit = &ctx.accounts.pending_batch_deposit;
+        ensure_timed_out(pending_batch_deposit.queued_slot, Clock::get()?.slot)?;
+
+        let amount = pending_batch_deposit.total;
+        pay_from_sol_vault(&ctx.accounts.sol_vault, &ctx.accounts.payer, amount)?;
+
+        let clock = Clock::get()?;
+        emit!(ComputationReclaimed {
+            owner: ctx.accounts.payer.key(),
+            computation_offset,
+            refunded: amount,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Create a private balance for an SPL token, separate from the SOL balance
+    /// Also opens the program's vault for the mint on first use
+    pub fn create_token_balance_account(
+        ctx: Context<CreateTokenBalanceAccount>,
+        computation_offset: u64,
+        nonce: u128,
+    ) -> Result<()> {
+        ctx.accounts.config.ensure_open(0)?;
+        ensure_computation_fee(&ctx.accounts.payer, 0)?;
+
+        let args = ArgBuilder::new().build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        let balance_account = &mut ctx.accounts.balance_account;
+        balance_account.owner = ctx.accounts.payer.key();
+        balance_account.bump = ctx.bumps.balance_account;
+        balance_account.label = 0;
+        balance_account.nonce = nonce;
+        balance_account.balance_state = [0u8; 64]; // Set to an encrypted zero by the callback
+        balance_account.last_accrual_slot = Clock::get()?.slot;
+        balance_account.min_balance_state = [0u8; 64];
+        balance_account.min_balance_nonce = 0;
+        balance_account.mint = ctx.accounts.mint.key();
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![CreateBalanceCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &CallbackAccountsBuilder::new()
+                    .writable(ctx.accounts.balance_account.key())
+                    .build(),
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Deposit SPL tokens into a private token balance
+    /// The tokens move into the program vault for the mint and the `deposit`
+    /// circuit credits the encrypted balance, settling in `deposit_callback`.
+    /// Unlike SOL deposits these can't be refunded with `reclaim_failed_computation`.
+    pub fn deposit_token(
+        ctx: Context<DepositToken>,
+        computation_offset: u64,
+        amount: u64,
+    ) -> Result<()> {
+        ctx.accounts.config.ensure_open(PAUSE_DEPOSITS)?;
+        ensure_computation_fee(&ctx.accounts.payer, 0)?;
+        require!(amount > 0, ErrorCode::ZeroDeposit);
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.user_token_account.to_account_info(),
+            to: ctx.accounts.token_vault.to_account_info(),
+            authority: ctx.accounts.payer.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+        token::transfer(cpi_ctx, amount)?;
+
+        let pending_deposit = &mut ctx.accounts.pending_deposit;
+        pending_deposit.owner = ctx.accounts.payer.key();
+        pending_deposit.balance_account = ctx.accounts.balance_account.key();
+        pending_deposit.computation_offset = computation_offset;
+        pending_deposit.amount = amount;
+        pending_deposit.queued_slot = Clock::get()?.slot;
+        pending_deposit.bump = ctx.bumps.pending_deposit;
+
+        let balance_account = &ctx.accounts.balance_account;
+        let args = ArgBuilder::new()
+            .plaintext_u64(amount)
+            .plaintext_u128(balance_account.nonce)
+            .encrypted_bytes(balance_account.balance_state)
+            .build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![DepositCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &CallbackAccountsBuilder::new()
+                    .writable(ctx.accounts.balance_account.key())
+                    .writable(ctx.accounts.pending_deposit.key())
+                    .writable(ctx.accounts.payer.key())
+                    .build(),
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Withdraw SPL tokens from a private token balance
+    /// Works like `withdraw_funds` through the same `withdraw` computation,
+    /// whose callback pays out of the mint's vault for a token balance
+    pub fn withdraw_token(
+        ctx: Context<WithdrawToken>,
+        computation_offset: u64,
+        amount: u64,
+    ) -> Result<()> {
+        ctx.accounts.config.ensure_open(PAUSE_WITHDRAWALS)?;
+        ensure_computation_fee(&ctx.accounts.payer, 0)?;
+        require!(amount > 0, ErrorCode::WithdrawFailed);
+        ctx.accounts.balance_account.record_spend(amount, Clock::get()?.slot)?;
+
+        let balance_account = &ctx.accounts.balance_account;
+        let args = ArgBuilder::new()
+            .plaintext_u64(amount)
+            .plaintext_u128(balance_account.nonce)
+            .encrypted_bytes(balance_account.balance_state)
+            .plaintext_u128(balance_account.min_balance_nonce)
+            .encrypted_bytes(balance_account.min_balance_state)
+            .build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![WithdrawCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &CallbackAccountsBuilder::new()
+                    .writable(ctx.accounts.balance_account.key())
+                    .writable(ctx.accounts.payer.key())
+                    .readonly(crate::ID) // No SOL vault
+                    .writable(ctx.accounts.token_vault.key())
+                    .writable(ctx.accounts.user_token_account.key())
+                    .readonly(ctx.accounts.token_program.key())
+                    .build(),
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Create a fixed-denomination mixing pool
+    /// Every deposit and withdrawal moves exactly `denomination` lamports,
+    /// so amounts can't be used to link depositors to withdrawers
+    pub fn init_mixing_pool(
+        ctx: Context<InitMixingPool>,
+        computation_offset: u64,
+        denomination: u64,
+    ) -> Result<()> {
+        ctx.accounts.config.ensure_open(0)?;
+        ensure_computation_fee(&ctx.accounts.payer, 0)?;
+
+        require!(denomination > 0, ErrorCode::InvalidDenomination);
+
+        let mixing_pool = &mut ctx.accounts.mixing_pool;
+        mixing_pool.denomination = denomination;
+        mixing_pool.bump = ctx.bumps.mixing_pool;
+        mixing_pool.deposit_count = 0;
+        mixing_pool.withdraw_count = 0;
+        mixing_pool.state_nonce = 0;
+        mixing_pool.encrypted_state = [[0u8; 32]; MIXING_POOL_STATE_WORDS];
+        mixing_pool.pending = ComputationLock::default();
+
+        let args = ArgBuilder::new().build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![InitMixingPoolCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &CallbackAccountsBuilder::new()
+                    .writable(ctx.accounts.mixing_pool.key())
+                    .build(),
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Callback storing the empty encrypted commitment set
+    #[arcium_callback(encrypted_ix = "init_mixing_pool")]
+    pub fn init_mixing_pool_callback(
+        ctx: Context<InitMixingPoolCallback>,
+        output: SignedComputationOutputs<InitMixingPoolOutput>,
+    ) -> Result<()> {
+        let pool_state = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(InitMixingPoolOutput { pool_state }) => pool_state,
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let mixing_pool = &mut ctx.accounts.mixing_pool;
+        mixing_pool.encrypted_state = pool_state.ciphertexts;
+        mixing_pool.state_nonce = pool_state.nonce;
+
+        let clock = Clock::get()?;
+        emit!(MixingPoolCreated {
+            mixing_pool: mixing_pool.key(),
+            denomination: mixing_pool.denomination,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Deposit one denomination into the mixing pool
+    /// The encrypted note secret is added to the shared commitment set. The
+    /// pool is locked until the callback, and a `PendingPoolDeposit` tracks
+    /// the denomination so `reclaim_pool_deposit` can refund it if the
+    /// computation never settles.
+    pub fn pool_deposit(
+        ctx: Context<PoolDeposit>,
+        computation_offset: u64,
+        encrypted_secret: [u8; 64], // Encrypted note secret
+        pub_key: [u8; 32],
+        nonce: u128,
+    ) -> Result<()> {
+        ctx.accounts.config.ensure_open(PAUSE_DEPOSITS)?;
+        ensure_computation_fee(&ctx.accounts.payer, 0)?;
+
+        let current_slot = Clock::get()?.slot;
+        let computation = ctx.accounts.computation_account.key();
+        ctx.accounts.mixing_pool.pending.lock(computation, current_slot)?;
+
+        let pending_pool_deposit = &mut ctx.accounts.pending_pool_deposit;
+        pending_pool_deposit.depositor = ctx.accounts.payer.key();
+        pending_pool_deposit.mixing_pool = ctx.accounts.mixing_pool.key();
+        pending_pool_deposit.computation = computation;
+        pending_pool_deposit.queued_slot = current_slot;
+        pending_pool_deposit.bump = ctx.bumps.pending_pool_deposit;
+
+        let mixing_pool = &ctx.accounts.mixing_pool;
+
+        let transfer_ix = anchor_lang::solana_program::system_instruction::transfer(
+            &ctx.accounts.payer.key(),
+            &mixing_pool.key(),
+            mixing_pool.denomination,
+        );
+
+        anchor_lang::solana_program::program::invoke(
+            &transfer_ix,
+            &[
+                ctx.accounts.payer.to_account_info(),
+                ctx.accounts.mixing_pool.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+
+        let args = ArgBuilder::new()
+            .x25519_pubkey(pub_key)
+            .plaintext_u128(nonce)
+            .encrypted_bytes(encrypted_secret)
+            .plaintext_u128(mixing_pool.state_nonce)
+            .account(
+                mixing_pool.key(),
+                MixingPool::STATE_OFFSET,
+                MixingPool::STATE_LEN,
+            )
+            .build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![PoolDepositCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &CallbackAccountsBuilder::new()
+                    .writable(ctx.accounts.mixing_pool.key())
+                    .writable(ctx.accounts.pending_pool_deposit.key())
+                    .writable(ctx.accounts.payer.key())
+                    .build(),
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Callback after a note is inserted into the mixing pool
+    /// A note the pool had no room for, or an invalid one, is refunded here
+    /// rather than failing, so the pool's lock is released either way
+    #[arcium_callback(encrypted_ix = "pool_deposit")]
+    pub fn pool_deposit_callback(
+        ctx: Context<PoolDepositCallback>,
+        output: SignedComputationOutputs<PoolDepositOutput>,
+    ) -> Result<()> {
+        let (pool_state, success) = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(PoolDepositOutput { pool_state, success }) => (pool_state, success),
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let mixing_pool = &mut ctx.accounts.mixing_pool;
+        mixing_pool.pending.unlock(ctx.accounts.computation_account.key())?;
+        // The pending deposit is closed to the depositor by the account constraint
+        if !success {
+            let denomination = mixing_pool.denomination;
+            **mixing_pool.to_account_info().try_borrow_mut_lamports()? -= denomination;
+            **ctx.accounts.depositor.try_borrow_mut_lamports()? += denomination;
+            return Ok(());
+        }
+        mixing_pool.encrypted_state = pool_state.ciphertexts;
+        mixing_pool.state_nonce = pool_state.nonce;
+        mixing_pool.deposit_count = mixing_pool
+            .deposit_count
+            .checked_add(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        let clock = Clock::get()?;
+        emit!(PoolDeposited {
+            mixing_pool: mixing_pool.key(),
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Withdraw one denomination from the mixing pool to any recipient
+    /// The payer and recipient need not be related to the original depositor
+    pub fn pool_withdraw(
+        ctx: Context<PoolWithdraw>,
+        computation_offset: u64,
+        encrypted_secret: [u8; 64], // Encrypted note secret
+        pub_key: [u8; 32],
+        nonce: u128,
+    ) -> Result<()> {
+        ctx.accounts.config.ensure_open(PAUSE_WITHDRAWALS)?;
+        ensure_computation_fee(&ctx.accounts.payer, 0)?;
+
+        let computation = ctx.accounts.computation_account.key();
+        ctx.accounts.mixing_pool.pending.lock(computation, Clock::get()?.slot)?;
+
+        let mixing_pool = &ctx.accounts.mixing_pool;
+
+        let args = ArgBuilder::new()
+            .x25519_pubkey(pub_key)
+            .plaintext_u128(nonce)
+            .encrypted_bytes(encrypted_secret)
+            .plaintext_u128(mixing_pool.state_nonce)
+            .account(
+                mixing_pool.key(),
+                MixingPool::STATE_OFFSET,
+                MixingPool::STATE_LEN,
+            )
+            .build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![PoolWithdrawCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &CallbackAccountsBuilder::new()
+                    .writable(ctx.accounts.mixing_pool.key())
+                    .writable(ctx.accounts.recipient.key())
+                    .build(),
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Callback after a note is spent, paying out one denomination
+    /// An unknown note pays nothing but still releases the pool's lock, so a
+    /// bad withdrawal can't hold the pool until the timeout
+    #[arcium_callback(encrypted_ix = "pool_withdraw")]
+    pub fn pool_withdraw_callback(
+        ctx: Context<PoolWithdrawCallback>,
+        output: SignedComputationOutputs<PoolWithdrawOutput>,
+    ) -> Result<()> {
+        let (pool_state, success) = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(PoolWithdrawOutput { pool_state, success }) => (pool_state, success),
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let mixing_pool = &mut ctx.accounts.mixing_pool;
+        mixing_pool.pending.unlock(ctx.accounts.computation_account.key())?;
+        if !success {
+            return Ok(());
+        }
+        mixing_pool.encrypted_state = pool_state.ciphertexts;
+        mixing_pool.state_nonce = pool_state.nonce;
+        mixing_pool.withdraw_count = mixing_pool
+            .withdraw_count
+            .checked_add(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        let denomination = mixing_pool.denomination;
+        **mixing_pool.to_account_info().try_borrow_mut_lamports()? -= denomination;
+        **ctx.accounts.recipient.try_borrow_mut_lamports()? += denomination;
+
+        let clock = Clock::get()?;
+        emit!(PoolWithdrawn {
+            mixing_pool: mixing_pool.key(),
+            recipient: ctx.accounts.recipient.key(),
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Refund a mixing pool deposit whose computation never settled
+    /// Open to the depositor once COMPUTATION_TIMEOUT_SLOTS have passed. The
+    /// pending record is closed, so a late callback for the deposit fails
+    /// instead of inserting the note, and the pool's lock is released if
+    /// the deposit still holds it.
+    pub fn reclaim_pool_deposit(ctx: Context<ReclaimPoolDeposit>) -> Result<()> {
+        let pending_pool_deposit = &ctx.accounts.pending_pool_deposit;
+        ensure_timed_out(pending_pool_deposit.queued_slot, Clock::get()?.slot)?;
+
+        let mixing_pool = &mut ctx.accounts.mixing_pool;
+        if mixing_pool.pending.computation == pending_pool_deposit.computation {
+            mixing_pool.pending = ComputationLock::default();
+        }
+        let denomination = mixing_pool.denomination;
+        **mixing_pool.to_account_info().try_borrow_mut_lamports()? -= denomination;
+        **ctx.accounts.depositor.try_borrow_mut_lamports()? += denomination;
+
+        let clock = Clock::get()?;
+        emit!(PoolDepositReclaimed {
+            mixing_pool: mixing_pool.key(),
+            depositor: ctx.accounts.depositor.key(),
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Create the compliance config holding the designated auditor key
+    pub fn init_compliance_config(
+        ctx: Context<InitComplianceConfig>,
+        auditor_pubkey: [u8; 32], // Auditor x25519 public key
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.compliance_config;
+        config.authority = ctx.accounts.authority.key();
+        config.auditor_pubkey = auditor_pubkey;
+        config.bump = ctx.bumps.compliance_config;
+        Ok(())
+    }
+
+    /// Rotate the designated auditor key
+    pub fn set_auditor(
+        ctx: Context<SetAuditor>,
+        auditor_pubkey: [u8; 32],
+    ) -> Result<()> {
+        ctx.accounts.compliance_config.auditor_pubkey = auditor_pubkey;
+        Ok(())
+    }
+
+    /// Disclose the current balance to the compliance auditor
+    /// The owner must sign; the balance is re-encrypted so only the auditor can read it
+    pub fn disclose_to_auditor(
+        ctx: Context<DiscloseToAuditor>,
+        computation_offset: u64,
+        auditor_nonce: u128,
+    ) -> Result<()> {
+        ctx.accounts.config.ensure_open(0)?;
+        ensure_computation_fee(&ctx.accounts.payer, 0)?;
+
+        let balance_account = &ctx.accounts.balance_account;
+
+        let args = ArgBuilder::new()
+            .plaintext_u128(balance_account.nonce)
+            .encrypted_bytes(balance_account.balance_state)
+            .x25519_pubkey(ctx.accounts.compliance_config.auditor_pubkey)
+            .plaintext_u128(auditor_nonce)
+            .build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![DiscloseBalanceCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &CallbackAccountsBuilder::new()
+                    .readonly(ctx.accounts.balance_account.key())
+                    .readonly(ctx.accounts.compliance_config.key())
+                    .build(),
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Callback emitting the auditor-only ciphertext
+    #[arcium_callback(encrypted_ix = "disclose_balance")]
+    pub fn disclose_balance_callback(
+        ctx: Context<DiscloseBalanceCallback>,
+        output: SignedComputationOutputs<DiscloseBalanceOutput>,
+    ) -> Result<()> {
+        let result = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(DiscloseBalanceOutput { encrypted_balance, nonce }) => (encrypted_balance, nonce),
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let clock = Clock::get()?;
+        emit!(BalanceDisclosed {
+            owner: ctx.accounts.balance_account.owner,
+            auditor_pubkey: ctx.accounts.compliance_config.auditor_pubkey,
+            encrypted_balance: result.0,
+            nonce: result.1,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Show the owner their balance without moving funds
+    /// The balance is re-encrypted to a fresh client key, so only the
+    /// requester can decrypt the ciphertext emitted in `BalanceRevealed`
+    pub fn request_balance_reveal(
+        ctx: Context<RequestBalanceReveal>,
+        computation_offset: u64,
+        client_pubkey: [u8; 32],
+        client_nonce: u128,
+    ) -> Result<()> {
+        ctx.accounts.config.ensure_open(0)?;
+        ensure_computation_fee(&ctx.accounts.payer, 0)?;
+        ctx.accounts.balance_account.ensure_created()?;
+
+        let balance_account = &ctx.accounts.balance_account;
+
+        let args = ArgBuilder::new()
+            .plaintext_u128(balance_account.nonce)
+            .encrypted_bytes(balance_account.balance_state)
+            .x25519_pubkey(client_pubkey)
+            .plaintext_u128(client_nonce)
+            .build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![RevealBalanceCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &CallbackAccountsBuilder::new()
+                    .readonly(ctx.accounts.balance_account.key())
+                    .build(),
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Callback emitting the requester-only ciphertext
+    #[arcium_callback(encrypted_ix = "reveal_balance")]
+    pub fn reveal_balance_callback(
+        ctx: Context<RevealBalanceCallback>,
+        output: SignedComputationOutputs<RevealBalanceOutput>,
+    ) -> Result<()> {
+        let result = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(RevealBalanceOutput { encrypted_balance, nonce }) => (encrypted_balance, nonce),
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let clock = Clock::get()?;
+        emit!(BalanceRevealed {
+            owner: ctx.accounts.balance_account.owner,
+            balance_account: ctx.accounts.balance_account.key(),
+            encrypted_balance: result.0,
+            nonce: result.1,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Approve a delegate to spend up to an encrypted cap from the owner's balance
+    /// Re-approving replaces the remaining allowance
+    pub fn approve_private_allowance(
+        ctx: Context<ApprovePrivateAllowance>,
+        computation_offset: u64,
+        encrypted_amount: [u8; 64], // Encrypted allowance cap
+        pub_key: [u8; 32],
+        nonce: u128,
+    ) -> Result<()> {
+        ctx.accounts.config.ensure_open(0)?;
+        ensure_computation_fee(&ctx.accounts.payer, 0)?;
+
+        let allowance = &mut ctx.accounts.allowance;
+        allowance.owner = ctx.accounts.payer.key();
+        allowance.delegate = ctx.accounts.delegate.key();
+        allowance.bump = ctx.bumps.allowance;
+
+        let args = ArgBuilder::new()
+            .x25519_pubkey(pub_key)
+            .plaintext_u128(nonce)
+            .encrypted_bytes(encrypted_amount)
+            .build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![ApproveAllowanceCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &CallbackAccountsBuilder::new()
+                    .writable(ctx.accounts.allowance.key())
+                    .build(),
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Callback storing the encrypted allowance
+    #[arcium_callback(encrypted_ix = "approve_allowance")]
+    pub fn approve_allowance_callback(
+        ctx: Context<ApproveAllowanceCallback>,
+        output: SignedComputationOutputs<ApproveAllowanceOutput>,
+    ) -> Result<()> {
+        let result = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(ApproveAllowanceOutput { allowance, nonce }) => (allowance, nonce),
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let allowance = &mut ctx.accounts.allowance;
+        allowance.allowance_state = result.0;
+        allowance.nonce = result.1;
+
+        let clock = Clock::get()?;
+        emit!(AllowanceApproved {
+            owner: allowance.owner,
+            delegate: allowance.delegate,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Spend from an allowance as the delegate
+    /// The encrypted amount moves from the owner's balance into the delegate's
+    pub fn spend_from_allowance(
+        ctx: Context<SpendFromAllowance>,
+        computation_offset: u64,
+        encrypted_amount: [u8; 64], // Encrypted spend amount
+        pub_key: [u8; 32],
+        nonce: u128,
+    ) -> Result<()> {
+        ctx.accounts.config.ensure_open(0)?;
+        ensure_computation_fee(&ctx.accounts.payer, 0)?;
+
+        let allowance = &ctx.accounts.allowance;
+        let owner_balance = &ctx.accounts.owner_balance;
+        let delegate_balance = &ctx.accounts.delegate_balance;
+
+        let args = ArgBuilder::new()
+            .x25519_pubkey(pub_key)
+            .plaintext_u128(nonce)
+            .encrypted_bytes(encrypted_amount)
+            .plaintext_u128(allowance.nonce)
+            .encrypted_bytes(allowance.allowance_state)
+            .plaintext_u128(owner_balance.nonce)
+            .encrypted_bytes(owner_balance.balance_state)
+            .plaintext_u128(delegate_balance.nonce)
+            .encrypted_bytes(delegate_balance.balance_state)
+            .build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![SpendAllowanceCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &CallbackAccountsBuilder::new()
+                    .writable(ctx.accounts.allowance.key())
+                    .writable(ctx.accounts.owner_balance.key())
+                    .writable(ctx.accounts.delegate_balance.key())
+                    .build(),
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Callback after an allowance spend
+    /// State is only written when the spend fit within the allowance and balance
+    #[arcium_callback(encrypted_ix = "spend_allowance")]
+    pub fn spend_allowance_callback(
+        ctx: Context<SpendAllowanceCallback>,
+        output: SignedComputationOutputs<SpendAllowanceOutput>,
+    ) -> Result<()> {
+        match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(SpendAllowanceOutput {
+                allowance,
+                allowance_nonce,
+                owner_balance,
+                owner_nonce,
+                delegate_balance,
+                delegate_nonce,
+                success,
+            }) => {
+                if !success {
+                    return Err(ErrorCode::AllowanceExceeded.into());
+                }
+                let allowance_account = &mut ctx.accounts.allowance;
+                allowance_account.allowance_state = allowance;
+                allowance_account.nonce = allowance_nonce;
+                ctx.accounts.owner_balance.write_balance(owner_balance, owner_nonce);
+                ctx.accounts.delegate_balance.write_balance(delegate_balance, delegate_nonce);
+            }
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let clock = Clock::get()?;
+        emit!(AllowanceSpent {
+            owner: ctx.accounts.allowance.owner,
+            delegate: ctx.accounts.allowance.delegate,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Create the program config
+    pub fn init_config(
+        ctx: Context<InitConfig>,
+        interest_rate_per_slot: u64, // Scaled by INTEREST_SCALE
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.authority = ctx.accounts.authority.key();
+        config.interest_rate_per_slot = interest_rate_per_slot;
+        config.bump = ctx.bumps.config;
+        config.paused_ops = 0;
+        config.paused = false;
+        config.dark_pool_program = Pubkey::default();
+        config.dark_pool_pubkey = [0u8; 32];
+        config.mxe_pubkey = [0u8; 32];
+        Ok(())
+    }
+
+    /// Create the SOL vault backing every SOL balance and the empty interest
+    /// reserve
+    pub fn init_sol_vault(ctx: Context<InitSolVault>) -> Result<()> {
+        ctx.accounts.sol_vault.bump = ctx.bumps.sol_vault;
+        let interest_reserve = &mut ctx.accounts.interest_reserve;
+        interest_reserve.reserve_state = [0u8; 64]; // Set by the first funding
+        interest_reserve.nonce = 0;
+        interest_reserve.pending = ComputationLock::default();
+        interest_reserve.bump = ctx.bumps.interest_reserve;
+        Ok(())
+    }
+
+    /// Move the lamports a SOL balance account holds past its rent into the
+    /// SOL vault
+    /// Deposits used to stay in each balance account; once swept they back
+    /// the balance from the vault like every later deposit. Permissionless,
+    /// since the lamports already belong to the program.
+    pub fn sweep_balance_lamports(ctx: Context<SweepBalanceLamports>) -> Result<()> {
+        let balance_info = ctx.accounts.balance_account.to_account_info();
+        let rent_floor = Rent::get()?.minimum_balance(balance_info.data_len());
+        let excess = balance_info.lamports().saturating_sub(rent_floor);
+        require!(excess > 0, ErrorCode::NothingToSweep);
+        **balance_info.try_borrow_mut_lamports()? -= excess;
+        **ctx.accounts.sol_vault.to_account_info().try_borrow_mut_lamports()? += excess;
+        Ok(())
+    }
+
+    /// Halt or resume every computation the program queues
+    /// Accounts are left untouched, and setting the current value is a no-op
+    pub fn set_paused(ctx: Context<UpdateConfig>, paused: bool) -> Result<()> {
+        ctx.accounts.config.paused = paused;
+        let clock = Clock::get()?;
+        emit!(PausedUpdated {
+            paused,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Pause or resume individual operations (see the PAUSE_* bits)
+    pub fn set_paused_ops(ctx: Context<UpdateConfig>, paused_ops: u8) -> Result<()> {
+        ctx.accounts.config.paused_ops = paused_ops;
+        let clock = Clock::get()?;
+        emit!(PausedOpsUpdated {
+            paused_ops,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Update the per-slot interest rate applied by `accrue_interest`
+    pub fn set_interest_rate(
+        ctx: Context<UpdateConfig>,
+        interest_rate_per_slot: u64,
+    ) -> Result<()> {
+        ctx.accounts.config.interest_rate_per_slot = interest_rate_per_slot;
+        Ok(())
+    }
+
+    /// Add lamports to the interest reserve `accrue_interest` pays from
+    /// The lamports go to the SOL vault and the amount is added to the
+    /// encrypted reserve. A funding whose callback never lands leaves its
+    /// lamports in the vault unclaimed.
+    pub fn fund_interest_reserve(
+        ctx: Context<FundInterestReserve>,
+        computation_offset: u64,
+        amount: u64,
+    ) -> Result<()> {
+        ctx.accounts.config.ensure_open(0)?;
+        ensure_computation_fee(&ctx.accounts.authority, 0)?;
+        require!(amount > 0, ErrorCode::ZeroDeposit);
+
+        let current_slot = Clock::get()?.slot;
+        ctx.accounts.interest_reserve.pending.lock(ctx.accounts.computation_account.key(), current_slot)?;
+
+        let transfer_ix = anchor_lang::solana_program::system_instruction::transfer(
+            &ctx.accounts.authority.key(),
+            &ctx.accounts.sol_vault.key(),
+            amount,
+        );
+        anchor_lang::solana_program::program::invoke(
+            &transfer_ix,
+            &[
+                ctx.accounts.authority.to_account_info(),
+                ctx.accounts.sol_vault.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+
+        let interest_reserve = &ctx.accounts.interest_reserve;
+        let args = ArgBuilder::new()
+            .plaintext_u64(amount)
+            .plaintext_u128(interest_reserve.nonce)
+            .encrypted_bytes(interest_reserve.reserve_state)
+            .plaintext_bool(interest_reserve.reserve_state == [0u8; 64])
+            .build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![FundInterestReserveCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &CallbackAccountsBuilder::new()
+                    .writable(ctx.accounts.interest_reserve.key())
+                    .build(),
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Callback storing the funded interest reserve
+    #[arcium_callback(encrypted_ix = "fund_interest_reserve")]
+    pub fn fund_interest_reserve_callback(
+        ctx: Context<FundInterestReserveCallback>,
+        output: SignedComputationOutputs<FundInterestReserveOutput>,
+    ) -> Result<()> {
+        ctx.accounts.interest_reserve.pending.unlock(ctx.accounts.computation_account.key())?;
+        match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(FundInterestReserveOutput { reserve, nonce, success }) => {
+                if !success {
+                    return Err(ErrorCode::DepositFailed.into());
+                }
+                let interest_reserve = &mut ctx.accounts.interest_reserve;
+                interest_reserve.reserve_state = reserve;
+                interest_reserve.nonce = nonce;
+            }
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let clock = Clock::get()?;
+        emit!(InterestReserveFunded {
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Compound interest onto a private balance for the slots since the last accrual
+    /// Permissionless: the elapsed span is tracked on-chain, so calling it
+    /// early or often never credits more than the schedule allows. Interest
+    /// comes out of the interest reserve, whose lamports are already in the
+    /// SOL vault, and stops once the reserve runs dry. One accrual runs at a
+    /// time, since each rewrites the reserve.
+    pub fn accrue_interest(
+        ctx: Context<AccrueInterest>,
+        computation_offset: u64,
+    ) -> Result<()> {
+        ctx.accounts.config.ensure_open(0)?;
+        ensure_computation_fee(&ctx.accounts.payer, 0)?;
+        require!(
+            ctx.accounts.balance_account.mint == Pubkey::default(),
+            ErrorCode::MintMismatch
+        );
+        require!(
+            ctx.accounts.interest_reserve.reserve_state != [0u8; 64],
+            ErrorCode::InterestReserveEmpty
+        );
+
+        let current_slot = Clock::get()?.slot;
+        ctx.accounts.interest_reserve.pending.lock(ctx.accounts.computation_account.key(), current_slot)?;
+        let balance_account = &mut ctx.accounts.balance_account;
+        let elapsed = current_slot.saturating_sub(balance_account.last_accrual_slot);
+        require!(elapsed > 0, ErrorCode::NothingToAccrue);
+
+        let factor = compound_factor(ctx.accounts.config.interest_rate_per_slot, elapsed)?;
+
+        // Advance the checkpoint now so overlapping calls can't accrue the same span
+        balance_account.last_accrual_slot = current_slot;
+
+        let interest_reserve = &ctx.accounts.interest_reserve;
+        let args = ArgBuilder::new()
+            .plaintext_u128(balance_account.nonce)
+            .encrypted_bytes(balance_account.balance_state)
+            .plaintext_u128(interest_reserve.nonce)
+            .encrypted_bytes(interest_reserve.reserve_state)
+            .plaintext_u128(factor)
+            .build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![AccrueInterestCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &CallbackAccountsBuilder::new()
+                    .writable(ctx.accounts.balance_account.key())
+                    .writable(ctx.accounts.interest_reserve.key())
+                    .build(),
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Callback storing the balance with interest applied
+    #[arcium_callback(encrypted_ix = "accrue_interest")]
+    pub fn accrue_interest_callback(
+        ctx: Context<AccrueInterestCallback>,
+        output: SignedComputationOutputs<AccrueInterestOutput>,
+    ) -> Result<()> {
+        ctx.accounts.interest_reserve.pending.unlock(ctx.accounts.computation_account.key())?;
+        match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(AccrueInterestOutput {
+                new_balance,
+                nonce,
+                reserve,
+                reserve_nonce,
+            }) => {
+                ctx.accounts.balance_account.write_balance(new_balance, nonce);
+                let interest_reserve = &mut ctx.accounts.interest_reserve;
+                interest_reserve.reserve_state = reserve;
+                interest_reserve.nonce = reserve_nonce;
+            }
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let clock = Clock::get()?;
+        emit!(InterestAccrued {
+            owner: ctx.accounts.balance_account.owner,
+            accrual_slot: ctx.accounts.balance_account.last_accrual_slot,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Deposit one amount across several of the payer's labeled balances
+    /// The total is public; how it is divided stays encrypted. Lamports go to
+    /// the SOL vault like every other deposit, so the split can't be read
+    /// from balances.
+    pub fn split_deposit(
+        ctx: Context<SplitDeposit>,
+        computation_offset: u64,
+        amount: u64,
+        labels: [u8; SPLIT_TARGETS],
+        encrypted_ratios: [[u8; 64]; SPLIT_TARGETS], // Encrypted bps per target
+        pub_key: [u8; 32],
+        nonce: u128,
+    ) -> Result<()> {
+        ctx.accounts.config.ensure_open(PAUSE_DEPOSITS)?;
+        ensure_computation_fee(&ctx.accounts.payer, 0)?;
+
+        require!(amount > 0, ErrorCode::DepositFailed);
+        for i in 0..SPLIT_TARGETS {
+            require!(labels[i] != 0, ErrorCode::InvalidBalanceLabel);
+            for j in 0..i {
+                require!(labels[i] != labels[j], ErrorCode::InvalidBalanceLabel);
+            }
+        }
+
+        let transfer_ix = anchor_lang::solana_program::system_instruction::transfer(
+            &ctx.accounts.payer.key(),
+            &ctx.accounts.sol_vault.key(),
+            amount,
+        );
+
+        anchor_lang::solana_program::program::invoke(
+            &transfer_ix,
+            &[
+                ctx.accounts.payer.to_account_info(),
+                ctx.accounts.sol_vault.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+
+        let mut args = ArgBuilder::new()
+            .plaintext_u64(amount)
+            .x25519_pubkey(pub_key)
+            .plaintext_u128(nonce);
+        for ratio in encrypted_ratios {
+            args = args.encrypted_bytes(ratio);
+        }
+        let targets = [
+            &ctx.accounts.balance_0,
+            &ctx.accounts.balance_1,
+            &ctx.accounts.balance_2,
+            &ctx.accounts.balance_3,
+        ];
+        for target in targets {
+            args = args
+                .plaintext_u128(target.nonce)
+                .encrypted_bytes(target.balance_state);
+        }
+        let args = args.build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![SplitDepositCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &CallbackAccountsBuilder::new()
+                    .writable(ctx.accounts.balance_0.key())
+                    .writable(ctx.accounts.balance_1.key())
+                    .writable(ctx.accounts.balance_2.key())
+                    .writable(ctx.accounts.balance_3.key())
+                    .build(),
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Callback writing each target's balance after a split deposit
+    #[arcium_callback(encrypted_ix = "split_deposit")]
+    pub fn split_deposit_callback(
+        ctx: Context<SplitDepositCallback>,
+        output: SignedComputationOutputs<SplitDepositOutput>,
+    ) -> Result<()> {
+        match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(SplitDepositOutput {
+                balance_0,
+                nonce_0,
+                balance_1,
+                nonce_1,
+                balance_2,
+                nonce_2,
+                balance_3,
+                nonce_3,
+                success,
+            }) => {
+                if !success {
+                    return Err(ErrorCode::InvalidSplitRatios.into());
+                }
+                ctx.accounts.balance_0.write_balance(balance_0, nonce_0);
+                ctx.accounts.balance_1.write_balance(balance_1, nonce_1);
+                ctx.accounts.balance_2.write_balance(balance_2, nonce_2);
+                ctx.accounts.balance_3.write_balance(balance_3, nonce_3);
+            }
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let clock = Clock::get()?;
+        emit!(DepositSplit {
+            owner: ctx.accounts.balance_0.owner,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Schedule a recurring private payment to `recipient`
+    /// The first installment is due `interval_slots` from now. Rescheduling
+    /// the same recipient replaces the amount and restarts the schedule.
+    pub fn schedule_payment(
+        ctx: Context<SchedulePayment>,
+        computation_offset: u64,
+        encrypted_amount: [u8; 64], // Encrypted installment amount
+        interval_slots: u64,
+        pub_key: [u8; 32],
+        nonce: u128,
+    ) -> Result<()> {
+        ctx.accounts.config.ensure_open(0)?;
+        ensure_computation_fee(&ctx.accounts.payer, 0)?;
+        require!(interval_slots > 0, ErrorCode::InvalidSchedule);
+
+        let schedule = &mut ctx.accounts.scheduled_payment;
+        schedule.owner = ctx.accounts.payer.key();
+        schedule.recipient = ctx.accounts.recipient.key();
+        schedule.interval_slots = interval_slots;
+        schedule.next_due_slot = Clock::get()?
+            .slot
+            .checked_add(interval_slots)
+            .ok_or(ErrorCode::MathOverflow)?;
+        schedule.bump = ctx.bumps.scheduled_payment;
+
+        let args = ArgBuilder::new()
+            .x25519_pubkey(pub_key)
+            .plaintext_u128(nonce)
+            .encrypted_bytes(encrypted_amount)
+            .build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![SchedulePaymentCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &CallbackAccountsBuilder::new()
+                    .writable(ctx.accounts.scheduled_payment.key())
+                    .build(),
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Callback storing the encrypted installment amount
+    #[arcium_callback(encrypted_ix = "schedule_payment")]
+    pub fn schedule_payment_callback(
+        ctx: Context<SchedulePaymentCallback>,
+        output: SignedComputationOutputs<SchedulePaymentOutput>,
+    ) -> Result<()> {
+        let result = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(SchedulePaymentOutput { amount, nonce }) => (amount, nonce),
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let schedule = &mut ctx.accounts.scheduled_payment;
+        schedule.amount_state = result.0;
+        schedule.nonce = result.1;
+
+        let clock = Clock::get()?;
+        emit!(PaymentScheduled {
+            owner: schedule.owner,
+            recipient: schedule.recipient,
+            interval_slots: schedule.interval_slots,
+            next_due_slot: schedule.next_due_slot,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Pay the next installment of a scheduled payment once it is due
+    /// Permissionless crank. The due slot advances when queued, so each
+    /// installment is attempted exactly once even if cranks race.
+    pub fn execute_scheduled_payment(
+        ctx: Context<ExecuteScheduledPayment>,
+        computation_offset: u64,
+    ) -> Result<()> {
+        ctx.accounts.config.ensure_open(0)?;
+        ensure_computation_fee(&ctx.accounts.payer, 0)?;
+
+        let current_slot = Clock::get()?.slot;
+        let schedule = &mut ctx.accounts.scheduled_payment;
+        require!(current_slot >= schedule.next_due_slot, ErrorCode::PaymentNotDue);
+        schedule.next_due_slot = schedule
+            .next_due_slot
+            .checked_add(schedule.interval_slots)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        let owner_balance = &ctx.accounts.owner_balance;
+        let recipient_balance = &ctx.accounts.recipient_balance;
+
+        let args = ArgBuilder::new()
+            .plaintext_u128(schedule.nonce)
+            .encrypted_bytes(schedule.amount_state)
+            .plaintext_u128(owner_balance.nonce)
+            .encrypted_bytes(owner_balance.balance_state)
+            .plaintext_u128(owner_balance.min_balance_nonce)
+            .encrypted_bytes(owner_balance.min_balance_state)
+            .plaintext_u128(recipient_balance.nonce)
+            .encrypted_bytes(recipient_balance.balance_state)
+            .build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![ExecuteScheduledPaymentCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &CallbackAccountsBuilder::new()
+                    .readonly(ctx.accounts.scheduled_payment.key())
+                    .writable(ctx.accounts.owner_balance.key())
+                    .writable(ctx.accounts.recipient_balance.key())
+                    .build(),
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Callback after a scheduled installment
+    /// An underfunded installment is skipped rather than retried
+    #[arcium_callback(encrypted_ix = "execute_scheduled_payment")]
+    pub fn execute_scheduled_payment_callback(
+        ctx: Context<ExecuteScheduledPaymentCallback>,
+        output: SignedComputationOutputs<ExecuteScheduledPaymentOutput>,
+    ) -> Result<()> {
+        let success = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(ExecuteScheduledPaymentOutput {
+                owner_balance,
+                owner_nonce,
+                recipient_balance,
+                recipient_nonce,
+                success,
+            }) => {
+                if success {
+                    ctx.accounts.owner_balance.write_balance(owner_balance, owner_nonce);
+                    ctx.accounts
+                        .recipient_balance
+                        .write_balance(recipient_balance, recipient_nonce);
+                }
+                success
+            }
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let clock = Clock::get()?;
+        emit!(ScheduledPaymentExecuted {
+            owner: ctx.accounts.scheduled_payment.owner,
+            recipient: ctx.accounts.scheduled_payment.recipient,
+            success,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Stop a scheduled payment and reclaim its rent
+    pub fn cancel_scheduled_payment(_ctx: Context<CancelScheduledPayment>) -> Result<()> {
+        Ok(())
+    }
+
+    /// Lock an encrypted amount of the payer's balance for `beneficiary`
+    /// The grant vests linearly over `duration_slots` from now and is paid out
+    /// to the beneficiary's private balance by `claim_vested`. The amount
+    /// leaves the grantor's balance in MPC and is never revealed.
+    pub fn create_vesting(
+        ctx: Context<CreateVesting>,
+        computation_offset: u64,
+        encrypted_amount: [u8; 64], // Encrypted grant amount
+        duration_slots: u64,
+        pub_key: [u8; 32],
+        nonce: u128,
+    ) -> Result<()> {
+        ctx.accounts.config.ensure_open(0)?;
+        ensure_computation_fee(&ctx.accounts.payer, 0)?;
+        require!(duration_slots > 0, ErrorCode::InvalidSchedule);
+
+        let escrow = &mut ctx.accounts.vesting_escrow;
+        escrow.grantor = ctx.accounts.payer.key();
+        escrow.beneficiary = ctx.accounts.beneficiary.key();
+        escrow.bump = ctx.bumps.vesting_escrow;
+        escrow.start_slot = Clock::get()?.slot;
+        escrow.duration_slots = duration_slots;
+
+        let grantor_balance = &ctx.accounts.grantor_balance;
+        let args = ArgBuilder::new()
+            .x25519_pubkey(pub_key)
+            .plaintext_u128(nonce)
+            .encrypted_bytes(encrypted_amount)
+            .plaintext_u128(grantor_balance.nonce)
+            .encrypted_bytes(grantor_balance.balance_state)
+            .plaintext_u128(grantor_balance.min_balance_nonce)
+            .encrypted_bytes(grantor_balance.min_balance_state)
+            .build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![CreateVestingCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &CallbackAccountsBuilder::new()
+                    .writable(ctx.accounts.grantor_balance.key())
+                    .writable(ctx.accounts.vesting_escrow.key())
+                    .build(),
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Callback funding the escrow from the grantor's balance
+    /// An underfunded grant fails here, leaving the escrow unfunded for the
+    /// grantor to close with `close_vesting`
+    #[arcium_callback(encrypted_ix = "create_vesting")]
+    pub fn create_vesting_callback(
+        ctx: Context<CreateVestingCallback>,
+        output: SignedComputationOutputs<CreateVestingOutput>,
+    ) -> Result<()> {
+        match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(CreateVestingOutput {
+                grantor_balance,
+                grantor_nonce,
+                vesting_state,
+                success,
+            }) => {
+                if !success {
+                    return Err(ErrorCode::InsufficientBalance.into());
+                }
+                ctx.accounts.grantor_balance.write_balance(grantor_balance, grantor_nonce);
+                let escrow = &mut ctx.accounts.vesting_escrow;
+                escrow.encrypted_state = vesting_state.ciphertexts;
+                escrow.state_nonce = vesting_state.nonce;
+            }
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let escrow = &ctx.accounts.vesting_escrow;
+        let clock = Clock::get()?;
+        emit!(VestingCreated {
+            grantor: escrow.grantor,
+            beneficiary: escrow.beneficiary,
+            start_slot: escrow.start_slot,
+            duration_slots: escrow.duration_slots,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Release whatever has vested so far to the beneficiary's private balance
+    /// Permissionless crank; the vested amount is computed in MPC from the
+    /// slots elapsed when queued, so neither it nor the grant is revealed
+    pub fn claim_vested(ctx: Context<ClaimVested>, computation_offset: u64) -> Result<()> {
+        ctx.accounts.config.ensure_open(0)?;
+        ensure_computation_fee(&ctx.accounts.payer, 0)?;
+
+        let current_slot = Clock::get()?.slot;
+        let escrow = &mut ctx.accounts.vesting_escrow;
+        require!(escrow.is_funded(), ErrorCode::VestingNotFunded);
+        escrow.last_claim_slot = current_slot;
+
+        let beneficiary_balance = &ctx.accounts.beneficiary_balance;
+        let args = ArgBuilder::new()
+            .plaintext_u128(escrow.state_nonce)
+            .account(
+                escrow.key(),
+                VestingEscrow::STATE_OFFSET,
+                VestingEscrow::STATE_LEN,
+            )
+            .plaintext_u128(beneficiary_balance.nonce)
+            .encrypted_bytes(beneficiary_balance.balance_state)
+            .plaintext_u64(current_slot.saturating_sub(escrow.start_slot))
+            .plaintext_u64(escrow.duration_slots)
+            .build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![ClaimVestedCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &CallbackAccountsBuilder::new()
+                    .writable(ctx.accounts.vesting_escrow.key())
+                    .writable(ctx.accounts.beneficiary_balance.key())
+                    .build(),
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Callback crediting the newly vested amount
+    /// A claim queued after the schedule ended releases the rest of the grant
+    #[arcium_callback(encrypted_ix = "claim_vested")]
+    pub fn claim_vested_callback(
+        ctx: Context<ClaimVestedCallback>,
+        output: SignedComputationOutputs<ClaimVestedOutput>,
+    ) -> Result<()> {
+        let success = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(ClaimVestedOutput {
+                vesting_state,
+                beneficiary_balance,
+                beneficiary_nonce,
+                success,
+            }) => {
+                if success {
+                    let escrow = &mut ctx.accounts.vesting_escrow;
+                    escrow.encrypted_state = vesting_state.ciphertexts;
+                    escrow.state_nonce = vesting_state.nonce;
+                    escrow.settle_claim();
+                    ctx.accounts
+                        .beneficiary_balance
+                        .write_balance(beneficiary_balance, beneficiary_nonce);
+                }
+                success
+            }
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let escrow = &ctx.accounts.vesting_escrow;
+        let clock = Clock::get()?;
+        emit!(VestedClaimed {
+            grantor: escrow.grantor,
+            beneficiary: escrow.beneficiary,
+            success,
+            fully_claimed: escrow.fully_claimed,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Close a vesting escrow and return its rent to the grantor
+    /// Only an escrow that was never funded, or whose grant has been claimed
+    /// in full, can be closed
+    pub fn close_vesting(ctx: Context<CloseVesting>) -> Result<()> {
+        ctx.accounts.vesting_escrow.ensure_closable()
+    }
+
+    /// Transfer an encrypted amount between two private balances
+    /// Neither the amount nor either resulting balance is revealed
+    pub fn private_transfer(
+        ctx: Context<PrivateTransfer>,
+        computation_offset: u64,
+        encrypted_amount: [u8; 64], // Encrypted transfer amount
+        pub_key: [u8; 32],
+        nonce: u128,
+    ) -> Result<()> {
+        ctx.accounts.config.ensure_open(0)?;
+        ensure_computation_fee(&ctx.accounts.payer, 0)?;
+
+        let sender_balance = &ctx.accounts.sender_balance;
+        let recipient_balance = &ctx.accounts.recipient_balance;
+
+        let args = ArgBuilder::new()
+            .x25519_pubkey(pub_key)
+            .plaintext_u128(nonce)
+            .encrypted_bytes(encrypted_amount)
+            .plaintext_u128(sender_balance.nonce)
+            .encrypted_bytes(sender_balance.balance_state)
+            .plaintext_u128(sender_balance.min_balance_nonce)
+            .encrypted_bytes(sender_balance.min_balance_state)
+            .plaintext_u128(recipient_balance.nonce)
+            .encrypted_bytes(recipient_balance.balance_state)
+            .build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![TransferCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &CallbackAccountsBuilder::new()
+                    .writable(ctx.accounts.sender_balance.key())
+                    .writable(ctx.accounts.recipient_balance.key())
+                    .build(),
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Callback after a private transfer
+    /// Both balances are written together, and only when the transfer succeeded
+    #[arcium_callback(encrypted_ix = "transfer")]
+    pub fn transfer_callback(
+        ctx: Context<TransferCallback>,
+        output: SignedComputationOutputs<TransferOutput>,
+    ) -> Result<()> {
+        match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(TransferOutput {
+                sender_balance,
+                sender_nonce,
+                recipient_balance,
+                recipient_nonce,
+                success,
+            }) => apply_transfer(
+                &mut ctx.accounts.sender_balance,
+                &mut ctx.accounts.recipient_balance,
+                (sender_balance, sender_nonce),
+                (recipient_balance, recipient_nonce),
+                success,
+            )?,
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let clock = Clock::get()?;
+        emit!(PrivateTransferCompleted {
+            sender: ctx.accounts.sender_balance.owner,
+            recipient: ctx.accounts.recipient_balance.owner,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Pay BATCH_RECIPIENTS private balances from the payer's balance at once
+    /// Each amount stays encrypted; the transfer applies to every recipient
+    /// or, if the payer can't cover the total, to none
+    pub fn batch_transfer(
+        ctx: Context<BatchTransfer>,
+        computation_offset: u64,
+        encrypted_amounts: [[u8; 64]; BATCH_RECIPIENTS], // Encrypted amount per recipient
+        pub_key: [u8; 32],
+        nonce: u128,
+    ) -> Result<()> {
+        ctx.accounts.config.ensure_open(0)?;
+        ensure_computation_fee(&ctx.accounts.payer, 0)?;
+
+        let mut args = ArgBuilder::new()
+            .x25519_pubkey(pub_key)
+            .plaintext_u128(nonce);
+        for amount in encrypted_amounts {
+            args = args.encrypted_bytes(amount);
+        }
+        let sender_balance = &ctx.accounts.sender_balance;
+        args = args
+            .plaintext_u128(sender_balance.nonce)
+            .encrypted_bytes(sender_balance.balance_state)
+            .plaintext_u128(sender_balance.min_balance_nonce)
+            .encrypted_bytes(sender_balance.min_balance_state);
+        let recipients = [
+            &ctx.accounts.recipient_0,
+            &ctx.accounts.recipient_1,
+            &ctx.accounts.recipient_2,
+        ];
+        validate_distinct_balances(
+            (sender_balance.key(), &**sender_balance),
+            &recipients.map(|recipient| (recipient.key(), &**recipient)),
+        )?;
+        for recipient in recipients {
+            args = args
+                .plaintext_u128(recipient.nonce)
+                .encrypted_bytes(recipient.balance_state);
+        }
+        let args = args.build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![BatchTransferCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &CallbackAccountsBuilder::new()
+                    .writable(ctx.accounts.sender_balance.key())
+                    .writable(ctx.accounts.recipient_0.key())
+                    .writable(ctx.accounts.recipient_1.key())
+                    .writable(ctx.accounts.recipient_2.key())
+                    .build(),
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Callback after a batch transfer
+    /// Every balance is written together, and only when the batch succeeded
+    #[arcium_callback(encrypted_ix = "batch_transfer")]
+    pub fn batch_transfer_callback(
+        ctx: Context<BatchTransferCallback>,
+        output: SignedComputationOutputs<BatchTransferOutput>,
+    ) -> Result<()> {
+        match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(BatchTransferOutput {
+                sender_balance,
+                sender_nonce,
+                balance_0,
+                nonce_0,
+                balance_1,
+                nonce_1,
+                balance_2,
+                nonce_2,
+                success,
+            }) => {
+                if !success {
+                    return Err(ErrorCode::InsufficientBalance.into());
+                }
+                ctx.accounts.sender_balance.write_balance(sender_balance, sender_nonce);
+                ctx.accounts.recipient_0.write_balance(balance_0, nonce_0);
+                ctx.accounts.recipient_1.write_balance(balance_1, nonce_1);
+                ctx.accounts.recipient_2.write_balance(balance_2, nonce_2);
+            }
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let clock = Clock::get()?;
+        emit!(BatchTransferCompleted {
+            sender: ctx.accounts.sender_balance.owner,
+            recipients: [
+                ctx.accounts.recipient_0.owner,
+                ctx.accounts.recipient_1.owner,
+                ctx.accounts.recipient_2.owner,
+            ],
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Check whether the payer's balance covers an encrypted amount
+    /// A cheap pre-flight before `private_transfer`; nothing is written and
+    /// only the yes/no verdict is revealed
+    pub fn check_transfer_feasible(
+        ctx: Context<CheckTransferFeasible>,
+        computation_offset: u64,
+        encrypted_amount: [u8; 64], // Encrypted amount the payer intends to send
+        pub_key: [u8; 32],
+        nonce: u128,
+    ) -> Result<()> {
+        ctx.accounts.config.ensure_open(0)?;
+        ensure_computation_fee(&ctx.accounts.payer, 0)?;
+
+        let sender_balance = &ctx.accounts.sender_balance;
+
+        let args = ArgBuilder::new()
+            .x25519_pubkey(pub_key)
+            .plaintext_u128(nonce)
+            .encrypted_bytes(encrypted_amount)
+            .plaintext_u128(sender_balance.nonce)
+            .encrypted_bytes(sender_balance.balance_state)
+            .plaintext_u128(sender_balance.min_balance_nonce)
+            .encrypted_bytes(sender_balance.min_balance_state)
+            .build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![CheckTransferFeasibleCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &CallbackAccountsBuilder::new()
+                    .readonly(ctx.accounts.sender_balance.key())
+                    .build(),
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "check_transfer_feasible")]
+    pub fn check_transfer_feasible_callback(
+        ctx: Context<CheckTransferFeasibleCallback>,
+        output: SignedComputationOutputs<CheckTransferFeasibleOutput>,
+    ) -> Result<()> {
+        let feasible = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(CheckTransferFeasibleOutput { feasible }) => feasible,
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let clock = Clock::get()?;
+        emit!(TransferFeasibilityChecked {
+            owner: ctx.accounts.sender_balance.owner,
+            feasible,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Move an encrypted amount between two of the payer's own balances
+    /// Runs the same `transfer` circuit as `private_transfer`, so both
+    /// balances are updated together and only when `from_balance` covers it
+    pub fn rebalance_balances(
+        ctx: Context<RebalanceBalances>,
+        computation_offset: u64,
+        encrypted_amount: [u8; 64], // Encrypted amount to move
+        pub_key: [u8; 32],
+        nonce: u128,
+    ) -> Result<()> {
+        ctx.accounts.config.ensure_open(0)?;
+        ensure_computation_fee(&ctx.accounts.payer, 0)?;
+
+        let from_balance = &ctx.accounts.from_balance;
+        let to_balance = &ctx.accounts.to_balance;
+
+        let args = ArgBuilder::new()
+            .x25519_pubkey(pub_key)
+            .plaintext_u128(nonce)
+            .encrypted_bytes(encrypted_amount)
+            .plaintext_u128(from_balance.nonce)
+            .encrypted_bytes(from_balance.balance_state)
+            .plaintext_u128(from_balance.min_balance_nonce)
+            .encrypted_bytes(from_balance.min_balance_state)
+            .plaintext_u128(to_balance.nonce)
+            .encrypted_bytes(to_balance.balance_state)
+            .build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![TransferCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &CallbackAccountsBuilder::new()
+                    .writable(ctx.accounts.from_balance.key())
+                    .writable(ctx.accounts.to_balance.key())
+                    .build(),
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Merge three of the payer's balances into a destination balance
+    /// The sources are left holding an encrypted zero once the merge settles
+    pub fn merge_balances(
+        ctx: Context<MergeBalances>,
+        computation_offset: u64,
+    ) -> Result<()> {
+        ctx.accounts.config.ensure_open(0)?;
+        ensure_computation_fee(&ctx.accounts.payer, 0)?;
+
+        let destination = &ctx.accounts.destination_balance;
+        let sources = [
+            &ctx.accounts.first_balance,
+            &ctx.accounts.second_balance,
+            &ctx.accounts.third_balance,
+        ];
+        validate_merge(
+            &ctx.accounts.payer.key(),
+            (destination.key(), &**destination),
+            sources.map(|source| (source.key(), &**source)),
+        )?;
+
+        let mut builder = ArgBuilder::new()
+            .plaintext_u128(destination.nonce)
+            .encrypted_bytes(destination.balance_state);
+        for source in sources {
+            builder = builder
+                .plaintext_u128(source.nonce)
+                .encrypted_bytes(source.balance_state);
+        }
+        let args = builder.build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![MergeBalancesCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &CallbackAccountsBuilder::new()
+                    .writable(ctx.accounts.destination_balance.key())
+                    .writable(ctx.accounts.first_balance.key())
+                    .writable(ctx.accounts.second_balance.key())
+                    .writable(ctx.accounts.third_balance.key())
+                    .build(),
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Callback after balances are merged
+    #[arcium_callback(encrypted_ix = "merge_balances")]
+    pub fn merge_balances_callback(
+        ctx: Context<MergeBalancesCallback>,
+        output: SignedComputationOutputs<MergeBalancesOutput>,
+    ) -> Result<()> {
+        match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(MergeBalancesOutput {
+                destination_balance,
+                destination_nonce,
+                first_balance,
+                first_nonce,
+                second_balance,
+                second_nonce,
+                third_balance,
+                third_nonce,
+                success,
+            }) => {
+                if !success {
+                    return Err(ErrorCode::MathOverflow.into());
+                }
+                ctx.accounts
+                    .destination_balance
+                    .write_balance(destination_balance, destination_nonce);
+                ctx.accounts.first_balance.write_balance(first_balance, first_nonce);
+                ctx.accounts.second_balance.write_balance(second_balance, second_nonce);
+                ctx.accounts.third_balance.write_balance(third_balance, third_nonce);
+            }
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let clock = Clock::get()?;
+        emit!(BalancesMerged {
+            owner: ctx.accounts.destination_balance.owner,
+            destination: ctx.accounts.destination_balance.key(),
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Set a private minimum balance on one of the payer's balances
+    /// `private_transfer` and `rebalance_balances` fail rather than leave the
+    /// balance below it. The minimum itself is never revealed.
+    pub fn set_min_balance(
+        ctx: Context<SetMinBalance>,
+        computation_offset: u64,
+        encrypted_amount: [u8; 64], // Encrypted minimum balance
+        pub_key: [u8; 32],
+        nonce: u128,
+    ) -> Result<()> {
+        ctx.accounts.config.ensure_open(0)?;
+        ensure_computation_fee(&ctx.accounts.payer, 0)?;
+
+        let args = ArgBuilder::new()
+            .x25519_pubkey(pub_key)
+            .plaintext_u128(nonce)
+            .encrypted_bytes(encrypted_amount)
+            .build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![SetMinBalanceCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &CallbackAccountsBuilder::new()
+                    .writable(ctx.accounts.balance_account.key())
+                    .build(),
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Callback storing the encrypted minimum balance
+    #[arcium_callback(encrypted_ix = "set_min_balance")]
+    pub fn set_min_balance_callback(
+        ctx: Context<SetMinBalanceCallback>,
+        output: SignedComputationOutputs<SetMinBalanceOutput>,
+    ) -> Result<()> {
+        let result = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(SetMinBalanceOutput { min_balance, nonce }) => (min_balance, nonce),
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let balance_account = &mut ctx.accounts.balance_account;
+        balance_account.min_balance_state = result.0;
+        balance_account.min_balance_nonce = result.1;
+
+        let clock = Clock::get()?;
+        emit!(MinBalanceUpdated {
+            owner: balance_account.owner,
+            balance_account: balance_account.key(),
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Re-encrypt the payer's balance and minimum balance under fresh nonces
+    /// Outputs owned by the MXE are always encrypted under a nonce the
+    /// cluster picks, so the client supplies none. The old ciphertexts are
+    /// only decrypted inside MPC.
+    pub fn rotate_nonce(ctx: Context<RotateNonce>, computation_offset: u64) -> Result<()> {
+        ctx.accounts.config.ensure_open(0)?;
+        ensure_computation_fee(&ctx.accounts.payer, 0)?;
+        ctx.accounts.balance_account.ensure_created()?;
+
+        let balance_account = &ctx.accounts.balance_account;
+        let args = ArgBuilder::new()
+            .plaintext_u128(balance_account.nonce)
+            .encrypted_bytes(balance_account.balance_state)
+            .plaintext_u128(balance_account.min_balance_nonce)
+            .encrypted_bytes(balance_account.min_balance_state)
+            .build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![RotateBalanceNonceCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &CallbackAccountsBuilder::new()
+                    .writable(ctx.accounts.balance_account.key())
+                    .build(),
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Callback storing the re-encrypted balance and minimum balance
+    #[arcium_callback(encrypted_ix = "rotate_balance_nonce")]
+    pub fn rotate_balance_nonce_callback(
+        ctx: Context<RotateBalanceNonceCallback>,
+        output: SignedComputationOutputs<RotateBalanceNonceOutput>,
+    ) -> Result<()> {
+        let balance_account = &mut ctx.accounts.balance_account;
+        match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(RotateBalanceNonceOutput {
+                balance,
+                balance_nonce,
+                min_balance,
+                min_balance_nonce,
+            }) => {
+                balance_account.write_balance(balance, balance_nonce);
+                balance_account.min_balance_state = min_balance;
+                balance_account.min_balance_nonce = min_balance_nonce;
+            }
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let clock = Clock::get()?;
+        emit!(BalanceNonceRotated {
+            owner: balance_account.owner,
+            balance_account: balance_account.key(),
+            nonce: balance_account.nonce,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Report whether the MXE has a cluster assigned, and which one
+    /// Read-only; clients simulate it and read the `ClusterStatus` return
+    /// data to explain a missing cluster before anything fails with
+    /// `ClusterNotSet`. `cluster` is the default key while unassigned.
+    pub fn cluster_info(ctx: Context<ClusterInfo>) -> Result<ClusterStatus> {
+        let mxe_account = &ctx.accounts.mxe_account;
+        let cluster = if mxe_account.cluster.is_none() {
+            None
+        } else {
+            Some(derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))
+        };
+        Ok(ClusterStatus::new(cluster))
+    }
+
+    /// Cap how much a balance can withdraw per SPENDING_WINDOW_SLOTS
+    /// Zero removes the limit. Amounts already withdrawn in the current window
+    /// still count against the new limit.
+    pub fn set_spending_limit(ctx: Context<SetSpendingLimit>, daily_limit: u64) -> Result<()> {
+        let balance_account = &mut ctx.accounts.balance_account;
+        balance_account.daily_limit = daily_limit;
+
+        let clock = Clock::get()?;
+        emit!(SpendingLimitUpdated {
+            owner: balance_account.owner,
+            balance_account: balance_account.key(),
+            daily_limit,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Link dark pool funding to the dark pool program and its MXE
+    /// `dark_pool_pubkey` is the key handoffs are encrypted to and
+    /// `mxe_pubkey` this program's own MXE key, which the dark pool needs to
+    /// decrypt them. A zero `dark_pool_pubkey` disables `fund_dark_pool_order`.
+    pub fn set_dark_pool_link(
+        ctx: Context<UpdateConfig>,
+        dark_pool_program: Pubkey,
+        dark_pool_pubkey: [u8; 32],
+        mxe_pubkey: [u8; 32],
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.dark_pool_program = dark_pool_program;
+        config.dark_pool_pubkey = dark_pool_pubkey;
+        config.mxe_pubkey = mxe_pubkey;
+        Ok(())
+    }
+
+    /// Debit a token balance to fund a dark pool order
+    /// `escrow_amount` leaves the balance and moves into the dark pool's
+    /// escrow once the order rests, so it is public like any order escrow.
+    /// The encrypted amount, which sizes the order and must fit within the
+    /// escrow, is re-encrypted to the dark pool MXE and parked in a
+    /// `DarkPoolFunding` account for `add_funded_order` to consume.
+    pub fn fund_dark_pool_order(
+        ctx: Context<FundDarkPoolOrder>,
+        computation_offset: u64,
+        encrypted_amount: [u8; 64], // Encrypted order amount, at most `escrow_amount`
+        escrow_amount: u64,         // Public amount debited and escrowed by the dark pool
+        pub_key: [u8; 32],
+        nonce: u128,
+        handoff_nonce: u128, // Nonce the handoff is encrypted under
+    ) -> Result<()> {
+        ctx.accounts.config.ensure_open(PAUSE_WITHDRAWALS)?;
+        ensure_computation_fee(&ctx.accounts.payer, 0)?;
+        require!(
+            ctx.accounts.config.dark_pool_pubkey != [0u8; 32],
+            ErrorCode::DarkPoolFundingDisabled
+        );
+        // Dark pool books trade SPL mints, and only token vaults can pay one
+        require!(
+            ctx.accounts.balance_account.mint != Pubkey::default(),
+            ErrorCode::MintMismatch
+        );
+        require!(escrow_amount > 0, ErrorCode::WithdrawFailed);
+        let current_slot = Clock::get()?.slot;
+        ctx.accounts.balance_account.record_spend(escrow_amount, current_slot)?;
+
+        let funding = &mut ctx.accounts.funding;
+        funding.owner = ctx.accounts.payer.key();
+        funding.mint = ctx.accounts.balance_account.mint;
+        funding.mxe_pubkey = ctx.accounts.config.mxe_pubkey;
+        funding.encrypted_amount = [0u8; 64];
+        funding.nonce = 0;
+        funding.escrow_amount = escrow_amount;
+        funding.queued_slot = current_slot;
+        funding.ready = false;
+        funding.bump = ctx.bumps.funding;
+
+        let balance_account = &ctx.accounts.balance_account;
+        let args = ArgBuilder::new()
+            .x25519_pubkey(pub_key)
+            .plaintext_u128(nonce)
+            .encrypted_bytes(encrypted_amount)
+            .plaintext_u128(balance_account.nonce)
+            .encrypted_bytes(balance_account.balance_state)
+            .plaintext_u128(balance_account.min_balance_nonce)
+            .encrypted_bytes(balance_account.min_balance_state)
+            .x25519_pubkey(ctx.accounts.config.dark_pool_pubkey)
+            .plaintext_u128(handoff_nonce)
+            .plaintext_u64(escrow_amount)
+            .build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![ExportToDarkPoolCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &CallbackAccountsBuilder::new()
+                    .writable(ctx.accounts.balance_account.key())
+                    .writable(ctx.accounts.funding.key())
+                    .build(),
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Callback after a dark pool funding debit
+    /// The balance and the handoff are written together, only on success
+    #[arcium_callback(encrypted_ix = "export_to_dark_pool")]
+    pub fn export_to_dark_pool_callback(
+        ctx: Context<ExportToDarkPoolCallback>,
+        output: SignedComputationOutputs<ExportToDarkPoolOutput>,
+    ) -> Result<()> {
+        match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(ExportToDarkPoolOutput {
+                new_balance,
+                nonce,
+                encrypted_amount,
+                amount_nonce,
+                success,
+            }) => {
+                if !success {
+                    return Err(ErrorCode::InsufficientBalance.into());
+                }
+                ctx.accounts.balance_account.write_balance(new_balance, nonce);
+                let funding = &mut ctx.accounts.funding;
+                funding.encrypted_amount = encrypted_amount;
+                funding.nonce = amount_nonce;
+                funding.ready = true;
+            }
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let clock = Clock::get()?;
+        emit!(DarkPoolOrderFunded {
+            owner: ctx.accounts.funding.owner,
+            funding: ctx.accounts.funding.key(),
+            mint: ctx.accounts.funding.mint,
+            escrow_amount: ctx.accounts.funding.escrow_amount,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Hand a ready funding over to the dark pool, closing it to its owner
+    /// Pays the funding's escrow from the mint's vault into `destination`,
+    /// the dark pool's escrow vault. Only the dark pool's `funding_authority`
+    /// PDA can sign, which it does from `add_funded_order_callback` once the
+    /// order is on the book, so each handoff funds exactly one order.
+    pub fn consume_dark_pool_funding(ctx: Context<ConsumeDarkPoolFunding>) -> Result<()> {
+        let funding = &ctx.accounts.funding;
+        let amount = funding.escrow_amount;
+        let bump = [ctx.bumps.token_vault];
+        let signer_seeds: &[&[&[u8]]] = &[&[b"token_vault", funding.mint.as_ref(), &bump]];
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.token_vault.to_account_info(),
+            to: ctx.accounts.destination.to_account_info(),
+            authority: ctx.accounts.token_vault.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        );
+        token::transfer(cpi_ctx, amount)?;
+
+        let clock = Clock::get()?;
+        emit!(DarkPoolFundingConsumed {
+            owner: ctx.accounts.owner.key(),
+            funding: ctx.accounts.funding.key(),
+            amount,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Credit a ready funding that no order consumed back to its balance
+    /// For a funded order the dark pool rejected, or one the owner no longer
+    /// wants to place. The funding closes here, so an `add_funded_order`
+    /// still in flight against it fails in its callback instead of resting
+    /// unbacked. The escrow never left the vault, so it is credited through
+    /// the `deposit` circuit with a `PendingDeposit` record, like
+    /// `deposit_token`.
+    pub fn recredit_dark_pool_funding(
+        ctx: Context<RecreditDarkPoolFunding>,
+        computation_offset: u64,
+    ) -> Result<()> {
+        ctx.accounts.config.ensure_open(0)?;
+        ensure_computation_fee(&ctx.accounts.payer, 0)?;
+
+        let amount = ctx.accounts.funding.escrow_amount;
+        let pending_deposit = &mut ctx.accounts.pending_deposit;
+        pending_deposit.owner = ctx.accounts.payer.key();
+        pending_deposit.balance_account = ctx.accounts.balance_account.key();
+        pending_deposit.computation_offset = computation_offset;
+        pending_deposit.amount = amount;
+        pending_deposit.queued_slot = Clock::get()?.slot;
+        pending_deposit.bump = ctx.bumps.pending_deposit;
+
+        let balance_account = &ctx.accounts.balance_account;
+        let args = ArgBuilder::new()
+            .plaintext_u64(amount)
+            .plaintext_u128(balance_account.nonce)
+            .encrypted_bytes(balance_account.balance_state)
+            .build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![DepositCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &CallbackAccountsBuilder::new()
+                    .writable(ctx.accounts.balance_account.key())
+                    .writable(ctx.accounts.pending_deposit.key())
+                    .writable(ctx.accounts.payer.key())
+                    .build(),
+            )?],
+            1,
+            0,
+        )?;
+
+        let clock = Clock::get()?;
+        emit!(DarkPoolFundingRecredited {
+            owner: ctx.accounts.payer.key(),
+            funding: ctx.accounts.funding.key(),
+            amount,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Close a funding whose callback never landed, refunding its rent
+    /// Nothing was debited, since the balance is only written together with
+    /// the handoff; a ready funding goes through `recredit_dark_pool_funding`
+    /// instead. Open once COMPUTATION_TIMEOUT_SLOTS have passed.
+    pub fn close_dark_pool_funding(ctx: Context<CloseDarkPoolFunding>) -> Result<()> {
+        ctx.accounts.funding.ensure_stalled(Clock::get()?.slot)
+    }
+}
+
+// ============ Account Structures ============
+
+#[account]
+pub struct PrivateBalanceAccount {
+    pub owner: Pubkey,
+    pub bump: u8,
+    pub label: u8, // 0 for the primary balance
+    pub balance_state: [u8; 64], // Encrypted balance
+    pub nonce: u128,
+    pub last_accrual_slot: u64,
+    pub min_balance_state: [u8; 64], // Encrypted minimum that transfers out must leave
+    pub min_balance_nonce: u128,
+    pub mint: Pubkey, // SPL mint the balance tracks, Pubkey::default() for SOL
+    pub daily_limit: u64, // Max withdrawn per SPENDING_WINDOW_SLOTS, 0 for no limit
+    pub spent_today: u64,
+    pub limit_reset_slot: u64, // Slot the current spending window ends
+}
+
+impl PrivateBalanceAccount {
+    pub const SIZE: usize = 8 + 32 + 1 + 1 + 64 + 16 + 8 + 64 + 16 + 32 + 8 + 8 + 8;
+
+    /// Store a freshly encrypted balance returned by a computation
+    pub fn write_balance(&mut self, balance_state: [u8; 64], nonce: u128) {
+        self.balance_state = balance_state;
+        self.nonce = nonce;
+    }
+
+    /// Whether the creation callback has written the encrypted zero balance
+    pub fn is_created(&self) -> bool {
+        self.balance_state != [0u8; 64]
+    }
+
+    /// Fail until the creation callback has written the balance
+    pub fn ensure_created(&self) -> Result<()> {
+        require!(self.is_created(), ErrorCode::BalanceNotCreated);
+        Ok(())
+    }
+
+    /// Fail unless the creation computation, queued at `last_accrual_slot`,
+    /// never wrote the balance and has timed out
+    pub fn ensure_creation_stalled(&self, current_slot: u64) -> Result<()> {
+        require!(!self.is_created(), ErrorCode::BalanceAlreadyCreated);
+        ensure_timed_out(self.last_accrual_slot, current_slot)
+    }
+
+    /// Count a withdrawal of `amount` against the spending limit
+    /// Starts a fresh window once `limit_reset_slot` has passed; a zero limit
+    /// disables the check
+    pub fn record_spend(&mut self, amount: u64, current_slot: u64) -> Result<()> {
+        if self.daily_limit == 0 {
+            return Ok(());
+        }
+        if current_slot >= self.limit_reset_slot {
+            self.spent_today = 0;
+            self.limit_reset_slot = current_slot.saturating_add(SPENDING_WINDOW_SLOTS);
+        }
+        let spent = self
+            .spent_today
+            .checked_add(amount)
+            .ok_or(ErrorCode::SpendingLimitExceeded)?;
+        require!(spent <= self.daily_limit, ErrorCode::SpendingLimitExceeded);
+        self.spent_today = spent;
+        Ok(())
+    }
+}
+
+/// Number of encrypted words in the mixing pool commitment set
+/// (secret and used flag for each slot)
+pub const MIXING_POOL_STATE_WORDS: usize = 2 * 16;
+
+#[account]
+pub struct MixingPool {
+    pub denomination: u64,
+    pub bump: u8,
+    pub deposit_count: u64,
+    pub withdraw_count: u64,
+    pub state_nonce: u128,
+    pub encrypted_state: [[u8; 32]; MIXING_POOL_STATE_WORDS],
+    pub pending: ComputationLock, // Held while a deposit or withdrawal is in flight
+}
+
+impl MixingPool {
+    pub const SIZE: usize = 8 + 1 + 8 + 8 + 16 + 32 * MIXING_POOL_STATE_WORDS + ComputationLock::SIZE;
+    /// Offset of `encrypted_state` in the account data, including the discriminator
+    pub const STATE_OFFSET: u32 = 8 + 8 + 1 + 8 + 8 + 16;
+    pub const STATE_LEN: u32 = 32 * MIXING_POOL_STATE_WORDS as u32;
+}
+
+#[account]
+pub struct ComplianceConfig {
+    pub authority: Pubkey,
+    pub auditor_pubkey: [u8; 32], // x25519 key disclosures are encrypted to
+    pub bump: u8,
+}
+
+impl ComplianceConfig {
+    pub const SIZE: usize = 8 + 32 + 32 + 1;
+}
+
+#[account]
+pub struct PrivateAllowance {
+    pub owner: Pubkey,
+    pub delegate: Pubkey,
+    pub bump: u8,
+    pub allowance_state: [u8; 64], // Encrypted remaining allowance
+    pub nonce: u128,
+}
+
+impl PrivateAllowance {
+    pub const SIZE: usize = 32 + 32 + 1 + 64 + 16;
+
+    /// Whether `owner` may grant `delegate` an allowance
+    /// A self-allowance would make the spend's source and destination the
+    /// same balance account, and the callback's second write would credit
+    /// the spend without debiting it
+    pub fn valid_delegate(owner: &Pubkey, delegate: &Pubkey) -> bool {
+        owner != delegate
+    }
+}
+
+/// Fixed-point scale of interest rates and factors (1e12 = 1.0)
+pub const INTEREST_SCALE: u128 = 1_000_000_000_000;
+
+#[account]
+pub struct PayConfig {
+    pub authority: Pubkey,
+    pub interest_rate_per_slot: u64, // Scaled by INTEREST_SCALE
+    pub bump: u8,
+    pub paused_ops: u8, // Bitmask of PAUSE_* operations
+    pub paused: bool,   // Blocks every computation, set with `set_paused`
+    pub dark_pool_program: Pubkey,  // Program allowed to consume `DarkPoolFunding`
+    pub dark_pool_pubkey: [u8; 32], // Dark pool MXE x25519 key, zero disables funding orders
+    pub mxe_pubkey: [u8; 32],       // This program's MXE x25519 key, for the dark pool to decrypt against
+}
+
+impl PayConfig {
+    pub const SIZE: usize = 32 + 8 + 1 + 1 + 1 + 32 + 32 + 32;
+
+    /// Fail if the program is paused, or if any of the PAUSE_* bits in `ops`
+    /// is paused; pass 0 for an operation no `paused_ops` bit covers
+    pub fn ensure_open(&self, ops: u8) -> Result<()> {
+        require!(!self.paused, ErrorCode::ProgramPaused);
+        require!(self.paused_ops & ops == 0, ErrorCode::OperationPaused);
+        Ok(())
+    }
+}
+
+/// `paused_ops` bit blocking deposits into private balances and mixing pools
+pub const PAUSE_DEPOSITS: u8 = 1 << 4;
+/// `paused_ops` bit blocking withdrawals
+pub const PAUSE_WITHDRAWALS: u8 = 1 << 5;
+
+/// Compound factor `(1 + rate)^slots`, scaled by INTEREST_SCALE
+fn compound_factor(rate_per_slot: u64, slots: u64) -> Result<u128> {
+    let mut factor = INTEREST_SCALE;
+    let mut base = INTEREST_SCALE + rate_per_slot as u128;
+    let mut remaining = slots;
+    while remaining > 0 {
+        if remaining & 1 == 1 {
+            factor = factor.checked_mul(base).ok_or(ErrorCode::MathOverflow)? / INTEREST_SCALE;
+        }
+        remaining >>= 1;
+        if remaining > 0 {
+            base = base.checked_mul(base).ok_or(ErrorCode::MathOverflow)? / INTEREST_SCALE;
+        }
+    }
+    Ok(factor)
+}
+
+/// Write both balances returned by the `transfer` circuit, or neither when
+/// it reported the sender underfunded
+fn apply_transfer(
+    sender: &mut PrivateBalanceAccount,
+    recipient: &mut PrivateBalanceAccount,
+    (sender_state, sender_nonce): ([u8; 64], u128),
+    (recipient_state, recipient_nonce): ([u8; 64], u128),
+    success: bool,
+) -> Result<()> {
+    require!(success, ErrorCode::InsufficientBalance);
+    sender.write_balance(sender_state, sender_nonce);
+    recipient.write_balance(recipient_state, recipient_nonce);
+    Ok(())
+}
+
+/// Check that `merge_balances` folds three distinct balances of `owner`
+/// into a fourth of the same mint
+fn validate_merge(
+    owner: &Pubkey,
+    destination: (Pubkey, &PrivateBalanceAccount),
+    sources: [(Pubkey, &PrivateBalanceAccount); 3],
+) -> Result<()> {
+    require_keys_eq!(destination.1.owner, *owner, ErrorCode::InvalidAuthority);
+    for (_, source) in &sources {
+        require_keys_eq!(source.owner, *owner, ErrorCode::InvalidAuthority);
+    }
+    validate_distinct_balances(destination, &sources)
+}
+
+/// Check that `others` are distinct balances, none of them `anchor`, all on
+/// `anchor`'s mint
+fn validate_distinct_balances(
+    anchor: (Pubkey, &PrivateBalanceAccount),
+    others: &[(Pubkey, &PrivateBalanceAccount)],
+) -> Result<()> {
+    for (i, (key, balance)) in others.iter().enumerate() {
+        require!(
+            *key != anchor.0 && others[..i].iter().all(|(earlier, _)| earlier != key),
+            ErrorCode::InvalidRecipient
+        );
+        require_keys_eq!(balance.mint, anchor.1.mint, ErrorCode::MintMismatch);
+    }
+    Ok(())
+}
+
+/// Amounts `batch_deposit` credits to each of `balances`, and their total
+/// Each deposit must name the balance in its slot, a distinct canonical SOL
+/// balance; unused slots must repeat the first balance and get nothing.
+fn batch_deposit_amounts(
+    deposits: &[(Pubkey, u64)],
+    balances: [(Pubkey, &PrivateBalanceAccount); BATCH_DEPOSITS],
+) -> Result<([u64; BATCH_DEPOSITS], u64)> {
+    require!(
+        !deposits.is_empty() && deposits.len() <= BATCH_DEPOSITS,
+        ErrorCode::InvalidBatchSize
+    );
+    let mut amounts = [0u64; BATCH_DEPOSITS];
+    for (i, (key, balance)) in balances.iter().enumerate() {
+        let Some(&(target, amount)) = deposits.get(i) else {
+            require_keys_eq!(*key, balances[0].0, ErrorCode::InvalidRecipient);
+            continue;
+        };
+        require_keys_eq!(*key, target, ErrorCode::InvalidRecipient);
+        require!(
+            balances[..i].iter().all(|(earlier, _)| *earlier != target),
+            ErrorCode::InvalidRecipient
+        );
+        // Only an owner's canonical SOL balance can be credited
+        let expected = Pubkey::create_program_address(
+            &[b"balance", balance.owner.as_ref(), &[balance.bump]],
+            &crate::ID,
+        )
+        .map_err(|_| ErrorCode::InvalidRecipient)?;
+        require_keys_eq!(expected, target, ErrorCode::InvalidRecipient);
+        require!(balance.mint == Pubkey::default(), ErrorCode::MintMismatch);
+        require!(amount > 0, ErrorCode::ZeroDeposit);
+        amounts[i] = amount;
+    }
+
+    let total = amounts
+        .iter()
+        .try_fold(0u64, |total, &amount| total.checked_add(amount))
+        .ok_or(ErrorCode::MathOverflow)?;
+    Ok((amounts, total))
+}
+
+/// Number of labeled balances a deposit can be split across
+pub const SPLIT_TARGETS: usize = 4;
+
+/// Number of recipients paid by one `batch_transfer`
+pub const BATCH_RECIPIENTS: usize = 3;
+
+/// Number of balances one `batch_deposit` can credit, mirrors `BATCH_DEPOSITS`
+/// in encrypted-ixs
+pub const BATCH_DEPOSITS: usize = 4;
+
+/// Reject a payer that can't cover the computation fee plus `locked`
+/// lamports before the queue CPI fails opaquely
+fn ensure_computation_fee(payer: &Signer, locked: u64) -> Result<()> {
+    require!(
+        covers_computation_fee(payer.lamports(), locked),
+        ErrorCode::InsufficientComputationFee
+    );
+    Ok(())
+}
+
+#[account]
+pub struct ScheduledPayment {
+    pub owner: Pubkey,
+    pub recipient: Pubkey,
+    pub bump: u8,
+    pub amount_state: [u8; 64], // Encrypted installment amount
+    pub nonce: u128,
+    pub interval_slots: u64,
+    pub next_due_slot: u64,
+}
+
+impl ScheduledPayment {
+    pub const SIZE: usize = 32 + 32 + 1 + 64 + 16 + 8 + 8;
+}
+
+/// Number of encrypted words in a vesting grant (total and claimed)
+pub const VESTING_STATE_WORDS: usize = 2;
+
+/// Private balance locked for a beneficiary and released linearly
+#[account]
+pub struct VestingEscrow {
+    pub grantor: Pubkey,
+    pub beneficiary: Pubkey,
+    pub bump: u8,
+    pub start_slot: u64,
+    pub duration_slots: u64,
+    pub last_claim_slot: u64, // Slot the latest claim vested through
+    pub fully_claimed: bool,
+    pub state_nonce: u128,
+    pub encrypted_state: [[u8; 32]; VESTING_STATE_WORDS], // All zeros until funded
+}
+
+impl VestingEscrow {
+    pub const SIZE: usize = 32 + 32 + 1 + 8 + 8 + 8 + 1 + 16 + 32 * VESTING_STATE_WORDS;
+    /// Offset of `encrypted_state` in the account data, including the discriminator
+    pub const STATE_OFFSET: u32 = 8 + 32 + 32 + 1 + 8 + 8 + 8 + 1 + 16;
+    pub const STATE_LEN: u32 = 32 * VESTING_STATE_WORDS as u32;
+
+    /// Whether the creation callback has moved the grant into the escrow
+    pub fn is_funded(&self) -> bool {
+        self.encrypted_state != [[0u8; 32]; VESTING_STATE_WORDS]
+    }
+
+    /// Slot from which the whole grant is vested
+    pub fn end_slot(&self) -> u64 {
+        self.start_slot.saturating_add(self.duration_slots)
+    }
+
+    /// Mark the grant fully claimed once a claim vested through its end
+    pub fn settle_claim(&mut self) {
+        if self.last_claim_slot >= self.end_slot() {
+            self.fully_claimed = true;
+        }
+    }
+
+    /// Fail while a funded grant still has something left to claim
+    pub fn ensure_closable(&self) -> Result<()> {
+        require!(!self.is_funded() || self.fully_claimed, ErrorCode::VestingActive);
+        Ok(())
+    }
+}
+
+/// Program-owned account holding the lamports behind every SOL balance
+/// Deposits land here and withdrawals are paid from here, so value moved
+/// between balances stays withdrawable by whichever balance ends up with it.
+#[account]
+pub struct SolVault {
+    pub bump: u8,
+}
+
+impl SolVault {
+    pub const SIZE: usize = 1;
+}
+
+/// The one computation allowed to write an account's encrypted state next
+/// Set while a computation reading that state is in flight, so a second one
+/// queued against the same state can't overwrite the first's result.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct ComputationLock {
+    pub computation: Pubkey,
+    pub queued_slot: u64, // 0 when no computation holds the lock
+}
+
+impl ComputationLock {
+    pub const SIZE: usize = 32 + 8;
+
+    /// Claim the lock for `computation`
+    /// A claim whose callback never landed lapses after
+    /// COMPUTATION_TIMEOUT_SLOTS, and its late callback is then rejected.
+    pub fn lock(&mut self, computation: Pubkey, current_slot: u64) -> Result<()> {
+        require!(
+            self.queued_slot == 0
+                || current_slot >= self.queued_slot.saturating_add(COMPUTATION_TIMEOUT_SLOTS),
+            ErrorCode::ComputationInFlight
+        );
+        self.computation = computation;
+        self.queued_slot = current_slot;
+        Ok(())
+    }
+
+    /// Release the claim held by `computation`
+    pub fn unlock(&mut self, computation: Pubkey) -> Result<()> {
+        require_keys_eq!(computation, self.computation, ErrorCode::StaleComputation);
+        *self = Self::default();
+        Ok(())
+    }
+}
+
+/// Encrypted lamports set aside for interest, backed by the SOL vault
+#[account]
+pub struct InterestReserve {
+    pub reserve_state: [u8; 64], // Encrypted reserve, all zero until first funded
+    pub nonce: u128,
+    pub pending: ComputationLock, // Held while a funding or accrual is in flight
+    pub bump: u8,
+}
+
+impl InterestReserve {
+    pub const SIZE: usize = 64 + 16 + ComputationLock::SIZE + 1;
+}
+
+/// Whether an account holding `lamports` can pay out `amount` and stay at or
+/// above its `rent_floor`
+fn can_pay_above_rent(lamports: u64, rent_floor: u64, amount: u64) -> bool {
+    rent_floor.checked_add(amount).is_some_and(|needed| lamports >= needed)
+}
+
+/// Fail unless an account went from `lamports_before` to `lamports_after`
+/// by exactly the deposited `amount`
+fn ensure_deposit_received(lamports_before: u64, lamports_after: u64, amount: u64) -> Result<()> {
+    require!(
+        lamports_after.checked_sub(lamports_before) == Some(amount),
+        ErrorCode::DepositNotReceived
+    );
+    Ok(())
+}
+
+/// Vault holding every deposit of `mint`, and the bump it signs with
+/// Mirrors the `token_vault` seeds `deposit_token` transfers into
+fn token_vault_address(mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"token_vault", mint.as_ref()], &crate::ID)
+}
+
+/// Pay `amount` lamports out of the SOL vault, never dipping into its rent
+fn pay_from_sol_vault<'info>(
+    sol_vault: &Account<'info, SolVault>,
+    recipient: &impl ToAccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    let vault_info = sol_vault.to_account_info();
+    let rent_floor = Rent::get()?.minimum_balance(vault_info.data_len());
+    require!(
+        can_pay_above_rent(vault_info.lamports(), rent_floor, amount),
+        ErrorCode::InsufficientBalance
+    );
+    **vault_info.try_borrow_mut_lamports()? -= amount;
+    **recipient.to_account_info().try_borrow_mut_lamports()? += amount;
+    Ok(())
+}
+
+/// Fail unless a computation queued at `queued_slot` has had
+/// `COMPUTATION_TIMEOUT_SLOTS` to land, after which it counts as failed and
+/// what it held can be reclaimed
+fn ensure_timed_out(queued_slot: u64, current_slot: u64) -> Result<()> {
+    require!(
+        current_slot >= queued_slot.saturating_add(COMPUTATION_TIMEOUT_SLOTS),
+        ErrorCode::ComputationStillPending
+    );
+    Ok(())
+}
+
+/// A deposit whose computation has been queued but not settled, open from
+/// `deposit_funds` or `deposit_token` until its callback, or until
+/// `reclaim_failed_computation` or `reclaim_failed_token_deposit` refunds it
+#[account]
+pub struct PendingDeposit {
+    pub owner: Pubkey,
+    pub balance_account: Pubkey,
+    pub computation_offset: u64,
+    pub amount: u64, // Lamports or tokens moved into a vault before queuing
+    pub queued_slot: u64,
+    pub bump: u8,
+}
+
+impl PendingDeposit {
+    pub const SIZE: usize = 32 + 32 + 8 + 8 + 8 + 1;
+}
+
+/// A batch deposit whose total is in the SOL vault but whose computation
+/// hasn't settled, open from `batch_deposit` until its callback or
+/// `reclaim_batch_deposit`
+#[account]
+pub struct PendingBatchDeposit {
+    pub payer: Pubkey,
+    pub computation_offset: u64,
+    pub total: u64, // Lamports moved into the SOL vault before queuing
+    pub queued_slot: u64,
+    pub bump: u8,
+}
+
+impl PendingBatchDeposit {
+    pub const SIZE: usize = 32 + 8 + 8 + 8 + 1;
+}
+
+/// A mixing pool deposit whose denomination is held by the pool but whose
+/// note isn't in the commitment set yet
+/// Closed by the callback; `reclaim_pool_deposit` refunds it if the
+/// computation never settles.
+#[account]
+pub struct PendingPoolDeposit {
+    pub depositor: Pubkey,
+    pub mixing_pool: Pubkey,
+    pub computation: Pubkey, // Computation account holding the pool's lock
+    pub queued_slot: u64,
+    pub bump: u8,
+}
+
+impl PendingPoolDeposit {
+    pub const SIZE: usize = 32 + 32 + 32 + 8 + 1;
+}
+
+/// An amount debited from a private balance for a dark pool order
+/// Open from `fund_dark_pool_order` until the dark pool consumes it through
+/// `consume_dark_pool_funding` once the funded order rests, or the owner
+/// takes it back with `recredit_dark_pool_funding`. `encrypted_amount` is
+/// encrypted between `mxe_pubkey` and the dark pool MXE, so neither the owner
+/// nor anyone else can read it; `escrow_amount` is what the order escrows.
+#[account]
+pub struct DarkPoolFunding {
+    pub owner: Pubkey,
+    pub mint: Pubkey, // Mint of the debited balance, Pubkey::default() for SOL
+    pub mxe_pubkey: [u8; 32],
+    pub encrypted_amount: [u8; 64],
+    pub nonce: u128,
+    pub escrow_amount: u64, // Tokens debited, held in the mint's vault until consumed
+    pub queued_slot: u64,
+    pub ready: bool, // Set once the callback has debited the balance
+    pub bump: u8,
+}
+
+impl DarkPoolFunding {
+    pub const SIZE: usize = 32 + 32 + 32 + 64 + 16 + 8 + 8 + 1 + 1;
+
+    /// Fail unless the export computation never debited the balance and
+    /// has timed out
+    pub fn ensure_stalled(&self, current_slot: u64) -> Result<()> {
+        require!(!self.ready, ErrorCode::FundingReady);
+        ensure_timed_out(self.queued_slot, current_slot)
+    }
+}
+
+/// Slots after queuing before an unsettled deposit can be reclaimed,
+/// roughly ten minutes
+pub const COMPUTATION_TIMEOUT_SLOTS: u64 = 1_500;
+
+/// Length of a spending limit window, roughly a day
+pub const SPENDING_WINDOW_SLOTS: u64 = 216_000;
+
+/// Cluster assignment returned by `cluster_info`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct ClusterStatus {
+    pub assigned: bool,
+    pub cluster: Pubkey, // Cluster account, Pubkey::default() while unassigned
+}
+
+impl ClusterStatus {
+    /// Status for the MXE's cluster account, `None` while unassigned
+    pub fn new(cluster: Option<Pubkey>) -> Self {
+        Self {
+            assigned: cluster.is_some(),
+            cluster: cluster.unwrap_or_default(),
+        }
+    }
+}
+
+// ============ Instruction Contexts ============
+
+#[queue_computation_accounts("init_balance", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct CreateBalanceAccount<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, PayConfig>,
+    
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + PrivateBalanceAccount::SIZE,
+        seeds = [b"balance", payer.key().as_ref()],
+        bump,
+    )]
+    pub balance_account: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_INIT_BALANCE))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("init_balance")]
+#[derive(Accounts)]
+pub struct CreateBalanceCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_INIT_BALANCE))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut)]
+    pub balance_account: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[queue_computation_accounts("deposit", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct DepositFunds<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, PayConfig>,
+    
+    #[account(
+        mut,
+        seeds = [b"balance", payer.key().as_ref()],
+        bump = balance_account.bump,
+        constraint = balance_account.owner == payer.key() @ ErrorCode::InvalidAuthority,
+    )]
+    pub balance_account: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + PendingDeposit::SIZE,
+        seeds = [b"pending_deposit", balance_account.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub pending_deposit: Account<'info, PendingDeposit>,
+    
+    #[account(mut, seeds = [b"sol_vault"], bump = sol_vault.bump)]
+    pub sol_vault: Account<'info, SolVault>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_DEPOSIT))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("deposit")]
+#[derive(Accounts)]
+pub struct DepositCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_DEPOSIT))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut)]
+    pub balance_account: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(
+        mut,
+        close = owner,
+        has_one = owner,
+        has_one = balance_account,
+    )]
+    pub pending_deposit: Account<'info, PendingDeposit>,
+    
+    #[account(mut)]
+    /// CHECK: receives the pending deposit rent, checked against `pending_deposit.owner`
+    pub owner: UncheckedAccount<'info>,
+    
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CancelBalanceCreation<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    #[account(
+        mut,
+        close = owner,
+        has_one = owner @ ErrorCode::InvalidAuthority,
+    )]
+    pub balance_account: Account<'info, PrivateBalanceAccount>,
+}
+
+#[derive(Accounts)]
+pub struct ClusterInfo<'info> {
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+}
+
+#[derive(Accounts)]
+pub struct SetSpendingLimit<'info> {
+    pub owner: Signer<'info>,
+    
+    #[account(
+        mut,
+        has_one = owner @ ErrorCode::InvalidAuthority,
+    )]
+    pub balance_account: Account<'info, PrivateBalanceAccount>,
+}
+
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct ReclaimFailedComputation<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    #[account(mut)]
+    pub balance_account: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"pending_deposit", balance_account.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump = pending_deposit.bump,
+        has_one = owner,
+        has_one = balance_account,
+    )]
+    pub pending_deposit: Account<'info, PendingDeposit>,
+    
+    #[account(mut, seeds = [b"sol_vault"], bump = sol_vault.bump)]
+    pub sol_vault: Account<'info, SolVault>,
+}
+
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct ReclaimFailedTokenDeposit<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    #[account(constraint = balance_account.mint != Pubkey::default() @ ErrorCode::MintMismatch)]
+    pub balance_account: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"pending_deposit", balance_account.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump = pending_deposit.bump,
+        has_one = owner,
+        has_one = balance_account,
+    )]
+    pub pending_deposit: Account<'info, PendingDeposit>,
+    
+    #[account(
+        mut,
+        constraint = owner_token_account.mint == balance_account.mint @ ErrorCode::MintMismatch,
+        constraint = owner_token_account.owner == owner.key() @ ErrorCode::InvalidAuthority,
+    )]
+    pub owner_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"token_vault", balance_account.mint.as_ref()],
+        bump,
+    )]
+    pub token_vault: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct ReclaimBatchDeposit<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(
+        mut,
+        close = payer,
+        seeds = [b"pending_batch_deposit", payer.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump = pending_batch_deposit.bump,
+        has_one = payer,
+    )]
+    pub pending_batch_deposit: Account<'info, PendingBatchDeposit>,
+    
+    #[account(mut, seeds = [b"sol_vault"], bump = sol_vault.bump)]
+    pub sol_vault: Account<'info, SolVault>,
+}
+
+#[init_computation_definition_accounts("init_balance", payer)]
+#[derive(Accounts)]
+pub struct InitBalanceCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("deposit", payer)]
+#[derive(Accounts)]
+pub struct InitDepositCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("init_mixing_pool", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, denomination: u64)]
+pub struct InitMixingPool<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, PayConfig>,
+    
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + MixingPool::SIZE,
+        seeds = [b"mixing_pool", denomination.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub mixing_pool: Account<'info, MixingPool>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_INIT_MIXING_POOL))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("init_mixing_pool")]
+#[derive(Accounts)]
+pub struct InitMixingPoolCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_INIT_MIXING_POOL))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut)]
+    pub mixing_pool: Account<'info, MixingPool>,
+    
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[queue_computation_accounts("pool_deposit", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct PoolDeposit<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, PayConfig>,
+    
+    #[account(
+        mut,
+        seeds = [b"mixing_pool", mixing_pool.denomination.to_le_bytes().as_ref()],
+        bump = mixing_pool.bump,
+    )]
+    pub mixing_pool: Account<'info, MixingPool>,
+    
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + PendingPoolDeposit::SIZE,
+        seeds = [b"pending_pool_deposit", mixing_pool.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub pending_pool_deposit: Account<'info, PendingPoolDeposit>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_POOL_DEPOSIT))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("pool_deposit")]
+#[derive(Accounts)]
+pub struct PoolDepositCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_POOL_DEPOSIT))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut)]
+    pub mixing_pool: Account<'info, MixingPool>,
+    
+    #[account(
+        mut,
+        close = depositor,
+        has_one = depositor,
+        has_one = mixing_pool,
+    )]
+    pub pending_pool_deposit: Account<'info, PendingPoolDeposit>,
+    
+    #[account(mut)]
+    /// CHECK: receives the pending record rent and any refund, checked by `has_one`
+    pub depositor: UncheckedAccount<'info>,
+    
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ReclaimPoolDeposit<'info> {
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"mixing_pool", mixing_pool.denomination.to_le_bytes().as_ref()],
+        bump = mixing_pool.bump,
+    )]
+    pub mixing_pool: Account<'info, MixingPool>,
+    
+    #[account(
+        mut,
+        close = depositor,
+        has_one = depositor @ ErrorCode::InvalidAuthority,
+        has_one = mixing_pool,
+    )]
+    pub pending_pool_deposit: Account<'info, PendingPoolDeposit>,
+}
+
+#[queue_computation_accounts("pool_withdraw", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct PoolWithdraw<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, PayConfig>,
+    
+    #[account(
+        mut,
+        seeds = [b"mixing_pool", mixing_pool.denomination.to_le_bytes().as_ref()],
+        bump = mixing_pool.bump,
+    )]
+    pub mixing_pool: Account<'info, MixingPool>,
+    
+    #[account(mut)]
+    /// CHECK: Any account may receive the withdrawn denomination
+    pub recipient: UncheckedAccount<'info>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_POOL_WITHDRAW))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("pool_withdraw")]
+#[derive(Accounts)]
+pub struct PoolWithdrawCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_POOL_WITHDRAW))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut)]
+    pub mixing_pool: Account<'info, MixingPool>,
+    
+    #[account(mut)]
+    /// CHECK: recipient fixed at queue time
+    pub recipient: UncheckedAccount<'info>,
+    
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[init_computation_definition_accounts("init_mixing_pool", payer)]
+#[derive(Accounts)]
+pub struct InitMixingPoolCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("pool_deposit", payer)]
+#[derive(Accounts)]
+pub struct InitPoolDepositCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("pool_withdraw", payer)]
+#[derive(Accounts)]
+pub struct InitPoolWithdrawCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitComplianceConfig<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + ComplianceConfig::SIZE,
+        seeds = [b"compliance"],
+        bump,
+    )]
+    pub compliance_config: Account<'info, ComplianceConfig>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetAuditor<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"compliance"],
+        bump = compliance_config.bump,
+        has_one = authority @ ErrorCode::InvalidAuthority,
+    )]
+    pub compliance_config: Account<'info, ComplianceConfig>,
+}
+
+#[queue_computation_accounts("disclose_balance", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct DiscloseToAuditor<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, PayConfig>,
+    
+    #[account(
+        seeds = [b"balance", payer.key().as_ref()],
+        bump = balance_account.bump,
+        constraint = balance_account.owner == payer.key() @ ErrorCode::InvalidAuthority,
+    )]
+    pub balance_account: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(seeds = [b"compliance"], bump = compliance_config.bump)]
+    pub compliance_config: Account<'info, ComplianceConfig>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_DISCLOSE_BALANCE))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("disclose_balance")]
+#[derive(Accounts)]
+pub struct DiscloseBalanceCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_DISCLOSE_BALANCE))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    pub balance_account: Account<'info, PrivateBalanceAccount>,
+    
+    pub compliance_config: Account<'info, ComplianceConfig>,
+    
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[init_computation_definition_accounts("disclose_balance", payer)]
+#[derive(Accounts)]
+pub struct InitDiscloseBalanceCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("approve_allowance", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct ApprovePrivateAllowance<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, PayConfig>,
+    
+    /// CHECK: delegate allowed to spend from the payer's balance
+    #[account(
+        constraint = PrivateAllowance::valid_delegate(&payer.key(), &delegate.key())
+            @ ErrorCode::InvalidRecipient,
+    )]
+    pub delegate: UncheckedAccount<'info>,
+    
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + PrivateAllowance::SIZE,
+        seeds = [b"allowance", payer.key().as_ref(), delegate.key().as_ref()],
+        bump,
+    )]
+    pub allowance: Account<'info, PrivateAllowance>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_APPROVE_ALLOWANCE))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("approve_allowance")]
+#[derive(Accounts)]
+pub struct ApproveAllowanceCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_APPROVE_ALLOWANCE))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut)]
+    pub allowance: Account<'info, PrivateAllowance>,
+    
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[queue_computation_accounts("spend_allowance", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct SpendFromAllowance<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, PayConfig>,
+    
+    #[account(
+        mut,
+        seeds = [b"allowance", allowance.owner.as_ref(), payer.key().as_ref()],
+        bump = allowance.bump,
+        constraint = allowance.delegate == payer.key() @ ErrorCode::InvalidAuthority,
+    )]
+    pub allowance: Account<'info, PrivateAllowance>,
+    
+    #[account(
+        mut,
+        seeds = [b"balance", allowance.owner.as_ref()],
+        bump = owner_balance.bump,
+    )]
+    pub owner_balance: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"balance", payer.key().as_ref()],
+        bump = delegate_balance.bump,
+        constraint = delegate_balance.key() != owner_balance.key() @ ErrorCode::InvalidRecipient,
+    )]
+    pub delegate_balance: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_SPEND_ALLOWANCE))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("spend_allowance")]
+#[derive(Accounts)]
+pub struct SpendAllowanceCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_SPEND_ALLOWANCE))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut)]
+    pub allowance: Account<'info, PrivateAllowance>,
+    
+    #[account(mut)]
+    pub owner_balance: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(mut)]
+    pub delegate_balance: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[init_computation_definition_accounts("approve_allowance", payer)]
+#[derive(Accounts)]
+pub struct InitApproveAllowanceCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("spend_allowance", payer)]
+#[derive(Accounts)]
+pub struct InitSpendAllowanceCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitConfig<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + PayConfig::SIZE,
+        seeds = [b"config"],
+        bump,
+    )]
+    pub config: Account<'info, PayConfig>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = authority @ ErrorCode::InvalidAuthority,
+    )]
+    pub config: Account<'info, PayConfig>,
+}
+
+#[derive(Accounts)]
+pub struct InitSolVault<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(seeds = [b"config"], bump = config.bump, has_one = authority @ ErrorCode::InvalidAuthority)]
+    pub config: Account<'info, PayConfig>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + SolVault::SIZE,
+        seeds = [b"sol_vault"],
+        bump,
+    )]
+    pub sol_vault: Account<'info, SolVault>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + InterestReserve::SIZE,
+        seeds = [b"interest_reserve"],
+        bump,
+    )]
+    pub interest_reserve: Account<'info, InterestReserve>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SweepBalanceLamports<'info> {
+    #[account(
+        mut,
+        constraint = balance_account.mint == Pubkey::default() @ ErrorCode::MintMismatch,
+    )]
+    pub balance_account: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(mut, seeds = [b"sol_vault"], bump = sol_vault.bump)]
+    pub sol_vault: Account<'info, SolVault>,
+}
+
+#[queue_computation_accounts("fund_interest_reserve", authority)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct FundInterestReserve<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(seeds = [b"config"], bump = config.bump, has_one = authority @ ErrorCode::InvalidAuthority)]
+    pub config: Account<'info, PayConfig>,
+    
+    #[account(mut, seeds = [b"sol_vault"], bump = sol_vault.bump)]
+    pub sol_vault: Account<'info, SolVault>,
+    
+    #[account(mut, seeds = [b"interest_reserve"], bump = interest_reserve.bump)]
+    pub interest_reserve: Account<'info, InterestReserve>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = authority,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_FUND_INTEREST_RESERVE))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("fund_interest_reserve")]
+#[derive(Accounts)]
+pub struct FundInterestReserveCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_FUND_INTEREST_RESERVE))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut)]
+    pub interest_reserve: Account<'info, InterestReserve>,
+    
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[init_computation_definition_accounts("fund_interest_reserve", payer)]
+#[derive(Accounts)]
+pub struct InitFundInterestReserveCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("accrue_interest", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct AccrueInterest<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(mut)]
+    pub balance_account: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, PayConfig>,
+    
+    #[account(mut, seeds = [b"interest_reserve"], bump = interest_reserve.bump)]
+    pub interest_reserve: Account<'info, InterestReserve>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_ACCRUE_INTEREST))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("accrue_interest")]
+#[derive(Accounts)]
+pub struct AccrueInterestCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_ACCRUE_INTEREST))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut)]
+    pub balance_account: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(mut)]
+    pub interest_reserve: Account<'info, InterestReserve>,
+    
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[init_computation_definition_accounts("accrue_interest", payer)]
+#[derive(Accounts)]
+pub struct InitAccrueInterestCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("init_balance", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, label: u8)]
+pub struct CreateLabeledBalanceAccount<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, PayConfig>,
+    
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + PrivateBalanceAccount::SIZE,
+        seeds = [b"balance", payer.key().as_ref(), &[label]],
+        bump,
+    )]
+    pub balance_account: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_INIT_BALANCE))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("split_deposit", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, amount: u64, labels: [u8; SPLIT_TARGETS])]
+pub struct SplitDeposit<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, PayConfig>,
+    
+    #[account(
+        mut,
+        seeds = [b"balance", payer.key().as_ref(), &[labels[0]]],
+        bump = balance_0.bump,
+    )]
+    pub balance_0: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"balance", payer.key().as_ref(), &[labels[1]]],
+        bump = balance_1.bump,
+    )]
+    pub balance_1: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"balance", payer.key().as_ref(), &[labels[2]]],
+        bump = balance_2.bump,
+    )]
+    pub balance_2: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"balance", payer.key().as_ref(), &[labels[3]]],
+        bump = balance_3.bump,
+    )]
+    pub balance_3: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(mut, seeds = [b"sol_vault"], bump = sol_vault.bump)]
+    pub sol_vault: Account<'info, SolVault>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_SPLIT_DEPOSIT))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("split_deposit")]
+#[derive(Accounts)]
+pub struct SplitDepositCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_SPLIT_DEPOSIT))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut)]
+    pub balance_0: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(mut)]
+    pub balance_1: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(mut)]
+    pub balance_2: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(mut)]
+    pub balance_3: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[init_computation_definition_accounts("split_deposit", payer)]
+#[derive(Accounts)]
+pub struct InitSplitDepositCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("schedule_payment", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct SchedulePayment<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, PayConfig>,
+    
+    /// CHECK: recipient of the scheduled payments
+    pub recipient: UncheckedAccount<'info>,
+    
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + ScheduledPayment::SIZE,
+        seeds = [b"scheduled_payment", payer.key().as_ref(), recipient.key().as_ref()],
+        bump,
+    )]
+    pub scheduled_payment: Account<'info, ScheduledPayment>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_SCHEDULE_PAYMENT))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("schedule_payment")]
+#[derive(Accounts)]
+pub struct SchedulePaymentCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_SCHEDULE_PAYMENT))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut)]
+    pub scheduled_payment: Account<'info, ScheduledPayment>,
+    
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[queue_computation_accounts("execute_scheduled_payment", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct ExecuteScheduledPayment<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, PayConfig>,
+    
+    #[account(
+        mut,
+        seeds = [
+            b"scheduled_payment",
+            scheduled_payment.owner.as_ref(),
+            scheduled_payment.recipient.as_ref(),
+        ],
+        bump = scheduled_payment.bump,
+    )]
+    pub scheduled_payment: Account<'info, ScheduledPayment>,
+    
+    #[account(
+        mut,
+        seeds = [b"balance", scheduled_payment.owner.as_ref()],
+        bump = owner_balance.bump,
+    )]
+    pub owner_balance: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"balance", scheduled_payment.recipient.as_ref()],
+        bump = recipient_balance.bump,
+    )]
+    pub recipient_balance: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_EXECUTE_SCHEDULED_PAYMENT))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("execute_scheduled_payment")]
+#[derive(Accounts)]
+pub struct ExecuteScheduledPaymentCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_EXECUTE_SCHEDULED_PAYMENT))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    pub scheduled_payment: Account<'info, ScheduledPayment>,
+    
+    #[account(mut)]
+    pub owner_balance: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(mut)]
+    pub recipient_balance: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CancelScheduledPayment<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    #[account(
+        mut,
+        close = owner,
+        has_one = owner @ ErrorCode::InvalidAuthority,
+    )]
+    pub scheduled_payment: Account<'info, ScheduledPayment>,
+}
+
+#[init_computation_definition_accounts("schedule_payment", payer)]
+#[derive(Accounts)]
+pub struct InitSchedulePaymentCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("execute_scheduled_payment", payer)]
+#[derive(Accounts)]
+pub struct InitExecuteScheduledPaymentCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("transfer", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct PrivateTransfer<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, PayConfig>,
+    
+    #[account(
+        mut,
+        seeds = [b"balance", payer.key().as_ref()],
+        bump = sender_balance.bump,
+    )]
+    pub sender_balance: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(
+        mut,
+        constraint = recipient_balance.key() != sender_balance.key() @ ErrorCode::InvalidRecipient,
+        constraint = recipient_balance.mint == sender_balance.mint @ ErrorCode::MintMismatch,
+    )]
+    pub recipient_balance: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_TRANSFER))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("transfer")]
+#[derive(Accounts)]
+pub struct TransferCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_TRANSFER))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut)]
+    pub sender_balance: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(mut)]
+    pub recipient_balance: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[init_computation_definition_accounts("transfer", payer)]
+#[derive(Accounts)]
+pub struct InitTransferCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("transfer", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct RebalanceBalances<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, PayConfig>,
+    
+    #[account(
+        mut,
+        constraint = from_balance.owner == payer.key() @ ErrorCode::InvalidAuthority,
+    )]
+    pub from_balance: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(
+        mut,
+        constraint = to_balance.owner == payer.key() @ ErrorCode::InvalidAuthority,
+        constraint = to_balance.key() != from_balance.key() @ ErrorCode::InvalidRecipient,
+        constraint = to_balance.mint == from_balance.mint @ ErrorCode::MintMismatch,
+    )]
+    pub to_balance: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_TRANSFER))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("merge_balances", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct MergeBalances<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, PayConfig>,
+    
+    // Ownership, distinctness and mints are checked by `validate_merge`
+    #[account(mut)]
+    pub destination_balance: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(mut)]
+    pub first_balance: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(mut)]
+    pub second_balance: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(mut)]
+    pub third_balance: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_MERGE_BALANCES))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("merge_balances")]
+#[derive(Accounts)]
+pub struct MergeBalancesCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_MERGE_BALANCES))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut)]
+    pub destination_balance: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(mut)]
+    pub first_balance: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(mut)]
+    pub second_balance: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(mut)]
+    pub third_balance: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[init_computation_definition_accounts("merge_balances", payer)]
+#[derive(Accounts)]
+pub struct InitMergeBalancesCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("set_min_balance", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct SetMinBalance<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, PayConfig>,
+    
+    #[account(
+        mut,
+        constraint = balance_account.owner == payer.key() @ ErrorCode::InvalidAuthority,
+    )]
+    pub balance_account: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_SET_MIN_BALANCE))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("set_min_balance")]
+#[derive(Accounts)]
+pub struct SetMinBalanceCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_SET_MIN_BALANCE))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut)]
+    pub balance_account: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[init_computation_definition_accounts("set_min_balance", payer)]
+#[derive(Accounts)]
+pub struct InitSetMinBalanceCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("withdraw", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct WithdrawFunds<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, PayConfig>,
+    
+    #[account(
+        mut,
+        constraint = balance_account.owner == payer.key() @ ErrorCode::InvalidAuthority,
+    )]
+    pub balance_account: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(mut, seeds = [b"sol_vault"], bump = sol_vault.bump)]
+    pub sol_vault: Account<'info, SolVault>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_WITHDRAW))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("withdraw")]
+#[derive(Accounts)]
+pub struct WithdrawCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_WITHDRAW))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut)]
+    pub balance_account: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(mut, address = balance_account.owner @ ErrorCode::InvalidAuthority)]
+    /// CHECK: balance owner, receives the withdrawn lamports
+    pub owner: UncheckedAccount<'info>,
+    
+    /// SOL balances only
+    #[account(mut, seeds = [b"sol_vault"], bump = sol_vault.bump)]
+    pub sol_vault: Option<Account<'info, SolVault>>,
+    
+    /// Token balances only, checked against the balance's mint
+    #[account(mut)]
+    pub token_vault: Option<Account<'info, TokenAccount>>,
+    
+    /// Token balances only
+    #[account(
+        mut,
+        constraint = user_token_account.mint == balance_account.mint @ ErrorCode::MintMismatch,
+        constraint = user_token_account.owner == balance_account.owner @ ErrorCode::InvalidAuthority,
+    )]
+    pub user_token_account: Option<Account<'info, TokenAccount>>,
+    
+    pub token_program: Option<Program<'info, Token>>,
+    
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[init_computation_definition_accounts("withdraw", payer)]
+#[derive(Accounts)]
+pub struct InitWithdrawCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("check_transfer_feasible", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct CheckTransferFeasible<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, PayConfig>,
+    
+    #[account(
+        seeds = [b"balance", payer.key().as_ref()],
+        bump = sender_balance.bump,
+    )]
+    pub sender_balance: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_CHECK_TRANSFER_FEASIBLE))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("check_transfer_feasible")]
+#[derive(Accounts)]
+pub struct CheckTransferFeasibleCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_CHECK_TRANSFER_FEASIBLE))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    pub sender_balance: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[init_computation_definition_accounts("check_transfer_feasible", payer)]
+#[derive(Accounts)]
+pub struct InitCheckTransferFeasibleCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("init_balance", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct CreateTokenBalanceAccount<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, PayConfig>,
+    
+    pub mint: Account<'info, Mint>,
+    
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + PrivateBalanceAccount::SIZE,
+        seeds = [b"token_balance", payer.key().as_ref(), mint.key().as_ref()],
+        bump,
+    )]
+    pub balance_account: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(
+        init_if_needed,
+        payer = payer,
+        seeds = [b"token_vault", mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = token_vault,
+    )]
+    pub token_vault: Account<'info, TokenAccount>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_INIT_BALANCE))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[queue_computation_accounts("deposit", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct DepositToken<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, PayConfig>,
+    
+    #[account(
+        mut,
+        seeds = [b"token_balance", payer.key().as_ref(), balance_account.mint.as_ref()],
+        bump = balance_account.bump,
+    )]
+    pub balance_account: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + PendingDeposit::SIZE,
+        seeds = [b"pending_deposit", balance_account.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub pending_deposit: Account<'info, PendingDeposit>,
+    
+    #[account(
+        mut,
+        constraint = user_token_account.mint == balance_account.mint @ ErrorCode::MintMismatch,
+        constraint = user_token_account.owner == payer.key() @ ErrorCode::InvalidAuthority,
+    )]
+    pub user_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"token_vault", balance_account.mint.as_ref()],
+        bump,
+    )]
+    pub token_vault: Account<'info, TokenAccount>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_DEPOSIT))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[queue_computation_accounts("withdraw", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct WithdrawToken<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, PayConfig>,
+    
+    #[account(
+        mut,
+        seeds = [b"token_balance", payer.key().as_ref(), balance_account.mint.as_ref()],
+        bump = balance_account.bump,
+    )]
+    pub balance_account: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(
+        mut,
+        constraint = user_token_account.mint == balance_account.mint @ ErrorCode::MintMismatch,
+        constraint = user_token_account.owner == payer.key() @ ErrorCode::InvalidAuthority,
+    )]
+    pub user_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"token_vault", balance_account.mint.as_ref()],
+        bump,
+    )]
+    pub token_vault: Account<'info, TokenAccount>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_WITHDRAW))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[queue_computation_accounts("batch_transfer", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct BatchTransfer<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, PayConfig>,
+    
+    #[account(
+        mut,
+        seeds = [b"balance", payer.key().as_ref()],
+        bump = sender_balance.bump,
+    )]
+    pub sender_balance: Account<'info, PrivateBalanceAccount>,
+    
+    // Distinctness and mints are checked by `validate_distinct_balances`
+    #[account(mut)]
+    pub recipient_0: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(mut)]
+    pub recipient_1: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(mut)]
+    pub recipient_2: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_BATCH_TRANSFER))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("batch_transfer")]
+#[derive(Accounts)]
+pub struct BatchTransferCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_BATCH_TRANSFER))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut)]
+    pub sender_balance: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(mut)]
+    pub recipient_0: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(mut)]
+    pub recipient_1: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(mut)]
+    pub recipient_2: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[init_computation_definition_accounts("batch_transfer", payer)]
+#[derive(Accounts)]
+pub struct InitBatchTransferCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("rotate_balance_nonce", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct RotateNonce<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, PayConfig>,
+    
+    #[account(
+        mut,
+        constraint = balance_account.owner == payer.key() @ ErrorCode::InvalidAuthority,
+    )]
+    pub balance_account: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_ROTATE_BALANCE_NONCE))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("rotate_balance_nonce")]
+#[derive(Accounts)]
+pub struct RotateBalanceNonceCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_ROTATE_BALANCE_NONCE))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut)]
+    pub balance_account: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[init_computation_definition_accounts("rotate_balance_nonce", payer)]
+#[derive(Accounts)]
+pub struct InitRotateBalanceNonceCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("reveal_balance", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct RequestBalanceReveal<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, PayConfig>,
+    
+    #[account(constraint = balance_account.owner == payer.key() @ ErrorCode::InvalidAuthority)]
+    pub balance_account: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_REVEAL_BALANCE))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("reveal_balance")]
+#[derive(Accounts)]
+pub struct RevealBalanceCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_REVEAL_BALANCE))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    pub balance_account: Account<'info, PrivateBalanceAccount>,
+    
+
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[init_computation_definition_accounts("reveal_balance", payer)]
+#[derive(Accounts)]
+pub struct InitRevealBalanceCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("create_vesting", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct CreateVesting<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, PayConfig>,
+    
+    /// CHECK: receives the vested funds in their private balance
+    pub beneficiary: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"balance", payer.key().as_ref()],
+        bump = grantor_balance.bump,
+    )]
+    pub grantor_balance: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + VestingEscrow::SIZE,
+        seeds = [b"vesting", payer.key().as_ref(), beneficiary.key().as_ref()],
+        bump,
+    )]
+    pub vesting_escrow: Account<'info, VestingEscrow>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_CREATE_VESTING))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("create_vesting")]
+#[derive(Accounts)]
+pub struct CreateVestingCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_CREATE_VESTING))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut)]
+    pub grantor_balance: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(mut)]
+    pub vesting_escrow: Account<'info, VestingEscrow>,
+    
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[queue_computation_accounts("claim_vested", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct ClaimVested<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, PayConfig>,
+    
+    #[account(
+        mut,
+        seeds = [
+            b"vesting",
+            vesting_escrow.grantor.as_ref(),
+            vesting_escrow.beneficiary.as_ref(),
+        ],
+        bump = vesting_escrow.bump,
+    )]
+    pub vesting_escrow: Account<'info, VestingEscrow>,
+    
+    #[account(
+        mut,
+        seeds = [b"balance", vesting_escrow.beneficiary.as_ref()],
+        bump = beneficiary_balance.bump,
+    )]
+    pub beneficiary_balance: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_CLAIM_VESTED))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("claim_vested")]
+#[derive(Accounts)]
+pub struct ClaimVestedCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_CLAIM_VESTED))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut)]
+    pub vesting_escrow: Account<'info, VestingEscrow>,
+    
+    #[account(mut)]
+    pub beneficiary_balance: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseVesting<'info> {
+    #[account(mut)]
+    pub grantor: Signer<'info>,
+    
+    #[account(
+        mut,
+        close = grantor,
+        has_one = grantor @ ErrorCode::InvalidAuthority,
+    )]
+    pub vesting_escrow: Account<'info, VestingEscrow>,
+}
+
+#[init_computation_definition_accounts("create_vesting", payer)]
+#[derive(Accounts)]
+pub struct InitCreateVestingCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("claim_vested", payer)]
+#[derive(Accounts)]
+pub struct InitClaimVestedCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("batch_deposit", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct BatchDeposit<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, PayConfig>,
+    
+    #[account(mut)]
+    pub balance_0: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(mut)]
+    pub balance_1: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(mut)]
+    pub balance_2: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(mut)]
+    pub balance_3: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(mut, seeds = [b"sol_vault"], bump = sol_vault.bump)]
+    pub sol_vault: Account<'info, SolVault>,
+    
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + PendingBatchDeposit::SIZE,
+        seeds = [b"pending_batch_deposit", payer.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub pending_batch_deposit: Account<'info, PendingBatchDeposit>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_BATCH_DEPOSIT))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("batch_deposit")]
+#[derive(Accounts)]
+pub struct BatchDepositCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_BATCH_DEPOSIT))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut)]
+    pub balance_0: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(mut)]
+    pub balance_1: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(mut)]
+    pub balance_2: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(mut)]
+    pub balance_3: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(mut, close = payer, has_one = payer)]
+    pub pending_batch_deposit: Account<'info, PendingBatchDeposit>,
+    
+    #[account(mut)]
+    /// CHECK: receives the pending batch deposit rent, checked against `pending_batch_deposit.payer`
+    pub payer: UncheckedAccount<'info>,
+    
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[init_computation_definition_accounts("batch_deposit", payer)]
+#[derive(Accounts)]
+pub struct InitBatchDepositCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("export_to_dark_pool", payer)]
+#[derive(Accounts)]
+pub struct InitExportToDarkPoolCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("export_to_dark_pool", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct FundDarkPoolOrder<'info> {
+    #[account(mut)]
     pub payer: Signer<'info>,
     
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, PayConfig>,
+    
     #[account(
         mut,
-        seeds = [b"balance", payer.key().as_ref()],
-        bump = balance_account.bump,
         constraint = balance_account.owner == payer.key() @ ErrorCode::InvalidAuthority,
     )]
     pub balance_account: Account<'info, PrivateBalanceAccount>,
     
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + DarkPoolFunding::SIZE,
+        seeds = [b"dark_pool_funding", payer.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub funding: Account<'info, DarkPoolFunding>,
+    
     #[account(
         init_if_needed,
         space = 9,
@@ -299,7 +7093,7 @@ pub struct DepositFunds<'info> {
     /// CHECK: computation_account
     pub computation_account: UncheckedAccount<'info>,
     
-    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_DEPOSIT))]
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_EXPORT_TO_DARK_POOL))]
     pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
     
     #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
@@ -315,12 +7109,12 @@ pub struct DepositFunds<'info> {
     pub arcium_program: Program<'info, Arcium>,
 }
 
-#[callback_accounts("deposit")]
+#[callback_accounts("export_to_dark_pool")]
 #[derive(Accounts)]
-pub struct DepositCallback<'info> {
+pub struct ExportToDarkPoolCallback<'info> {
     pub arcium_program: Program<'info, Arcium>,
     
-    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_DEPOSIT))]
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_EXPORT_TO_DARK_POOL))]
     pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
     
     #[account(address = derive_mxe_pda!())]
@@ -329,49 +7123,136 @@ pub struct DepositCallback<'info> {
     /// CHECK: computation_account
     pub computation_account: UncheckedAccount<'info>,
     
-    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
-    pub cluster_account: Account<'info, Cluster>,
-    
-    #[account(mut)]
-    pub balance_account: Account<'info, PrivateBalanceAccount>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut)]
+    pub balance_account: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(mut)]
+    pub funding: Account<'info, DarkPoolFunding>,
+    
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ConsumeDarkPoolFunding<'info> {
+    #[account(seeds = [b"funding_authority"], bump, seeds::program = config.dark_pool_program)]
+    pub dark_pool_authority: Signer<'info>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, PayConfig>,
+    
+    #[account(
+        mut,
+        close = owner,
+        has_one = owner @ ErrorCode::InvalidAuthority,
+        constraint = funding.ready @ ErrorCode::FundingNotReady,
+    )]
+    pub funding: Account<'info, DarkPoolFunding>,
+    
+    #[account(mut)]
+    /// CHECK: funding owner, receives the rent; checked by `has_one`
+    pub owner: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"token_vault", funding.mint.as_ref()],
+        bump,
+    )]
+    pub token_vault: Account<'info, TokenAccount>,
+    
+    /// The dark pool's escrow vault for the order's side
+    #[account(mut, constraint = destination.mint == funding.mint @ ErrorCode::MintMismatch)]
+    pub destination: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[queue_computation_accounts("deposit", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct RecreditDarkPoolFunding<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, PayConfig>,
+    
+    #[account(
+        mut,
+        close = payer,
+        constraint = funding.owner == payer.key() @ ErrorCode::InvalidAuthority,
+        constraint = funding.ready @ ErrorCode::FundingNotReady,
+    )]
+    pub funding: Account<'info, DarkPoolFunding>,
+    
+    #[account(
+        mut,
+        seeds = [b"token_balance", payer.key().as_ref(), funding.mint.as_ref()],
+        bump = balance_account.bump,
+    )]
+    pub balance_account: Account<'info, PrivateBalanceAccount>,
+    
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + PendingDeposit::SIZE,
+        seeds = [b"pending_deposit", balance_account.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub pending_deposit: Account<'info, PendingDeposit>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_DEPOSIT))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
     
-    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
-    /// CHECK: instructions_sysvar
-    pub instructions_sysvar: AccountInfo<'info>,
-}
-
-#[init_computation_definition_accounts("init_balance", payer)]
-#[derive(Accounts)]
-pub struct InitBalanceCompDef<'info> {
-    #[account(mut)]
-    pub payer: Signer<'info>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
     
-    #[account(mut, address = derive_mxe_pda!())]
-    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
     
-    #[account(mut)]
-    /// CHECK: comp_def_account
-    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
     
-    pub arcium_program: Program<'info, Arcium>,
     pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
 }
 
-#[init_computation_definition_accounts("deposit", payer)]
 #[derive(Accounts)]
-pub struct InitDepositCompDef<'info> {
-    #[account(mut)]
-    pub payer: Signer<'info>,
-    
-    #[account(mut, address = derive_mxe_pda!())]
-    pub mxe_account: Box<Account<'info, MXEAccount>>,
-    
+pub struct CloseDarkPoolFunding<'info> {
     #[account(mut)]
-    /// CHECK: comp_def_account
-    pub comp_def_account: UncheckedAccount<'info>,
+    pub owner: Signer<'info>,
     
-    pub arcium_program: Program<'info, Arcium>,
-    pub system_program: Program<'info, System>,
+    #[account(mut, close = owner, has_one = owner @ ErrorCode::InvalidAuthority)]
+    pub funding: Account<'info, DarkPoolFunding>,
 }
 
 // ============ Events ============
@@ -379,12 +7260,286 @@ pub struct InitDepositCompDef<'info> {
 #[event]
 pub struct BalanceCreated {
     pub owner: Pubkey,
+    pub slot: u64,
+    pub unix_timestamp: i64,
 }
 
 #[event]
 pub struct FundsDeposited {
     pub owner: Pubkey,
-    pub new_balance: u64,
+    pub encrypted_balance: [u8; 64], // Balance after the deposit, MXE-encrypted
+    pub nonce: u128,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct MixingPoolCreated {
+    pub mixing_pool: Pubkey,
+    pub denomination: u64,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct PoolDeposited {
+    pub mixing_pool: Pubkey,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct PoolDepositReclaimed {
+    pub mixing_pool: Pubkey,
+    pub depositor: Pubkey,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct PoolWithdrawn {
+    pub mixing_pool: Pubkey,
+    pub recipient: Pubkey,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct BalanceDisclosed {
+    pub owner: Pubkey,
+    pub auditor_pubkey: [u8; 32],
+    pub encrypted_balance: [u8; 64], // Decryptable only by the auditor key
+    pub nonce: u128,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct AllowanceApproved {
+    pub owner: Pubkey,
+    pub delegate: Pubkey,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct AllowanceSpent {
+    pub owner: Pubkey,
+    pub delegate: Pubkey,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct InterestReserveFunded {
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct InterestAccrued {
+    pub owner: Pubkey,
+    pub accrual_slot: u64,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct DepositSplit {
+    pub owner: Pubkey,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct PaymentScheduled {
+    pub owner: Pubkey,
+    pub recipient: Pubkey,
+    pub interval_slots: u64,
+    pub next_due_slot: u64,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct ScheduledPaymentExecuted {
+    pub owner: Pubkey,
+    pub recipient: Pubkey,
+    pub success: bool, // False when the installment was skipped for lack of funds
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct PrivateTransferCompleted {
+    pub sender: Pubkey,
+    pub recipient: Pubkey,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct BalanceCreationCancelled {
+    pub owner: Pubkey,
+    pub label: u8,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct FundsWithdrawn {
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct ComputationReclaimed {
+    pub owner: Pubkey,
+    pub computation_offset: u64,
+    pub refunded: u64,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct PausedOpsUpdated {
+    pub paused_ops: u8,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct PausedUpdated {
+    pub paused: bool,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct BalancesMerged {
+    pub owner: Pubkey,
+    pub destination: Pubkey,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct MinBalanceUpdated {
+    pub owner: Pubkey,
+    pub balance_account: Pubkey,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct TransferFeasibilityChecked {
+    pub owner: Pubkey,
+    pub feasible: bool,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct TokensWithdrawn {
+    pub owner: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct BatchTransferCompleted {
+    pub sender: Pubkey,
+    pub recipients: [Pubkey; BATCH_RECIPIENTS],
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct BalanceNonceRotated {
+    pub owner: Pubkey,
+    pub balance_account: Pubkey,
+    pub nonce: u128, // New balance nonce
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct BalanceRevealed {
+    pub owner: Pubkey,
+    pub balance_account: Pubkey,
+    pub encrypted_balance: [u8; 64], // Decryptable only by the requester's client key
+    pub nonce: u128,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct SpendingLimitUpdated {
+    pub owner: Pubkey,
+    pub balance_account: Pubkey,
+    pub daily_limit: u64,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct VestingCreated {
+    pub grantor: Pubkey,
+    pub beneficiary: Pubkey,
+    pub start_slot: u64,
+    pub duration_slots: u64,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct VestedClaimed {
+    pub grantor: Pubkey,
+    pub beneficiary: Pubkey,
+    pub success: bool,       // False when the beneficiary's balance would overflow
+    pub fully_claimed: bool, // The whole grant has been released
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct BatchDeposited {
+    pub count: u8,  // Balances credited
+    pub total: u64, // Lamports credited across the batch
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct DarkPoolOrderFunded {
+    pub owner: Pubkey,
+    pub funding: Pubkey,
+    pub mint: Pubkey,
+    pub escrow_amount: u64,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct DarkPoolFundingConsumed {
+    pub owner: Pubkey,
+    pub funding: Pubkey,
+    pub amount: u64, // Tokens moved into the dark pool's escrow
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct DarkPoolFundingRecredited {
+    pub owner: Pubkey,
+    pub funding: Pubkey,
+    pub amount: u64,
+    pub slot: u64,
+    pub unix_timestamp: i64,
 }
 
 // ============ Errors ============
@@ -403,4 +7558,469 @@ pub enum ErrorCode {
     InitializationFailed,
     #[msg("Deposit failed")]
     DepositFailed,
+    #[msg("Mixing pool denomination must be non-zero")]
+    InvalidDenomination,
+    #[msg("Withdraw failed")]
+    WithdrawFailed,
+    #[msg("Spend exceeds the remaining allowance or balance")]
+    AllowanceExceeded,
+    #[msg("No slots have elapsed since the last accrual")]
+    NothingToAccrue,
+    #[msg("Balance account holds nothing past its rent")]
+    NothingToSweep,
+    #[msg("Interest reserve has never been funded")]
+    InterestReserveEmpty,
+    #[msg("Another computation on this account is still in flight")]
+    ComputationInFlight,
+    #[msg("Callback is not for the computation this account is waiting on")]
+    StaleComputation,
+    #[msg("Arithmetic overflow")]
+    MathOverflow,
+    #[msg("Balance label is reserved or repeated")]
+    InvalidBalanceLabel,
+    #[msg("Split ratios must sum to 10000 bps")]
+    InvalidSplitRatios,
+    #[msg("Payer cannot cover the computation fee")]
+    InsufficientComputationFee,
+    #[msg("Payment interval must be non-zero")]
+    InvalidSchedule,
+    #[msg("Scheduled payment is not due yet")]
+    PaymentNotDue,
+    #[msg("Cannot transfer to the sending balance")]
+    InvalidRecipient,
+    #[msg("This operation is paused")]
+    OperationPaused,
+    #[msg("Program is paused")]
+    ProgramPaused,
+    #[msg("Computation may still settle, wait for the timeout")]
+    ComputationStillPending,
+    #[msg("Balance account creation already completed")]
+    BalanceAlreadyCreated,
+    #[msg("Balance account tracks a different mint")]
+    MintMismatch,
+    #[msg("Balance account hasn't been initialized by its creation callback")]
+    BalanceNotCreated,
+    #[msg("Deposit amount must be greater than zero")]
+    ZeroDeposit,
+    #[msg("Deposited lamports did not reach the balance account")]
+    DepositNotReceived,
+    #[msg("Withdrawal exceeds the balance's spending limit for this window")]
+    SpendingLimitExceeded,
+    #[msg("Vesting escrow hasn't been funded by its creation callback")]
+    VestingNotFunded,
+    #[msg("Vesting grant hasn't been fully claimed")]
+    VestingActive,
+    #[msg("Batch must hold between one and BATCH_DEPOSITS deposits")]
+    InvalidBatchSize,
+    #[msg("Dark pool funding is not configured")]
+    DarkPoolFundingDisabled,
+    #[msg("Dark pool funding has not been debited yet")]
+    FundingNotReady,
+    #[msg("Dark pool funding was already debited")]
+    FundingReady,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn balance_with_limit(daily_limit: u64) -> PrivateBalanceAccount {
+        PrivateBalanceAccount {
+            owner: Pubkey::new_unique(),
+            bump: 255,
+            label: 0,
+            balance_state: [0u8; 64],
+            nonce: 0,
+            last_accrual_slot: 0,
+            min_balance_state: [0u8; 64],
+            min_balance_nonce: 0,
+            mint: Pubkey::default(),
+            daily_limit,
+            spent_today: 0,
+            limit_reset_slot: 0,
+        }
+    }
+
+    /// Primary SOL balance of `owner`
+    fn balance_pda(owner: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[b"balance", owner.as_ref()], &crate::ID).0
+    }
+
+    #[test]
+    fn underfunded_transfer_leaves_both_balances_untouched() {
+        let mut sender = balance_with_limit(0);
+        let mut recipient = balance_with_limit(0);
+        sender.write_balance([1u8; 64], 10);
+        recipient.write_balance([2u8; 64], 20);
+
+        assert_eq!(
+            apply_transfer(&mut sender, &mut recipient, ([3u8; 64], 11), ([4u8; 64], 21), false)
+                .unwrap_err(),
+            ErrorCode::InsufficientBalance.into()
+        );
+        assert_eq!((sender.balance_state, sender.nonce), ([1u8; 64], 10));
+        assert_eq!((recipient.balance_state, recipient.nonce), ([2u8; 64], 20));
+
+        apply_transfer(&mut sender, &mut recipient, ([3u8; 64], 11), ([4u8; 64], 21), true).unwrap();
+        assert_eq!((sender.balance_state, sender.nonce), ([3u8; 64], 11));
+        assert_eq!((recipient.balance_state, recipient.nonce), ([4u8; 64], 21));
+    }
+
+    #[test]
+    fn self_allowance_is_rejected() {
+        let owner = Pubkey::new_unique();
+        let delegate = Pubkey::new_unique();
+        assert!(PrivateAllowance::valid_delegate(&owner, &delegate));
+        assert!(!PrivateAllowance::valid_delegate(&owner, &owner));
+
+        // The spend's owner and delegate balances alias exactly when the
+        // delegate is the owner, which `SpendFromAllowance` also rejects
+        assert_eq!(balance_pda(&owner), balance_pda(&owner));
+        assert_ne!(balance_pda(&owner), balance_pda(&delegate));
+    }
+
+    #[test]
+    fn merge_takes_three_distinct_balances_of_one_owner_and_mint() {
+        let owner = Pubkey::new_unique();
+        let mut balances = [0u8; 4].map(|_| {
+            let mut balance = balance_with_limit(0);
+            balance.owner = owner;
+            balance
+        });
+        let keys = [0u8; 4].map(|_| Pubkey::new_unique());
+        let merge = |balances: &[PrivateBalanceAccount; 4], keys: [Pubkey; 4]| {
+            validate_merge(
+                &owner,
+                (keys[0], &balances[0]),
+                [(keys[1], &balances[1]), (keys[2], &balances[2]), (keys[3], &balances[3])],
+            )
+            .map_err(u32::from)
+        };
+        assert!(merge(&balances, keys).is_ok());
+
+        // The destination, or an earlier source, passed twice
+        let repeated = [keys[0], keys[1], keys[2], keys[0]];
+        assert_eq!(merge(&balances, repeated), Err(ErrorCode::InvalidRecipient.into()));
+        let repeated = [keys[0], keys[1], keys[2], keys[2]];
+        assert_eq!(merge(&balances, repeated), Err(ErrorCode::InvalidRecipient.into()));
+
+        balances[2].mint = Pubkey::new_unique();
+        assert_eq!(merge(&balances, keys), Err(ErrorCode::MintMismatch.into()));
+        balances[2].mint = Pubkey::default();
+
+        balances[3].owner = Pubkey::new_unique();
+        assert_eq!(merge(&balances, keys), Err(ErrorCode::InvalidAuthority.into()));
+    }
+
+    #[test]
+    fn pause_blocks_every_operation_until_lifted() {
+        let mut config = PayConfig::deserialize(&mut &[0u8; PayConfig::SIZE][..]).unwrap();
+        assert!(config.ensure_open(0).is_ok());
+        assert!(config.ensure_open(PAUSE_DEPOSITS).is_ok());
+
+        config.paused_ops = PAUSE_WITHDRAWALS;
+        assert!(config.ensure_open(PAUSE_DEPOSITS).is_ok());
+        assert_eq!(
+            config.ensure_open(PAUSE_WITHDRAWALS).unwrap_err(),
+            ErrorCode::OperationPaused.into()
+        );
+
+        // The program-wide switch wins over the per-operation mask
+        config.paused = true;
+        for ops in [0, PAUSE_DEPOSITS, PAUSE_WITHDRAWALS] {
+            assert_eq!(config.ensure_open(ops).unwrap_err(), ErrorCode::ProgramPaused.into());
+        }
+        config.paused = false;
+        config.paused_ops = 0;
+        assert!(config.ensure_open(PAUSE_WITHDRAWALS).is_ok());
+    }
+
+    #[test]
+    fn stranded_deposits_are_reclaimable_only_after_the_timeout() {
+        assert_eq!(
+            ensure_timed_out(100, 100 + COMPUTATION_TIMEOUT_SLOTS - 1).unwrap_err(),
+            ErrorCode::ComputationStillPending.into()
+        );
+        assert!(ensure_timed_out(100, 100 + COMPUTATION_TIMEOUT_SLOTS).is_ok());
+        // A queued slot near the end of time saturates rather than wrapping
+        assert!(ensure_timed_out(u64::MAX - 1, u64::MAX - 1).is_err());
+    }
+
+    #[test]
+    fn only_stalled_balance_creation_can_be_cancelled() {
+        let mut balance = balance_with_limit(0);
+        balance.last_accrual_slot = 50;
+        assert_eq!(
+            balance.ensure_creation_stalled(50 + COMPUTATION_TIMEOUT_SLOTS - 1).unwrap_err(),
+            ErrorCode::ComputationStillPending.into()
+        );
+        assert!(balance.ensure_creation_stalled(50 + COMPUTATION_TIMEOUT_SLOTS).is_ok());
+
+        // Once the callback wrote the encrypted zero the balance is live
+        balance.write_balance([7u8; 64], 1);
+        assert_eq!(
+            balance.ensure_creation_stalled(50 + COMPUTATION_TIMEOUT_SLOTS).unwrap_err(),
+            ErrorCode::BalanceAlreadyCreated.into()
+        );
+    }
+
+    #[test]
+    fn withdrawals_never_touch_the_vault_rent() {
+        assert!(can_pay_above_rent(1_500, 1_000, 500));
+        assert!(!can_pay_above_rent(1_500, 1_000, 501));
+        assert!(can_pay_above_rent(1_000, 1_000, 0));
+        // An amount that would overflow the floor can't be paid either
+        assert!(!can_pay_above_rent(u64::MAX, 1, u64::MAX));
+    }
+
+    #[test]
+    fn events_end_with_their_emit_time() {
+        let event = InterestAccrued {
+            owner: Pubkey::new_unique(),
+            accrual_slot: 90,
+            slot: 100,
+            unix_timestamp: -5,
+        };
+        let data = anchor_lang::Event::data(&event);
+        assert_eq!(data.len(), InterestAccrued::DISCRIMINATOR.len() + 32 + 8 + 8 + 8);
+        // Indexers read the stamp from the tail, after the renamed accrual slot
+        let tail = &data[data.len() - 24..];
+        assert_eq!(tail[..8], 90u64.to_le_bytes());
+        assert_eq!(tail[8..16], 100u64.to_le_bytes());
+        assert_eq!(tail[16..], (-5i64).to_le_bytes());
+    }
+
+    #[test]
+    fn token_withdrawals_sign_for_the_vault_their_mint_deposited_into() {
+        let mint = Pubkey::new_unique();
+        let (vault, bump) = token_vault_address(&mint);
+        let signer = Pubkey::create_program_address(
+            &[b"token_vault", mint.as_ref(), &[bump]],
+            &crate::ID,
+        )
+        .unwrap();
+        assert_eq!(signer, vault);
+
+        // Each mint, and SOL, keeps its own vault
+        assert_ne!(token_vault_address(&Pubkey::new_unique()).0, vault);
+        let sol_vault = Pubkey::find_program_address(&[b"sol_vault"], &crate::ID).0;
+        assert_ne!(token_vault_address(&Pubkey::default()).0, sol_vault);
+    }
+
+    #[test]
+    fn batch_recipients_are_distinct_balances_on_the_sender_mint() {
+        let sender = balance_with_limit(0);
+        let recipients = [0u8; BATCH_RECIPIENTS].map(|_| balance_with_limit(0));
+        let sender_key = Pubkey::new_unique();
+        let keys = [0u8; BATCH_RECIPIENTS].map(|_| Pubkey::new_unique());
+        let batch = |keys: [Pubkey; BATCH_RECIPIENTS], recipients: &[PrivateBalanceAccount]| {
+            let others: Vec<_> = keys.iter().copied().zip(recipients).collect();
+            validate_distinct_balances((sender_key, &sender), &others).map_err(u32::from)
+        };
+        assert!(batch(keys, &recipients).is_ok());
+
+        // Paying the sender itself, or one recipient twice
+        let to_sender = [keys[0], sender_key, keys[2]];
+        assert_eq!(batch(to_sender, &recipients), Err(ErrorCode::InvalidRecipient.into()));
+        let twice = [keys[0], keys[1], keys[0]];
+        assert_eq!(batch(twice, &recipients), Err(ErrorCode::InvalidRecipient.into()));
+
+        let mut token_recipients = recipients;
+        token_recipients[1].mint = Pubkey::new_unique();
+        assert_eq!(batch(keys, &token_recipients), Err(ErrorCode::MintMismatch.into()));
+    }
+
+    #[test]
+    fn zero_limit_never_blocks() {
+        let mut balance = balance_with_limit(0);
+        balance.record_spend(u64::MAX, 10).unwrap();
+        balance.record_spend(u64::MAX, 10).unwrap();
+        assert_eq!(balance.spent_today, 0);
+    }
+
+    #[test]
+    fn spends_count_against_the_window() {
+        let mut balance = balance_with_limit(100);
+        balance.record_spend(60, 10).unwrap();
+        assert_eq!(balance.limit_reset_slot, 10 + SPENDING_WINDOW_SLOTS);
+        balance.record_spend(40, 11).unwrap();
+        assert_eq!(balance.spent_today, 100);
+        assert_eq!(
+            balance.record_spend(1, 12).unwrap_err(),
+            ErrorCode::SpendingLimitExceeded.into()
+        );
+        assert_eq!(balance.spent_today, 100);
+    }
+
+    #[test]
+    fn window_resets_once_it_ends() {
+        let mut balance = balance_with_limit(100);
+        balance.record_spend(100, 10).unwrap();
+        let reset = balance.limit_reset_slot;
+        balance.record_spend(100, reset).unwrap();
+        assert_eq!(balance.spent_today, 100);
+        assert_eq!(balance.limit_reset_slot, reset + SPENDING_WINDOW_SLOTS);
+    }
+
+    #[test]
+    fn overflowing_spend_is_rejected() {
+        let mut balance = balance_with_limit(u64::MAX);
+        balance.record_spend(u64::MAX, 10).unwrap();
+        assert_eq!(
+            balance.record_spend(1, 11).unwrap_err(),
+            ErrorCode::SpendingLimitExceeded.into()
+        );
+    }
+
+    #[test]
+    fn nonces_rotate_only_on_created_balances() {
+        let mut balance = balance_with_limit(0);
+        assert_eq!(
+            balance.ensure_created().unwrap_err(),
+            ErrorCode::BalanceNotCreated.into()
+        );
+
+        balance.write_balance([7u8; 64], 1);
+        assert!(balance.ensure_created().is_ok());
+    }
+
+    #[test]
+    fn deposits_are_credited_only_for_lamports_that_arrived() {
+        assert!(ensure_deposit_received(1_000, 1_500, 500).is_ok());
+        for (before, after) in [(1_000, 1_400), (1_000, 1_600), (1_000, 900)] {
+            assert_eq!(
+                ensure_deposit_received(before, after, 500).unwrap_err(),
+                ErrorCode::DepositNotReceived.into()
+            );
+        }
+    }
+
+    #[test]
+    fn balance_reveals_hand_back_the_requester_ciphertext_and_nonce() {
+        let event = BalanceRevealed {
+            owner: Pubkey::new_unique(),
+            balance_account: Pubkey::new_unique(),
+            encrypted_balance: [9u8; 64],
+            nonce: 42,
+            slot: 100,
+            unix_timestamp: 7,
+        };
+        let data = anchor_lang::Event::data(&event);
+        let (discriminator, body) = data.split_at(BalanceRevealed::DISCRIMINATOR.len());
+        assert_eq!(discriminator, BalanceRevealed::DISCRIMINATOR);
+
+        // Wallets decrypt with the nonce the cluster chose, not the one they sent
+        let decoded = BalanceRevealed::deserialize(&mut &body[..]).unwrap();
+        assert_eq!((decoded.owner, decoded.balance_account), (event.owner, event.balance_account));
+        assert_eq!((decoded.encrypted_balance, decoded.nonce), ([9u8; 64], 42));
+    }
+
+    #[test]
+    fn cluster_status_reports_the_default_key_while_unassigned() {
+        let unassigned = ClusterStatus::new(None);
+        assert!(!unassigned.assigned);
+        assert_eq!(unassigned.cluster, Pubkey::default());
+
+        let cluster = Pubkey::new_unique();
+        let assigned = ClusterStatus::new(Some(cluster));
+        assert!(assigned.assigned);
+        assert_eq!(assigned.cluster, cluster);
+
+        // Return data is the flag byte followed by the cluster key
+        let mut data = Vec::new();
+        assigned.serialize(&mut data).unwrap();
+        assert_eq!(data[0], 1);
+        assert_eq!(data[1..], cluster.to_bytes());
+    }
+
+    #[test]
+    fn vesting_escrows_close_only_once_unfunded_or_fully_claimed() {
+        let mut escrow = VestingEscrow::deserialize(&mut &[0u8; VestingEscrow::SIZE][..]).unwrap();
+        escrow.start_slot = 100;
+        escrow.duration_slots = 50;
+        assert!(escrow.ensure_closable().is_ok());
+
+        escrow.encrypted_state = [[3u8; 32]; VESTING_STATE_WORDS];
+        assert_eq!(escrow.ensure_closable().unwrap_err(), ErrorCode::VestingActive.into());
+
+        escrow.last_claim_slot = 149;
+        escrow.settle_claim();
+        assert!(!escrow.fully_claimed);
+        assert_eq!(escrow.ensure_closable().unwrap_err(), ErrorCode::VestingActive.into());
+
+        escrow.last_claim_slot = 150;
+        escrow.settle_claim();
+        assert!(escrow.fully_claimed);
+        assert!(escrow.ensure_closable().is_ok());
+    }
+
+    #[test]
+    fn batch_deposits_credit_distinct_canonical_sol_balances() {
+        fn batch(
+            deposits: &[(Pubkey, u64)],
+            keys: [Pubkey; BATCH_DEPOSITS],
+            balances: &[PrivateBalanceAccount; BATCH_DEPOSITS],
+        ) -> std::result::Result<([u64; BATCH_DEPOSITS], u64), u32> {
+            let slots = std::array::from_fn(|i| (keys[i], &balances[i]));
+            batch_deposit_amounts(deposits, slots).map_err(u32::from)
+        }
+        let balances: [PrivateBalanceAccount; BATCH_DEPOSITS] = std::array::from_fn(|_| {
+            let mut balance = balance_with_limit(0);
+            let seeds: &[&[u8]] = &[b"balance", balance.owner.as_ref()];
+            balance.bump = Pubkey::find_program_address(seeds, &crate::ID).1;
+            balance
+        });
+        let keys = std::array::from_fn(|i| balance_pda(&balances[i].owner));
+
+        // Two deposits; the unused slots repeat the first balance
+        let padded = [keys[0], keys[1], keys[0], keys[0]];
+        let deposits = [(keys[0], 5), (keys[1], 7)];
+        let padded_balances = [0, 1, 0, 0].map(|i| balances[i].clone());
+        assert_eq!(batch(&deposits, padded, &padded_balances), Ok(([5, 7, 0, 0], 12)));
+        assert_eq!(batch(&[], padded, &padded_balances), Err(ErrorCode::InvalidBatchSize.into()));
+        let unpadded = [keys[0], keys[1], keys[2], keys[0]];
+        assert_eq!(
+            batch(&deposits, unpadded, &balances),
+            Err(ErrorCode::InvalidRecipient.into())
+        );
+
+        let deposits = keys.map(|key| (key, 1));
+        assert_eq!(batch(&deposits, keys, &balances), Ok(([1; BATCH_DEPOSITS], 4)));
+        // Crediting one balance twice, or a balance that isn't its owner's
+        // canonical SOL balance
+        let twice = [deposits[0], deposits[1], deposits[0], deposits[3]];
+        let twice_keys = [keys[0], keys[1], keys[0], keys[3]];
+        assert_eq!(
+            batch(&twice, twice_keys, &balances),
+            Err(ErrorCode::InvalidRecipient.into())
+        );
+        let mut labeled = balances.clone();
+        labeled[2].bump = labeled[2].bump.wrapping_sub(1);
+        assert_eq!(batch(&deposits, keys, &labeled), Err(ErrorCode::InvalidRecipient.into()));
+
+        let mut token = balances.clone();
+        token[1].mint = Pubkey::new_unique();
+        assert_eq!(batch(&deposits, keys, &token), Err(ErrorCode::MintMismatch.into()));
+        let empty = [deposits[0], deposits[1], (keys[2], 0), deposits[3]];
+        assert_eq!(batch(&empty, keys, &balances), Err(ErrorCode::ZeroDeposit.into()));
+    }
+
+    #[test]
+    fn only_stalled_dark_pool_fundings_close_without_a_recredit() {
+        let mut funding =
+            DarkPoolFunding::deserialize(&mut &[0u8; DarkPoolFunding::SIZE][..]).unwrap();
+        funding.queued_slot = 50;
+        assert_eq!(
+            funding.ensure_stalled(50 + COMPUTATION_TIMEOUT_SLOTS - 1).unwrap_err(),
+            ErrorCode::ComputationStillPending.into()
+        );
+        assert!(funding.ensure_stalled(50 + COMPUTATION_TIMEOUT_SLOTS).is_ok());
+
+        // A debited funding holds escrow, so closing it would strand the tokens
+        funding.ready = true;
+        assert_eq!(
+            funding.ensure_stalled(50 + COMPUTATION_TIMEOUT_SLOTS).unwrap_err(),
+            ErrorCode::FundingReady.into()
+        );
+    }
 }
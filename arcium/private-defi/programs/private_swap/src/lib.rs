@@ -1,8 +1,19 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+use anchor_lang::solana_program::program_option::COption;
+use anchor_spl::token::spl_token::{instruction::AuthorityType, native_mint};
+use anchor_spl::token::{
+    self, Approve, Burn, CloseAccount, Mint, MintTo, Revoke, SetAuthority, Token, TokenAccount,
+    Transfer,
+};
 use arcium_anchor::prelude::*;
+use private_defi_common::{covers_computation_fee, CallbackAccountsBuilder};
 
 const COMP_DEF_OFFSET_SWAP: u32 = comp_def_offset("execute_swap");
+const COMP_DEF_OFFSET_ROUTE_EXACT_OUT: u32 = comp_def_offset("route_swap_exact_out");
+const COMP_DEF_OFFSET_SWAP_SEALED: u32 = comp_def_offset("execute_swap_sealed");
+const COMP_DEF_OFFSET_REVEAL_SWAP_RESULT: u32 = comp_def_offset("reveal_swap_result");
+const COMP_DEF_OFFSET_MULTI_HOP_SWAP: u32 = comp_def_offset("execute_multi_hop_swap");
+const COMP_DEF_OFFSET_SWAP_EXACT_OUT: u32 = comp_def_offset("execute_swap_exact_out");
 
 declare_id!("6qqmuL4qmRMXrpPsUPsKLzabsbSoiKHRdhH817xFE1aa");
 
@@ -16,11 +27,43 @@ pub mod private_swap {
         Ok(())
     }
 
+    /// Initialize the computation definition for exact-output routing
+    pub fn init_route_exact_out_comp_def(ctx: Context<InitRouteExactOutCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    /// Initialize the computation definition for sealed swaps
+    pub fn init_swap_sealed_comp_def(ctx: Context<InitSwapSealedCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    /// Initialize the computation definition for revealing sealed swap results
+    pub fn init_reveal_swap_result_comp_def(ctx: Context<InitRevealSwapResultCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    /// Initialize the computation definition for multi-hop swaps
+    pub fn init_multi_hop_swap_comp_def(ctx: Context<InitMultiHopSwapCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_swap_exact_out_comp_def(ctx: Context<InitSwapExactOutCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
     /// Initialize a swap pool for a token pair
     pub fn init_pool(
         ctx: Context<InitPool>,
         fee_rate: u16, // Fee in basis points (100 = 1%)
+        min_fee: u64,  // Minimum fee charged per swap, in input token units
     ) -> Result<()> {
+        validate_fee_rate(fee_rate)?;
+
         let pool = &mut ctx.accounts.pool;
         pool.authority = ctx.accounts.authority.key();
         pool.token_mint_a = ctx.accounts.token_mint_a.key();
@@ -30,6 +73,280 @@ pub mod private_swap {
         pool.fee_rate = fee_rate;
         pool.bump = ctx.bumps.pool;
         pool.total_swaps = 0;
+        pool.min_fee = min_fee;
+        pool.pending_swaps = 0;
+        pool.in_flight = false;
+        pool.in_flight_offset = 0;
+        pool.in_flight_slot = 0;
+        pool.halted = false;
+        pool.paused_ops = 0;
+        pool.lp_mint = ctx.accounts.lp_mint.key();
+        pool.verify_swap_math = false;
+        pool.fee_growth_a = 0;
+        pool.fee_growth_b = 0;
+        pool.paused = false;
+        pool.price_cumulative_a = 0;
+        pool.price_cumulative_b = 0;
+        pool.last_update_slot = Clock::get()?.slot;
+        pool.price_tick = 1;
+        pool.reveal_delay_slots = 0;
+        pool.governance_mint = Pubkey::default();
+        pool.discount_thresholds = [0; FEE_DISCOUNT_TIERS];
+        pool.discount_bps = [0; FEE_DISCOUNT_TIERS];
+        pool.fee_mode = FeeMode::Extracted;
+        pool.min_swap_notional = 0;
+
+        // Count the pool against its authority's open pool limit
+        let registry = &mut ctx.accounts.pool_registry;
+        registry.authority = ctx.accounts.authority.key();
+        registry.bump = ctx.bumps.pool_registry;
+        registry.open_pool(ctx.accounts.config.max_pools_per_authority)
+    }
+
+    /// Create the program config
+    pub fn init_config(ctx: Context<InitConfig>, max_pools_per_authority: u32) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.authority = ctx.accounts.authority.key();
+        config.max_pools_per_authority = max_pools_per_authority;
+        config.bump = ctx.bumps.config;
+        Ok(())
+    }
+
+    /// Change how many pools one authority may have open at a time
+    /// Authorities already above a lowered limit keep their pools but can't
+    /// open more until they close enough of them
+    pub fn set_max_pools_per_authority(
+        ctx: Context<UpdateConfig>,
+        max_pools_per_authority: u32,
+    ) -> Result<()> {
+        ctx.accounts.config.max_pools_per_authority = max_pools_per_authority;
+        let clock = Clock::get()?;
+        emit!(MaxPoolsUpdated {
+            max_pools_per_authority,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Halt or resume every computation on this pool
+    /// Accounts are left untouched, and setting the current value is a no-op
+    pub fn set_paused(ctx: Context<SetPoolParams>, paused: bool) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        pool.paused = paused;
+        let clock = Clock::get()?;
+        emit!(PausedUpdated {
+            pool: pool.key(),
+            paused,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Update the pool fee rate, in basis points up to `MAX_FEE_BPS`
+    /// Swaps already queued keep the rate they were quoted with
+    pub fn update_fee_rate(ctx: Context<SetPoolParams>, fee_rate: u16) -> Result<()> {
+        validate_fee_rate(fee_rate)?;
+        let pool = &mut ctx.accounts.pool;
+        pool.fee_rate = fee_rate;
+        let clock = Clock::get()?;
+        emit!(FeeRateUpdated {
+            pool: pool.key(),
+            fee_rate,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Turn on-chain re-verification of swap results on or off
+    pub fn set_verify_swap_math(ctx: Context<SetPoolParams>, enabled: bool) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        pool.verify_swap_math = enabled;
+        let clock = Clock::get()?;
+        emit!(SwapMathVerificationUpdated {
+            pool: pool.key(),
+            enabled,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Choose whether swap fees join the reserves or accrue for LPs to claim
+    /// Only fees from later swaps follow the new mode; fee growth already
+    /// accrued stays claimable.
+    pub fn set_fee_mode(ctx: Context<SetPoolParams>, fee_mode: FeeMode) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        pool.fee_mode = fee_mode;
+        let clock = Clock::get()?;
+        emit!(FeeModeUpdated {
+            pool: pool.key(),
+            fee_mode,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Reject swaps whose input is worth less than `min_swap_notional`
+    /// The input is valued in token B, token A inputs at the spot price.
+    /// `execute_swap` checks it inside MPC so the amount stays private;
+    /// swaps whose input is only revealed at settlement (exact-out, sealed,
+    /// conditional and each multi-hop leg) are checked there with
+    /// `require_min_notional`. A swap below it fails with
+    /// `SwapBelowMinimum`. 0 disables the check.
+    pub fn set_min_swap_notional(ctx: Context<SetPoolParams>, min_swap_notional: u64) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        pool.min_swap_notional = min_swap_notional;
+        let clock = Clock::get()?;
+        emit!(MinSwapNotionalUpdated {
+            pool: pool.key(),
+            min_swap_notional,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Set the grid conditional swap trigger prices must sit on
+    /// A tick of 1 accepts any price
+    pub fn set_price_tick(ctx: Context<SetPoolParams>, price_tick: u64) -> Result<()> {
+        require!(price_tick > 0, ErrorCode::InvalidPriceTick);
+        let pool = &mut ctx.accounts.pool;
+        pool.price_tick = price_tick;
+        let clock = Clock::get()?;
+        emit!(PriceTickUpdated {
+            pool: pool.key(),
+            price_tick,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Set how many slots a swap result stays encrypted before settlement
+    /// While non-zero, swaps must go through `execute_sealed_swap` and are
+    /// settled by `finalize_swap_result`; 0 settles swaps in the callback.
+    /// Can't change while a swap is in flight, which was queued under the
+    /// old setting.
+    pub fn set_reveal_delay(ctx: Context<SetPoolParams>, reveal_delay_slots: u64) -> Result<()> {
+        require!(
+            reveal_delay_slots <= MAX_REVEAL_DELAY_SLOTS,
+            ErrorCode::RevealDelayTooHigh
+        );
+        let pool = &mut ctx.accounts.pool;
+        require!(!pool.in_flight, ErrorCode::SwapInFlight);
+        pool.reveal_delay_slots = reveal_delay_slots;
+        let clock = Clock::get()?;
+        emit!(RevealDelayUpdated {
+            pool: pool.key(),
+            reveal_delay_slots,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Set the governance token and the fee discounts its holders get
+    /// Enabled tiers (non-zero threshold) must have ascending thresholds and
+    /// non-decreasing discounts, each at most 10000 bps of the fee.
+    /// `Pubkey::default()` as the mint turns discounts off.
+    pub fn set_fee_discounts(
+        ctx: Context<SetPoolParams>,
+        governance_mint: Pubkey,
+        discount_thresholds: [u64; FEE_DISCOUNT_TIERS],
+        discount_bps: [u16; FEE_DISCOUNT_TIERS],
+    ) -> Result<()> {
+        validate_discount_schedule(&discount_thresholds, &discount_bps)?;
+
+        let pool = &mut ctx.accounts.pool;
+        pool.governance_mint = governance_mint;
+        pool.discount_thresholds = discount_thresholds;
+        pool.discount_bps = discount_bps;
+        let clock = Clock::get()?;
+        emit!(FeeDiscountsUpdated {
+            pool: pool.key(),
+            governance_mint,
+            discount_thresholds,
+            discount_bps,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Pause or resume individual pool operations (see the PAUSE_* bits)
+    pub fn set_paused_ops(ctx: Context<SetPoolParams>, paused_ops: u8) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        pool.paused_ops = paused_ops;
+        let clock = Clock::get()?;
+        emit!(PausedOpsUpdated {
+            pool: pool.key(),
+            paused_ops,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Close a drained pool and return its rent to the authority
+    /// Every LP token must be burned first; `remove_liquidity` requires the
+    /// position's fees to be claimed, so whatever the vaults still hold is
+    /// fee rounding dust no LP can claim and is swept to the authority.
+    /// The sign PDA is shared by every pool, so it is left open
+    pub fn close_pool(ctx: Context<ClosePool>) -> Result<()> {
+        let pool = &ctx.accounts.pool;
+        require!(pool.is_empty(ctx.accounts.lp_mint.supply), ErrorCode::NonEmptyAccount);
+
+        let bump = [pool.bump];
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"pool",
+            pool.token_mint_a.as_ref(),
+            pool.token_mint_b.as_ref(),
+            &bump,
+        ]];
+        let swept_a = ctx.accounts.pool_token_a.amount;
+        let swept_b = ctx.accounts.pool_token_b.amount;
+        if swept_a > 0 {
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.pool_token_a.to_account_info(),
+                to: ctx.accounts.authority_token_a.to_account_info(),
+                authority: pool.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                cpi_accounts,
+                signer_seeds,
+            );
+            token::transfer(cpi_ctx, swept_a)?;
+        }
+        if swept_b > 0 {
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.pool_token_b.to_account_info(),
+                to: ctx.accounts.authority_token_b.to_account_info(),
+                authority: pool.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                cpi_accounts,
+                signer_seeds,
+            );
+            token::transfer(cpi_ctx, swept_b)?;
+        }
+
+        ctx.accounts.pool_registry.close_pool();
+
+        let clock = Clock::get()?;
+        emit!(PoolClosed {
+            pool: pool.key(),
+            authority: ctx.accounts.authority.key(),
+            swept_a,
+            swept_b,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
         Ok(())
     }
 
@@ -39,6 +356,17 @@ pub mod private_swap {
         amount_a: u64,
         amount_b: u64,
     ) -> Result<()> {
+        let checkpoint = &mut ctx.accounts.lp_checkpoint;
+        if checkpoint.pool == Pubkey::default() {
+            // A new position starts with nothing owed
+            checkpoint.pool = ctx.accounts.pool.key();
+            checkpoint.owner = ctx.accounts.user.key();
+            checkpoint.fee_growth_a = ctx.accounts.pool.fee_growth_a;
+            checkpoint.fee_growth_b = ctx.accounts.pool.fee_growth_b;
+            checkpoint.bump = ctx.bumps.lp_checkpoint;
+        }
+        require!(checkpoint.is_current(&ctx.accounts.pool), ErrorCode::UnclaimedLpFees);
+
         // Transfer token A to pool
         let cpi_accounts_a = Transfer {
             from: ctx.accounts.user_token_a.to_account_info(),
@@ -57,32 +385,325 @@ pub mod private_swap {
         let cpi_ctx_b = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts_b);
         token::transfer(cpi_ctx_b, amount_b)?;
 
+        // Mint the depositor's share against the pre-deposit reserves
+        let lp_amount = lp_tokens_for_deposit(
+            &ctx.accounts.pool,
+            ctx.accounts.lp_mint.supply,
+            amount_a,
+            amount_b,
+        )?;
+        require!(lp_amount > 0, ErrorCode::ZeroLiquidity);
+
+        let pool = &ctx.accounts.pool;
+        let bump = [pool.bump];
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"pool",
+            pool.token_mint_a.as_ref(),
+            pool.token_mint_b.as_ref(),
+            &bump,
+        ]];
+        let cpi_accounts_mint = MintTo {
+            mint: ctx.accounts.lp_mint.to_account_info(),
+            to: ctx.accounts.user_lp_token.to_account_info(),
+            authority: pool.to_account_info(),
+        };
+        let cpi_ctx_mint = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts_mint,
+            signer_seeds,
+        );
+        token::mint_to(cpi_ctx_mint, lp_amount)?;
+
         // Update pool reserves
+        let clock = Clock::get()?;
         let pool = &mut ctx.accounts.pool;
-        pool.reserve_a = pool.reserve_a.checked_add(amount_a).unwrap();
-        pool.reserve_b = pool.reserve_b.checked_add(amount_b).unwrap();
+        pool.update_price_cumulative(clock.slot);
+        pool.reserve_a = pool.reserve_a.checked_add(amount_a).ok_or(ErrorCode::MathOverflow)?;
+        pool.reserve_b = pool.reserve_b.checked_add(amount_b).ok_or(ErrorCode::MathOverflow)?;
 
         emit!(LiquidityAdded {
             pool: pool.key(),
             amount_a,
             amount_b,
+            lp_minted: lp_amount,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Burn LP tokens and withdraw the matching share of both reserves
+    pub fn remove_liquidity(
+        ctx: Context<RemoveLiquidity>,
+        lp_amount: u64,
+    ) -> Result<()> {
+        let pool = &ctx.accounts.pool;
+        require!(ctx.accounts.lp_checkpoint.is_current(pool), ErrorCode::UnclaimedLpFees);
+        let supply = ctx.accounts.lp_mint.supply;
+        require!(lp_amount > 0 && lp_amount <= supply, ErrorCode::ZeroLiquidity);
+
+        let amount_a = pro_rata(pool.reserve_a, lp_amount, supply)?;
+        let amount_b = pro_rata(pool.reserve_b, lp_amount, supply)?;
+        require!(amount_a > 0 || amount_b > 0, ErrorCode::ZeroLiquidity);
+
+        let cpi_accounts_burn = Burn {
+            mint: ctx.accounts.lp_mint.to_account_info(),
+            from: ctx.accounts.user_lp_token.to_account_info(),
+            authority: ctx.accounts.user.to_account_info(),
+        };
+        let cpi_ctx_burn = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts_burn);
+        token::burn(cpi_ctx_burn, lp_amount)?;
+
+        let bump = [pool.bump];
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"pool",
+            pool.token_mint_a.as_ref(),
+            pool.token_mint_b.as_ref(),
+            &bump,
+        ]];
+
+        let cpi_accounts_a = Transfer {
+            from: ctx.accounts.pool_token_a.to_account_info(),
+            to: ctx.accounts.user_token_a.to_account_info(),
+            authority: pool.to_account_info(),
+        };
+        let cpi_ctx_a = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts_a,
+            signer_seeds,
+        );
+        token::transfer(cpi_ctx_a, amount_a)?;
+
+        let cpi_accounts_b = Transfer {
+            from: ctx.accounts.pool_token_b.to_account_info(),
+            to: ctx.accounts.user_token_b.to_account_info(),
+            authority: pool.to_account_info(),
+        };
+        let cpi_ctx_b = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts_b,
+            signer_seeds,
+        );
+        token::transfer(cpi_ctx_b, amount_b)?;
+
+        let clock = Clock::get()?;
+        let pool = &mut ctx.accounts.pool;
+        pool.update_price_cumulative(clock.slot);
+        pool.reserve_a = pool.reserve_a.checked_sub(amount_a).ok_or(ErrorCode::MathOverflow)?;
+        pool.reserve_b = pool.reserve_b.checked_sub(amount_b).ok_or(ErrorCode::MathOverflow)?;
+
+        emit!(LiquidityRemoved {
+            pool: pool.key(),
+            amount_a,
+            amount_b,
+            lp_burned: lp_amount,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Claim the swap fees earned by the caller's LP tokens since their last claim
+    pub fn claim_lp_fees(ctx: Context<ClaimLpFees>) -> Result<()> {
+        let pool = &ctx.accounts.pool;
+        let checkpoint = &ctx.accounts.lp_checkpoint;
+        let lp_balance = ctx.accounts.user_lp_token.amount;
+
+        let amount_a = lp_fees_owed(lp_balance, pool.fee_growth_a, checkpoint.fee_growth_a)?;
+        let amount_b = lp_fees_owed(lp_balance, pool.fee_growth_b, checkpoint.fee_growth_b)?;
+
+        let bump = [pool.bump];
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"pool",
+            pool.token_mint_a.as_ref(),
+            pool.token_mint_b.as_ref(),
+            &bump,
+        ]];
+
+        if amount_a > 0 {
+            let cpi_accounts_a = Transfer {
+                from: ctx.accounts.pool_token_a.to_account_info(),
+                to: ctx.accounts.user_token_a.to_account_info(),
+                authority: pool.to_account_info(),
+            };
+            let cpi_ctx_a = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                cpi_accounts_a,
+                signer_seeds,
+            );
+            token::transfer(cpi_ctx_a, amount_a)?;
+        }
+
+        if amount_b > 0 {
+            let cpi_accounts_b = Transfer {
+                from: ctx.accounts.pool_token_b.to_account_info(),
+                to: ctx.accounts.user_token_b.to_account_info(),
+                authority: pool.to_account_info(),
+            };
+            let cpi_ctx_b = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                cpi_accounts_b,
+                signer_seeds,
+            );
+            token::transfer(cpi_ctx_b, amount_b)?;
+        }
+
+        let (fee_growth_a, fee_growth_b) = (pool.fee_growth_a, pool.fee_growth_b);
+        let checkpoint = &mut ctx.accounts.lp_checkpoint;
+        checkpoint.fee_growth_a = fee_growth_a;
+        checkpoint.fee_growth_b = fee_growth_b;
+
+        let clock = Clock::get()?;
+        emit!(LpFeesClaimed {
+            pool: ctx.accounts.pool.key(),
+            owner: ctx.accounts.user.key(),
+            amount_a,
+            amount_b,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
         });
 
         Ok(())
     }
 
+    /// Attest that the pool's recorded reserves are backed by its vaults
+    /// Reserves and vault balances are both public, so the check runs on-chain
+    /// without a computation. The vaults also hold LP fees not yet claimed,
+    /// so a consistent pool holds at least its reserves rather than exactly
+    /// them. Any pool-owned account of the right mint is accepted, since only
+    /// the pool can move what it holds. The verdict is emitted as
+    /// `ReservesAttested` and returned for callers simulating the instruction.
+    pub fn prove_reserves(ctx: Context<ProveReserves>) -> Result<bool> {
+        let pool = &ctx.accounts.pool;
+        let vault_a = ctx.accounts.pool_token_a.amount;
+        let vault_b = ctx.accounts.pool_token_b.amount;
+        let consistent = pool.reserves_backed_by(vault_a, vault_b);
+
+        let clock = Clock::get()?;
+        emit!(ReservesAttested {
+            pool: pool.key(),
+            reserve_a: pool.reserve_a,
+            reserve_b: pool.reserve_b,
+            vault_a,
+            vault_b,
+            consistent,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(consistent)
+    }
+
+    /// Quote the output of a swap of a plaintext `amount_in` at the current
+    /// reserves, after fees
+    /// Uses `expected_swap_output`, the same math `verify_swap_math` checks
+    /// the circuit against, so a quote matches execution at unchanged
+    /// reserves. Pass the trader's governance token account to quote their
+    /// discounted fee. Nothing is written; the output is emitted as
+    /// `SwapQuoted` and returned for callers simulating the instruction.
+    pub fn quote_swap(ctx: Context<QuoteSwap>, amount_in: u64, is_a_to_b: bool) -> Result<u64> {
+        let pool = &ctx.accounts.pool;
+        let holding = ctx.accounts.governance_token.as_ref().map_or(0, |account| account.amount);
+        let (amount_out, fee_rate) = pool.quote(amount_in, is_a_to_b, holding)?;
+
+        let clock = Clock::get()?;
+        emit!(SwapQuoted {
+            pool: pool.key(),
+            amount_in,
+            amount_out,
+            is_a_to_b,
+            fee_rate,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(amount_out)
+    }
+
     /// Execute a private swap with encrypted amount
-    /// The swap amount is encrypted and processed by MPC nodes
+    /// The swap amount is encrypted and processed by MPC nodes. `fee_payer`
+    /// may be a relayer sponsoring the transaction; the swap is still the
+    /// signing trader's, who also gets the pending swap rent back. Only
+    /// `max_input` of the input token is delegated to the pool, and the
+    /// callback leaves none of it delegated (see `take_swap_input`).
     pub fn execute_swap(
         ctx: Context<ExecuteSwap>,
         computation_offset: u64,
         encrypted_amount: [u8; 64], // Encrypted swap amount
         min_output: u64,            // Minimum output (slippage protection)
+        max_input: u64,             // Most the pool may pull for the encrypted amount
         is_a_to_b: bool,            // Swap direction
+        unwrap_output: bool,        // Close a WSOL output account into native SOL on settlement
         pub_key: [u8; 32],
         nonce: u128,
     ) -> Result<()> {
-        let pool = &ctx.accounts.pool;
+        require!(!ctx.accounts.pool.paused, ErrorCode::ProgramPaused);
+        ensure_computation_fee(&ctx.accounts.fee_payer, 0)?;
+
+        require!(!ctx.accounts.pool.halted, ErrorCode::PoolHalted);
+        require!(
+            ctx.accounts.pool.paused_ops & PAUSE_SWAPS == 0,
+            ErrorCode::OperationPaused
+        );
+        require!(
+            ctx.accounts.pool.reveal_delay_slots == 0,
+            ErrorCode::SealedSwapRequired
+        );
+
+        // Governance token holders pay a reduced fee; the tier is picked from
+        // their balance on-chain and MPC only sees the resulting rate
+        let holding = ctx.accounts.governance_token.as_ref().map_or(0, |account| account.amount);
+        let fee_rate = ctx.accounts.pool.discounted_fee_rate(holding);
+
+        // Track the in-flight swap so it can be force-settled in an emergency
+        let pending_swap = &mut ctx.accounts.pending_swap;
+        pending_swap.pool = ctx.accounts.pool.key();
+        pending_swap.user = ctx.accounts.payer.key();
+        pending_swap.computation_offset = computation_offset;
+        pending_swap.is_a_to_b = is_a_to_b;
+        pending_swap.bump = ctx.bumps.pending_swap;
+        pending_swap.reserve_a = ctx.accounts.pool.reserve_a;
+        pending_swap.reserve_b = ctx.accounts.pool.reserve_b;
+        pending_swap.fee_rate = fee_rate;
+        pending_swap.min_fee = ctx.accounts.pool.min_fee;
+        pending_swap.unwrap_output = unwrap_output;
+        pending_swap.min_output = min_output;
+        pending_swap.ready_slot = 0;
+
+        // The input amount stays encrypted until the callback, so let the pool
+        // pull up to `max_input` of the input token there
+        let user_token_in = if is_a_to_b {
+            &ctx.accounts.user_token_a
+        } else {
+            &ctx.accounts.user_token_b
+        };
+        let cpi_accounts = Approve {
+            to: user_token_in.to_account_info(),
+            delegate: ctx.accounts.pool.to_account_info(),
+            authority: ctx.accounts.payer.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+        token::approve(cpi_ctx, max_input)?;
+
+        // Unwrapping closes the user's WSOL output account in the callback,
+        // where the user can't sign, so hand the pool its close authority
+        if unwrap_output {
+            let user_token_out = if is_a_to_b {
+                &ctx.accounts.user_token_b
+            } else {
+                &ctx.accounts.user_token_a
+            };
+            require_keys_eq!(user_token_out.mint, native_mint::ID, ErrorCode::OutputNotWrappedSol);
+            let cpi_accounts = SetAuthority {
+                current_authority: ctx.accounts.payer.to_account_info(),
+                account_or_mint: user_token_out.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+            token::set_authority(cpi_ctx, AuthorityType::CloseAccount, Some(ctx.accounts.pool.key()))?;
+        }
+
+        let pool = &mut ctx.accounts.pool;
+        pool.begin_swap(computation_offset, Clock::get()?.slot)?;
         
         // Build encrypted arguments for MPC
         let args = ArgBuilder::new()
@@ -93,7 +714,9 @@ pub mod private_swap {
             .plaintext_u64(pool.reserve_b)
             .plaintext_u64(min_output)
             .plaintext_bool(is_a_to_b)
-            .plaintext_u16(pool.fee_rate)
+            .plaintext_u16(fee_rate)
+            .plaintext_u64(pool.min_fee)
+            .plaintext_u64(pool.min_swap_notional)
             .build();
 
         ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
@@ -107,7 +730,17 @@ pub mod private_swap {
             vec![ExecuteSwapCallback::callback_ix(
                 computation_offset,
                 &ctx.accounts.mxe_account,
-                &[],
+                &CallbackAccountsBuilder::new()
+                    .writable(ctx.accounts.pool.key())
+                    .writable(ctx.accounts.pending_swap.key())
+                    .writable(ctx.accounts.payer.key())
+                    .writable(ctx.accounts.user_token_a.key())
+                    .writable(ctx.accounts.user_token_b.key())
+                    .writable(ctx.accounts.pool_token_a.key())
+                    .writable(ctx.accounts.pool_token_b.key())
+                    .readonly(ctx.accounts.lp_mint.key())
+                    .readonly(ctx.accounts.token_program.key())
+                    .build(),
             )?],
             1,
             0,
@@ -116,110 +749,3783 @@ pub mod private_swap {
         Ok(())
     }
 
+    /// Execute a private swap bounded by a percentage slippage instead of an
+    /// absolute `min_output`
+    /// The input amount is encrypted, so the output floor can't be fixed
+    /// when queuing. The pool's current spot price for this direction must
+    /// be within `max_slippage_bps` of the price at the reserves the client
+    /// quoted against, and the callback, once the input is revealed, rejects
+    /// any output more than `max_slippage_bps` below the quote for that input
+    /// at the quoted reserves (see `PendingSwap::output_floor`).
+    pub fn execute_swap_with_slippage(
+        ctx: Context<ExecuteSwap>,
+        computation_offset: u64,
+        encrypted_amount: [u8; 64],
+        max_input: u64,
+        quoted_reserve_a: u64,
+        quoted_reserve_b: u64,
+        max_slippage_bps: u16,
+        is_a_to_b: bool,
+        unwrap_output: bool,
+        pub_key: [u8; 32],
+        nonce: u128,
+    ) -> Result<()> {
+        require!(max_slippage_bps <= 10_000, ErrorCode::InvalidSlippage);
+        require!(
+            within_slippage(
+                &ctx.accounts.pool,
+                is_a_to_b,
+                quoted_reserve_a,
+                quoted_reserve_b,
+                max_slippage_bps,
+            ),
+            ErrorCode::SlippageExceeded
+        );
+
+        let (quoted_reserve_in, quoted_reserve_out) = if is_a_to_b {
+            (quoted_reserve_a, quoted_reserve_b)
+        } else {
+            (quoted_reserve_b, quoted_reserve_a)
+        };
+        let pending_swap = &mut ctx.accounts.pending_swap;
+        pending_swap.quoted_reserve_in = quoted_reserve_in;
+        pending_swap.quoted_reserve_out = quoted_reserve_out;
+        pending_swap.max_slippage_bps = max_slippage_bps;
+
+        execute_swap(
+            ctx,
+            computation_offset,
+            encrypted_amount,
+            0,
+            max_input,
+            is_a_to_b,
+            unwrap_output,
+            pub_key,
+            nonce,
+        )
+    }
+
     /// Callback after MPC computation completes
     #[arcium_callback(encrypted_ix = "execute_swap")]
     pub fn execute_swap_callback(
         ctx: Context<ExecuteSwapCallback>,
         output: SignedComputationOutputs<SwapOutput>,
     ) -> Result<()> {
-        let result = match output.verify_output(
-            &ctx.accounts.cluster_account,
-            &ctx.accounts.computation_account,
+        let accounts = &mut *ctx.accounts;
+        let settlement = SwapSettlement {
+            pool: &mut accounts.pool,
+            pending_swap: &accounts.pending_swap,
+            user: &accounts.user,
+            user_token_a: &accounts.user_token_a,
+            user_token_b: &accounts.user_token_b,
+            pool_token_a: &accounts.pool_token_a,
+            pool_token_b: &accounts.pool_token_b,
+            lp_mint: &accounts.lp_mint,
+            token_program: &accounts.token_program,
+        };
+        // A rejected swap still has to release the pool, so it returns Ok
+        let (amount_in, amount_out) = match output.verify_output(
+            &accounts.cluster_account,
+            &accounts.computation_account,
         ) {
-            Ok(SwapOutput { amount_in, amount_out, success }) => {
-                if !success {
-                    return Err(ErrorCode::SwapFailed.into());
-                }
-                (amount_in, amount_out)
+            Ok(SwapOutput { below_minimum: true, .. }) => {
+                return settlement.abort(ErrorCode::SwapBelowMinimum)
             }
-            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+            Ok(SwapOutput { success: false, .. }) => return settlement.abort(ErrorCode::SwapFailed),
+            Ok(SwapOutput { amount_in, amount_out, .. }) => (amount_in, amount_out),
+            Err(_) => return settlement.abort(ErrorCode::AbortedComputation),
         };
+        settlement.settle(amount_in, amount_out)
+    }
 
-        emit!(SwapExecuted {
-            amount_in: result.0,
-            amount_out: result.1,
-        });
+    /// Swap for an exact encrypted output, paying at most `max_input`
+    /// The dual of `execute_swap`: MPC back-solves the fee-inclusive input
+    /// for the desired output and fails if it exceeds `max_input`. Only
+    /// `max_input` is delegated to the pool, so settlement can never pull more.
+    pub fn execute_swap_exact_out(
+        ctx: Context<ExecuteSwapExactOut>,
+        computation_offset: u64,
+        encrypted_amount_out: [u8; 64], // Encrypted desired output
+        max_input: u64,                 // Maximum input (slippage protection)
+        is_a_to_b: bool,                // Swap direction
+        unwrap_output: bool,            // Close a WSOL output account into native SOL on settlement
+        pub_key: [u8; 32],
+        nonce: u128,
+    ) -> Result<()> {
+        require!(!ctx.accounts.pool.paused, ErrorCode::ProgramPaused);
+        ensure_computation_fee(&ctx.accounts.fee_payer, 0)?;
 
-        Ok(())
-    }
-}
+        require!(!ctx.accounts.pool.halted, ErrorCode::PoolHalted);
+        require!(
+            ctx.accounts.pool.paused_ops & PAUSE_SWAPS == 0,
+            ErrorCode::OperationPaused
+        );
+        require!(
+            ctx.accounts.pool.reveal_delay_slots == 0,
+            ErrorCode::SealedSwapRequired
+        );
 
-// ============ Account Structures ============
+        let holding = ctx.accounts.governance_token.as_ref().map_or(0, |account| account.amount);
+        let fee_rate = ctx.accounts.pool.discounted_fee_rate(holding);
 
-#[account]
-pub struct SwapPool {
-    pub authority: Pubkey,
-    pub token_mint_a: Pubkey,
-    pub token_mint_b: Pubkey,
-    pub reserve_a: u64,
-    pub reserve_b: u64,
-    pub fee_rate: u16,
-    pub bump: u8,
-    pub total_swaps: u64,
-}
+        // The output is exact, so there is no output floor to hold it to
+        let pending_swap = &mut ctx.accounts.pending_swap;
+        pending_swap.pool = ctx.accounts.pool.key();
+        pending_swap.user = ctx.accounts.payer.key();
+        pending_swap.computation_offset = computation_offset;
+        pending_swap.is_a_to_b = is_a_to_b;
+        pending_swap.bump = ctx.bumps.pending_swap;
+        pending_swap.reserve_a = ctx.accounts.pool.reserve_a;
+        pending_swap.reserve_b = ctx.accounts.pool.reserve_b;
+        pending_swap.fee_rate = fee_rate;
+        pending_swap.min_fee = ctx.accounts.pool.min_fee;
+        pending_swap.unwrap_output = unwrap_output;
+        pending_swap.min_output = 0;
+        pending_swap.ready_slot = 0;
 
-impl SwapPool {
-    pub const SIZE: usize = 8 + 32 + 32 + 32 + 8 + 8 + 2 + 1 + 8;
-}
+        let user_token_in = if is_a_to_b {
+            &ctx.accounts.user_token_a
+        } else {
+            &ctx.accounts.user_token_b
+        };
+        let cpi_accounts = Approve {
+            to: user_token_in.to_account_info(),
+            delegate: ctx.accounts.pool.to_account_info(),
+            authority: ctx.accounts.payer.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+        token::approve(cpi_ctx, max_input)?;
 
-// ============ Instruction Contexts ============
+        if unwrap_output {
+            let user_token_out = if is_a_to_b {
+                &ctx.accounts.user_token_b
+            } else {
+                &ctx.accounts.user_token_a
+            };
+            require_keys_eq!(user_token_out.mint, native_mint::ID, ErrorCode::OutputNotWrappedSol);
+            let cpi_accounts = SetAuthority {
+                current_authority: ctx.accounts.payer.to_account_info(),
+                account_or_mint: user_token_out.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+            token::set_authority(cpi_ctx, AuthorityType::CloseAccount, Some(ctx.accounts.pool.key()))?;
+        }
 
-#[derive(Accounts)]
-pub struct InitPool<'info> {
-    #[account(mut)]
-    pub authority: Signer<'info>,
-    
-    #[account(
-        init,
-        payer = authority,
-        space = 8 + SwapPool::SIZE,
-        seeds = [b"pool", token_mint_a.key().as_ref(), token_mint_b.key().as_ref()],
-        bump,
-    )]
-    pub pool: Account<'info, SwapPool>,
-    
-    /// CHECK: Token mint A
-    pub token_mint_a: AccountInfo<'info>,
-    /// CHECK: Token mint B
-    pub token_mint_b: AccountInfo<'info>,
-    
-    pub system_program: Program<'info, System>,
-}
+        let pool = &mut ctx.accounts.pool;
+        pool.begin_swap(computation_offset, Clock::get()?.slot)?;
 
-#[derive(Accounts)]
-pub struct AddLiquidity<'info> {
-    #[account(mut)]
-    pub user: Signer<'info>,
-    
-    #[account(mut)]
-    pub pool: Account<'info, SwapPool>,
-    
-    #[account(mut)]
-    pub user_token_a: Account<'info, TokenAccount>,
-    #[account(mut)]
-    pub user_token_b: Account<'info, TokenAccount>,
-    #[account(mut)]
-    pub pool_token_a: Account<'info, TokenAccount>,
-    #[account(mut)]
-    pub pool_token_b: Account<'info, TokenAccount>,
-    
-    pub token_program: Program<'info, Token>,
-}
+        let args = ArgBuilder::new()
+            .x25519_pubkey(pub_key)
+            .plaintext_u128(nonce)
+            .encrypted_bytes(encrypted_amount_out)
+            .plaintext_u64(pool.reserve_a)
+            .plaintext_u64(pool.reserve_b)
+            .plaintext_u64(max_input)
+            .plaintext_bool(is_a_to_b)
+            .plaintext_u16(fee_rate)
+            .plaintext_u64(pool.min_fee)
+            .build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![ExecuteSwapExactOutCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &CallbackAccountsBuilder::new()
+                    .writable(ctx.accounts.pool.key())
+                    .writable(ctx.accounts.pending_swap.key())
+                    .writable(ctx.accounts.payer.key())
+                    .writable(ctx.accounts.user_token_a.key())
+                    .writable(ctx.accounts.user_token_b.key())
+                    .writable(ctx.accounts.pool_token_a.key())
+                    .writable(ctx.accounts.pool_token_b.key())
+                    .readonly(ctx.accounts.lp_mint.key())
+                    .readonly(ctx.accounts.token_program.key())
+                    .build(),
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Callback after an exact-output swap computation completes
+    #[arcium_callback(encrypted_ix = "execute_swap_exact_out")]
+    pub fn execute_swap_exact_out_callback(
+        ctx: Context<ExecuteSwapExactOutCallback>,
+        output: SignedComputationOutputs<SwapExactOutOutput>,
+    ) -> Result<()> {
+        let accounts = &mut *ctx.accounts;
+        let settlement = SwapSettlement {
+            pool: &mut accounts.pool,
+            pending_swap: &accounts.pending_swap,
+            user: &accounts.user,
+            user_token_a: &accounts.user_token_a,
+            user_token_b: &accounts.user_token_b,
+            pool_token_a: &accounts.pool_token_a,
+            pool_token_b: &accounts.pool_token_b,
+            lp_mint: &accounts.lp_mint,
+            token_program: &accounts.token_program,
+        };
+        let (amount_in, amount_out) = match output.verify_output(
+            &accounts.cluster_account,
+            &accounts.computation_account,
+        ) {
+            Ok(SwapExactOutOutput { success: false, .. }) => {
+                return settlement.abort(ErrorCode::SwapFailed)
+            }
+            Ok(SwapExactOutOutput { amount_in, amount_out, .. }) => (amount_in, amount_out),
+            Err(_) => return settlement.abort(ErrorCode::AbortedComputation),
+        };
+        settlement.settle_exact_out(amount_in, amount_out)
+    }
+
+    /// Execute a private swap whose result stays encrypted for the pool's
+    /// reveal delay, so the amounts aren't public in the slot they trade
+    /// The `max_input` approval stays in place until `finalize_swap_result`
+    /// settles, or is used up at once if the circuit rejects the swap
+    pub fn execute_sealed_swap(
+        ctx: Context<ExecuteSealedSwap>,
+        computation_offset: u64,
+        encrypted_amount: [u8; 64], // Encrypted swap amount
+        min_output: u64,            // Minimum output (slippage protection)
+        max_input: u64,             // Most the pool may pull for the encrypted amount
+        is_a_to_b: bool,            // Swap direction
+        unwrap_output: bool,        // Close a WSOL output account into native SOL on settlement
+        pub_key: [u8; 32],
+        nonce: u128,
+    ) -> Result<()> {
+        require!(!ctx.accounts.pool.paused, ErrorCode::ProgramPaused);
+        ensure_computation_fee(&ctx.accounts.fee_payer, 0)?;
+
+        require!(!ctx.accounts.pool.halted, ErrorCode::PoolHalted);
+        require!(
+            ctx.accounts.pool.paused_ops & PAUSE_SWAPS == 0,
+            ErrorCode::OperationPaused
+        );
+        require!(
+            ctx.accounts.pool.reveal_delay_slots > 0,
+            ErrorCode::SwapNotSealed
+        );
+
+        // Governance token holders pay a reduced fee; the tier is picked from
+        // their balance on-chain and MPC only sees the resulting rate
+        let holding = ctx.accounts.governance_token.as_ref().map_or(0, |account| account.amount);
+        let fee_rate = ctx.accounts.pool.discounted_fee_rate(holding);
+
+        // Track the in-flight swap so it can be force-settled in an emergency
+        let pending_swap = &mut ctx.accounts.pending_swap;
+        pending_swap.pool = ctx.accounts.pool.key();
+        pending_swap.user = ctx.accounts.payer.key();
+        pending_swap.computation_offset = computation_offset;
+        pending_swap.is_a_to_b = is_a_to_b;
+        pending_swap.bump = ctx.bumps.pending_swap;
+        pending_swap.reserve_a = ctx.accounts.pool.reserve_a;
+        pending_swap.reserve_b = ctx.accounts.pool.reserve_b;
+        pending_swap.fee_rate = fee_rate;
+        pending_swap.min_fee = ctx.accounts.pool.min_fee;
+        pending_swap.unwrap_output = unwrap_output;
+        pending_swap.min_output = min_output;
+        pending_swap.ready_slot = 0;
+
+        // The input amount stays encrypted until the sealed result is revealed,
+        // so let the pool pull up to `max_input` of the input token when
+        // `finalize_swap_result` settles
+        let user_token_in = if is_a_to_b {
+            &ctx.accounts.user_token_a
+        } else {
+            &ctx.accounts.user_token_b
+        };
+        let cpi_accounts = Approve {
+            to: user_token_in.to_account_info(),
+            delegate: ctx.accounts.pool.to_account_info(),
+            authority: ctx.accounts.payer.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+        token::approve(cpi_ctx, max_input)?;
+
+        // Unwrapping closes the user's WSOL output account in the callback,
+        // where the user can't sign, so hand the pool its close authority
+        if unwrap_output {
+            let user_token_out = if is_a_to_b {
+                &ctx.accounts.user_token_b
+            } else {
+                &ctx.accounts.user_token_a
+            };
+            require_keys_eq!(user_token_out.mint, native_mint::ID, ErrorCode::OutputNotWrappedSol);
+            let cpi_accounts = SetAuthority {
+                current_authority: ctx.accounts.payer.to_account_info(),
+                account_or_mint: user_token_out.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+            token::set_authority(cpi_ctx, AuthorityType::CloseAccount, Some(ctx.accounts.pool.key()))?;
+        }
+
+        let pool = &mut ctx.accounts.pool;
+        pool.begin_swap(computation_offset, Clock::get()?.slot)?;
+        
+        // Build encrypted arguments for MPC
+        let args = ArgBuilder::new()
+            .x25519_pubkey(pub_key)
+            .plaintext_u128(nonce)
+            .encrypted_bytes(encrypted_amount)
+            .plaintext_u64(pool.reserve_a)
+            .plaintext_u64(pool.reserve_b)
+            .plaintext_u64(min_output)
+            .plaintext_bool(is_a_to_b)
+            .plaintext_u16(fee_rate)
+            .plaintext_u64(pool.min_fee)
+            .build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        // Queue the MPC computation
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![ExecuteSwapSealedCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &CallbackAccountsBuilder::new()
+                    .writable(ctx.accounts.pool.key())
+                    .writable(ctx.accounts.pending_swap.key())
+                    .writable(ctx.accounts.payer.key())
+                    .writable(ctx.accounts.user_token_a.key())
+                    .writable(ctx.accounts.user_token_b.key())
+                    .writable(ctx.accounts.pool_token_a.key())
+                    .writable(ctx.accounts.pool_token_b.key())
+                    .readonly(ctx.accounts.token_program.key())
+                    .build(),
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Store a sealed swap result until the pool's reveal delay has passed
+    #[arcium_callback(encrypted_ix = "execute_swap_sealed")]
+    pub fn execute_swap_sealed_callback(
+        ctx: Context<ExecuteSwapSealedCallback>,
+        output: SignedComputationOutputs<ExecuteSwapSealedOutput>,
+    ) -> Result<()> {
+        let sealed_result = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(ExecuteSwapSealedOutput { success: false, .. }) => {
+                return abort_sealed_swap(ctx.accounts, ErrorCode::SwapFailed)
+            }
+            Ok(ExecuteSwapSealedOutput { result, .. }) => result,
+            Err(_) => return abort_sealed_swap(ctx.accounts, ErrorCode::AbortedComputation),
+        };
+
+        let clock = Clock::get()?;
+        let ready_slot = clock
+            .slot
+            .checked_add(ctx.accounts.pool.reveal_delay_slots)
+            .ok_or(ErrorCode::MathOverflow)?;
+        let pending_swap = &mut ctx.accounts.pending_swap;
+        pending_swap.sealed_result = sealed_result.ciphertexts;
+        pending_swap.sealed_nonce = sealed_result.nonce;
+        pending_swap.ready_slot = ready_slot;
+
+        // Let the pool trade on while the result is sealed
+        ctx.accounts.pool.unlock(pending_swap.computation_offset)?;
+
+        emit!(SwapSealed {
+            pool: pending_swap.pool,
+            pending_swap: pending_swap.key(),
+            ready_slot,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Reveal and settle a sealed swap once its reveal delay has passed
+    /// Anyone may call this, so a keeper can finalize on the trader's behalf.
+    /// The pool was released while the result was sealed, so this holds it
+    /// again until the reveal settles.
+    pub fn finalize_swap_result(ctx: Context<FinalizeSwapResult>, computation_offset: u64) -> Result<()> {
+        require!(!ctx.accounts.pool.paused, ErrorCode::ProgramPaused);
+        ensure_computation_fee(&ctx.accounts.payer, 0)?;
+
+        let current_slot = Clock::get()?.slot;
+        let pending_swap = &ctx.accounts.pending_swap;
+        require!(pending_swap.ready_slot != 0, ErrorCode::SwapNotSealed);
+        require!(
+            current_slot >= pending_swap.ready_slot,
+            ErrorCode::RevealDelayNotElapsed
+        );
+        ctx.accounts.pool.lock(pending_swap.computation_offset, current_slot)?;
+
+        let args = ArgBuilder::new()
+            .plaintext_u128(pending_swap.sealed_nonce)
+            .account(
+                pending_swap.key(),
+                PendingSwap::SEALED_RESULT_OFFSET,
+                PendingSwap::SEALED_RESULT_LEN,
+            )
+            .build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![RevealSwapResultCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &CallbackAccountsBuilder::new()
+                    .writable(ctx.accounts.pool.key())
+                    .writable(ctx.accounts.pending_swap.key())
+                    .writable(ctx.accounts.user.key())
+                    .writable(ctx.accounts.user_token_a.key())
+                    .writable(ctx.accounts.user_token_b.key())
+                    .writable(ctx.accounts.pool_token_a.key())
+                    .writable(ctx.accounts.pool_token_b.key())
+                    .readonly(ctx.accounts.lp_mint.key())
+                    .readonly(ctx.accounts.token_program.key())
+                    .build(),
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Settle a sealed swap with its revealed amounts
+    #[arcium_callback(encrypted_ix = "reveal_swap_result")]
+    pub fn reveal_swap_result_callback(
+        ctx: Context<RevealSwapResultCallback>,
+        output: SignedComputationOutputs<RevealSwapResultOutput>,
+    ) -> Result<()> {
+        let accounts = &mut *ctx.accounts;
+        let settlement = SwapSettlement {
+            pool: &mut accounts.pool,
+            pending_swap: &accounts.pending_swap,
+            user: &accounts.user,
+            user_token_a: &accounts.user_token_a,
+            user_token_b: &accounts.user_token_b,
+            pool_token_a: &accounts.pool_token_a,
+            pool_token_b: &accounts.pool_token_b,
+            lp_mint: &accounts.lp_mint,
+            token_program: &accounts.token_program,
+        };
+        let (amount_in, amount_out) = match output.verify_output(
+            &accounts.cluster_account,
+            &accounts.computation_account,
+        ) {
+            Ok(RevealSwapResultOutput { amount_in, amount_out }) => (amount_in, amount_out),
+            Err(_) => return settlement.abort(ErrorCode::AbortedComputation),
+        };
+        settlement.settle_revealed(amount_in, amount_out)
+    }
+
+    /// Route an exact-output swap through two pools (e.g. A -> B -> C)
+    /// The user fixes the encrypted final `amount_out` and a plaintext
+    /// `max_input`; MPC back-solves both hops and the route settles like
+    /// `execute_multi_hop_swap`, with the intermediate token moving vault to
+    /// vault. Only `max_input` of the input token is delegated.
+    pub fn route_swap_exact_out(
+        ctx: Context<RouteSwapExactOut>,
+        computation_offset: u64,
+        encrypted_amount_out: [u8; 64], // Encrypted desired final output
+        max_input: u64,                 // Maximum input (slippage protection)
+        first_is_a_to_b: bool,          // Direction through the first pool
+        second_is_a_to_b: bool,         // Direction through the second pool
+        pub_key: [u8; 32],
+        nonce: u128,
+    ) -> Result<()> {
+        let first_pool = &ctx.accounts.first_pool;
+        let second_pool = &ctx.accounts.second_pool;
+        require!(!first_pool.paused && !second_pool.paused, ErrorCode::ProgramPaused);
+        ensure_computation_fee(&ctx.accounts.payer, 0)?;
+        require!(!first_pool.halted && !second_pool.halted, ErrorCode::PoolHalted);
+        require!(
+            (first_pool.paused_ops | second_pool.paused_ops) & PAUSE_SWAPS == 0,
+            ErrorCode::OperationPaused
+        );
+        // The hops settle as soon as MPC returns, which a reveal delay forbids
+        require!(
+            first_pool.reveal_delay_slots == 0 && second_pool.reveal_delay_slots == 0,
+            ErrorCode::SealedSwapRequired
+        );
+        require_keys_neq!(first_pool.key(), second_pool.key(), ErrorCode::InvalidRoute);
+
+        let (mint_in, intermediate_mint, mint_out) =
+            route_mints(first_pool, first_is_a_to_b, second_pool, second_is_a_to_b)?;
+        require_keys_eq!(ctx.accounts.user_token_in.mint, mint_in, ErrorCode::WrongTokenMint);
+        require_keys_eq!(ctx.accounts.user_token_out.mint, mint_out, ErrorCode::WrongTokenMint);
+        require_keys_eq!(ctx.accounts.first_vault_in.mint, mint_in, ErrorCode::WrongTokenMint);
+        require_keys_eq!(ctx.accounts.first_vault_out.mint, intermediate_mint, ErrorCode::WrongTokenMint);
+        require_keys_eq!(ctx.accounts.second_vault_in.mint, intermediate_mint, ErrorCode::WrongTokenMint);
+        require_keys_eq!(ctx.accounts.second_vault_out.mint, mint_out, ErrorCode::WrongTokenMint);
+
+        let (first_reserve_in, first_reserve_out) = hop_reserves(first_pool, first_is_a_to_b);
+        let (second_reserve_in, second_reserve_out) = hop_reserves(second_pool, second_is_a_to_b);
+        // Each hop gets its pool's holder discount, as a single swap would;
+        // the pools may use different governance tokens, so a hop only counts
+        // the holding if the account is of its pool's governance mint
+        let governance_token = ctx.accounts.governance_token.as_ref();
+        let holding = |pool: &SwapPool| {
+            governance_token
+                .filter(|account| account.mint == pool.governance_mint)
+                .map_or(0, |account| account.amount)
+        };
+        let first_fee_rate = first_pool.discounted_fee_rate(holding(first_pool));
+        let second_fee_rate = second_pool.discounted_fee_rate(holding(second_pool));
+        let (first_min_fee, second_min_fee) = (first_pool.min_fee, second_pool.min_fee);
+
+        // The output is fixed in MPC, so the only plaintext bound is the
+        // delegation below; `min_output` stays zero
+        let pending = &mut ctx.accounts.pending_multi_hop;
+        pending.user = ctx.accounts.payer.key();
+        pending.first_pool = first_pool.key();
+        pending.second_pool = second_pool.key();
+        pending.computation_offset = computation_offset;
+        pending.first_is_a_to_b = first_is_a_to_b;
+        pending.second_is_a_to_b = second_is_a_to_b;
+        pending.bump = ctx.bumps.pending_multi_hop;
+        pending.min_output = 0;
+        pending.first_reserve_in = first_reserve_in;
+        pending.first_reserve_out = first_reserve_out;
+        pending.first_fee_rate = first_fee_rate;
+        pending.first_min_fee = first_min_fee;
+        pending.second_reserve_in = second_reserve_in;
+        pending.second_reserve_out = second_reserve_out;
+        pending.second_fee_rate = second_fee_rate;
+        pending.second_min_fee = second_min_fee;
+
+        // Capping the delegation at `max_input` enforces it on settlement
+        // even if the revealed input were larger
+        let cpi_accounts = Approve {
+            to: ctx.accounts.user_token_in.to_account_info(),
+            delegate: ctx.accounts.first_pool.to_account_info(),
+            authority: ctx.accounts.payer.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+        token::approve(cpi_ctx, max_input)?;
+
+        let current_slot = Clock::get()?.slot;
+        ctx.accounts.first_pool.begin_swap(computation_offset, current_slot)?;
+        ctx.accounts.second_pool.begin_swap(computation_offset, current_slot)?;
+
+        let args = ArgBuilder::new()
+            .x25519_pubkey(pub_key)
+            .plaintext_u128(nonce)
+            .encrypted_bytes(encrypted_amount_out)
+            .plaintext_u64(first_reserve_in)
+            .plaintext_u64(first_reserve_out)
+            .plaintext_u16(first_fee_rate)
+            .plaintext_u64(first_min_fee)
+            .plaintext_u64(second_reserve_in)
+            .plaintext_u64(second_reserve_out)
+            .plaintext_u16(second_fee_rate)
+            .plaintext_u64(second_min_fee)
+            .plaintext_u64(max_input)
+            .build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![RouteSwapExactOutCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &CallbackAccountsBuilder::new()
+                    .writable(ctx.accounts.first_pool.key())
+                    .writable(ctx.accounts.second_pool.key())
+                    .writable(ctx.accounts.pending_multi_hop.key())
+                    .writable(ctx.accounts.payer.key())
+                    .writable(ctx.accounts.user_token_in.key())
+                    .writable(ctx.accounts.user_token_out.key())
+                    .writable(ctx.accounts.first_vault_in.key())
+                    .writable(ctx.accounts.first_vault_out.key())
+                    .writable(ctx.accounts.second_vault_in.key())
+                    .writable(ctx.accounts.second_vault_out.key())
+                    .readonly(ctx.accounts.first_lp_mint.key())
+                    .readonly(ctx.accounts.second_lp_mint.key())
+                    .readonly(ctx.accounts.token_program.key())
+                    .build(),
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Callback settling both hops of an exact-output route
+    #[arcium_callback(encrypted_ix = "route_swap_exact_out")]
+    pub fn route_swap_exact_out_callback(
+        ctx: Context<RouteSwapExactOutCallback>,
+        output: SignedComputationOutputs<RouteExactOutOutput>,
+    ) -> Result<()> {
+        let accounts = &mut *ctx.accounts;
+        let settlement = MultiHopSettlement {
+            first_pool: &mut accounts.first_pool,
+            second_pool: &mut accounts.second_pool,
+            pending_multi_hop: &accounts.pending_multi_hop,
+            user_token_in: &accounts.user_token_in,
+            user_token_out: &accounts.user_token_out,
+            first_vault_in: &accounts.first_vault_in,
+            first_vault_out: &accounts.first_vault_out,
+            second_vault_in: &accounts.second_vault_in,
+            second_vault_out: &accounts.second_vault_out,
+            first_lp_mint: &accounts.first_lp_mint,
+            second_lp_mint: &accounts.second_lp_mint,
+            token_program: &accounts.token_program,
+        };
+        let (amount_in, intermediate, amount_out) = match output.verify_output(
+            &accounts.cluster_account,
+            &accounts.computation_account,
+        ) {
+            Ok(RouteExactOutOutput { success: false, .. }) => return settlement.abort(ErrorCode::SwapFailed),
+            Ok(RouteExactOutOutput { amount_in, intermediate, amount_out, .. }) => {
+                (amount_in, intermediate, amount_out)
+            }
+            Err(_) => return settlement.abort(ErrorCode::AbortedComputation),
+        };
+        if !settlement.settle_exact_out(amount_in, intermediate, amount_out)? {
+            return Ok(());
+        }
+
+        let clock = Clock::get()?;
+        emit!(RouteSwapExecuted {
+            first_pool: accounts.first_pool.key(),
+            second_pool: accounts.second_pool.key(),
+            amount_in,
+            amount_out,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Swap through two pools in one computation (e.g. A -> B -> C)
+    /// The encrypted input goes through both hops inside MPC and only the
+    /// end-to-end output is held to `min_output`. The user never holds the
+    /// intermediate token: on settlement the first pool pays it straight from
+    /// its output vault into the second pool's input vault.
+    pub fn execute_multi_hop_swap(
+        ctx: Context<ExecuteMultiHopSwap>,
+        computation_offset: u64,
+        encrypted_amount: [u8; 64], // Encrypted input amount
+        min_output: u64,            // Minimum final output (slippage protection)
+        max_input: u64,             // Most the first pool may pull for the encrypted amount
+        first_is_a_to_b: bool,      // Direction through the first pool
+        second_is_a_to_b: bool,     // Direction through the second pool
+        pub_key: [u8; 32],
+        nonce: u128,
+    ) -> Result<()> {
+        let first_pool = &ctx.accounts.first_pool;
+        let second_pool = &ctx.accounts.second_pool;
+        require!(!first_pool.paused && !second_pool.paused, ErrorCode::ProgramPaused);
+        ensure_computation_fee(&ctx.accounts.payer, 0)?;
+        require!(!first_pool.halted && !second_pool.halted, ErrorCode::PoolHalted);
+        require!(
+            (first_pool.paused_ops | second_pool.paused_ops) & PAUSE_SWAPS == 0,
+            ErrorCode::OperationPaused
+        );
+        // The hops settle as soon as MPC returns, which a reveal delay forbids
+        require!(
+            first_pool.reveal_delay_slots == 0 && second_pool.reveal_delay_slots == 0,
+            ErrorCode::SealedSwapRequired
+        );
+        require_keys_neq!(first_pool.key(), second_pool.key(), ErrorCode::InvalidRoute);
+
+        let (mint_in, intermediate_mint, mint_out) =
+            route_mints(first_pool, first_is_a_to_b, second_pool, second_is_a_to_b)?;
+        require_keys_eq!(ctx.accounts.user_token_in.mint, mint_in, ErrorCode::WrongTokenMint);
+        require_keys_eq!(ctx.accounts.user_token_out.mint, mint_out, ErrorCode::WrongTokenMint);
+        require_keys_eq!(ctx.accounts.first_vault_in.mint, mint_in, ErrorCode::WrongTokenMint);
+        require_keys_eq!(ctx.accounts.first_vault_out.mint, intermediate_mint, ErrorCode::WrongTokenMint);
+        require_keys_eq!(ctx.accounts.second_vault_in.mint, intermediate_mint, ErrorCode::WrongTokenMint);
+        require_keys_eq!(ctx.accounts.second_vault_out.mint, mint_out, ErrorCode::WrongTokenMint);
+
+        let (first_reserve_in, first_reserve_out) = hop_reserves(first_pool, first_is_a_to_b);
+        let (second_reserve_in, second_reserve_out) = hop_reserves(second_pool, second_is_a_to_b);
+        // Each hop gets its pool's holder discount, as a single swap would;
+        // the pools may use different governance tokens, so a hop only counts
+        // the holding if the account is of its pool's governance mint
+        let governance_token = ctx.accounts.governance_token.as_ref();
+        let holding = |pool: &SwapPool| {
+            governance_token
+                .filter(|account| account.mint == pool.governance_mint)
+                .map_or(0, |account| account.amount)
+        };
+        let first_fee_rate = first_pool.discounted_fee_rate(holding(first_pool));
+        let second_fee_rate = second_pool.discounted_fee_rate(holding(second_pool));
+        let (first_min_fee, second_min_fee) = (first_pool.min_fee, second_pool.min_fee);
+
+        let pending = &mut ctx.accounts.pending_multi_hop;
+        pending.user = ctx.accounts.payer.key();
+        pending.first_pool = first_pool.key();
+        pending.second_pool = second_pool.key();
+        pending.computation_offset = computation_offset;
+        pending.first_is_a_to_b = first_is_a_to_b;
+        pending.second_is_a_to_b = second_is_a_to_b;
+        pending.bump = ctx.bumps.pending_multi_hop;
+        pending.min_output = min_output;
+        pending.first_reserve_in = first_reserve_in;
+        pending.first_reserve_out = first_reserve_out;
+        pending.first_fee_rate = first_fee_rate;
+        pending.first_min_fee = first_min_fee;
+        pending.second_reserve_in = second_reserve_in;
+        pending.second_reserve_out = second_reserve_out;
+        pending.second_fee_rate = second_fee_rate;
+        pending.second_min_fee = second_min_fee;
+
+        // As in `execute_swap`, the first pool may pull up to `max_input` of
+        // the input token once the amount is revealed
+        let cpi_accounts = Approve {
+            to: ctx.accounts.user_token_in.to_account_info(),
+            delegate: ctx.accounts.first_pool.to_account_info(),
+            authority: ctx.accounts.payer.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+        token::approve(cpi_ctx, max_input)?;
+
+        // Each hop holds its pool like a single swap until the callback
+        let current_slot = Clock::get()?.slot;
+        ctx.accounts.first_pool.begin_swap(computation_offset, current_slot)?;
+        ctx.accounts.second_pool.begin_swap(computation_offset, current_slot)?;
+
+        let args = ArgBuilder::new()
+            .x25519_pubkey(pub_key)
+            .plaintext_u128(nonce)
+            .encrypted_bytes(encrypted_amount)
+            .plaintext_u64(first_reserve_in)
+            .plaintext_u64(first_reserve_out)
+            .plaintext_u16(first_fee_rate)
+            .plaintext_u64(first_min_fee)
+            .plaintext_u64(second_reserve_in)
+            .plaintext_u64(second_reserve_out)
+            .plaintext_u16(second_fee_rate)
+            .plaintext_u64(second_min_fee)
+            .plaintext_u64(min_output)
+            .build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![ExecuteMultiHopSwapCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &CallbackAccountsBuilder::new()
+                    .writable(ctx.accounts.first_pool.key())
+                    .writable(ctx.accounts.second_pool.key())
+                    .writable(ctx.accounts.pending_multi_hop.key())
+                    .writable(ctx.accounts.payer.key())
+                    .writable(ctx.accounts.user_token_in.key())
+                    .writable(ctx.accounts.user_token_out.key())
+                    .writable(ctx.accounts.first_vault_in.key())
+                    .writable(ctx.accounts.first_vault_out.key())
+                    .writable(ctx.accounts.second_vault_in.key())
+                    .writable(ctx.accounts.second_vault_out.key())
+                    .readonly(ctx.accounts.first_lp_mint.key())
+                    .readonly(ctx.accounts.second_lp_mint.key())
+                    .readonly(ctx.accounts.token_program.key())
+                    .build(),
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Callback settling both hops of a multi-hop swap
+    #[arcium_callback(encrypted_ix = "execute_multi_hop_swap")]
+    pub fn execute_multi_hop_swap_callback(
+        ctx: Context<ExecuteMultiHopSwapCallback>,
+        output: SignedComputationOutputs<ExecuteMultiHopSwapOutput>,
+    ) -> Result<()> {
+        let accounts = &mut *ctx.accounts;
+        let settlement = MultiHopSettlement {
+            first_pool: &mut accounts.first_pool,
+            second_pool: &mut accounts.second_pool,
+            pending_multi_hop: &accounts.pending_multi_hop,
+            user_token_in: &accounts.user_token_in,
+            user_token_out: &accounts.user_token_out,
+            first_vault_in: &accounts.first_vault_in,
+            first_vault_out: &accounts.first_vault_out,
+            second_vault_in: &accounts.second_vault_in,
+            second_vault_out: &accounts.second_vault_out,
+            first_lp_mint: &accounts.first_lp_mint,
+            second_lp_mint: &accounts.second_lp_mint,
+            token_program: &accounts.token_program,
+        };
+        let (amount_in, intermediate, amount_out) = match output.verify_output(
+            &accounts.cluster_account,
+            &accounts.computation_account,
+        ) {
+            Ok(ExecuteMultiHopSwapOutput { success: false, .. }) => return settlement.abort(ErrorCode::SwapFailed),
+            Ok(ExecuteMultiHopSwapOutput { amount_in, intermediate, amount_out, .. }) => {
+                (amount_in, intermediate, amount_out)
+            }
+            Err(_) => return settlement.abort(ErrorCode::AbortedComputation),
+        };
+        if !settlement.settle(amount_in, intermediate, amount_out)? {
+            return Ok(());
+        }
+
+        let clock = Clock::get()?;
+        emit!(MultiHopSwapExecuted {
+            first_pool: accounts.first_pool.key(),
+            second_pool: accounts.second_pool.key(),
+            amount_in,
+            amount_out,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Free a pool held by a swap whose callback never landed
+    /// Permissionless once `swap_lock_expired`. Closes the pending swap to
+    /// its user, so a late callback fails instead of settling, and leaves the
+    /// pool open for the next swap; nothing else needs to move, since the
+    /// input was only delegated.
+    pub fn release_stale_swap(ctx: Context<ReleaseStaleSwap>) -> Result<()> {
+        let current_slot = Clock::get()?.slot;
+        let pool = &mut ctx.accounts.pool;
+        require!(pool.swap_lock_expired(current_slot), ErrorCode::SwapNotStale);
+        let computation_offset = ctx.accounts.pending_swap.computation_offset;
+        pool.end_swap(computation_offset)?;
+
+        let clock = Clock::get()?;
+        emit!(StaleSwapReleased {
+            pool: pool.key(),
+            computation_offset,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Free both pools held by a multi-hop swap whose callback never landed
+    /// Same rules as `release_stale_swap`, applied to each hop
+    pub fn release_stale_multi_hop(ctx: Context<ReleaseStaleMultiHop>) -> Result<()> {
+        let current_slot = Clock::get()?.slot;
+        let computation_offset = ctx.accounts.pending_multi_hop.computation_offset;
+        let accounts = &mut *ctx.accounts;
+        for pool in [&mut accounts.first_pool, &mut accounts.second_pool] {
+            require!(pool.swap_lock_expired(current_slot), ErrorCode::SwapNotStale);
+            pool.end_swap(computation_offset)?;
+
+            let clock = Clock::get()?;
+            emit!(StaleSwapReleased {
+                pool: pool.key(),
+                computation_offset,
+                slot: clock.slot,
+                unix_timestamp: clock.unix_timestamp,
+            });
+        }
+        Ok(())
+    }
+
+    /// Emergency kill-switch: settle every in-flight swap and halt the pool
+    /// Remaining accounts are `(pending_swap, user)` pairs. Each pending swap
+    /// is closed with its deposit refunded to the user, so a late callback for
+    /// it fails instead of touching the pool, and a swap can't be settled twice.
+    /// A single stuck swap doesn't need this; see `release_stale_swap`.
+    pub fn force_settle<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ForceSettle<'info>>,
+    ) -> Result<()> {
+        require!(
+            ctx.remaining_accounts.len() % 2 == 0,
+            ErrorCode::InvalidPendingSwap
+        );
+
+        let pool_key = ctx.accounts.pool.key();
+        let mut settled = 0u32;
+        for pair in ctx.remaining_accounts.chunks(2) {
+            // Fails for accounts that were already closed, by either the
+            // swap callback or an earlier pair in this call
+            let pending_swap: Account<PendingSwap> = Account::try_from(&pair[0])?;
+            require_keys_eq!(pending_swap.pool, pool_key, ErrorCode::InvalidPendingSwap);
+            require_keys_eq!(pending_swap.user, pair[1].key(), ErrorCode::InvalidPendingSwap);
+
+            pending_swap.close(pair[1].clone())?;
+            settled += 1;
+        }
+
+        let pool = &mut ctx.accounts.pool;
+        pool.pending_swaps = pool.pending_swaps.saturating_sub(settled);
+        pool.in_flight = false;
+        pool.halted = true;
+
+        let clock = Clock::get()?;
+        emit!(SwapsForceSettled {
+            pool: pool_key,
+            settled,
+            remaining: pool.pending_swaps,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Create the price oracle conditional swaps on this pool are checked against
+    /// `updater` is the only key allowed to publish prices
+    pub fn init_price_oracle(ctx: Context<InitPriceOracle>, updater: Pubkey) -> Result<()> {
+        let oracle = &mut ctx.accounts.oracle;
+        oracle.pool = ctx.accounts.pool.key();
+        oracle.updater = updater;
+        oracle.price = 0;
+        oracle.updated_slot = 0;
+        oracle.bump = ctx.bumps.oracle;
+        Ok(())
+    }
+
+    /// Publish the current price, in raw token B atoms per token A atom
+    pub fn update_oracle_price(ctx: Context<UpdateOraclePrice>, price: u64) -> Result<()> {
+        require!(price > 0, ErrorCode::InvalidOraclePrice);
+        let oracle = &mut ctx.accounts.oracle;
+        oracle.price = price;
+        oracle.updated_slot = Clock::get()?.slot;
+
+        let clock = Clock::get()?;
+        emit!(OraclePriceUpdated {
+            pool: oracle.pool,
+            price,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Place a private take-profit or stop swap
+    /// The encrypted amount is stored until the oracle price crosses
+    /// `trigger_price` (at or above it if `trigger_above`, else at or below),
+    /// when anyone may fire it with `trigger_conditional_swap`. The pool is
+    /// approved for `max_input` of the input account now, since the user
+    /// can't sign at trigger time; a later approval on that account replaces
+    /// it and the swap is then aborted at settlement until placed again.
+    pub fn place_conditional_swap(
+        ctx: Context<PlaceConditionalSwap>,
+        swap_id: u64,
+        encrypted_amount: [u8; 64], // Encrypted swap amount
+        min_output: u64,            // Minimum output (slippage protection)
+        max_input: u64,             // Most the pool may pull for the encrypted amount
+        is_a_to_b: bool,
+        trigger_price: u64,         // Raw token B atoms per token A atom
+        trigger_above: bool,
+        pub_key: [u8; 32],
+        nonce: u128,
+    ) -> Result<()> {
+        require!(!ctx.accounts.pool.paused, ErrorCode::ProgramPaused);
+        require!(trigger_price > 0, ErrorCode::InvalidOraclePrice);
+        require!(
+            ctx.accounts.pool.reveal_delay_slots == 0,
+            ErrorCode::SealedSwapRequired
+        );
+        require!(ctx.accounts.pool.on_price_grid(trigger_price), ErrorCode::InvalidPriceTick);
+
+        let user_token_in = if is_a_to_b {
+            &ctx.accounts.user_token_a
+        } else {
+            &ctx.accounts.user_token_b
+        };
+        let cpi_accounts = Approve {
+            to: user_token_in.to_account_info(),
+            delegate: ctx.accounts.pool.to_account_info(),
+            authority: ctx.accounts.user.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+        token::approve(cpi_ctx, max_input)?;
+
+        let conditional_swap = &mut ctx.accounts.conditional_swap;
+        conditional_swap.pool = ctx.accounts.pool.key();
+        conditional_swap.user = ctx.accounts.user.key();
+        conditional_swap.swap_id = swap_id;
+        conditional_swap.encrypted_amount = encrypted_amount;
+        conditional_swap.min_output = min_output;
+        conditional_swap.is_a_to_b = is_a_to_b;
+        conditional_swap.trigger_price = trigger_price;
+        conditional_swap.trigger_above = trigger_above;
+        conditional_swap.pub_key = pub_key;
+        conditional_swap.nonce = nonce;
+        conditional_swap.bump = ctx.bumps.conditional_swap;
+
+        let clock = Clock::get()?;
+        emit!(ConditionalSwapPlaced {
+            pool: conditional_swap.pool,
+            user: conditional_swap.user,
+            swap_id,
+            trigger_price,
+            trigger_above,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Withdraw a conditional swap that hasn't fired
+    /// Revokes the pool's approval over the input account if it still holds it.
+    pub fn cancel_conditional_swap(ctx: Context<CancelConditionalSwap>) -> Result<()> {
+        let user_token_in = &ctx.accounts.user_token_in;
+        if user_token_in.delegate == COption::Some(ctx.accounts.conditional_swap.pool) {
+            let cpi_accounts = Revoke {
+                source: user_token_in.to_account_info(),
+                authority: ctx.accounts.user.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+            token::revoke(cpi_ctx)?;
+        }
+
+        let clock = Clock::get()?;
+        emit!(ConditionalSwapCancelled {
+            pool: ctx.accounts.conditional_swap.pool,
+            user: ctx.accounts.user.key(),
+            swap_id: ctx.accounts.conditional_swap.swap_id,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Fire a conditional swap once a fresh oracle price meets its condition
+    /// Permissionless. The swap runs through the regular `execute_swap`
+    /// circuit and callback. The conditional swap is closed to the crank,
+    /// covering the pending swap rent it pays, which goes to the user on settlement.
+    pub fn trigger_conditional_swap(
+        ctx: Context<TriggerConditionalSwap>,
+        computation_offset: u64,
+    ) -> Result<()> {
+        require!(!ctx.accounts.pool.paused, ErrorCode::ProgramPaused);
+        ensure_computation_fee(&ctx.accounts.payer, 0)?;
+        require!(!ctx.accounts.pool.halted, ErrorCode::PoolHalted);
+        require!(
+            ctx.accounts.pool.paused_ops & PAUSE_SWAPS == 0,
+            ErrorCode::OperationPaused
+        );
+        // The regular swap callback settles at once, which a reveal delay
+        // set since the swap was placed forbids
+        require!(
+            ctx.accounts.pool.reveal_delay_slots == 0,
+            ErrorCode::SealedSwapRequired
+        );
+
+        let current_slot = Clock::get()?.slot;
+        let price = ctx.accounts.oracle.fresh_price(current_slot)?;
+        let conditional_swap = &ctx.accounts.conditional_swap;
+        require!(conditional_swap.is_triggered(price), ErrorCode::ConditionNotMet);
+
+        let pending_swap = &mut ctx.accounts.pending_swap;
+        pending_swap.pool = ctx.accounts.pool.key();
+        pending_swap.user = conditional_swap.user;
+        pending_swap.computation_offset = computation_offset;
+        pending_swap.is_a_to_b = conditional_swap.is_a_to_b;
+        pending_swap.bump = ctx.bumps.pending_swap;
+        pending_swap.reserve_a = ctx.accounts.pool.reserve_a;
+        pending_swap.reserve_b = ctx.accounts.pool.reserve_b;
+        pending_swap.fee_rate = ctx.accounts.pool.fee_rate;
+        pending_swap.min_fee = ctx.accounts.pool.min_fee;
+        pending_swap.unwrap_output = false;
+        pending_swap.min_output = conditional_swap.min_output;
+        pending_swap.ready_slot = 0;
+
+        let pool = &mut ctx.accounts.pool;
+        pool.begin_swap(computation_offset, current_slot)?;
+
+        let args = ArgBuilder::new()
+            .x25519_pubkey(conditional_swap.pub_key)
+            .plaintext_u128(conditional_swap.nonce)
+            .encrypted_bytes(conditional_swap.encrypted_amount)
+            .plaintext_u64(pool.reserve_a)
+            .plaintext_u64(pool.reserve_b)
+            .plaintext_u64(conditional_swap.min_output)
+            .plaintext_bool(conditional_swap.is_a_to_b)
+            .plaintext_u16(pool.fee_rate)
+            .plaintext_u64(pool.min_fee)
+            .plaintext_u64(pool.min_swap_notional)
+            .build();
+
+        let clock = Clock::get()?;
+        emit!(ConditionalSwapTriggered {
+            pool: pool.key(),
+            user: conditional_swap.user,
+            swap_id: conditional_swap.swap_id,
+            oracle_price: oracle.price,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![ExecuteSwapCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &CallbackAccountsBuilder::new()
+                    .writable(ctx.accounts.pool.key())
+                    .writable(ctx.accounts.pending_swap.key())
+                    .writable(ctx.accounts.user.key())
+                    .writable(ctx.accounts.user_token_a.key())
+                    .writable(ctx.accounts.user_token_b.key())
+                    .writable(ctx.accounts.pool_token_a.key())
+                    .writable(ctx.accounts.pool_token_b.key())
+                    .readonly(ctx.accounts.lp_mint.key())
+                    .readonly(ctx.accounts.token_program.key())
+                    .build(),
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+}
+
+// ============ Account Structures ============
+
+#[account]
+pub struct SwapConfig {
+    pub authority: Pubkey,
+    pub max_pools_per_authority: u32,
+    pub bump: u8,
+}
+
+impl SwapConfig {
+    pub const SIZE: usize = 32 + 4 + 1;
+}
+
+/// Pools an authority currently has open, capped by
+/// `SwapConfig::max_pools_per_authority`
+#[account]
+pub struct PoolRegistry {
+    pub authority: Pubkey,
+    pub open_pools: u32,
+    pub bump: u8,
+}
+
+impl PoolRegistry {
+    pub const SIZE: usize = 32 + 4 + 1;
+
+    /// Count a newly opened pool, failing once `max_pools` are open
+    pub fn open_pool(&mut self, max_pools: u32) -> Result<()> {
+        require!(self.open_pools < max_pools, ErrorCode::PoolLimitReached);
+        self.open_pools = self.open_pools.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Free the slot of a closed pool
+    pub fn close_pool(&mut self) {
+        self.open_pools = self.open_pools.saturating_sub(1);
+    }
+}
+
+/// Where a pool's swap fees go
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FeeMode {
+    /// Fees join the reserves, raising the value of every LP token
+    LpRetained,
+    /// Fees are kept out of the reserves in `fee_growth_a`/`fee_growth_b`
+    /// until each LP claims their share with `claim_lp_fees`
+    Extracted,
+}
+
+#[account]
+pub struct SwapPool {
+    pub authority: Pubkey,
+    pub token_mint_a: Pubkey,
+    pub token_mint_b: Pubkey,
+    pub reserve_a: u64,
+    pub reserve_b: u64,
+    pub fee_rate: u16,
+    pub bump: u8,
+    pub total_swaps: u64,
+    pub min_fee: u64,
+    pub pending_swaps: u32, // Swaps queued but not yet settled
+    pub halted: bool,       // Set by `force_settle`; blocks new swaps
+    pub paused_ops: u8,     // Bitmask of PAUSE_* operations
+    pub lp_mint: Pubkey,    // Liquidity share mint, authority is the pool PDA
+    pub verify_swap_math: bool, // Re-check every swap result on-chain in the callback
+    // Fees earned per LP token, scaled by FEE_GROWTH_SCALE. Fees are held in
+    // the vaults outside the reserves until claimed with `claim_lp_fees`.
+    pub fee_growth_a: u128,
+    pub fee_growth_b: u128,
+    pub paused: bool, // Blocks every computation, set with `set_paused`
+    // Running sums of the spot price x slots, in Q64.64 (see `update_price_cumulative`)
+    pub price_cumulative_a: u128, // Token B per token A
+    pub price_cumulative_b: u128, // Token A per token B
+    pub last_update_slot: u64,
+    pub price_tick: u64, // Conditional swap trigger prices must be a multiple of this
+    pub reveal_delay_slots: u64, // Slots a sealed swap result stays encrypted; 0 disables sealed swaps
+    // Holder fee discounts, see `discounted_fee_rate`; default mint disables them
+    pub governance_mint: Pubkey,
+    pub discount_thresholds: [u64; FEE_DISCOUNT_TIERS],
+    pub discount_bps: [u16; FEE_DISCOUNT_TIERS],
+    pub fee_mode: FeeMode,
+    pub in_flight: bool, // A swap is queued and its callback hasn't settled it
+    pub min_swap_notional: u64, // Swaps reject smaller inputs, valued in token B; 0 disables
+    pub in_flight_offset: u64, // Computation offset of the swap holding `in_flight`
+    pub in_flight_slot: u64,   // Slot that swap was queued in
+}
+
+impl SwapPool {
+    pub const SIZE: usize = 8 + 32 + 32 + 32 + 8 + 8 + 2 + 1 + 8 + 8 + 4 + 1 + 1 + 32 + 1 + 16 + 16 + 1 + 16 + 16 + 8 + 8 + 8
+        + 32 + 8 * FEE_DISCOUNT_TIERS + 2 * FEE_DISCOUNT_TIERS
+        + 1 + 1 + 8 + 8 + 8;
+
+    /// Add the current spot prices, weighted by the slots since the last
+    /// update, to the TWAP accumulators. Must run before reserves change.
+    /// Nothing accrues while either reserve is empty. The sums wrap on
+    /// overflow; consumers difference two observations with wrapping
+    /// arithmetic, see `time_weighted_price`.
+    pub fn update_price_cumulative(&mut self, current_slot: u64) {
+        let elapsed = current_slot.saturating_sub(self.last_update_slot) as u128;
+        if elapsed > 0 && self.reserve_a > 0 && self.reserve_b > 0 {
+            let price_a = ((self.reserve_b as u128) << 64) / self.reserve_a as u128;
+            let price_b = ((self.reserve_a as u128) << 64) / self.reserve_b as u128;
+            self.price_cumulative_a = self.price_cumulative_a.wrapping_add(price_a.wrapping_mul(elapsed));
+            self.price_cumulative_b = self.price_cumulative_b.wrapping_add(price_b.wrapping_mul(elapsed));
+        }
+        self.last_update_slot = current_slot;
+    }
+
+    /// Fee rate charged to a trader holding `holding` governance tokens
+    /// The highest tier whose threshold the holding reaches discounts the
+    /// pool's fee rate by that tier's `discount_bps` of it
+    pub fn discounted_fee_rate(&self, holding: u64) -> u16 {
+        if self.governance_mint == Pubkey::default() {
+            return self.fee_rate;
+        }
+        let mut discount = 0u16;
+        for (threshold, bps) in self.discount_thresholds.iter().zip(self.discount_bps) {
+            if *threshold > 0 && holding >= *threshold {
+                discount = bps;
+            }
+        }
+        ((self.fee_rate as u32) * (10_000 - discount as u32) / 10_000) as u16
+    }
+
+    /// Output of a swap of `amount_in` at the current reserves, and the fee
+    /// rate charged to a trader holding `holding` governance tokens
+    pub fn quote(&self, amount_in: u64, is_a_to_b: bool, holding: u64) -> Result<(u64, u16)> {
+        let fee_rate = self.discounted_fee_rate(holding);
+        let (reserve_in, reserve_out) = hop_reserves(self, is_a_to_b);
+        let amount_out = expected_swap_output(amount_in, reserve_in, reserve_out, fee_rate, self.min_fee)?;
+        Ok((amount_out, fee_rate))
+    }
+
+    /// Fail a swap whose input is worth less than `min_swap_notional`
+    /// Values the input the way the `execute_swap` circuit does, in token B
+    /// with token A at the spot price of the reserves the swap was quoted
+    /// against, so every swap path enforces the same minimum
+    pub fn require_min_notional(
+        &self,
+        amount_in: u64,
+        is_a_to_b: bool,
+        reserve_in: u64,
+        reserve_out: u64,
+    ) -> Result<()> {
+        if self.min_swap_notional == 0 {
+            return Ok(());
+        }
+        let notional = if is_a_to_b {
+            if reserve_in > 0 {
+                (amount_in as u128) * (reserve_out as u128) / (reserve_in as u128)
+            } else {
+                0
+            }
+        } else {
+            amount_in as u128
+        };
+        require!(
+            notional >= self.min_swap_notional as u128,
+            ErrorCode::SwapBelowMinimum
+        );
+        Ok(())
+    }
+
+    /// Claim the pool for a newly queued swap
+    /// Only one swap per pool may be in flight: settlement transfers tokens
+    /// through the token program, and a Token-2022 transfer hook re-entering
+    /// the pool mid-settlement must not find a second swap to queue or settle
+    /// against stale reserves. This serializes swaps on a pool, so throughput
+    /// is one swap per MPC round trip (a sealed swap holds the pool for its
+    /// two round trips but not across its reveal delay, see `lock`); the
+    /// per-computation `PendingSwap` alone would allow
+    /// concurrency but not this guarantee. The guard is keyed by computation
+    /// offset, so only the swap holding it can release it, and anyone can
+    /// release one whose callback never landed with `release_stale_swap`
+    /// once `swap_lock_expired`.
+    pub fn begin_swap(&mut self, computation_offset: u64, current_slot: u64) -> Result<()> {
+        self.lock(computation_offset, current_slot)?;
+        self.pending_swaps = self.pending_swaps.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Take the swap guard for an already counted swap
+    /// A sealed swap releases the pool with `unlock` once its result is
+    /// sealed and takes it again in `finalize_swap_result`, so other swaps
+    /// trade through its reveal delay; the reveal then re-checks its price
+    /// against the reserves at that point.
+    pub fn lock(&mut self, computation_offset: u64, current_slot: u64) -> Result<()> {
+        require!(!self.in_flight, ErrorCode::SwapInFlight);
+        self.in_flight = true;
+        self.in_flight_offset = computation_offset;
+        self.in_flight_slot = current_slot;
+        Ok(())
+    }
+
+    /// Release the swap guard without settling the swap holding it
+    pub fn unlock(&mut self, computation_offset: u64) -> Result<()> {
+        require!(self.holds_swap(computation_offset), ErrorCode::StaleSwap);
+        self.in_flight = false;
+        Ok(())
+    }
+
+    /// Whether `price` is a multiple of the pool's price tick
+    pub fn on_price_grid(&self, price: u64) -> bool {
+        price.checked_rem(self.price_tick) == Some(0)
+    }
+
+    /// Whether nothing is left for anyone to claim: no reserves, no LP tokens
+    /// out of `lp_supply`, and no swap waiting to settle
+    pub fn is_empty(&self, lp_supply: u64) -> bool {
+        self.reserve_a == 0 && self.reserve_b == 0 && self.pending_swaps == 0 && lp_supply == 0
+    }
+
+    /// Whether vaults holding `vault_a` and `vault_b` cover the reserves
+    /// Vaults may hold more, such as LP fees not yet claimed
+    pub fn reserves_backed_by(&self, vault_a: u64, vault_b: u64) -> bool {
+        vault_a >= self.reserve_a && vault_b >= self.reserve_b
+    }
+
+    /// Whether the pool is currently held by the swap queued at `computation_offset`
+    pub fn holds_swap(&self, computation_offset: u64) -> bool {
+        self.in_flight && self.in_flight_offset == computation_offset
+    }
+
+    /// Release the pool once the swap holding it has settled or expired
+    pub fn end_swap(&mut self, computation_offset: u64) -> Result<()> {
+        self.unlock(computation_offset)?;
+        self.pending_swaps = self.pending_swaps.saturating_sub(1);
+        Ok(())
+    }
+
+    /// Whether the swap holding the pool has gone SWAP_TIMEOUT_SLOTS without
+    /// settling
+    pub fn swap_lock_expired(&self, current_slot: u64) -> bool {
+        self.in_flight
+            && current_slot
+                >= self
+                    .in_flight_slot
+                    .saturating_add(SWAP_TIMEOUT_SLOTS)
+    }
+
+    /// Book a settled swap into the reserves and count it
+    /// The fee stays in the input vault but outside the reserves, owed to LPs
+    pub fn apply_swap(
+        &mut self,
+        is_a_to_b: bool,
+        amount_in: u64,
+        amount_out: u64,
+        fee: u64,
+        lp_supply: u64,
+        current_slot: u64,
+    ) -> Result<()> {
+        self.total_swaps = self.total_swaps.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+        self.update_price_cumulative(current_slot);
+        let k_before = (self.reserve_a as u128) * (self.reserve_b as u128);
+        let amount_in_after_fee = amount_in.checked_sub(fee).ok_or(ErrorCode::MathOverflow)?;
+        if is_a_to_b {
+            self.reserve_a = self.reserve_a.checked_add(amount_in_after_fee).ok_or(ErrorCode::MathOverflow)?;
+            self.reserve_b = self.reserve_b.checked_sub(amount_out).ok_or(ErrorCode::InsufficientLiquidity)?;
+        } else {
+            self.reserve_b = self.reserve_b.checked_add(amount_in_after_fee).ok_or(ErrorCode::MathOverflow)?;
+            self.reserve_a = self.reserve_a.checked_sub(amount_out).ok_or(ErrorCode::InsufficientLiquidity)?;
+        }
+
+        // A correct constant-product swap never shrinks k; a circuit bug
+        // that overpays would otherwise drain the pool one swap at a time
+        let k_after = (self.reserve_a as u128) * (self.reserve_b as u128);
+        require!(k_after >= k_before, ErrorCode::InvariantViolation);
+        self.accrue_fee(is_a_to_b, fee, lp_supply)
+    }
+
+    /// Credit a swap fee in the input token to current LP holders
+    /// Under `FeeMode::LpRetained`, or with no LP supply to pay, the fee
+    /// joins the reserve; otherwise it accrues to the fee growth counters
+    pub fn accrue_fee(&mut self, is_token_a: bool, fee: u64, lp_supply: u64) -> Result<()> {
+        if self.fee_mode == FeeMode::LpRetained || lp_supply == 0 {
+            let reserve = if is_token_a { &mut self.reserve_a } else { &mut self.reserve_b };
+            *reserve = reserve.checked_add(fee).ok_or(ErrorCode::MathOverflow)?;
+            return Ok(());
+        }
+        let growth = (fee as u128)
+            .checked_mul(FEE_GROWTH_SCALE)
+            .ok_or(ErrorCode::MathOverflow)?
+            / lp_supply as u128;
+        let fee_growth = if is_token_a { &mut self.fee_growth_a } else { &mut self.fee_growth_b };
+        *fee_growth = fee_growth.checked_add(growth).ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+}
+
+/// Fee growth already paid out to one LP
+/// add_liquidity and remove_liquidity require it to be current, so a
+/// position can't change size with fees outstanding. LP tokens moved by a
+/// plain token transfer are not tracked, so claim before transferring them.
+#[account]
+pub struct LpFeeCheckpoint {
+    pub pool: Pubkey,
+    pub owner: Pubkey,
+    pub fee_growth_a: u128,
+    pub fee_growth_b: u128,
+    pub bump: u8,
+}
+
+impl LpFeeCheckpoint {
+    pub const SIZE: usize = 32 + 32 + 16 + 16 + 1;
+
+    /// Whether every fee earned so far has been claimed
+    pub fn is_current(&self, pool: &SwapPool) -> bool {
+        self.fee_growth_a == pool.fee_growth_a && self.fee_growth_b == pool.fee_growth_b
+    }
+}
+
+/// Decimals of every pool's LP mint
+pub const LP_DECIMALS: u8 = 6;
+
+/// Highest fee rate a pool may charge, in basis points
+pub const MAX_FEE_BPS: u16 = 1_000;
+
+/// Reject a fee rate above `MAX_FEE_BPS`
+fn validate_fee_rate(fee_rate: u16) -> Result<()> {
+    require!(fee_rate <= MAX_FEE_BPS, ErrorCode::FeeTooHigh);
+    Ok(())
+}
+
+/// Reject a discount schedule whose enabled tiers don't ascend in both
+/// threshold and discount, or that discounts more than the whole fee
+/// A zero threshold disables its tier.
+fn validate_discount_schedule(
+    discount_thresholds: &[u64; FEE_DISCOUNT_TIERS],
+    discount_bps: &[u16; FEE_DISCOUNT_TIERS],
+) -> Result<()> {
+    let mut previous = (0u64, 0u16);
+    for (threshold, bps) in discount_thresholds.iter().zip(discount_bps) {
+        require!(*bps <= 10_000, ErrorCode::InvalidDiscountSchedule);
+        if *threshold > 0 {
+            require!(
+                *threshold > previous.0 && *bps >= previous.1,
+                ErrorCode::InvalidDiscountSchedule
+            );
+            previous = (*threshold, *bps);
+        }
+    }
+    Ok(())
+}
+
+/// Slots a queued swap may hold its pool before `release_stale_swap` can
+/// free it, roughly ten minutes, on top of any reveal delay
+pub const SWAP_TIMEOUT_SLOTS: u64 = 1_500;
+
+/// `paused_ops` bit blocking swaps, including routed swaps through the pool
+pub const PAUSE_SWAPS: u8 = 1 << 3;
+
+/// An in-flight swap, open from `execute_swap` until its callback or `force_settle`
+#[account]
+pub struct PendingSwap {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub computation_offset: u64,
+    pub is_a_to_b: bool,
+    pub bump: u8,
+    // Pool parameters passed to the circuit, kept for `verify_swap_math`
+    pub reserve_a: u64,
+    pub reserve_b: u64,
+    pub fee_rate: u16,
+    pub min_fee: u64,
+    pub unwrap_output: bool, // Close the WSOL output account to the user on settlement
+    pub min_output: u64,     // Slippage bound committed at queue time, re-checked on settlement
+    // Sealed swaps only: the encrypted `SwapResult` and when it may be revealed
+    pub ready_slot: u64, // 0 until the sealed result is stored
+    pub sealed_nonce: u128,
+    pub sealed_result: [[u8; 32]; 2],
+    // `execute_swap_with_slippage` only, 0 otherwise: the reserves the
+    // client quoted against, in swap direction, and its bound on the output
+    pub quoted_reserve_in: u64,
+    pub quoted_reserve_out: u64,
+    pub max_slippage_bps: u16,
+}
+
+impl PendingSwap {
+    pub const SIZE: usize = 32 + 32 + 8 + 1 + 1 + 8 + 8 + 2 + 8 + 1 + 8 + 8 + 16 + 64 + 8 + 8 + 2;
+    /// Offset of `sealed_result` in the account data, including the discriminator
+    pub const SEALED_RESULT_OFFSET: u32 = 8 + 32 + 32 + 8 + 1 + 1 + 8 + 8 + 2 + 8 + 1 + 8 + 8 + 16;
+    pub const SEALED_RESULT_LEN: u32 = 64;
+
+    /// Least output the swap may settle for once `amount_in` is revealed:
+    /// `min_output`, or for a slippage-bounded swap the quote for
+    /// `amount_in` at the quoted reserves less `max_slippage_bps`, if higher
+    pub fn output_floor(&self, amount_in: u64) -> Result<u64> {
+        if self.quoted_reserve_in == 0 {
+            return Ok(self.min_output);
+        }
+        let quote = expected_swap_output(
+            amount_in,
+            self.quoted_reserve_in,
+            self.quoted_reserve_out,
+            self.fee_rate,
+            self.min_fee,
+        )?;
+        let floor = (quote as u128) * (10_000 - self.max_slippage_bps as u128) / 10_000;
+        Ok(self.min_output.max(floor as u64))
+    }
+}
+
+/// Tracks an in-flight `execute_multi_hop_swap` or `route_swap_exact_out`
+/// until its callback settles it
+#[account]
+pub struct PendingMultiHop {
+    pub user: Pubkey,
+    pub first_pool: Pubkey,
+    pub second_pool: Pubkey,
+    pub computation_offset: u64,
+    pub first_is_a_to_b: bool,
+    pub second_is_a_to_b: bool,
+    pub bump: u8,
+    pub min_output: u64, // End-to-end slippage bound, re-checked on settlement
+    // Hop parameters passed to the circuit, kept for `verify_swap_math`
+    pub first_reserve_in: u64,
+    pub first_reserve_out: u64,
+    pub first_fee_rate: u16,
+    pub first_min_fee: u64,
+    pub second_reserve_in: u64,
+    pub second_reserve_out: u64,
+    pub second_fee_rate: u16,
+    pub second_min_fee: u64,
+}
+
+impl PendingMultiHop {
+    pub const SIZE: usize = 32 + 32 + 32 + 8 + 1 + 1 + 1 + 8 + 8 + 8 + 2 + 8 + 8 + 8 + 2 + 8;
+}
+
+/// Number of governance token holder fee discount tiers per pool
+pub const FEE_DISCOUNT_TIERS: usize = 3;
+
+/// Longest reveal delay a pool may set for sealed swaps, about an hour
+pub const MAX_REVEAL_DELAY_SLOTS: u64 = 9_000;
+
+/// Pool price published by a designated updater for conditional swaps
+#[account]
+pub struct PriceOracle {
+    pub pool: Pubkey,
+    pub updater: Pubkey,
+    pub price: u64, // Raw token B atoms per token A atom
+    pub updated_slot: u64,
+    pub bump: u8,
+}
+
+impl PriceOracle {
+    pub const SIZE: usize = 32 + 32 + 8 + 8 + 1;
+
+    /// The published price, unless none was published yet or it is older
+    /// than `MAX_ORACLE_AGE_SLOTS`
+    pub fn fresh_price(&self, current_slot: u64) -> Result<u64> {
+        require!(
+            self.price > 0
+                && current_slot <= self.updated_slot.saturating_add(MAX_ORACLE_AGE_SLOTS),
+            ErrorCode::StaleOraclePrice
+        );
+        Ok(self.price)
+    }
+}
+
+/// Oldest oracle price, in slots, a conditional swap may fire on
+pub const MAX_ORACLE_AGE_SLOTS: u64 = 150;
+
+/// A private swap waiting for the oracle to cross its trigger price
+#[account]
+pub struct ConditionalSwap {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub swap_id: u64,
+    pub encrypted_amount: [u8; 64],
+    pub min_output: u64,
+    pub is_a_to_b: bool,
+    pub trigger_price: u64,
+    pub trigger_above: bool, // Fire at or above the trigger price, else at or below
+    pub pub_key: [u8; 32],
+    pub nonce: u128,
+    pub bump: u8,
+}
+
+impl ConditionalSwap {
+    pub const SIZE: usize = 32 + 32 + 8 + 64 + 8 + 1 + 8 + 1 + 32 + 16 + 1;
+
+    /// Whether an oracle `price` has crossed the trigger
+    pub fn is_triggered(&self, price: u64) -> bool {
+        if self.trigger_above {
+            price >= self.trigger_price
+        } else {
+            price <= self.trigger_price
+        }
+    }
+}
+
+/// Largest difference, in output token units, tolerated between a revealed
+/// swap output and the on-chain recomputation
+pub const SWAP_MATH_TOLERANCE: u64 = 1;
+
+/// Swap fee in basis points of `amount_in`, floored at `min_fee`, mirroring
+/// `swap_fee` in encrypted-ixs but capped at the input
+fn swap_fee(amount_in: u64, fee_rate: u16, min_fee: u64) -> u64 {
+    let fee = ((amount_in as u128) * (fee_rate as u128) / 10_000) as u64;
+    fee.max(min_fee).min(amount_in)
+}
+
+/// Time-weighted average price between two observations of a pool's
+/// `price_cumulative_*` and `last_update_slot`, in Q64.64
+/// For off-chain consumers reading the account data; None if no slots passed
+pub fn time_weighted_price(
+    cumulative_start: u128,
+    slot_start: u64,
+    cumulative_end: u128,
+    slot_end: u64,
+) -> Option<u128> {
+    let elapsed = slot_end.checked_sub(slot_start).filter(|slots| *slots > 0)?;
+    Some(cumulative_end.wrapping_sub(cumulative_start) / elapsed as u128)
+}
+
+/// Scale of the per-LP-token fee growth accumulators
+pub const FEE_GROWTH_SCALE: u128 = 1_000_000_000_000;
+
+/// Fees earned by `lp_balance` LP tokens since `checkpoint`
+fn lp_fees_owed(lp_balance: u64, fee_growth: u128, checkpoint: u128) -> Result<u64> {
+    let owed = (lp_balance as u128)
+        .checked_mul(fee_growth.saturating_sub(checkpoint))
+        .ok_or(ErrorCode::MathOverflow)?
+        / FEE_GROWTH_SCALE;
+    u64::try_from(owed).map_err(|_| ErrorCode::MathOverflow.into())
+}
+
+/// Constant-product output for `amount_in`, mirroring `execute_swap` in
+/// encrypted-ixs including the fee floor
+fn expected_swap_output(
+    amount_in: u64,
+    reserve_in: u64,
+    reserve_out: u64,
+    fee_rate: u16,
+    min_fee: u64,
+) -> Result<u64> {
+    let fee = swap_fee(amount_in, fee_rate, min_fee);
+    let amount_in_after_fee = amount_in.saturating_sub(fee) as u128;
+    let numerator = amount_in_after_fee
+        .checked_mul(reserve_out as u128)
+        .ok_or(ErrorCode::MathOverflow)?;
+    let denominator = (reserve_in as u128)
+        .checked_add(amount_in_after_fee)
+        .ok_or(ErrorCode::MathOverflow)?;
+    if denominator == 0 {
+        return Ok(0);
+    }
+    u64::try_from(numerator / denominator).map_err(|_| ErrorCode::MathOverflow.into())
+}
+
+/// Release the pool held by a sealed swap the circuit rejected and refund
+/// its pending swap, mirroring `SwapSettlement::abort`
+/// Nothing was sealed, so there is nothing for `finalize_swap_result` to reveal.
+fn abort_sealed_swap(accounts: &mut ExecuteSwapSealedCallback, reason: ErrorCode) -> Result<()> {
+    let pending = &accounts.pending_swap;
+    let (user_in, vault_in) = if pending.is_a_to_b {
+        (&accounts.user_token_a, &accounts.pool_token_a)
+    } else {
+        (&accounts.user_token_b, &accounts.pool_token_b)
+    };
+    take_swap_input(&accounts.pool, user_in, vault_in, &accounts.token_program, 0)?;
+    accounts.pool.end_swap(pending.computation_offset)?;
+
+    let clock = Clock::get()?;
+    emit!(SwapAborted {
+        pool: accounts.pool.key(),
+        user: pending.user,
+        computation_offset: pending.computation_offset,
+        reason: reason.into(),
+        slot: clock.slot,
+        unix_timestamp: clock.unix_timestamp,
+    });
+
+    accounts.pending_swap.close(accounts.user.to_account_info())
+}
+
+/// Validate the amounts a swap circuit revealed against what was committed
+/// at queue time and what the output vault holds
+fn check_revealed_swap(
+    pool: &SwapPool,
+    pending: &PendingSwap,
+    vault_out_amount: u64,
+    amount_in: u64,
+    amount_out: u64,
+    exact_out: bool,
+) -> std::result::Result<(), ErrorCode> {
+    // Don't take the circuit's word for the slippage check
+    let floor = pending.output_floor(amount_in).map_err(|_| ErrorCode::MathOverflow)?;
+    if amount_out < floor {
+        return Err(ErrorCode::SwapFailed);
+    }
+
+    let (reserve_in, reserve_out) = if pending.is_a_to_b {
+        (pending.reserve_a, pending.reserve_b)
+    } else {
+        (pending.reserve_b, pending.reserve_a)
+    };
+    pool.require_min_notional(amount_in, pending.is_a_to_b, reserve_in, reserve_out)
+        .map_err(|_| ErrorCode::SwapBelowMinimum)?;
+
+    // Re-run the constant-product math against the reserves the circuit saw
+    if pool.verify_swap_math {
+        let expected = expected_swap_output(
+            amount_in,
+            reserve_in,
+            reserve_out,
+            pending.fee_rate,
+            pending.min_fee,
+        )
+        .map_err(|_| ErrorCode::MathOverflow)?;
+        // A back-solved input is rounded up, so its exact-input quote can
+        // beat the fixed output by more than rounding; only require that
+        // the pool doesn't pay out more than that quote
+        let consistent = if exact_out {
+            amount_out <= expected
+        } else {
+            amount_out.abs_diff(expected) <= SWAP_MATH_TOLERANCE
+        };
+        if !consistent {
+            return Err(ErrorCode::SwapMathMismatch);
+        }
+    }
+
+    if vault_out_amount < amount_out {
+        return Err(ErrorCode::InsufficientLiquidity);
+    }
+    Ok(())
+}
+
+/// Accounts a revealed swap settles against, shared by swaps settled in
+/// their own callback and sealed swaps settled by `finalize_swap_result`
+struct SwapSettlement<'a, 'info> {
+    pool: &'a mut Account<'info, SwapPool>,
+    pending_swap: &'a Account<'info, PendingSwap>,
+    user: &'a UncheckedAccount<'info>,
+    user_token_a: &'a Account<'info, TokenAccount>,
+    user_token_b: &'a Account<'info, TokenAccount>,
+    pool_token_a: &'a Account<'info, TokenAccount>,
+    pool_token_b: &'a Account<'info, TokenAccount>,
+    lp_mint: &'a Account<'info, Mint>,
+    token_program: &'a Program<'info, Token>,
+}
+
+impl<'info> SwapSettlement<'_, 'info> {
+    /// Check the revealed amounts, move the tokens and book the swap into
+    /// the pool's reserves
+    fn settle(self, amount_in: u64, amount_out: u64) -> Result<()> {
+        self.settle_checked(amount_in, amount_out, false)
+    }
+
+    /// Settle a swap whose output was fixed and input back-solved
+    fn settle_exact_out(self, amount_in: u64, amount_out: u64) -> Result<()> {
+        self.settle_checked(amount_in, amount_out, true)
+    }
+
+    /// Settle a sealed swap, priced at queue time, against a pool that
+    /// kept trading through its reveal delay
+    /// The pool never pays more than its current quote for the same input;
+    /// if the price moved against the revealed amounts the swap is aborted.
+    fn settle_revealed(self, amount_in: u64, amount_out: u64) -> Result<()> {
+        let pending = self.pending_swap;
+        let (reserve_in, reserve_out) = hop_reserves(self.pool, pending.is_a_to_b);
+        match expected_swap_output(amount_in, reserve_in, reserve_out, pending.fee_rate, pending.min_fee) {
+            Ok(quote) if quote >= amount_out => self.settle(amount_in, amount_out),
+            _ => self.abort(ErrorCode::SealedReservesMoved),
+        }
+    }
+
+    /// User and pool accounts of the swap's input token
+    fn input_accounts(&self) -> (&Account<'info, TokenAccount>, &Account<'info, TokenAccount>) {
+        if self.pending_swap.is_a_to_b {
+            (self.user_token_a, self.pool_token_a)
+        } else {
+            (self.user_token_b, self.pool_token_b)
+        }
+    }
+
+    /// Release the pool and the input approval without swapping, for a swap
+    /// the circuit rejected or whose revealed amounts fail `check_revealed_swap`
+    /// Returning an error here would revert the callback and leave the pool
+    /// locked until `release_stale_swap`, so the failure is reported through
+    /// `SwapAborted` instead. The pending swap is still closed to the user by
+    /// the account constraint.
+    fn abort(self, reason: ErrorCode) -> Result<()> {
+        let (user_in, vault_in) = self.input_accounts();
+        take_swap_input(&*self.pool, user_in, vault_in, self.token_program, 0)?;
+        let pending = self.pending_swap;
+        self.pool.end_swap(pending.computation_offset)?;
+
+        let clock = Clock::get()?;
+        emit!(SwapAborted {
+            pool: self.pool.key(),
+            user: pending.user,
+            computation_offset: pending.computation_offset,
+            reason: reason.into(),
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    fn settle_checked(self, amount_in: u64, amount_out: u64, exact_out: bool) -> Result<()> {
+        let vault_out = if self.pending_swap.is_a_to_b {
+            self.pool_token_b
+        } else {
+            self.pool_token_a
+        };
+        if let Err(reason) = check_revealed_swap(
+            self.pool,
+            self.pending_swap,
+            vault_out.amount,
+            amount_in,
+            amount_out,
+            exact_out,
+        ) {
+            return self.abort(reason);
+        }
+        let (user_in, _) = self.input_accounts();
+        if amount_in > approved_input(&self.pool.key(), user_in) {
+            return self.abort(ErrorCode::InputNotApproved);
+        }
+
+        let pending = self.pending_swap;
+        let (user_in, user_out, vault_in, vault_out) = if pending.is_a_to_b {
+            (
+                self.user_token_a,
+                self.user_token_b,
+                self.pool_token_a,
+                self.pool_token_b,
+            )
+        } else {
+            (
+                self.user_token_b,
+                self.user_token_a,
+                self.pool_token_b,
+                self.pool_token_a,
+            )
+        };
+
+        let pool = &*self.pool;
+        let bump = [pool.bump];
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"pool",
+            pool.token_mint_a.as_ref(),
+            pool.token_mint_b.as_ref(),
+            &bump,
+        ]];
+
+        take_swap_input(pool, user_in, vault_in, self.token_program, amount_in)?;
+
+        let cpi_accounts_out = Transfer {
+            from: vault_out.to_account_info(),
+            to: user_out.to_account_info(),
+            authority: pool.to_account_info(),
+        };
+        let cpi_ctx_out = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            cpi_accounts_out,
+            signer_seeds,
+        );
+        token::transfer(cpi_ctx_out, amount_out)?;
+
+        // Closing a native account releases its wrapped lamports with the
+        // rent. If the user took the close authority back after queuing, the
+        // output stays wrapped rather than failing the settlement.
+        if pending.unwrap_output && can_unwrap_output(&pool.key(), user_out) {
+            let cpi_accounts_close = CloseAccount {
+                account: user_out.to_account_info(),
+                destination: self.user.to_account_info(),
+                authority: pool.to_account_info(),
+            };
+            let cpi_ctx_close = CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                cpi_accounts_close,
+                signer_seeds,
+            );
+            token::close_account(cpi_ctx_close)?;
+        }
+
+        // The pending swap is closed back to the user by the account constraint
+        let fee = swap_fee(amount_in, pending.fee_rate, pending.min_fee);
+        let lp_supply = self.lp_mint.supply;
+        let clock = Clock::get()?;
+        let pool = &mut *self.pool;
+        pool.end_swap(pending.computation_offset)?;
+        pool.apply_swap(pending.is_a_to_b, amount_in, amount_out, fee, lp_supply, clock.slot)?;
+
+        emit!(SwapExecuted {
+            amount_in,
+            amount_out,
+            reserve_a: pool.reserve_a,
+            reserve_b: pool.reserve_b,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+/// Accounts a revealed two-pool route settles against, shared by
+/// `execute_multi_hop_swap` and `route_swap_exact_out`
+struct MultiHopSettlement<'a, 'info> {
+    first_pool: &'a mut Account<'info, SwapPool>,
+    second_pool: &'a mut Account<'info, SwapPool>,
+    pending_multi_hop: &'a Account<'info, PendingMultiHop>,
+    user_token_in: &'a Account<'info, TokenAccount>,
+    user_token_out: &'a Account<'info, TokenAccount>,
+    first_vault_in: &'a Account<'info, TokenAccount>,
+    first_vault_out: &'a Account<'info, TokenAccount>,
+    second_vault_in: &'a Account<'info, TokenAccount>,
+    second_vault_out: &'a Account<'info, TokenAccount>,
+    first_lp_mint: &'a Account<'info, Mint>,
+    second_lp_mint: &'a Account<'info, Mint>,
+    token_program: &'a Program<'info, Token>,
+}
+
+impl MultiHopSettlement<'_, '_> {
+    /// Check the revealed amounts, move the tokens through both pools and
+    /// book each hop into its pool's reserves
+    /// Returns false if the route failed its checks and was aborted instead.
+    fn settle(self, amount_in: u64, intermediate: u64, amount_out: u64) -> Result<bool> {
+        self.settle_checked(amount_in, intermediate, amount_out, false)
+    }
+
+    /// Settle a route whose final output was fixed and inputs back-solved
+    fn settle_exact_out(self, amount_in: u64, intermediate: u64, amount_out: u64) -> Result<bool> {
+        self.settle_checked(amount_in, intermediate, amount_out, true)
+    }
+
+    /// Release both pools and the input approval without swapping, for a
+    /// route the circuit rejected or whose revealed amounts don't pass `check`
+    /// As with `SwapSettlement::abort`, the failure is reported through
+    /// `MultiHopSwapAborted` rather than reverting the callback. A pool
+    /// `force_settle` already freed is left alone.
+    fn abort(self, reason: ErrorCode) -> Result<()> {
+        take_swap_input(
+            &*self.first_pool,
+            self.user_token_in,
+            self.first_vault_in,
+            self.token_program,
+            0,
+        )?;
+        let pending = self.pending_multi_hop;
+        let computation_offset = pending.computation_offset;
+        for pool in [&mut *self.first_pool, &mut *self.second_pool] {
+            if pool.holds_swap(computation_offset) {
+                pool.end_swap(computation_offset)?;
+            }
+        }
+
+        let clock = Clock::get()?;
+        emit!(MultiHopSwapAborted {
+            first_pool: self.first_pool.key(),
+            second_pool: self.second_pool.key(),
+            user: pending.user,
+            computation_offset,
+            reason: reason.into(),
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Validate the revealed amounts of both hops against what was
+    /// committed at queue time
+    fn check(
+        &self,
+        amount_in: u64,
+        intermediate: u64,
+        amount_out: u64,
+        exact_out: bool,
+    ) -> std::result::Result<(), ErrorCode> {
+        let pending = self.pending_multi_hop;
+
+        // A pool halted by `force_settle` since queuing takes no more swaps
+        if self.first_pool.halted || self.second_pool.halted {
+            return Err(ErrorCode::PoolHalted);
+        }
+        if amount_out < pending.min_output {
+            return Err(ErrorCode::SwapFailed);
+        }
+        if amount_in > approved_input(&self.first_pool.key(), self.user_token_in) {
+            return Err(ErrorCode::InputNotApproved);
+        }
+        self.first_pool
+            .require_min_notional(
+                amount_in,
+                pending.first_is_a_to_b,
+                pending.first_reserve_in,
+                pending.first_reserve_out,
+            )
+            .map_err(|_| ErrorCode::SwapBelowMinimum)?;
+        self.second_pool
+            .require_min_notional(
+                intermediate,
+                pending.second_is_a_to_b,
+                pending.second_reserve_in,
+                pending.second_reserve_out,
+            )
+            .map_err(|_| ErrorCode::SwapBelowMinimum)?;
+
+        // Re-run each hop against the reserves the circuit saw; back-solved
+        // inputs are rounded up, so as in `SwapSettlement` an exact-output
+        // hop only has to pay out no more than its exact-input quote
+        let consistent = |actual: u64, expected: u64| {
+            if exact_out {
+                actual <= expected
+            } else {
+                actual.abs_diff(expected) <= SWAP_MATH_TOLERANCE
+            }
+        };
+        if self.first_pool.verify_swap_math {
+            let expected = expected_swap_output(
+                amount_in,
+                pending.first_reserve_in,
+                pending.first_reserve_out,
+                pending.first_fee_rate,
+                pending.first_min_fee,
+            )
+            .map_err(|_| ErrorCode::MathOverflow)?;
+            if !consistent(intermediate, expected) {
+                return Err(ErrorCode::SwapMathMismatch);
+            }
+        }
+        if self.second_pool.verify_swap_math {
+            let expected = expected_swap_output(
+                intermediate,
+                pending.second_reserve_in,
+                pending.second_reserve_out,
+                pending.second_fee_rate,
+                pending.second_min_fee,
+            )
+            .map_err(|_| ErrorCode::MathOverflow)?;
+            if !consistent(amount_out, expected) {
+                return Err(ErrorCode::SwapMathMismatch);
+            }
+        }
+        if self.first_vault_out.amount < intermediate || self.second_vault_out.amount < amount_out {
+            return Err(ErrorCode::InsufficientLiquidity);
+        }
+        Ok(())
+    }
+
+    fn settle_checked(
+        self,
+        amount_in: u64,
+        intermediate: u64,
+        amount_out: u64,
+        exact_out: bool,
+    ) -> Result<bool> {
+        if let Err(reason) = self.check(amount_in, intermediate, amount_out, exact_out) {
+            self.abort(reason)?;
+            return Ok(false);
+        }
+
+        let pending = self.pending_multi_hop;
+        let first_pool = &*self.first_pool;
+        let first_bump = [first_pool.bump];
+        let first_seeds: &[&[&[u8]]] = &[&[
+            b"pool",
+            first_pool.token_mint_a.as_ref(),
+            first_pool.token_mint_b.as_ref(),
+            &first_bump,
+        ]];
+        let second_pool = &*self.second_pool;
+        let second_bump = [second_pool.bump];
+        let second_seeds: &[&[&[u8]]] = &[&[
+            b"pool",
+            second_pool.token_mint_a.as_ref(),
+            second_pool.token_mint_b.as_ref(),
+            &second_bump,
+        ]];
+
+        // First hop: pull the input under the approval granted at queue time
+        take_swap_input(
+            first_pool,
+            self.user_token_in,
+            self.first_vault_in,
+            self.token_program,
+            amount_in,
+        )?;
+
+        // The intermediate token moves vault to vault, never through the user
+        let cpi_accounts = Transfer {
+            from: self.first_vault_out.to_account_info(),
+            to: self.second_vault_in.to_account_info(),
+            authority: first_pool.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            cpi_accounts,
+            first_seeds,
+        );
+        token::transfer(cpi_ctx, intermediate)?;
+
+        // Second hop: pay the final output to the user
+        let cpi_accounts = Transfer {
+            from: self.second_vault_out.to_account_info(),
+            to: self.user_token_out.to_account_info(),
+            authority: second_pool.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            cpi_accounts,
+            second_seeds,
+        );
+        token::transfer(cpi_ctx, amount_out)?;
+
+        // The pending multi-hop swap is closed back to the user by the account constraint
+        let first_fee = swap_fee(amount_in, pending.first_fee_rate, pending.first_min_fee);
+        let second_fee = swap_fee(intermediate, pending.second_fee_rate, pending.second_min_fee);
+        let (first_is_a_to_b, second_is_a_to_b) = (pending.first_is_a_to_b, pending.second_is_a_to_b);
+        let first_lp_supply = self.first_lp_mint.supply;
+        let second_lp_supply = self.second_lp_mint.supply;
+        let clock = Clock::get()?;
+        let computation_offset = pending.computation_offset;
+        self.first_pool.end_swap(computation_offset)?;
+        self.second_pool.end_swap(computation_offset)?;
+        self.first_pool.apply_swap(
+            first_is_a_to_b,
+            amount_in,
+            intermediate,
+            first_fee,
+            first_lp_supply,
+            clock.slot,
+        )?;
+        self.second_pool.apply_swap(
+            second_is_a_to_b,
+            intermediate,
+            amount_out,
+            second_fee,
+            second_lp_supply,
+            clock.slot,
+        )?;
+
+        Ok(true)
+    }
+}
+
+/// Input `pool` can still pull from `user_token_in`: its approval, up to
+/// the account's balance
+fn approved_input(pool: &Pubkey, user_token_in: &TokenAccount) -> u64 {
+    if user_token_in.delegate == COption::Some(*pool) {
+        user_token_in.delegated_amount.min(user_token_in.amount)
+    } else {
+        0
+    }
+}
+
+/// Whether `pool` can close `user_token_out` into native SOL: a WSOL account
+/// whose close authority was handed to the pool when the swap was queued
+fn can_unwrap_output(pool: &Pubkey, user_token_out: &TokenAccount) -> bool {
+    user_token_out.mint == native_mint::ID && user_token_out.close_authority == COption::Some(*pool)
+}
+
+/// Take `amount_in` of a swap's input under the approval granted when it
+/// was queued, leaving the pool no allowance over the user's account
+/// The token program only lets an account's owner revoke, and the user
+/// can't sign a callback, so the approval is used up instead: the pool
+/// pulls all it may still take and returns what the swap didn't use. An
+/// aborted swap passes 0 and gets everything back. If the user's balance
+/// fell below the approval, only the shortfall stays delegated.
+fn take_swap_input<'info>(
+    pool: &Account<'info, SwapPool>,
+    user_token_in: &Account<'info, TokenAccount>,
+    vault_in: &Account<'info, TokenAccount>,
+    token_program: &Program<'info, Token>,
+    amount_in: u64,
+) -> Result<()> {
+    let pulled = approved_input(&pool.key(), user_token_in);
+    require!(amount_in <= pulled, ErrorCode::InputNotApproved);
+    if pulled == 0 {
+        return Ok(());
+    }
+
+    let bump = [pool.bump];
+    let signer_seeds: &[&[&[u8]]] = &[&[
+        b"pool",
+        pool.token_mint_a.as_ref(),
+        pool.token_mint_b.as_ref(),
+        &bump,
+    ]];
+    let cpi_accounts = Transfer {
+        from: user_token_in.to_account_info(),
+        to: vault_in.to_account_info(),
+        authority: pool.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        cpi_accounts,
+        signer_seeds,
+    );
+    token::transfer(cpi_ctx, pulled)?;
+
+    let unused = pulled - amount_in;
+    if unused > 0 {
+        let cpi_accounts = Transfer {
+            from: vault_in.to_account_info(),
+            to: user_token_in.to_account_info(),
+            authority: pool.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        );
+        token::transfer(cpi_ctx, unused)?;
+    }
+    Ok(())
+}
+
+/// Whether the spot price of a swap through `pool` in the given direction is
+/// no more than `max_slippage_bps` worse than at the quoted reserves
+fn within_slippage(
+    pool: &SwapPool,
+    is_a_to_b: bool,
+    quoted_reserve_a: u64,
+    quoted_reserve_b: u64,
+    max_slippage_bps: u16,
+) -> bool {
+    let (reserve_in, reserve_out) = hop_reserves(pool, is_a_to_b);
+    let (quoted_in, quoted_out) = if is_a_to_b {
+        (quoted_reserve_a, quoted_reserve_b)
+    } else {
+        (quoted_reserve_b, quoted_reserve_a)
+    };
+    // out / in >= quoted_out / quoted_in * (1 - bps), cross-multiplied
+    let current = (reserve_out as u128) * (quoted_in as u128);
+    let floor = (quoted_out as u128) * (reserve_in as u128) / 10_000
+        * (10_000 - max_slippage_bps as u128);
+    current >= floor
+}
+
+/// Input and output mints for a swap through `pool` in the given direction
+fn hop_mints(pool: &SwapPool, is_a_to_b: bool) -> (Pubkey, Pubkey) {
+    if is_a_to_b {
+        (pool.token_mint_a, pool.token_mint_b)
+    } else {
+        (pool.token_mint_b, pool.token_mint_a)
+    }
+}
+
+/// Input, intermediate and output mints of a two-hop route
+/// Fails unless the first hop's output token is the second hop's input token
+fn route_mints(
+    first_pool: &SwapPool,
+    first_is_a_to_b: bool,
+    second_pool: &SwapPool,
+    second_is_a_to_b: bool,
+) -> Result<(Pubkey, Pubkey, Pubkey)> {
+    let (mint_in, intermediate_mint) = hop_mints(first_pool, first_is_a_to_b);
+    let (second_mint_in, mint_out) = hop_mints(second_pool, second_is_a_to_b);
+    require_keys_eq!(intermediate_mint, second_mint_in, ErrorCode::InvalidRoute);
+    Ok((mint_in, intermediate_mint, mint_out))
+}
+
+/// Input and output reserves for a swap through `pool` in the given direction
+fn hop_reserves(pool: &SwapPool, is_a_to_b: bool) -> (u64, u64) {
+    if is_a_to_b {
+        (pool.reserve_a, pool.reserve_b)
+    } else {
+        (pool.reserve_b, pool.reserve_a)
+    }
+}
+
+/// LP tokens owed for depositing `amount_a` and `amount_b` into `pool`
+/// The first deposit mints sqrt(a * b); later deposits mint the smaller of
+/// the two reserve ratios so an unbalanced deposit can't dilute the pool
+fn lp_tokens_for_deposit(pool: &SwapPool, supply: u64, amount_a: u64, amount_b: u64) -> Result<u64> {
+    if supply == 0 {
+        let product = (amount_a as u128)
+            .checked_mul(amount_b as u128)
+            .ok_or(ErrorCode::MathOverflow)?;
+        return Ok(integer_sqrt(product));
+    }
+    require!(pool.reserve_a > 0 && pool.reserve_b > 0, ErrorCode::InsufficientLiquidity);
+    let share_a = pro_rata(supply, amount_a, pool.reserve_a)?;
+    let share_b = pro_rata(supply, amount_b, pool.reserve_b)?;
+    Ok(share_a.min(share_b))
+}
+
+/// value * numerator / denominator, rounded down
+fn pro_rata(value: u64, numerator: u64, denominator: u64) -> Result<u64> {
+    let scaled = (value as u128)
+        .checked_mul(numerator as u128)
+        .ok_or(ErrorCode::MathOverflow)?
+        .checked_div(denominator as u128)
+        .ok_or(ErrorCode::MathOverflow)?;
+    u64::try_from(scaled).map_err(|_| ErrorCode::MathOverflow.into())
+}
+
+/// Floor of the square root, via Newton's method
+fn integer_sqrt(value: u128) -> u64 {
+    if value < 2 {
+        return value as u64;
+    }
+    let mut x = value;
+    let mut y = (x + 1) / 2;
+    while y < x {
+        x = y;
+        y = (x + value / x) / 2;
+    }
+    x as u64
+}
+
+/// Reject a payer that can't cover the computation fee plus `locked`
+/// lamports before the queue CPI fails opaquely
+fn ensure_computation_fee(payer: &Signer, locked: u64) -> Result<()> {
+    require!(
+        covers_computation_fee(payer.lamports(), locked),
+        ErrorCode::InsufficientComputationFee
+    );
+    Ok(())
+}
+
+// ============ Instruction Contexts ============
+
+#[derive(Accounts)]
+pub struct InitPool<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + SwapPool::SIZE,
+        seeds = [b"pool", token_mint_a.key().as_ref(), token_mint_b.key().as_ref()],
+        bump,
+    )]
+    pub pool: Account<'info, SwapPool>,
+    
+    /// CHECK: Token mint A
+    pub token_mint_a: AccountInfo<'info>,
+    /// CHECK: Token mint B
+    pub token_mint_b: AccountInfo<'info>,
+    
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"lp_mint", pool.key().as_ref()],
+        bump,
+        mint::decimals = LP_DECIMALS,
+        mint::authority = pool,
+    )]
+    pub lp_mint: Account<'info, Mint>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, SwapConfig>,
+    
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + PoolRegistry::SIZE,
+        seeds = [b"pool_registry", authority.key().as_ref()],
+        bump,
+    )]
+    pub pool_registry: Account<'info, PoolRegistry>,
+    
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct InitConfig<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + SwapConfig::SIZE,
+        seeds = [b"config"],
+        bump,
+    )]
+    pub config: Account<'info, SwapConfig>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub config: Account<'info, SwapConfig>,
+}
+
+#[derive(Accounts)]
+pub struct AddLiquidity<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    #[account(mut)]
+    pub pool: Account<'info, SwapPool>,
+    
+    #[account(mut, constraint = user_token_a.mint == pool.token_mint_a @ ErrorCode::WrongTokenMint)]
+    pub user_token_a: Account<'info, TokenAccount>,
+    #[account(mut, constraint = user_token_b.mint == pool.token_mint_b @ ErrorCode::WrongTokenMint)]
+    pub user_token_b: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = pool_token_a.mint == pool.token_mint_a @ ErrorCode::WrongTokenMint,
+        constraint = pool_token_a.owner == pool.key() @ ErrorCode::Unauthorized,
+    )]
+    pub pool_token_a: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = pool_token_b.mint == pool.token_mint_b @ ErrorCode::WrongTokenMint,
+        constraint = pool_token_b.owner == pool.key() @ ErrorCode::Unauthorized,
+    )]
+    pub pool_token_b: Account<'info, TokenAccount>,
+    
+    #[account(mut, address = pool.lp_mint)]
+    pub lp_mint: Account<'info, Mint>,
+    #[account(mut, constraint = user_lp_token.mint == pool.lp_mint @ ErrorCode::WrongTokenMint)]
+    pub user_lp_token: Account<'info, TokenAccount>,
+    
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + LpFeeCheckpoint::SIZE,
+        seeds = [b"lp_checkpoint", pool.key().as_ref(), user.key().as_ref()],
+        bump,
+    )]
+    pub lp_checkpoint: Account<'info, LpFeeCheckpoint>,
+    
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveLiquidity<'info> {
+    pub user: Signer<'info>,
+    
+    #[account(mut)]
+    pub pool: Account<'info, SwapPool>,
+    
+    #[account(mut, constraint = user_token_a.mint == pool.token_mint_a @ ErrorCode::WrongTokenMint)]
+    pub user_token_a: Account<'info, TokenAccount>,
+    #[account(mut, constraint = user_token_b.mint == pool.token_mint_b @ ErrorCode::WrongTokenMint)]
+    pub user_token_b: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = pool_token_a.mint == pool.token_mint_a @ ErrorCode::WrongTokenMint,
+        constraint = pool_token_a.owner == pool.key() @ ErrorCode::Unauthorized,
+    )]
+    pub pool_token_a: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = pool_token_b.mint == pool.token_mint_b @ ErrorCode::WrongTokenMint,
+        constraint = pool_token_b.owner == pool.key() @ ErrorCode::Unauthorized,
+    )]
+    pub pool_token_b: Account<'info, TokenAccount>,
+    
+    #[account(mut, address = pool.lp_mint)]
+    pub lp_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        constraint = user_lp_token.mint == pool.lp_mint @ ErrorCode::WrongTokenMint,
+        constraint = user_lp_token.owner == user.key() @ ErrorCode::Unauthorized,
+    )]
+    pub user_lp_token: Account<'info, TokenAccount>,
+    
+    #[account(
+        seeds = [b"lp_checkpoint", pool.key().as_ref(), user.key().as_ref()],
+        bump = lp_checkpoint.bump,
+    )]
+    pub lp_checkpoint: Account<'info, LpFeeCheckpoint>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ProveReserves<'info> {
+    pub pool: Account<'info, SwapPool>,
+    
+    #[account(
+        constraint = pool_token_a.mint == pool.token_mint_a @ ErrorCode::WrongTokenMint,
+        constraint = pool_token_a.owner == pool.key() @ ErrorCode::Unauthorized,
+    )]
+    pub pool_token_a: Account<'info, TokenAccount>,
+    
+    #[account(
+        constraint = pool_token_b.mint == pool.token_mint_b @ ErrorCode::WrongTokenMint,
+        constraint = pool_token_b.owner == pool.key() @ ErrorCode::Unauthorized,
+    )]
+    pub pool_token_b: Account<'info, TokenAccount>,
+}
+
+#[derive(Accounts)]
+pub struct QuoteSwap<'info> {
+    pub pool: Account<'info, SwapPool>,
+    
+    /// Governance token account of the trader being quoted, for the holder
+    /// fee discount
+    #[account(constraint = governance_token.mint == pool.governance_mint @ ErrorCode::WrongTokenMint)]
+    pub governance_token: Option<Account<'info, TokenAccount>>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimLpFees<'info> {
+    pub user: Signer<'info>,
+    
+    pub pool: Account<'info, SwapPool>,
+    
+    #[account(mut, constraint = user_token_a.mint == pool.token_mint_a @ ErrorCode::WrongTokenMint)]
+    pub user_token_a: Account<'info, TokenAccount>,
+    #[account(mut, constraint = user_token_b.mint == pool.token_mint_b @ ErrorCode::WrongTokenMint)]
+    pub user_token_b: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = pool_token_a.mint == pool.token_mint_a @ ErrorCode::WrongTokenMint,
+        constraint = pool_token_a.owner == pool.key() @ ErrorCode::Unauthorized,
+    )]
+    pub pool_token_a: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = pool_token_b.mint == pool.token_mint_b @ ErrorCode::WrongTokenMint,
+        constraint = pool_token_b.owner == pool.key() @ ErrorCode::Unauthorized,
+    )]
+    pub pool_token_b: Account<'info, TokenAccount>,
+    
+    #[account(
+        constraint = user_lp_token.mint == pool.lp_mint @ ErrorCode::WrongTokenMint,
+        constraint = user_lp_token.owner == user.key() @ ErrorCode::Unauthorized,
+    )]
+    pub user_lp_token: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"lp_checkpoint", pool.key().as_ref(), user.key().as_ref()],
+        bump = lp_checkpoint.bump,
+    )]
+    pub lp_checkpoint: Account<'info, LpFeeCheckpoint>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[queue_computation_accounts("execute_swap", fee_payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct ExecuteSwap<'info> {
+    /// Funds the computation and account rent; may be a relayer or the trader
+    #[account(mut)]
+    pub fee_payer: Signer<'info>,
+    
+    /// Trader whose tokens are swapped
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(mut)]
+    pub pool: Account<'info, SwapPool>,
+    
+    #[account(
+        init,
+        payer = fee_payer,
+        space = 8 + PendingSwap::SIZE,
+        seeds = [b"pending_swap", pool.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub pending_swap: Account<'info, PendingSwap>,
+    
+    #[account(
+        mut,
+        constraint = user_token_a.mint == pool.token_mint_a @ ErrorCode::WrongTokenMint,
+        constraint = user_token_a.owner == payer.key() @ ErrorCode::Unauthorized,
+    )]
+    pub user_token_a: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = user_token_b.mint == pool.token_mint_b @ ErrorCode::WrongTokenMint,
+        constraint = user_token_b.owner == payer.key() @ ErrorCode::Unauthorized,
+    )]
+    pub user_token_b: Account<'info, TokenAccount>,
+    #[account(
+        constraint = pool_token_a.mint == pool.token_mint_a @ ErrorCode::WrongTokenMint,
+        constraint = pool_token_a.owner == pool.key() @ ErrorCode::Unauthorized,
+    )]
+    pub pool_token_a: Account<'info, TokenAccount>,
+    #[account(
+        constraint = pool_token_b.mint == pool.token_mint_b @ ErrorCode::WrongTokenMint,
+        constraint = pool_token_b.owner == pool.key() @ ErrorCode::Unauthorized,
+    )]
+    pub pool_token_b: Account<'info, TokenAccount>,
+    
+    #[account(address = pool.lp_mint)]
+    pub lp_mint: Account<'info, Mint>,
+    
+    /// Trader's governance token account, for the holder fee discount
+    #[account(
+        constraint = governance_token.mint == pool.governance_mint @ ErrorCode::WrongTokenMint,
+        constraint = governance_token.owner == payer.key() @ ErrorCode::Unauthorized,
+    )]
+    pub governance_token: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = fee_payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_SWAP))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[callback_accounts("execute_swap")]
+#[derive(Accounts)]
+pub struct ExecuteSwapCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_SWAP))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut)]
+    pub pool: Account<'info, SwapPool>,
+    
+    #[account(
+        mut,
+        close = user,
+        has_one = pool,
+        has_one = user,
+    )]
+    pub pending_swap: Account<'info, PendingSwap>,
+    
+    #[account(mut)]
+    /// CHECK: refunded the pending swap deposit, checked against `pending_swap.user`
+    pub user: UncheckedAccount<'info>,
+    
+    #[account(mut, constraint = user_token_a.owner == user.key() @ ErrorCode::Unauthorized)]
+    pub user_token_a: Account<'info, TokenAccount>,
+    #[account(mut, constraint = user_token_b.owner == user.key() @ ErrorCode::Unauthorized)]
+    pub user_token_b: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = pool_token_a.mint == pool.token_mint_a @ ErrorCode::WrongTokenMint,
+        constraint = pool_token_a.owner == pool.key() @ ErrorCode::Unauthorized,
+    )]
+    pub pool_token_a: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = pool_token_b.mint == pool.token_mint_b @ ErrorCode::WrongTokenMint,
+        constraint = pool_token_b.owner == pool.key() @ ErrorCode::Unauthorized,
+    )]
+    pub pool_token_b: Account<'info, TokenAccount>,
+    
+    #[account(address = pool.lp_mint)]
+    pub lp_mint: Account<'info, Mint>,
+    
+    pub token_program: Program<'info, Token>,
+    
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[init_computation_definition_accounts("execute_swap", payer)]
+#[derive(Accounts)]
+pub struct InitSwapCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("route_swap_exact_out", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct RouteSwapExactOut<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(mut)]
+    pub first_pool: Account<'info, SwapPool>,
+    
+    #[account(mut)]
+    pub second_pool: Account<'info, SwapPool>,
+    
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + PendingMultiHop::SIZE,
+        seeds = [b"pending_multi_hop", payer.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub pending_multi_hop: Account<'info, PendingMultiHop>,
+    
+    #[account(mut, constraint = user_token_in.owner == payer.key() @ ErrorCode::Unauthorized)]
+    pub user_token_in: Account<'info, TokenAccount>,
+    #[account(constraint = user_token_out.owner == payer.key() @ ErrorCode::Unauthorized)]
+    pub user_token_out: Account<'info, TokenAccount>,
+    #[account(constraint = first_vault_in.owner == first_pool.key() @ ErrorCode::Unauthorized)]
+    pub first_vault_in: Account<'info, TokenAccount>,
+    #[account(constraint = first_vault_out.owner == first_pool.key() @ ErrorCode::Unauthorized)]
+    pub first_vault_out: Account<'info, TokenAccount>,
+    #[account(constraint = second_vault_in.owner == second_pool.key() @ ErrorCode::Unauthorized)]
+    pub second_vault_in: Account<'info, TokenAccount>,
+    #[account(constraint = second_vault_out.owner == second_pool.key() @ ErrorCode::Unauthorized)]
+    pub second_vault_out: Account<'info, TokenAccount>,
+    
+    #[account(address = first_pool.lp_mint)]
+    pub first_lp_mint: Account<'info, Mint>,
+    #[account(address = second_pool.lp_mint)]
+    pub second_lp_mint: Account<'info, Mint>,
+    
+    /// Trader's governance token account, for the holder fee discount on
+    /// whichever hops use its mint
+    #[account(constraint = governance_token.owner == payer.key() @ ErrorCode::Unauthorized)]
+    pub governance_token: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_ROUTE_EXACT_OUT))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[queue_computation_accounts("execute_multi_hop_swap", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct ExecuteMultiHopSwap<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(mut)]
+    pub first_pool: Account<'info, SwapPool>,
+    
+    #[account(mut)]
+    pub second_pool: Account<'info, SwapPool>,
+    
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + PendingMultiHop::SIZE,
+        seeds = [b"pending_multi_hop", payer.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub pending_multi_hop: Account<'info, PendingMultiHop>,
+    
+    #[account(mut, constraint = user_token_in.owner == payer.key() @ ErrorCode::Unauthorized)]
+    pub user_token_in: Account<'info, TokenAccount>,
+    #[account(constraint = user_token_out.owner == payer.key() @ ErrorCode::Unauthorized)]
+    pub user_token_out: Account<'info, TokenAccount>,
+    #[account(constraint = first_vault_in.owner == first_pool.key() @ ErrorCode::Unauthorized)]
+    pub first_vault_in: Account<'info, TokenAccount>,
+    #[account(constraint = first_vault_out.owner == first_pool.key() @ ErrorCode::Unauthorized)]
+    pub first_vault_out: Account<'info, TokenAccount>,
+    #[account(constraint = second_vault_in.owner == second_pool.key() @ ErrorCode::Unauthorized)]
+    pub second_vault_in: Account<'info, TokenAccount>,
+    #[account(constraint = second_vault_out.owner == second_pool.key() @ ErrorCode::Unauthorized)]
+    pub second_vault_out: Account<'info, TokenAccount>,
+    
+    #[account(address = first_pool.lp_mint)]
+    pub first_lp_mint: Account<'info, Mint>,
+    #[account(address = second_pool.lp_mint)]
+    pub second_lp_mint: Account<'info, Mint>,
+    
+    /// Trader's governance token account, for the holder fee discount on
+    /// whichever hops use its mint
+    #[account(constraint = governance_token.owner == payer.key() @ ErrorCode::Unauthorized)]
+    pub governance_token: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_MULTI_HOP_SWAP))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[callback_accounts("execute_multi_hop_swap")]
+#[derive(Accounts)]
+pub struct ExecuteMultiHopSwapCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_MULTI_HOP_SWAP))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut)]
+    pub first_pool: Account<'info, SwapPool>,
+    
+    #[account(mut)]
+    pub second_pool: Account<'info, SwapPool>,
+    
+    #[account(
+        mut,
+        close = user,
+        has_one = user,
+        has_one = first_pool,
+        has_one = second_pool,
+    )]
+    pub pending_multi_hop: Account<'info, PendingMultiHop>,
+    
+    #[account(mut)]
+    /// CHECK: refunded the pending swap deposit, checked against `pending_multi_hop.user`
+    pub user: UncheckedAccount<'info>,
+    
+    #[account(mut, constraint = user_token_in.owner == user.key() @ ErrorCode::Unauthorized)]
+    pub user_token_in: Account<'info, TokenAccount>,
+    #[account(mut, constraint = user_token_out.owner == user.key() @ ErrorCode::Unauthorized)]
+    pub user_token_out: Account<'info, TokenAccount>,
+    #[account(mut, constraint = first_vault_in.owner == first_pool.key() @ ErrorCode::Unauthorized)]
+    pub first_vault_in: Account<'info, TokenAccount>,
+    #[account(mut, constraint = first_vault_out.owner == first_pool.key() @ ErrorCode::Unauthorized)]
+    pub first_vault_out: Account<'info, TokenAccount>,
+    #[account(mut, constraint = second_vault_in.owner == second_pool.key() @ ErrorCode::Unauthorized)]
+    pub second_vault_in: Account<'info, TokenAccount>,
+    #[account(mut, constraint = second_vault_out.owner == second_pool.key() @ ErrorCode::Unauthorized)]
+    pub second_vault_out: Account<'info, TokenAccount>,
+    
+    #[account(address = first_pool.lp_mint)]
+    pub first_lp_mint: Account<'info, Mint>,
+    #[account(address = second_pool.lp_mint)]
+    pub second_lp_mint: Account<'info, Mint>,
+    
+    pub token_program: Program<'info, Token>,
+    
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[init_computation_definition_accounts("execute_multi_hop_swap", payer)]
+#[derive(Accounts)]
+pub struct InitMultiHopSwapCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[callback_accounts("route_swap_exact_out")]
+#[derive(Accounts)]
+pub struct RouteSwapExactOutCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_ROUTE_EXACT_OUT))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut)]
+    pub first_pool: Account<'info, SwapPool>,
+    
+    #[account(mut)]
+    pub second_pool: Account<'info, SwapPool>,
+    
+    #[account(
+        mut,
+        close = user,
+        has_one = user,
+        has_one = first_pool,
+        has_one = second_pool,
+    )]
+    pub pending_multi_hop: Account<'info, PendingMultiHop>,
+    
+    #[account(mut)]
+    /// CHECK: refunded the pending swap deposit, checked against `pending_multi_hop.user`
+    pub user: UncheckedAccount<'info>,
+    
+    #[account(mut, constraint = user_token_in.owner == user.key() @ ErrorCode::Unauthorized)]
+    pub user_token_in: Account<'info, TokenAccount>,
+    #[account(mut, constraint = user_token_out.owner == user.key() @ ErrorCode::Unauthorized)]
+    pub user_token_out: Account<'info, TokenAccount>,
+    #[account(mut, constraint = first_vault_in.owner == first_pool.key() @ ErrorCode::Unauthorized)]
+    pub first_vault_in: Account<'info, TokenAccount>,
+    #[account(mut, constraint = first_vault_out.owner == first_pool.key() @ ErrorCode::Unauthorized)]
+    pub first_vault_out: Account<'info, TokenAccount>,
+    #[account(mut, constraint = second_vault_in.owner == second_pool.key() @ ErrorCode::Unauthorized)]
+    pub second_vault_in: Account<'info, TokenAccount>,
+    #[account(mut, constraint = second_vault_out.owner == second_pool.key() @ ErrorCode::Unauthorized)]
+    pub second_vault_out: Account<'info, TokenAccount>,
+    
+    #[account(address = first_pool.lp_mint)]
+    pub first_lp_mint: Account<'info, Mint>,
+    #[account(address = second_pool.lp_mint)]
+    pub second_lp_mint: Account<'info, Mint>,
+    
+    pub token_program: Program<'info, Token>,
+    
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[init_computation_definition_accounts("route_swap_exact_out", payer)]
+#[derive(Accounts)]
+pub struct InitRouteExactOutCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitPriceOracle<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(has_one = authority @ ErrorCode::Unauthorized)]
+    pub pool: Account<'info, SwapPool>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + PriceOracle::SIZE,
+        seeds = [b"oracle", pool.key().as_ref()],
+        bump,
+    )]
+    pub oracle: Account<'info, PriceOracle>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateOraclePrice<'info> {
+    pub updater: Signer<'info>,
+    
+    #[account(mut, has_one = updater @ ErrorCode::Unauthorized)]
+    pub oracle: Account<'info, PriceOracle>,
+}
+
+#[derive(Accounts)]
+#[instruction(swap_id: u64)]
+pub struct PlaceConditionalSwap<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    pub pool: Account<'info, SwapPool>,
+    
+    #[account(
+        init,
+        payer = user,
+        space = 8 + ConditionalSwap::SIZE,
+        seeds = [b"conditional_swap", pool.key().as_ref(), user.key().as_ref(), &swap_id.to_le_bytes()],
+        bump,
+    )]
+    pub conditional_swap: Account<'info, ConditionalSwap>,
+    
+    #[account(
+        mut,
+        constraint = user_token_a.mint == pool.token_mint_a @ ErrorCode::WrongTokenMint,
+        constraint = user_token_a.owner == user.key() @ ErrorCode::Unauthorized,
+    )]
+    pub user_token_a: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = user_token_b.mint == pool.token_mint_b @ ErrorCode::WrongTokenMint,
+        constraint = user_token_b.owner == user.key() @ ErrorCode::Unauthorized,
+    )]
+    pub user_token_b: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelConditionalSwap<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    #[account(mut, close = user, has_one = user @ ErrorCode::Unauthorized)]
+    pub conditional_swap: Account<'info, ConditionalSwap>,
+    
+    /// Input account the swap was placed against
+    #[account(mut, constraint = user_token_in.owner == user.key() @ ErrorCode::Unauthorized)]
+    pub user_token_in: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+}
 
 #[queue_computation_accounts("execute_swap", payer)]
 #[derive(Accounts)]
 #[instruction(computation_offset: u64)]
-pub struct ExecuteSwap<'info> {
+pub struct TriggerConditionalSwap<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(mut)]
+    pub pool: Account<'info, SwapPool>,
+    
+    #[account(
+        seeds = [b"oracle", pool.key().as_ref()],
+        bump = oracle.bump,
+    )]
+    pub oracle: Account<'info, PriceOracle>,
+    
+    #[account(
+        mut,
+        close = payer,
+        has_one = pool @ ErrorCode::InvalidPendingSwap,
+        has_one = user @ ErrorCode::Unauthorized,
+    )]
+    pub conditional_swap: Account<'info, ConditionalSwap>,
+    
+    #[account(mut)]
+    /// CHECK: owner of the conditional swap, checked against `conditional_swap.user`
+    pub user: UncheckedAccount<'info>,
+    
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + PendingSwap::SIZE,
+        seeds = [b"pending_swap", pool.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub pending_swap: Account<'info, PendingSwap>,
+    
+    #[account(
+        mut,
+        constraint = user_token_a.mint == pool.token_mint_a @ ErrorCode::WrongTokenMint,
+        constraint = user_token_a.owner == user.key() @ ErrorCode::Unauthorized,
+    )]
+    pub user_token_a: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = user_token_b.mint == pool.token_mint_b @ ErrorCode::WrongTokenMint,
+        constraint = user_token_b.owner == user.key() @ ErrorCode::Unauthorized,
+    )]
+    pub user_token_b: Account<'info, TokenAccount>,
+    #[account(
+        constraint = pool_token_a.mint == pool.token_mint_a @ ErrorCode::WrongTokenMint,
+        constraint = pool_token_a.owner == pool.key() @ ErrorCode::Unauthorized,
+    )]
+    pub pool_token_a: Account<'info, TokenAccount>,
+    #[account(
+        constraint = pool_token_b.mint == pool.token_mint_b @ ErrorCode::WrongTokenMint,
+        constraint = pool_token_b.owner == pool.key() @ ErrorCode::Unauthorized,
+    )]
+    pub pool_token_b: Account<'info, TokenAccount>,
+    
+    #[account(address = pool.lp_mint)]
+    pub lp_mint: Account<'info, Mint>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_SWAP))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseStaleSwap<'info> {
+    #[account(mut)]
+    pub pool: Account<'info, SwapPool>,
+    
+    #[account(
+        mut,
+        close = user,
+        constraint = pending_swap.pool == pool.key() @ ErrorCode::InvalidPendingSwap,
+        constraint = pending_swap.user == user.key() @ ErrorCode::InvalidPendingSwap,
+    )]
+    pub pending_swap: Account<'info, PendingSwap>,
+    
+    #[account(mut)]
+    /// CHECK: receives the pending swap rent, checked against `pending_swap.user`
+    pub user: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseStaleMultiHop<'info> {
+    #[account(mut)]
+    pub first_pool: Account<'info, SwapPool>,
+    
+    #[account(mut)]
+    pub second_pool: Account<'info, SwapPool>,
+    
+    #[account(
+        mut,
+        close = user,
+        has_one = user @ ErrorCode::InvalidPendingSwap,
+        has_one = first_pool @ ErrorCode::InvalidPendingSwap,
+        has_one = second_pool @ ErrorCode::InvalidPendingSwap,
+    )]
+    pub pending_multi_hop: Account<'info, PendingMultiHop>,
+    
+    #[account(mut)]
+    /// CHECK: receives the pending swap rent, checked by `has_one`
+    pub user: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ForceSettle<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(mut, has_one = authority @ ErrorCode::Unauthorized)]
+    pub pool: Account<'info, SwapPool>,
+}
+
+#[derive(Accounts)]
+pub struct SetPoolParams<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(mut, has_one = authority @ ErrorCode::Unauthorized)]
+    pub pool: Account<'info, SwapPool>,
+}
+
+#[derive(Accounts)]
+pub struct ClosePool<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(mut, close = authority, has_one = authority @ ErrorCode::Unauthorized)]
+    pub pool: Account<'info, SwapPool>,
+    
+    #[account(
+        mut,
+        seeds = [b"pool_registry", authority.key().as_ref()],
+        bump = pool_registry.bump,
+    )]
+    pub pool_registry: Account<'info, PoolRegistry>,
+    
+    #[account(address = pool.lp_mint)]
+    pub lp_mint: Account<'info, Mint>,
+    
+    #[account(
+        mut,
+        constraint = pool_token_a.mint == pool.token_mint_a @ ErrorCode::WrongTokenMint,
+        constraint = pool_token_a.owner == pool.key() @ ErrorCode::Unauthorized,
+    )]
+    pub pool_token_a: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = pool_token_b.mint == pool.token_mint_b @ ErrorCode::WrongTokenMint,
+        constraint = pool_token_b.owner == pool.key() @ ErrorCode::Unauthorized,
+    )]
+    pub pool_token_b: Account<'info, TokenAccount>,
+    
+    #[account(mut, constraint = authority_token_a.mint == pool.token_mint_a @ ErrorCode::WrongTokenMint)]
+    pub authority_token_a: Account<'info, TokenAccount>,
+    
+    #[account(mut, constraint = authority_token_b.mint == pool.token_mint_b @ ErrorCode::WrongTokenMint)]
+    pub authority_token_b: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[queue_computation_accounts("execute_swap_sealed", fee_payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct ExecuteSealedSwap<'info> {
+    /// Funds the computation and account rent; may be a relayer or the trader
+    #[account(mut)]
+    pub fee_payer: Signer<'info>,
+    
+    /// Trader whose tokens are swapped
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(mut)]
+    pub pool: Account<'info, SwapPool>,
+    
+    #[account(
+        init,
+        payer = fee_payer,
+        space = 8 + PendingSwap::SIZE,
+        seeds = [b"pending_swap", pool.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub pending_swap: Account<'info, PendingSwap>,
+    
+    #[account(
+        mut,
+        constraint = user_token_a.mint == pool.token_mint_a @ ErrorCode::WrongTokenMint,
+        constraint = user_token_a.owner == payer.key() @ ErrorCode::Unauthorized,
+    )]
+    pub user_token_a: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = user_token_b.mint == pool.token_mint_b @ ErrorCode::WrongTokenMint,
+        constraint = user_token_b.owner == payer.key() @ ErrorCode::Unauthorized,
+    )]
+    pub user_token_b: Account<'info, TokenAccount>,
+    #[account(
+        constraint = pool_token_a.mint == pool.token_mint_a @ ErrorCode::WrongTokenMint,
+        constraint = pool_token_a.owner == pool.key() @ ErrorCode::Unauthorized,
+    )]
+    pub pool_token_a: Account<'info, TokenAccount>,
+    #[account(
+        constraint = pool_token_b.mint == pool.token_mint_b @ ErrorCode::WrongTokenMint,
+        constraint = pool_token_b.owner == pool.key() @ ErrorCode::Unauthorized,
+    )]
+    pub pool_token_b: Account<'info, TokenAccount>,
+    
+    #[account(address = pool.lp_mint)]
+    pub lp_mint: Account<'info, Mint>,
+    
+    /// Trader's governance token account, for the holder fee discount
+    #[account(
+        constraint = governance_token.mint == pool.governance_mint @ ErrorCode::WrongTokenMint,
+        constraint = governance_token.owner == payer.key() @ ErrorCode::Unauthorized,
+    )]
+    pub governance_token: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = fee_payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_SWAP_SEALED))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[callback_accounts("execute_swap_sealed")]
+#[derive(Accounts)]
+pub struct ExecuteSwapSealedCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_SWAP_SEALED))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut)]
+    pub pool: Account<'info, SwapPool>,
+    
+    #[account(mut, has_one = pool, has_one = user)]
+    pub pending_swap: Account<'info, PendingSwap>,
+    
+    #[account(mut)]
+    /// CHECK: refunded the pending swap deposit if the swap fails, checked against `pending_swap.user`
+    pub user: UncheckedAccount<'info>,
+    
+    #[account(mut, constraint = user_token_a.owner == user.key() @ ErrorCode::Unauthorized)]
+    pub user_token_a: Account<'info, TokenAccount>,
+    #[account(mut, constraint = user_token_b.owner == user.key() @ ErrorCode::Unauthorized)]
+    pub user_token_b: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = pool_token_a.mint == pool.token_mint_a @ ErrorCode::WrongTokenMint,
+        constraint = pool_token_a.owner == pool.key() @ ErrorCode::Unauthorized,
+    )]
+    pub pool_token_a: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = pool_token_b.mint == pool.token_mint_b @ ErrorCode::WrongTokenMint,
+        constraint = pool_token_b.owner == pool.key() @ ErrorCode::Unauthorized,
+    )]
+    pub pool_token_b: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[queue_computation_accounts("reveal_swap_result", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct FinalizeSwapResult<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(mut)]
+    pub pool: Account<'info, SwapPool>,
+    
+    #[account(
+        has_one = pool @ ErrorCode::InvalidPendingSwap,
+        has_one = user @ ErrorCode::InvalidPendingSwap,
+    )]
+    pub pending_swap: Account<'info, PendingSwap>,
+    
+    /// CHECK: trader the sealed swap settles to, checked against `pending_swap.user`
+    pub user: UncheckedAccount<'info>,
+    
+    #[account(
+        constraint = user_token_a.mint == pool.token_mint_a @ ErrorCode::WrongTokenMint,
+        constraint = user_token_a.owner == user.key() @ ErrorCode::Unauthorized,
+    )]
+    pub user_token_a: Account<'info, TokenAccount>,
+    #[account(
+        constraint = user_token_b.mint == pool.token_mint_b @ ErrorCode::WrongTokenMint,
+        constraint = user_token_b.owner == user.key() @ ErrorCode::Unauthorized,
+    )]
+    pub user_token_b: Account<'info, TokenAccount>,
+    #[account(
+        constraint = pool_token_a.mint == pool.token_mint_a @ ErrorCode::WrongTokenMint,
+        constraint = pool_token_a.owner == pool.key() @ ErrorCode::Unauthorized,
+    )]
+    pub pool_token_a: Account<'info, TokenAccount>,
+    #[account(
+        constraint = pool_token_b.mint == pool.token_mint_b @ ErrorCode::WrongTokenMint,
+        constraint = pool_token_b.owner == pool.key() @ ErrorCode::Unauthorized,
+    )]
+    pub pool_token_b: Account<'info, TokenAccount>,
+    
+    #[account(address = pool.lp_mint)]
+    pub lp_mint: Account<'info, Mint>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_REVEAL_SWAP_RESULT))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("reveal_swap_result")]
+#[derive(Accounts)]
+pub struct RevealSwapResultCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_REVEAL_SWAP_RESULT))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut)]
+    pub pool: Account<'info, SwapPool>,
+    
+    #[account(
+        mut,
+        close = user,
+        has_one = pool,
+        has_one = user,
+    )]
+    pub pending_swap: Account<'info, PendingSwap>,
+    
+    #[account(mut)]
+    /// CHECK: refunded the pending swap deposit, checked against `pending_swap.user`
+    pub user: UncheckedAccount<'info>,
+    
+    #[account(mut, constraint = user_token_a.owner == user.key() @ ErrorCode::Unauthorized)]
+    pub user_token_a: Account<'info, TokenAccount>,
+    #[account(mut, constraint = user_token_b.owner == user.key() @ ErrorCode::Unauthorized)]
+    pub user_token_b: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = pool_token_a.mint == pool.token_mint_a @ ErrorCode::WrongTokenMint,
+        constraint = pool_token_a.owner == pool.key() @ ErrorCode::Unauthorized,
+    )]
+    pub pool_token_a: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = pool_token_b.mint == pool.token_mint_b @ ErrorCode::WrongTokenMint,
+        constraint = pool_token_b.owner == pool.key() @ ErrorCode::Unauthorized,
+    )]
+    pub pool_token_b: Account<'info, TokenAccount>,
+    
+    #[account(address = pool.lp_mint)]
+    pub lp_mint: Account<'info, Mint>,
+    
+    pub token_program: Program<'info, Token>,
+    
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[init_computation_definition_accounts("execute_swap_sealed", payer)]
+#[derive(Accounts)]
+pub struct InitSwapSealedCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("reveal_swap_result", payer)]
+#[derive(Accounts)]
+pub struct InitRevealSwapResultCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("execute_swap_exact_out", fee_payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct ExecuteSwapExactOut<'info> {
+    /// Funds the computation and account rent; may be a relayer or the trader
+    #[account(mut)]
+    pub fee_payer: Signer<'info>,
+    
+    /// Trader whose tokens are swapped
     #[account(mut)]
     pub payer: Signer<'info>,
     
-    #[account(mut)]
-    pub pool: Account<'info, SwapPool>,
+    #[account(mut)]
+    pub pool: Account<'info, SwapPool>,
+    
+    #[account(
+        init,
+        payer = fee_payer,
+        space = 8 + PendingSwap::SIZE,
+        seeds = [b"pending_swap", pool.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub pending_swap: Account<'info, PendingSwap>,
+    
+    #[account(
+        mut,
+        constraint = user_token_a.mint == pool.token_mint_a @ ErrorCode::WrongTokenMint,
+        constraint = user_token_a.owner == payer.key() @ ErrorCode::Unauthorized,
+    )]
+    pub user_token_a: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = user_token_b.mint == pool.token_mint_b @ ErrorCode::WrongTokenMint,
+        constraint = user_token_b.owner == payer.key() @ ErrorCode::Unauthorized,
+    )]
+    pub user_token_b: Account<'info, TokenAccount>,
+    #[account(
+        constraint = pool_token_a.mint == pool.token_mint_a @ ErrorCode::WrongTokenMint,
+        constraint = pool_token_a.owner == pool.key() @ ErrorCode::Unauthorized,
+    )]
+    pub pool_token_a: Account<'info, TokenAccount>,
+    #[account(
+        constraint = pool_token_b.mint == pool.token_mint_b @ ErrorCode::WrongTokenMint,
+        constraint = pool_token_b.owner == pool.key() @ ErrorCode::Unauthorized,
+    )]
+    pub pool_token_b: Account<'info, TokenAccount>,
+    
+    #[account(address = pool.lp_mint)]
+    pub lp_mint: Account<'info, Mint>,
+    
+    /// Trader's governance token account, for the holder fee discount
+    #[account(
+        constraint = governance_token.mint == pool.governance_mint @ ErrorCode::WrongTokenMint,
+        constraint = governance_token.owner == payer.key() @ ErrorCode::Unauthorized,
+    )]
+    pub governance_token: Option<Account<'info, TokenAccount>>,
     
     #[account(
         init_if_needed,
         space = 9,
-        payer = payer,
+        payer = fee_payer,
         seeds = [&SIGN_PDA_SEED],
         bump,
         address = derive_sign_pda!(),
@@ -241,7 +4547,7 @@ pub struct ExecuteSwap<'info> {
     /// CHECK: computation_account
     pub computation_account: UncheckedAccount<'info>,
     
-    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_SWAP))]
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_SWAP_EXACT_OUT))]
     pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
     
     #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
@@ -258,12 +4564,12 @@ pub struct ExecuteSwap<'info> {
     pub token_program: Program<'info, Token>,
 }
 
-#[callback_accounts("execute_swap")]
+#[callback_accounts("execute_swap_exact_out")]
 #[derive(Accounts)]
-pub struct ExecuteSwapCallback<'info> {
+pub struct ExecuteSwapExactOutCallback<'info> {
     pub arcium_program: Program<'info, Arcium>,
     
-    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_SWAP))]
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_SWAP_EXACT_OUT))]
     pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
     
     #[account(address = derive_mxe_pda!())]
@@ -275,14 +4581,51 @@ pub struct ExecuteSwapCallback<'info> {
     #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
     pub cluster_account: Account<'info, Cluster>,
     
+    #[account(mut)]
+    pub pool: Account<'info, SwapPool>,
+    
+    #[account(
+        mut,
+        close = user,
+        has_one = pool,
+        has_one = user,
+    )]
+    pub pending_swap: Account<'info, PendingSwap>,
+    
+    #[account(mut)]
+    /// CHECK: refunded the pending swap deposit, checked against `pending_swap.user`
+    pub user: UncheckedAccount<'info>,
+    
+    #[account(mut, constraint = user_token_a.owner == user.key() @ ErrorCode::Unauthorized)]
+    pub user_token_a: Account<'info, TokenAccount>,
+    #[account(mut, constraint = user_token_b.owner == user.key() @ ErrorCode::Unauthorized)]
+    pub user_token_b: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = pool_token_a.mint == pool.token_mint_a @ ErrorCode::WrongTokenMint,
+        constraint = pool_token_a.owner == pool.key() @ ErrorCode::Unauthorized,
+    )]
+    pub pool_token_a: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = pool_token_b.mint == pool.token_mint_b @ ErrorCode::WrongTokenMint,
+        constraint = pool_token_b.owner == pool.key() @ ErrorCode::Unauthorized,
+    )]
+    pub pool_token_b: Account<'info, TokenAccount>,
+    
+    #[account(address = pool.lp_mint)]
+    pub lp_mint: Account<'info, Mint>,
+    
+    pub token_program: Program<'info, Token>,
+    
     #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
     /// CHECK: instructions_sysvar
     pub instructions_sysvar: AccountInfo<'info>,
 }
 
-#[init_computation_definition_accounts("execute_swap", payer)]
+#[init_computation_definition_accounts("execute_swap_exact_out", payer)]
 #[derive(Accounts)]
-pub struct InitSwapCompDef<'info> {
+pub struct InitSwapExactOutCompDef<'info> {
     #[account(mut)]
     pub payer: Signer<'info>,
     
@@ -304,12 +4647,258 @@ pub struct LiquidityAdded {
     pub pool: Pubkey,
     pub amount_a: u64,
     pub amount_b: u64,
+    pub lp_minted: u64,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct LiquidityRemoved {
+    pub pool: Pubkey,
+    pub amount_a: u64,
+    pub amount_b: u64,
+    pub lp_burned: u64,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct ReservesAttested {
+    pub pool: Pubkey,
+    pub reserve_a: u64,
+    pub reserve_b: u64,
+    pub vault_a: u64,
+    pub vault_b: u64,
+    pub consistent: bool, // Both vaults hold at least the recorded reserves
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct SwapQuoted {
+    pub pool: Pubkey,
+    pub amount_in: u64,
+    pub amount_out: u64, // After fees, at the reserves when quoted
+    pub is_a_to_b: bool,
+    pub fee_rate: u16,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct LpFeesClaimed {
+    pub pool: Pubkey,
+    pub owner: Pubkey,
+    pub amount_a: u64,
+    pub amount_b: u64,
+    pub slot: u64,
+    pub unix_timestamp: i64,
 }
 
 #[event]
 pub struct SwapExecuted {
     pub amount_in: u64,
     pub amount_out: u64,
+    pub reserve_a: u64, // Pool reserves after the swap
+    pub reserve_b: u64,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct RouteSwapExecuted {
+    pub first_pool: Pubkey,
+    pub second_pool: Pubkey,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct MultiHopSwapExecuted {
+    pub first_pool: Pubkey,
+    pub second_pool: Pubkey,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct SwapAborted {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub computation_offset: u64,
+    pub reason: u32, // ErrorCode the swap failed with
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct MultiHopSwapAborted {
+    pub first_pool: Pubkey,
+    pub second_pool: Pubkey,
+    pub user: Pubkey,
+    pub computation_offset: u64,
+    pub reason: u32, // ErrorCode the route failed with
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct StaleSwapReleased {
+    pub pool: Pubkey,
+    pub computation_offset: u64,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct SwapsForceSettled {
+    pub pool: Pubkey,
+    pub settled: u32,
+    pub remaining: u32, // In-flight swaps not included in this call
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct FeeRateUpdated {
+    pub pool: Pubkey,
+    pub fee_rate: u16,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct OraclePriceUpdated {
+    pub pool: Pubkey,
+    pub price: u64,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct ConditionalSwapPlaced {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub swap_id: u64,
+    pub trigger_price: u64,
+    pub trigger_above: bool,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct ConditionalSwapCancelled {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub swap_id: u64,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct ConditionalSwapTriggered {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub swap_id: u64,
+    pub oracle_price: u64,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct PausedOpsUpdated {
+    pub pool: Pubkey,
+    pub paused_ops: u8,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct PausedUpdated {
+    pub pool: Pubkey,
+    pub paused: bool,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct PriceTickUpdated {
+    pub pool: Pubkey,
+    pub price_tick: u64,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct FeeModeUpdated {
+    pub pool: Pubkey,
+    pub fee_mode: FeeMode,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct MinSwapNotionalUpdated {
+    pub pool: Pubkey,
+    pub min_swap_notional: u64,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct SwapMathVerificationUpdated {
+    pub pool: Pubkey,
+    pub enabled: bool,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct MaxPoolsUpdated {
+    pub max_pools_per_authority: u32,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct PoolClosed {
+    pub pool: Pubkey,
+    pub authority: Pubkey,
+    pub swept_a: u64, // Unclaimable fee dust swept to the authority
+    pub swept_b: u64,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct SwapSealed {
+    pub pool: Pubkey,
+    pub pending_swap: Pubkey,
+    pub ready_slot: u64,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct RevealDelayUpdated {
+    pub pool: Pubkey,
+    pub reveal_delay_slots: u64,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct FeeDiscountsUpdated {
+    pub pool: Pubkey,
+    pub governance_mint: Pubkey,
+    pub discount_thresholds: [u64; FEE_DISCOUNT_TIERS],
+    pub discount_bps: [u16; FEE_DISCOUNT_TIERS],
+    pub slot: u64,
+    pub unix_timestamp: i64,
 }
 
 // ============ Errors ============
@@ -324,4 +4913,664 @@ pub enum ErrorCode {
     SwapFailed,
     #[msg("Insufficient liquidity in pool")]
     InsufficientLiquidity,
+    #[msg("Route hops do not connect")]
+    InvalidRoute,
+    #[msg("Token account mint does not match the pool")]
+    WrongTokenMint,
+    #[msg("Arithmetic overflow")]
+    MathOverflow,
+    #[msg("Pool is halted")]
+    PoolHalted,
+    #[msg("Unauthorized")]
+    Unauthorized,
+    #[msg("Pending swap does not belong to this pool or user")]
+    InvalidPendingSwap,
+    #[msg("Payer cannot cover the computation fee")]
+    InsufficientComputationFee,
+    #[msg("This operation is paused")]
+    OperationPaused,
+    #[msg("Liquidity amount rounds to zero")]
+    ZeroLiquidity,
+    #[msg("Swap output does not match the pool's constant-product math")]
+    SwapMathMismatch,
+    #[msg("Only a wrapped SOL output can be unwrapped")]
+    OutputNotWrappedSol,
+    #[msg("Claim outstanding LP fees before changing the position")]
+    UnclaimedLpFees,
+    #[msg("Pool is paused")]
+    ProgramPaused,
+    #[msg("Fee rate exceeds the maximum")]
+    FeeTooHigh,
+    #[msg("Oracle price must be non-zero")]
+    InvalidOraclePrice,
+    #[msg("Oracle price is missing or too old")]
+    StaleOraclePrice,
+    #[msg("Oracle price hasn't crossed the trigger price")]
+    ConditionNotMet,
+    #[msg("Pool still holds reserves or pending swaps")]
+    NonEmptyAccount,
+    #[msg("Price is zero or off the pool's price tick")]
+    InvalidPriceTick,
+    #[msg("Pool has a reveal delay; use execute_sealed_swap")]
+    SealedSwapRequired,
+    #[msg("Swap is not sealed")]
+    SwapNotSealed,
+    #[msg("Sealed swap result is still within its reveal delay")]
+    RevealDelayNotElapsed,
+    #[msg("Reveal delay exceeds the maximum")]
+    RevealDelayTooHigh,
+    #[msg("Slippage must be at most 10000 basis points")]
+    InvalidSlippage,
+    #[msg("Pool price moved beyond the allowed slippage")]
+    SlippageExceeded,
+    #[msg("Swap would decrease the pool's constant-product invariant")]
+    InvariantViolation,
+    #[msg("Discount tiers must ascend and stay within 10000 bps")]
+    InvalidDiscountSchedule,
+    #[msg("Authority already has the maximum number of open pools")]
+    PoolLimitReached,
+    #[msg("Pool already has a swap awaiting settlement")]
+    SwapInFlight,
+    #[msg("Swap no longer holds the pool")]
+    StaleSwap,
+    #[msg("The swap holding the pool hasn't timed out")]
+    SwapNotStale,
+    #[msg("Swap input is below the pool's minimum notional")]
+    SwapBelowMinimum,
+    #[msg("Pool reserves moved against the sealed swap before it was revealed")]
+    SealedReservesMoved,
+    #[msg("Swap input exceeds what the pool was approved to take")]
+    InputNotApproved,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::solana_program::program_pack::Pack;
+    use anchor_spl::token::spl_token::state::{Account as SplAccount, AccountState};
+
+    fn empty_pool() -> SwapPool {
+        SwapPool::deserialize(&mut &[0u8; SwapPool::SIZE][..]).unwrap()
+    }
+
+    fn funded_pool(reserve_a: u64, reserve_b: u64) -> SwapPool {
+        let mut pool = empty_pool();
+        pool.reserve_a = reserve_a;
+        pool.reserve_b = reserve_b;
+        pool
+    }
+
+    #[test]
+    fn pool_fee_rates_are_capped_at_max_fee_bps() {
+        assert!(validate_fee_rate(MAX_FEE_BPS).is_ok());
+        assert_eq!(
+            validate_fee_rate(MAX_FEE_BPS + 1).unwrap_err(),
+            ErrorCode::FeeTooHigh.into()
+        );
+    }
+
+    #[test]
+    fn quote_applies_the_fee_floor_when_larger() {
+        // 30 bps of 100 rounds to nothing, so only the floor charges a fee
+        assert_eq!(expected_swap_output(100, 10_000, 10_000, 30, 0).unwrap(), 99);
+        assert_eq!(expected_swap_output(100, 10_000, 10_000, 30, 5).unwrap(), 94);
+        assert_eq!(expected_swap_output(5, 10_000, 10_000, 30, 5).unwrap(), 0);
+        assert_eq!(expected_swap_output(100, 0, 0, 30, 0).unwrap(), 0);
+    }
+
+    #[test]
+    fn quote_never_empties_the_output_reserve() {
+        for amount_in in [1, 1_000, u64::MAX / 2, u64::MAX] {
+            let out = expected_swap_output(amount_in, 1_000, 1_000, 30, 0).unwrap();
+            assert!(out < 1_000);
+        }
+        assert_eq!(expected_swap_output(u64::MAX, 1, u64::MAX, 0, 0).unwrap(), u64::MAX - 1);
+    }
+
+    #[test]
+    fn applied_swap_keeps_the_fee_out_of_the_curve() {
+        let amount_out = expected_swap_output(1_000, 1_000_000, 1_000_000, 30, 0).unwrap();
+
+        // Retained: the fee joins the input reserve after the curve moves
+        let mut pool = funded_pool(1_000_000, 1_000_000);
+        pool.apply_swap(true, 1_000, amount_out, 3, 500, 10).unwrap();
+        assert_eq!((pool.reserve_a, pool.reserve_b), (1_001_000, 1_000_000 - amount_out));
+        assert_eq!(pool.total_swaps, 1);
+
+        // Extracted: the fee accrues to LPs instead of the reserves
+        let mut pool = funded_pool(1_000_000, 1_000_000);
+        pool.fee_mode = FeeMode::Extracted;
+        pool.apply_swap(false, 1_000, amount_out, 3, 500, 10).unwrap();
+        assert_eq!((pool.reserve_a, pool.reserve_b), (1_000_000 - amount_out, 1_000_997));
+        assert_eq!(pool.fee_growth_b, 3 * FEE_GROWTH_SCALE / 500);
+        assert_eq!(pool.fee_growth_a, 0);
+    }
+
+    #[test]
+    fn applied_swap_rejects_fills_the_pool_cannot_back() {
+        let amount_out = expected_swap_output(1_000, 1_000_000, 1_000_000, 30, 0).unwrap();
+        let mut pool = funded_pool(1_000_000, 1_000_000);
+        assert_eq!(
+            pool.apply_swap(true, 1_000, amount_out + 1, 3, 0, 10).unwrap_err(),
+            ErrorCode::InvariantViolation.into()
+        );
+        let mut pool = funded_pool(1_000_000, 1_000);
+        assert_eq!(
+            pool.apply_swap(true, 1_000_000, 1_001, 0, 0, 10).unwrap_err(),
+            ErrorCode::InsufficientLiquidity.into()
+        );
+        let mut pool = funded_pool(1_000_000, 1_000_000);
+        assert_eq!(
+            pool.apply_swap(true, 2, 0, 3, 0, 10).unwrap_err(),
+            ErrorCode::MathOverflow.into()
+        );
+    }
+
+    fn packed_token_account(
+        mint: Pubkey,
+        amount: u64,
+        edit: impl FnOnce(&mut SplAccount),
+    ) -> TokenAccount {
+        let mut account = SplAccount {
+            mint,
+            owner: Pubkey::new_unique(),
+            amount,
+            delegate: COption::None,
+            state: AccountState::Initialized,
+            is_native: COption::None,
+            delegated_amount: 0,
+            close_authority: COption::None,
+        };
+        edit(&mut account);
+        let mut data = [0u8; SplAccount::LEN];
+        SplAccount::pack(account, &mut data).unwrap();
+        TokenAccount::try_deserialize(&mut &data[..]).unwrap()
+    }
+
+    #[test]
+    fn fees_are_owed_pro_rata_since_each_checkpoint() {
+        let mut pool = funded_pool(1_000_000, 1_000_000);
+        pool.fee_mode = FeeMode::Extracted;
+        let mut checkpoint =
+            LpFeeCheckpoint::deserialize(&mut &[0u8; LpFeeCheckpoint::SIZE][..]).unwrap();
+        assert!(checkpoint.is_current(&pool));
+
+        // Two LPs holding 300 and 100 of 400 LP tokens split a 40 token fee
+        pool.accrue_fee(true, 40, 400).unwrap();
+        assert!(!checkpoint.is_current(&pool));
+        assert_eq!(lp_fees_owed(300, pool.fee_growth_a, checkpoint.fee_growth_a).unwrap(), 30);
+        assert_eq!(lp_fees_owed(100, pool.fee_growth_a, checkpoint.fee_growth_a).unwrap(), 10);
+        assert_eq!(lp_fees_owed(300, pool.fee_growth_b, checkpoint.fee_growth_b).unwrap(), 0);
+
+        // Once claimed, only fees accrued afterwards are owed
+        checkpoint.fee_growth_a = pool.fee_growth_a;
+        assert!(checkpoint.is_current(&pool));
+        pool.accrue_fee(true, 8, 400).unwrap();
+        assert_eq!(lp_fees_owed(300, pool.fee_growth_a, checkpoint.fee_growth_a).unwrap(), 6);
+
+        assert_eq!(
+            lp_fees_owed(u64::MAX, u128::MAX, 0).unwrap_err(),
+            ErrorCode::MathOverflow.into()
+        );
+    }
+
+    #[test]
+    fn trigger_prices_must_sit_on_the_tick_grid() {
+        let mut pool = empty_pool();
+        pool.price_tick = 1;
+        assert!(pool.on_price_grid(7));
+        pool.price_tick = 25;
+        assert!(pool.on_price_grid(0) && pool.on_price_grid(100));
+        assert!(!pool.on_price_grid(110));
+        // An unset tick rejects every price instead of dividing by zero
+        pool.price_tick = 0;
+        assert!(!pool.on_price_grid(100));
+    }
+
+    #[test]
+    fn conditional_swaps_fire_on_a_fresh_crossing_price() {
+        let mut oracle = PriceOracle::deserialize(&mut &[0u8; PriceOracle::SIZE][..]).unwrap();
+        // Never published
+        assert_eq!(oracle.fresh_price(0).unwrap_err(), ErrorCode::StaleOraclePrice.into());
+
+        oracle.price = 120;
+        oracle.updated_slot = 1_000;
+        assert_eq!(oracle.fresh_price(1_000 + MAX_ORACLE_AGE_SLOTS).unwrap(), 120);
+        assert_eq!(
+            oracle.fresh_price(1_001 + MAX_ORACLE_AGE_SLOTS).unwrap_err(),
+            ErrorCode::StaleOraclePrice.into()
+        );
+
+        let mut swap =
+            ConditionalSwap::deserialize(&mut &[0u8; ConditionalSwap::SIZE][..]).unwrap();
+        swap.trigger_price = 120;
+        swap.trigger_above = true;
+        assert!(swap.is_triggered(120) && swap.is_triggered(121));
+        assert!(!swap.is_triggered(119));
+        swap.trigger_above = false;
+        assert!(swap.is_triggered(120) && swap.is_triggered(119));
+        assert!(!swap.is_triggered(121));
+    }
+
+    fn token_account(amount: u64, delegate: Option<Pubkey>, delegated_amount: u64) -> TokenAccount {
+        packed_token_account(Pubkey::new_unique(), amount, |account| {
+            account.delegate = delegate.map_or(COption::None, COption::Some);
+            account.delegated_amount = delegated_amount;
+        })
+    }
+
+    #[test]
+    fn swap_may_pull_only_its_own_approval() {
+        let pool = Pubkey::new_unique();
+        assert_eq!(approved_input(&pool, &token_account(1_000, Some(pool), 250)), 250);
+        // A balance spent down since queuing caps the approval
+        assert_eq!(approved_input(&pool, &token_account(100, Some(pool), 250)), 100);
+        // Replaced by the user, or never granted
+        let other = Pubkey::new_unique();
+        assert_eq!(approved_input(&pool, &token_account(1_000, Some(other), 250)), 0);
+        assert_eq!(approved_input(&pool, &token_account(1_000, None, 0)), 0);
+    }
+
+    #[test]
+    fn only_wrapped_sol_handed_to_the_pool_is_unwrapped() {
+        let pool = Pubkey::new_unique();
+        let output = |mint, close_authority| {
+            packed_token_account(mint, 500, |account| {
+                account.is_native = COption::Some(2_039_280);
+                account.close_authority = close_authority;
+            })
+        };
+        assert!(can_unwrap_output(&pool, &output(native_mint::ID, COption::Some(pool))));
+        // Close authority taken back, or handed to someone else
+        assert!(!can_unwrap_output(&pool, &output(native_mint::ID, COption::None)));
+        let other = Pubkey::new_unique();
+        assert!(!can_unwrap_output(&pool, &output(native_mint::ID, COption::Some(other))));
+        // Not a WSOL account at all
+        assert!(!can_unwrap_output(&pool, &output(Pubkey::new_unique(), COption::Some(pool))));
+    }
+
+    #[test]
+    fn first_deposit_mints_the_geometric_mean() {
+        let pool = empty_pool();
+        assert_eq!(lp_tokens_for_deposit(&pool, 0, 4, 9).unwrap(), 6);
+        assert_eq!(lp_tokens_for_deposit(&pool, 0, 1_000_000, 1_000_000).unwrap(), 1_000_000);
+        assert_eq!(lp_tokens_for_deposit(&pool, 0, u64::MAX, u64::MAX).unwrap(), u64::MAX);
+    }
+
+    #[test]
+    fn later_deposits_mint_the_smaller_reserve_share() {
+        let pool = funded_pool(1_000, 2_000);
+        assert_eq!(lp_tokens_for_deposit(&pool, 1_000, 100, 200).unwrap(), 100);
+        // Extra token B buys nothing beyond the token A share
+        assert_eq!(lp_tokens_for_deposit(&pool, 1_000, 100, 400).unwrap(), 100);
+        assert_eq!(lp_tokens_for_deposit(&pool, 1_000, 100, 100).unwrap(), 50);
+
+        assert_eq!(
+            lp_tokens_for_deposit(&funded_pool(0, 2_000), 1_000, 100, 200).unwrap_err(),
+            ErrorCode::InsufficientLiquidity.into()
+        );
+    }
+
+    #[test]
+    fn only_a_drained_pool_may_close() {
+        assert!(empty_pool().is_empty(0));
+        assert!(!empty_pool().is_empty(1));
+        assert!(!funded_pool(1, 0).is_empty(0));
+        assert!(!funded_pool(0, 1).is_empty(0));
+
+        // A queued swap still settles against the pool
+        let mut pool = empty_pool();
+        pool.begin_swap(7, 100).unwrap();
+        assert!(!pool.is_empty(0));
+        pool.end_swap(7).unwrap();
+        assert!(pool.is_empty(0));
+    }
+
+    #[test]
+    fn twap_weights_each_price_by_the_slots_it_held() {
+        const ONE: u128 = 1 << 64;
+        let mut pool = funded_pool(1_000, 2_000);
+        pool.update_price_cumulative(10);
+        assert_eq!(pool.price_cumulative_a, 2 * ONE * 10);
+        assert_eq!(pool.price_cumulative_b, ONE / 2 * 10);
+
+        // The price halves and holds for twice as long
+        pool.reserve_b = 1_000;
+        pool.update_price_cumulative(30);
+        assert_eq!(
+            time_weighted_price(0, 0, pool.price_cumulative_a, 30),
+            Some((2 * ONE * 10 + ONE * 20) / 30)
+        );
+        assert_eq!(time_weighted_price(0, 10, pool.price_cumulative_a, 10), None);
+
+        // Observations straddling a wrap of the accumulator still difference
+        let start = u128::MAX - ONE + 1;
+        assert_eq!(time_weighted_price(start, 0, start.wrapping_add(3 * ONE), 3), Some(ONE));
+
+        // Nothing accrues while a reserve is empty
+        let mut pool = funded_pool(0, 2_000);
+        pool.update_price_cumulative(10);
+        assert_eq!((pool.price_cumulative_a, pool.last_update_slot), (0, 10));
+    }
+
+    #[test]
+    fn swap_lock_is_released_only_by_its_own_swap() {
+        let mut pool = empty_pool();
+        pool.begin_swap(7, 100).unwrap();
+        assert!(pool.holds_swap(7));
+        assert!(!pool.holds_swap(8));
+        assert_eq!(pool.begin_swap(8, 101).unwrap_err(), ErrorCode::SwapInFlight.into());
+
+        // An aborted swap's callback releases through the same path as a settled one
+        assert_eq!(pool.end_swap(8).unwrap_err(), ErrorCode::StaleSwap.into());
+        pool.end_swap(7).unwrap();
+        assert!(!pool.holds_swap(7));
+        assert_eq!(pool.pending_swaps, 0);
+        pool.begin_swap(8, 102).unwrap();
+    }
+
+    #[test]
+    fn sealed_swap_frees_the_pool_through_its_reveal_delay() {
+        let mut pool = empty_pool();
+        pool.reveal_delay_slots = MAX_REVEAL_DELAY_SLOTS;
+        pool.begin_swap(7, 100).unwrap();
+
+        // Sealed: the swap is still counted but another one can trade
+        pool.unlock(7).unwrap();
+        assert_eq!(pool.pending_swaps, 1);
+        pool.begin_swap(8, 101).unwrap();
+        pool.end_swap(8).unwrap();
+
+        // Finalized: held again, and stale after the timeout alone
+        pool.lock(7, 200).unwrap();
+        assert!(!pool.swap_lock_expired(200 + SWAP_TIMEOUT_SLOTS - 1));
+        assert!(pool.swap_lock_expired(200 + SWAP_TIMEOUT_SLOTS));
+        pool.end_swap(7).unwrap();
+        assert_eq!(pool.pending_swaps, 0);
+    }
+
+    /// Constant-product output derived from the invariant rather than the
+    /// closed form the circuit and `expected_swap_output` share: the most
+    /// the pool can pay while `reserve_in * reserve_out` stays covered
+    fn invariant_output(
+        amount_in: u64,
+        reserve_in: u64,
+        reserve_out: u64,
+        fee_rate: u16,
+        min_fee: u64,
+    ) -> u64 {
+        let fee = ((amount_in as u128) * (fee_rate as u128) / 10_000).max(min_fee as u128);
+        let new_reserve_in = reserve_in as u128 + (amount_in as u128).saturating_sub(fee);
+        let k = (reserve_in as u128) * (reserve_out as u128);
+        (reserve_out as u128 - k.div_ceil(new_reserve_in)) as u64
+    }
+
+    /// A pool that re-checks the swap math, and a swap queued against it
+    fn queued_swap(
+        reserve_a: u64,
+        reserve_b: u64,
+        is_a_to_b: bool,
+        fee_rate: u16,
+        min_fee: u64,
+    ) -> (SwapPool, PendingSwap) {
+        let mut pool = empty_pool();
+        pool.verify_swap_math = true;
+        let mut pending = PendingSwap::deserialize(&mut &[0u8; PendingSwap::SIZE][..]).unwrap();
+        pending.reserve_a = reserve_a;
+        pending.reserve_b = reserve_b;
+        pending.is_a_to_b = is_a_to_b;
+        pending.fee_rate = fee_rate;
+        pending.min_fee = min_fee;
+        (pool, pending)
+    }
+
+    /// `(amount_in, reserve_a, reserve_b, fee_rate, min_fee, is_a_to_b)`
+    const REVEALED_SWAPS: [(u64, u64, u64, u16, u64, bool); 7] = [
+        (1_000, 1_000_000, 1_000_000, 30, 0, true),
+        (250_000, 1_000_000, 4_000_000, 30, 0, false),
+        (999_999, 3_000_000, 7_000_000, 100, 0, true),
+        (1, 1_000_000, 1_000_000, 30, 0, true),
+        (10, 1_000, 1_000, 30, 5, false),
+        (5, 1_000, 1_000, 30, 5, true),
+        (u32::MAX as u64, u64::MAX / 2, u64::MAX / 4, 9_999, 0, true),
+    ];
+
+    #[test]
+    fn revealed_swaps_match_an_invariant_reference() {
+        for (amount_in, reserve_a, reserve_b, fee_rate, min_fee, is_a_to_b) in REVEALED_SWAPS {
+            let (pool, pending) = queued_swap(reserve_a, reserve_b, is_a_to_b, fee_rate, min_fee);
+            let (reserve_in, reserve_out) =
+                if is_a_to_b { (reserve_a, reserve_b) } else { (reserve_b, reserve_a) };
+            let reference = invariant_output(amount_in, reserve_in, reserve_out, fee_rate, min_fee);
+
+            assert_eq!(
+                expected_swap_output(amount_in, reserve_in, reserve_out, fee_rate, min_fee).unwrap(),
+                reference
+            );
+            assert!(check_revealed_swap(&pool, &pending, u64::MAX, amount_in, reference, false).is_ok());
+            assert_eq!(
+                check_revealed_swap(
+                    &pool,
+                    &pending,
+                    u64::MAX,
+                    amount_in,
+                    reference + SWAP_MATH_TOLERANCE + 1,
+                    false,
+                )
+                .map_err(u32::from),
+                Err(ErrorCode::SwapMathMismatch.into())
+            );
+        }
+    }
+
+    #[test]
+    fn fee_that_swallows_the_input_reveals_nothing() {
+        // 5 in against a 5 atom fee floor leaves nothing to swap
+        assert_eq!(invariant_output(5, 1_000, 1_000, 30, 5), 0);
+        assert_eq!(expected_swap_output(5, 1_000, 1_000, 30, 5).unwrap(), 0);
+    }
+
+    #[test]
+    fn revealed_output_must_meet_the_committed_minimum() {
+        let (pool, mut pending) = queued_swap(1_000_000, 1_000_000, true, 30, 0);
+        let reference = invariant_output(1_000, 1_000_000, 1_000_000, 30, 0);
+
+        pending.min_output = reference;
+        assert!(check_revealed_swap(&pool, &pending, u64::MAX, 1_000, reference, false).is_ok());
+        pending.min_output = reference + 1;
+        assert_eq!(
+            check_revealed_swap(&pool, &pending, u64::MAX, 1_000, reference, false)
+                .map_err(u32::from),
+            Err(ErrorCode::SwapFailed.into())
+        );
+    }
+
+    #[test]
+    fn slippage_bound_swap_is_held_to_its_quote() {
+        let (pool, mut pending) = queued_swap(1_000_000, 1_000_000, true, 30, 0);
+        pending.quoted_reserve_in = 1_000_000;
+        pending.quoted_reserve_out = 1_000_000;
+        pending.max_slippage_bps = 100;
+        let at_quote = invariant_output(1_000, 1_000_000, 1_000_000, 30, 0);
+        assert!(check_revealed_swap(&pool, &pending, u64::MAX, 1_000, at_quote, false).is_ok());
+
+        // The pool moved 10% against the trader between quote and execution
+        pending.reserve_b = 900_000;
+        let moved = invariant_output(1_000, 1_000_000, 900_000, 30, 0);
+        assert!(moved < pending.output_floor(1_000).unwrap());
+        assert_eq!(
+            check_revealed_swap(&pool, &pending, u64::MAX, 1_000, moved, false)
+                .map_err(u32::from),
+            Err(ErrorCode::SwapFailed.into())
+        );
+    }
+
+    #[test]
+    fn revealed_output_must_fit_the_vault() {
+        let (pool, pending) = queued_swap(1_000_000, 1_000_000, true, 30, 0);
+        let reference = invariant_output(1_000, 1_000_000, 1_000_000, 30, 0);
+
+        assert!(check_revealed_swap(&pool, &pending, reference, 1_000, reference, false).is_ok());
+        assert_eq!(
+            check_revealed_swap(&pool, &pending, reference - 1, 1_000, reference, false)
+                .map_err(u32::from),
+            Err(ErrorCode::InsufficientLiquidity.into())
+        );
+    }
+
+    #[test]
+    fn exact_output_reveal_may_not_beat_its_quote() {
+        let (pool, pending) = queued_swap(1_000_000, 1_000_000, false, 30, 0);
+        let reference = invariant_output(10_000, 1_000_000, 1_000_000, 30, 0);
+
+        // Rounding the back-solved input up can leave the fixed output well
+        // under the quote, but never over it
+        for amount_out in [reference - 50, reference] {
+            assert!(check_revealed_swap(&pool, &pending, u64::MAX, 10_000, amount_out, true).is_ok());
+        }
+        assert_eq!(
+            check_revealed_swap(&pool, &pending, u64::MAX, 10_000, reference + 1, true)
+                .map_err(u32::from),
+            Err(ErrorCode::SwapMathMismatch.into())
+        );
+    }
+
+    #[test]
+    fn discount_schedules_must_ascend() {
+        assert!(validate_discount_schedule(&[100, 1_000, 0], &[1_000, 2_500, 0]).is_ok());
+        for (thresholds, bps) in [
+            ([1_000, 100, 0], [1_000, 2_500, 0]),
+            ([100, 1_000, 0], [2_500, 1_000, 0]),
+            ([100, 0, 100], [1_000, 0, 2_500]),
+            ([100, 0, 0], [10_001, 0, 0]),
+        ] {
+            assert_eq!(
+                validate_discount_schedule(&thresholds, &bps).unwrap_err(),
+                ErrorCode::InvalidDiscountSchedule.into()
+            );
+        }
+    }
+
+    #[test]
+    fn holders_pay_the_fee_of_the_highest_tier_they_reach() {
+        let mut pool = empty_pool();
+        pool.fee_rate = 30;
+        pool.discount_thresholds = [100, 1_000, 0];
+        pool.discount_bps = [1_000, 5_000, 0];
+        // No governance mint means no discounts, whatever the schedule says
+        assert_eq!(pool.discounted_fee_rate(1_000), 30);
+
+        pool.governance_mint = Pubkey::new_unique();
+        assert_eq!(pool.discounted_fee_rate(99), 30);
+        assert_eq!(pool.discounted_fee_rate(100), 27);
+        assert_eq!(pool.discounted_fee_rate(u64::MAX), 15);
+    }
+
+    #[test]
+    fn multi_hop_routes_must_connect_through_one_token() {
+        let (usdc, sol, bonk) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let mut first = empty_pool();
+        (first.token_mint_a, first.token_mint_b) = (usdc, sol);
+        let mut second = empty_pool();
+        (second.token_mint_a, second.token_mint_b) = (bonk, sol);
+
+        // USDC -> SOL through the first pool, SOL -> BONK back through the second
+        assert_eq!(route_mints(&first, true, &second, false).unwrap(), (usdc, sol, bonk));
+        assert_eq!(route_mints(&second, true, &first, false).unwrap(), (bonk, sol, usdc));
+        assert_eq!(
+            route_mints(&first, true, &second, true).unwrap_err(),
+            ErrorCode::InvalidRoute.into()
+        );
+        assert_eq!(
+            route_mints(&first, false, &second, false).unwrap_err(),
+            ErrorCode::InvalidRoute.into()
+        );
+    }
+
+    #[test]
+    fn closing_a_pool_frees_a_slot_under_the_authority_limit() {
+        let mut registry = PoolRegistry::deserialize(&mut &[0u8; PoolRegistry::SIZE][..]).unwrap();
+        registry.open_pool(2).unwrap();
+        registry.open_pool(2).unwrap();
+        assert_eq!(registry.open_pool(2).unwrap_err(), ErrorCode::PoolLimitReached.into());
+
+        registry.close_pool();
+        registry.open_pool(2).unwrap();
+        // A lowered limit leaves existing pools open but blocks new ones
+        assert_eq!(registry.open_pool(1).unwrap_err(), ErrorCode::PoolLimitReached.into());
+        assert_eq!(registry.open_pools, 2);
+    }
+
+    #[test]
+    fn retained_fees_join_the_reserve_of_their_token() {
+        let mut pool = funded_pool(1_000, 2_000);
+        pool.fee_mode = FeeMode::LpRetained;
+        pool.accrue_fee(true, 5, 500).unwrap();
+        pool.accrue_fee(false, 7, 500).unwrap();
+        assert_eq!((pool.reserve_a, pool.reserve_b), (1_005, 2_007));
+        assert_eq!((pool.fee_growth_a, pool.fee_growth_b), (0, 0));
+
+        // With no LP supply even extracted fees have nobody to accrue to
+        pool.fee_mode = FeeMode::Extracted;
+        pool.accrue_fee(true, 5, 0).unwrap();
+        assert_eq!((pool.reserve_a, pool.fee_growth_a), (1_010, 0));
+        pool.accrue_fee(true, 5, 500).unwrap();
+        assert_eq!((pool.reserve_a, pool.fee_growth_a), (1_010, 5 * FEE_GROWTH_SCALE / 500));
+    }
+
+    #[test]
+    fn reserves_are_proven_by_vaults_holding_at_least_as_much() {
+        let pool = funded_pool(1_000, 2_000);
+        assert!(pool.reserves_backed_by(1_000, 2_000));
+        // Unclaimed LP fees sit in the vaults on top of the reserves
+        assert!(pool.reserves_backed_by(1_005, 2_010));
+        assert!(!pool.reserves_backed_by(999, 2_000));
+        assert!(!pool.reserves_backed_by(1_000, 1_999));
+    }
+
+    #[test]
+    fn quotes_price_the_direction_and_holder_fee_asked_for() {
+        let mut pool = funded_pool(10_000, 40_000);
+        pool.fee_rate = 100;
+        assert_eq!(
+            pool.quote(100, true, 0).unwrap(),
+            (expected_swap_output(100, 10_000, 40_000, 100, 0).unwrap(), 100)
+        );
+        assert_eq!(
+            pool.quote(100, false, 0).unwrap(),
+            (expected_swap_output(100, 40_000, 10_000, 100, 0).unwrap(), 100)
+        );
+
+        pool.governance_mint = Pubkey::new_unique();
+        pool.discount_thresholds = [50, 0, 0];
+        pool.discount_bps = [5_000, 0, 0];
+        assert_eq!(
+            pool.quote(100, true, 50).unwrap(),
+            (expected_swap_output(100, 10_000, 40_000, 50, 0).unwrap(), 50)
+        );
+    }
+
+    #[test]
+    fn dust_swaps_are_valued_in_token_b_against_the_minimum() {
+        let mut pool = empty_pool();
+        // Zero disables the minimum
+        assert!(pool.require_min_notional(1, true, 1_000, 4_000).is_ok());
+
+        pool.min_swap_notional = 100;
+        assert!(pool.require_min_notional(100, false, 4_000, 1_000).is_ok());
+        assert_eq!(
+            pool.require_min_notional(99, false, 4_000, 1_000).unwrap_err(),
+            ErrorCode::SwapBelowMinimum.into()
+        );
+        // Token A at the spot price of 4 B per A
+        assert!(pool.require_min_notional(25, true, 1_000, 4_000).is_ok());
+        assert_eq!(
+            pool.require_min_notional(24, true, 1_000, 4_000).unwrap_err(),
+            ErrorCode::SwapBelowMinimum.into()
+        );
+        assert_eq!(
+            pool.require_min_notional(u64::MAX, true, 0, 4_000).unwrap_err(),
+            ErrorCode::SwapBelowMinimum.into()
+        );
+    }
 }